@@ -7,10 +7,12 @@
 
 //! Configuration module for the server binary, `hickory-dns`.
 
-#[cfg(feature = "__tls")]
-use std::ffi::OsStr;
+#[cfg(feature = "dnstap")]
+use std::future::Future;
 #[cfg(feature = "prometheus-metrics")]
 use std::net::SocketAddr;
+#[cfg(feature = "__tls")]
+use std::{ffi::OsStr, iter, time::SystemTime};
 use std::{
     fmt, fs, io,
     marker::PhantomData,
@@ -25,9 +27,9 @@ use cfg_if::cfg_if;
 use ipnet::IpNet;
 #[cfg(feature = "__tls")]
 use rustls::{
+    RootCertStore,
     pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
-    server::ResolvesServerCert,
-    sign::{CertifiedKey, SingleCertAndKey},
+    sign::CertifiedKey,
 };
 // `IgnoredAny` is only used by the fallback store variants below, so it is
 // unused when every store feature is enabled.
@@ -36,14 +38,22 @@ use serde::de::IgnoredAny;
 use serde::de::{self, MapAccess, SeqAccess, Visitor};
 use serde::{self, Deserialize, Deserializer};
 use thiserror::Error;
+#[cfg(any(feature = "dnstap", feature = "__tls"))]
+use tracing::warn;
 use tracing::{debug, info};
 
 #[cfg(feature = "__dnssec")]
 use crate::dnssec;
+#[cfg(feature = "dnstap")]
+use hickory_net::dnstap::Dnstap;
+#[cfg(all(feature = "dnstap", feature = "recursor"))]
+use hickory_net::dnstap::{UpstreamRole, UpstreamTap};
 #[cfg(feature = "__https")]
 use hickory_net::http::DEFAULT_DNS_QUERY_PATH;
 #[cfg(feature = "__tls")]
 use hickory_net::tls::default_provider;
+#[cfg(feature = "__dnssec")]
+use hickory_proto::rr::rdata::zonemd::HashAlgorithm;
 use hickory_proto::{ProtoError, rr::Name, serialize::txt::ParseError};
 #[cfg(feature = "recursor")]
 use hickory_resolver::recursor::RecursiveConfig;
@@ -51,6 +61,8 @@ use hickory_resolver::recursor::RecursiveConfig;
 use hickory_server::dnssec::NxProofKind;
 #[cfg(any(feature = "recursor", feature = "sqlite"))]
 use hickory_server::net::runtime::TokioRuntimeProvider;
+#[cfg(feature = "__tls")]
+use hickory_server::server::{ClientAuth, IdentityPattern, SniCertResolver};
 #[cfg(feature = "blocklist")]
 use hickory_server::store::blocklist::{BlocklistConfig, BlocklistZoneHandler};
 #[cfg(feature = "resolver")]
@@ -60,8 +72,13 @@ use hickory_server::store::recursor::RecursiveZoneHandler;
 #[cfg(feature = "sqlite")]
 use hickory_server::store::sqlite::{SqliteConfig, SqliteZoneHandler};
 use hickory_server::{
-    store::file::{FileConfig, FileZoneHandler},
-    zone_handler::{AxfrPolicy, ZoneHandler, ZoneType},
+    store::{
+        catalog_zone::{
+            CatalogZoneConfig, CatalogZoneConsumer, CatalogZoneSource, MemberZoneConfig,
+        },
+        file::{FileConfig, FileZoneHandler},
+    },
+    zone_handler::{AxfrPolicy, CatalogHandle, ZoneHandler, ZoneType},
 };
 
 #[cfg(test)]
@@ -118,6 +135,9 @@ pub(crate) struct Config {
     #[serde(default)]
     pub(crate) disable_prometheus: bool,
     /// Timeout associated to a request before it is closed.
+    ///
+    /// This is the idle timeout of TCP, TLS and QUIC connections, advertised to clients with the
+    /// edns-tcp-keepalive option.
     #[serde(
         deserialize_with = "parse_request_timeout",
         default = "default_request_timeout"
@@ -155,6 +175,9 @@ pub(crate) struct Config {
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_with_file")]
     pub(crate) zones: Vec<ZoneConfig>,
+    /// List of catalog zones (RFC 9432) used to provision member zones at runtime
+    #[serde(default)]
+    pub(crate) catalog_zones: Vec<CatalogZoneEntry>,
     /// Certificate to associate to TLS connections (currently the same is used for HTTPS and TLS)
     #[cfg(feature = "__tls")]
     pub(crate) tls_cert: Option<TlsCertConfig>,
@@ -163,18 +186,45 @@ pub(crate) struct Config {
     #[cfg(feature = "__https")]
     #[serde(default = "default_http_endpoint")]
     pub(crate) http_endpoint: String,
+    /// Answer the `_dns.resolver.arpa.` SVCB queries with the TLS, HTTPS and QUIC listeners,
+    /// letting clients of the unencrypted listeners discover and upgrade to them (RFC 9462)
+    ///
+    /// The `tls_cert` must be valid for the IP addresses clients send unencrypted queries to.
+    #[cfg(feature = "__tls")]
+    #[serde(default)]
+    pub(crate) designated_resolver: bool,
     /// Networks denied to access the server
     #[serde(default)]
     pub(crate) deny_networks: Vec<IpNet>,
     /// Networks allowed to access the server
     #[serde(default)]
     pub(crate) allow_networks: Vec<IpNet>,
+    /// Identities of TLS clients denied access to the server, regardless of their network
+    #[cfg(feature = "__tls")]
+    #[serde(default)]
+    pub(crate) deny_identities: Vec<IdentityPattern>,
+    /// Identities of TLS clients allowed access to the server, regardless of their network
+    ///
+    /// Certificate fingerprints, or names matching the subject alternative names of certificates
+    /// issued by the `client_ca` of `tls_cert`.
+    #[cfg(feature = "__tls")]
+    #[serde(default)]
+    pub(crate) allow_identities: Vec<IdentityPattern>,
+    /// Networks of proxies allowed to send the HAProxy PROXY protocol
+    ///
+    /// Connections and datagrams from these networks must start with a PROXY header, the client
+    /// address it carries is then used instead of the address of the proxy.
+    #[serde(default)]
+    pub(crate) proxy_protocol_networks: Vec<IpNet>,
     /// UDP socket configuration options.
     #[serde(default)]
     pub(crate) udp_socket: UdpSocketConfig,
     /// TCP socket configuration options.
     #[serde(default)]
     pub(crate) tcp_socket: TcpSocketConfig,
+    /// dnstap logging of queries and responses
+    #[cfg(feature = "dnstap")]
+    pub(crate) dnstap: Option<DnstapConfig>,
 }
 
 /// Configuration options for UDP sockets.
@@ -338,6 +388,7 @@ impl ZoneConfig {
     pub(crate) async fn load(
         self,
         zone_dir: &Path,
+        #[cfg(feature = "dnstap")] dnstap: Option<&Dnstap>,
     ) -> Result<Vec<Arc<dyn ZoneHandler>>, ProtoError> {
         debug!("loading zone with config: {self:#?}");
 
@@ -366,7 +417,7 @@ impl ZoneConfig {
                                 SqliteZoneHandler::<TokioRuntimeProvider>::try_from_config(
                                     zone_name.clone(),
                                     zone_type,
-                                    axfr_policy,
+                                    axfr_policy.clone(),
                                     server_config.is_dnssec_enabled(),
                                     Some(zone_dir),
                                     config,
@@ -375,6 +426,8 @@ impl ZoneConfig {
                                 )
                                 .await?;
 
+                            #[cfg(feature = "__dnssec")]
+                            handler.set_zonemd(server_config.zonemd);
                             #[cfg(feature = "__dnssec")]
                             dnssec::load_keys(&mut handler, &zone_name, &server_config.keys)
                                 .await?;
@@ -386,13 +439,15 @@ impl ZoneConfig {
                             let mut handler = FileZoneHandler::try_from_config(
                                 zone_name.clone(),
                                 zone_type,
-                                axfr_policy,
+                                axfr_policy.clone(),
                                 Some(zone_dir),
                                 config,
                                 #[cfg(feature = "__dnssec")]
                                 server_config.nx_proof_kind.clone(),
                             )?;
 
+                            #[cfg(feature = "__dnssec")]
+                            handler.set_zonemd(server_config.zonemd);
                             #[cfg(feature = "__dnssec")]
                             dnssec::load_keys(&mut handler, &zone_name, &server_config.keys)
                                 .await?;
@@ -433,18 +488,33 @@ impl ZoneConfig {
                         }
                         #[cfg(feature = "resolver")]
                         ExternalStoreConfig::Forward(config) => {
-                            let forwarder = ForwardZoneHandler::builder_tokio(config)
-                                .with_origin(zone_name.clone())
-                                .build()?;
+                            #[cfg_attr(not(feature = "dnstap"), allow(unused_mut))]
+                            let mut builder = ForwardZoneHandler::builder_tokio(config)
+                                .with_origin(zone_name.clone());
+                            #[cfg(feature = "dnstap")]
+                            if let Some(dnstap) = dnstap {
+                                builder = builder.with_dnstap(dnstap.clone());
+                            }
+
+                            let forwarder = builder.build()?;
 
                             Arc::new(forwarder)
                         }
                         #[cfg(feature = "recursor")]
                         ExternalStoreConfig::Recursor(config) => {
+                            #[cfg_attr(not(feature = "dnstap"), allow(unused_mut))]
+                            let mut config = *config;
+                            #[cfg(feature = "dnstap")]
+                            {
+                                config.options.dnstap = dnstap.map(|dnstap| {
+                                    UpstreamTap::new(dnstap.clone(), UpstreamRole::Resolver)
+                                });
+                            }
+
                             let recursor = RecursiveZoneHandler::try_from_config(
                                 zone_name.clone(),
                                 zone_type,
-                                *config,
+                                config,
                                 Some(zone_dir),
                                 TokioRuntimeProvider::default(),
                             )
@@ -503,6 +573,45 @@ impl ZoneConfig {
 
 const EMPTY_STORES: &str = "empty [[zones.stores]] in config";
 
+/// Configuration for a catalog zone
+///
+/// The fields mirror [`CatalogZoneConfig`], they are not flattened so unknown fields are rejected.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CatalogZoneEntry {
+    /// name of the catalog zone
+    pub zone: String,
+    /// where the catalog zone is read from
+    pub source: CatalogZoneSource,
+    /// how often to reload the catalog zone, in seconds
+    pub refresh_interval: Option<u64>,
+    /// how the member zones are served
+    pub members: MemberZoneConfig,
+}
+
+impl CatalogZoneEntry {
+    /// Create a consumer that keeps the member zones of this catalog zone in the `catalog`
+    pub(crate) fn consumer(
+        &self,
+        zone_dir: &Path,
+        catalog: CatalogHandle,
+    ) -> Result<CatalogZoneConsumer, ProtoError> {
+        let origin = Name::parse(&self.zone, Some(&Name::new()))?;
+        let config = CatalogZoneConfig {
+            source: self.source.clone(),
+            refresh_interval: self.refresh_interval,
+            members: self.members.clone(),
+        };
+
+        Ok(CatalogZoneConsumer::from_config(
+            origin,
+            &config,
+            Some(zone_dir),
+            catalog,
+        ))
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(tag = "zone_type")]
 #[serde(deny_unknown_fields)]
@@ -547,6 +656,11 @@ pub(crate) struct ServerZoneConfig {
     /// The kind of non-existence proof provided by the nameserver
     #[cfg(feature = "__dnssec")]
     pub nx_proof_kind: Option<NxProofKind>,
+    /// The hash algorithm of the ZONEMD digest published when the zone is signed
+    ///
+    /// By default no ZONEMD record is generated
+    #[cfg(feature = "__dnssec")]
+    pub zonemd: Option<HashAlgorithm>,
     /// Store configurations.  Note: we specify a default handler to get a Vec containing a
     /// StoreConfig::Default, which is used for authoritative file-based zones and legacy sqlite
     /// configurations. #[serde(default)] cannot be used, because it will invoke Default for Vec,
@@ -576,7 +690,7 @@ impl ServerZoneConfig {
 
     /// Return a policy that can be used to determine how AXFR requests should be handled.
     fn axfr_policy(&self) -> AxfrPolicy {
-        self.axfr_policy
+        self.axfr_policy.clone()
     }
 
     /// declare that this zone should be signed, see keys for configuration of the keys for signing
@@ -687,80 +801,310 @@ where
     deserializer.deserialize_any(MapOrSequence::<T>(PhantomData))
 }
 
+/// Configuration of the dnstap output
+#[cfg(feature = "dnstap")]
+#[derive(Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DnstapConfig {
+    /// Frame Streams UNIX socket of the dnstap collector
+    pub(crate) socket_path: Option<PathBuf>,
+    /// File to write the Frame Stream to, truncated on startup, if no socket is configured
+    pub(crate) file: Option<PathBuf>,
+    /// Identity of this server in the messages, typically its hostname
+    pub(crate) identity: Option<String>,
+    /// Log only one in every `sample_rate` exchanges
+    #[serde(default = "default_sample_rate")]
+    pub(crate) sample_rate: u32,
+    /// Log only the exchanges for names in these zones, all of them if empty
+    #[serde(default)]
+    pub(crate) zones: Vec<String>,
+    /// Log the queries received from clients and the responses sent to them
+    #[serde(default = "default_true")]
+    pub(crate) client: bool,
+    /// Log the exchanges of forwarders and recursors with upstream servers
+    #[serde(default = "default_true")]
+    pub(crate) upstream: bool,
+}
+
+#[cfg(feature = "dnstap")]
+impl DnstapConfig {
+    /// Opens the output, spawning the task writing to it
+    pub(crate) async fn open(&self) -> Result<Dnstap, String> {
+        let zones = self
+            .zones
+            .iter()
+            .map(|zone| Name::parse(zone, Some(&Name::root())))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("invalid dnstap zone: {err}"))?;
+
+        let mut builder = Dnstap::builder()
+            .with_sample_rate(self.sample_rate)
+            .with_zones(zones);
+        if let Some(identity) = &self.identity {
+            builder = builder.with_identity(identity.as_bytes());
+        }
+
+        match (&self.socket_path, &self.file) {
+            #[cfg(unix)]
+            (Some(path), None) => {
+                info!("logging dnstap to socket {path:?}");
+                spawn_dnstap(builder.to_unix_socket(path).await)
+            }
+            #[cfg(not(unix))]
+            (Some(_), None) => Err("dnstap sockets are only supported on Unix".into()),
+            (None, Some(path)) => {
+                info!("logging dnstap to file {path:?}");
+                spawn_dnstap(builder.to_file(path).await)
+            }
+            _ => Err("dnstap requires exactly one of `socket_path` and `file`".into()),
+        }
+    }
+}
+
+/// Spawns the task writing to an opened dnstap output
+#[cfg(feature = "dnstap")]
+fn spawn_dnstap(
+    output: io::Result<(
+        Dnstap,
+        impl Future<Output = io::Result<()>> + Send + 'static,
+    )>,
+) -> Result<Dnstap, String> {
+    let (dnstap, background) =
+        output.map_err(|err| format!("failed to open dnstap output: {err}"))?;
+    tokio::spawn(async move {
+        if let Err(error) = background.await {
+            warn!(%error, "dnstap output failed");
+        }
+    });
+
+    Ok(dnstap)
+}
+
+#[cfg(feature = "dnstap")]
+fn default_sample_rate() -> u32 {
+    1
+}
+
+#[cfg(feature = "dnstap")]
+fn default_true() -> bool {
+    true
+}
+
 /// Configuration for a TLS certificate
 #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
-#[derive(Deserialize, PartialEq, Eq, Debug)]
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
 #[serde(deny_unknown_fields)]
 #[non_exhaustive]
 pub(crate) struct TlsCertConfig {
     pub(crate) path: PathBuf,
     pub(crate) endpoint_name: Option<String>,
     pub(crate) private_key: PathBuf,
+    /// Additional certificates, presented to the clients asking for one of their names with the
+    /// server name indication (SNI), this certificate being the default one
+    #[serde(default)]
+    pub(crate) sni_certs: Vec<CertKeyConfig>,
+    /// How often to check the certificate and key files for changes, in seconds, reloading
+    /// all of them if any changed. Zero disables reloading.
+    #[serde(default = "default_cert_reload_interval")]
+    pub(crate) reload_interval: u64,
+    /// Request a certificate from DoT, DoH and DoQ clients, identifying them for
+    /// `allow_identities`, `deny_identities` and `axfr_policy = { RequireMutualTls = [...] }`.
+    /// The certificate is optional, clients without one are only subject to the network rules.
+    #[cfg(feature = "__tls")]
+    #[serde(default)]
+    pub(crate) request_client_cert: bool,
+    /// PEM bundle of the CA certificates issuing client certificates, implies
+    /// `request_client_cert`
+    ///
+    /// Clients with a certificate issued by one of these CAs are also identified by the subject
+    /// alternative names of their certificate, others only by its fingerprint.
+    #[cfg(feature = "__tls")]
+    pub(crate) client_ca: Option<PathBuf>,
+}
+
+/// Certificate chain and private key files
+#[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CertKeyConfig {
+    pub(crate) path: PathBuf,
+    pub(crate) private_key: PathBuf,
+}
+
+#[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
+fn default_cert_reload_interval() -> u64 {
+    60
 }
 
 #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
 impl TlsCertConfig {
-    /// Load a Certificate from the path (with rustls)
-    pub(crate) fn load(&self, zone_dir: &Path) -> Result<Arc<dyn ResolvesServerCert>, String> {
+    /// Load the certificates, selected by the server name indication of clients
+    pub(crate) fn load(&self, zone_dir: &Path) -> Result<Arc<SniCertResolver>, String> {
         if let Some(endpoint_name) = &self.endpoint_name {
             info!("loading TLS cert for {endpoint_name} from {:?}", self.path);
         } else {
             info!("loading TLS cert from {:?}", self.path);
         }
 
-        if self.path.extension().and_then(OsStr::to_str) != Some("pem") {
-            return Err(format!(
-                "unsupported certificate file format (expected `.pem` extension): {}",
-                self.path.display()
-            ));
+        let resolver = SniCertResolver::new(self.load_keys(zone_dir)?);
+        info!("loaded TLS certs for {:?}", resolver.names());
+        Ok(Arc::new(resolver))
+    }
+
+    /// Reload the certificates into `resolver` whenever their files change
+    ///
+    /// Certificates failing to load are retried on the next check, the previous ones being kept
+    /// in the meantime.
+    pub(crate) async fn watch(self, zone_dir: PathBuf, resolver: Arc<SniCertResolver>) {
+        if self.reload_interval == 0 {
+            return;
         }
 
-        let cert_path = zone_dir.join(&self.path);
-        info!(
-            "loading TLS PEM certificate chain from: {}",
-            cert_path.display()
-        );
+        let interval = Duration::from_secs(self.reload_interval);
+        let mut modified = self.modified(&zone_dir);
+        loop {
+            tokio::time::sleep(interval).await;
 
-        let cert_chain = CertificateDer::pem_file_iter(&cert_path)
-            .map_err(|e| {
-                format!(
-                    "failed to read cert chain from {}: {e}",
-                    cert_path.display()
-                )
-            })?
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| {
-                format!(
-                    "failed to parse cert chain from {}: {e}",
-                    cert_path.display()
-                )
-            })?;
-
-        let key_extension = self.private_key.extension();
-        let key = if key_extension.is_some_and(|ext| ext == "pem") {
-            let key_path = zone_dir.join(&self.private_key);
-            info!("loading TLS PKCS8 key from PEM: {}", key_path.display());
-            PrivateKeyDer::from_pem_file(&key_path)
-                .map_err(|e| format!("failed to read key from {}: {e}", key_path.display()))?
-        } else if key_extension.is_some_and(|ext| ext == "der" || ext == "key") {
-            let key_path = zone_dir.join(&self.private_key);
-            info!("loading TLS PKCS8 key from DER: {}", key_path.display());
-
-            let buf =
-                fs::read(&key_path).map_err(|e| format!("error reading key from file: {e}"))?;
-            PrivateKeyDer::try_from(buf).map_err(|e| format!("error parsing key DER: {e}"))?
-        } else {
+            let current = self.modified(&zone_dir);
+            if current == modified {
+                continue;
+            }
+
+            match self.load_keys(&zone_dir) {
+                Ok(keys) => {
+                    resolver.replace(keys);
+                    info!("reloaded TLS certs for {:?}", resolver.names());
+                    modified = current;
+                }
+                Err(err) => warn!("failed to reload TLS certs, keeping the previous ones: {err}"),
+            }
+        }
+    }
+
+    /// The certificate and key files, the default certificate first
+    fn files(&self) -> impl Iterator<Item = (&Path, &Path)> {
+        iter::once((&*self.path, &*self.private_key)).chain(
+            self.sni_certs
+                .iter()
+                .map(|config| (&*config.path, &*config.private_key)),
+        )
+    }
+
+    /// Modification times of the certificate and key files
+    fn modified(&self, zone_dir: &Path) -> Vec<Option<SystemTime>> {
+        self.files()
+            .flat_map(|(cert, key)| [cert, key])
+            .map(|path| {
+                fs::metadata(zone_dir.join(path))
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .collect()
+    }
+
+    fn load_keys(&self, zone_dir: &Path) -> Result<Vec<Arc<CertifiedKey>>, String> {
+        self.files()
+            .map(|(cert, key)| load_certified_key(zone_dir, cert, key).map(Arc::new))
+            .collect()
+    }
+
+    /// Whether the TLS listeners request a certificate from clients
+    #[cfg(feature = "__tls")]
+    pub(crate) fn request_client_cert(&self) -> bool {
+        self.request_client_cert || self.client_ca.is_some()
+    }
+
+    /// Build the authentication of clients, verifying their certificate against `client_ca`
+    #[cfg(feature = "__tls")]
+    pub(crate) fn client_auth(&self, zone_dir: &Path) -> Result<ClientAuth, String> {
+        let Some(client_ca) = &self.client_ca else {
+            return Ok(ClientAuth::default());
+        };
+
+        let ca_path = zone_dir.join(client_ca);
+        info!("loading client CA certificates from: {}", ca_path.display());
+
+        let mut roots = RootCertStore::empty();
+        let (added, ignored) = roots.add_parsable_certificates(
+            CertificateDer::pem_file_iter(&ca_path)
+                .map_err(|e| format!("failed to read client CA from {}: {e}", ca_path.display()))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| {
+                    format!("failed to parse client CA from {}: {e}", ca_path.display())
+                })?,
+        );
+        if ignored > 0 {
+            warn!("ignored {ignored} invalid client CA certificates");
+        }
+        if added == 0 {
             return Err(format!(
-                "unsupported private key file format (expected `.pem` or `.der` extension): {}",
-                self.private_key.display()
+                "no client CA certificates in {}",
+                ca_path.display()
             ));
-        };
+        }
 
-        let certified_key = CertifiedKey::from_der(cert_chain, key, &default_provider())
-            .map_err(|err| format!("failed to read certificate and keys: {err:?}"))?;
+        ClientAuth::default()
+            .with_ca(roots)
+            .map_err(|e| format!("failed to build client certificate verifier: {e}"))
+    }
+}
 
-        Ok(Arc::new(SingleCertAndKey::from(certified_key)))
+/// Load a certificate chain and its private key (with rustls)
+#[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
+fn load_certified_key(
+    zone_dir: &Path,
+    path: &Path,
+    private_key: &Path,
+) -> Result<CertifiedKey, String> {
+    if path.extension().and_then(OsStr::to_str) != Some("pem") {
+        return Err(format!(
+            "unsupported certificate file format (expected `.pem` extension): {}",
+            path.display()
+        ));
     }
+
+    let cert_path = zone_dir.join(path);
+    info!(
+        "loading TLS PEM certificate chain from: {}",
+        cert_path.display()
+    );
+
+    let cert_chain = CertificateDer::pem_file_iter(&cert_path)
+        .map_err(|e| {
+            format!(
+                "failed to read cert chain from {}: {e}",
+                cert_path.display()
+            )
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            format!(
+                "failed to parse cert chain from {}: {e}",
+                cert_path.display()
+            )
+        })?;
+
+    let key_extension = private_key.extension();
+    let key = if key_extension.is_some_and(|ext| ext == "pem") {
+        let key_path = zone_dir.join(private_key);
+        info!("loading TLS PKCS8 key from PEM: {}", key_path.display());
+        PrivateKeyDer::from_pem_file(&key_path)
+            .map_err(|e| format!("failed to read key from {}: {e}", key_path.display()))?
+    } else if key_extension.is_some_and(|ext| ext == "der" || ext == "key") {
+        let key_path = zone_dir.join(private_key);
+        info!("loading TLS PKCS8 key from DER: {}", key_path.display());
+
+        let buf = fs::read(&key_path).map_err(|e| format!("error reading key from file: {e}"))?;
+        PrivateKeyDer::try_from(buf).map_err(|e| format!("error parsing key DER: {e}"))?
+    } else {
+        return Err(format!(
+            "unsupported private key file format (expected `.pem` or `.der` extension): {}",
+            private_key.display()
+        ));
+    };
+
+    CertifiedKey::from_der(cert_chain, key, &default_provider())
+        .map_err(|err| format!("failed to read certificate and keys: {err:?}"))
 }
 
 /// The error kind for errors that get returned in the crate
@@ -172,6 +172,34 @@ signer_name = \"ns.example.com.\"
     );
 }
 
+#[cfg(feature = "__dnssec")]
+#[test]
+fn test_parse_zonemd() {
+    use hickory_proto::rr::rdata::zonemd::HashAlgorithm;
+
+    let config = Config::from_toml(
+        "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+zonemd = \"SHA384\"
+[zones.stores]
+type = \"file\"
+zone_path = \"example.com.zone\"
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Primary\"
+[zones.stores]
+type = \"file\"
+zone_path = \"example.net.zone\"
+",
+    )
+    .unwrap();
+    assert_eq!(server_zone(&config, 0).zonemd, Some(HashAlgorithm::SHA384));
+    assert_eq!(server_zone(&config, 1).zonemd, None);
+}
+
 #[test]
 #[cfg(feature = "__tls")]
 fn test_parse_tls() {
@@ -180,21 +208,78 @@ fn test_parse_tls() {
 
     assert_eq!(config.tls_listen_port, 853);
     assert_eq!(config.tls_cert, None);
+    assert!(!config.designated_resolver);
 
     let config = Config::from_toml(
         "tls_cert = { path = \"path/to/some.pkcs12\", endpoint_name = \"ns.example.com\", private_key = \"foo.pem\" }
 tls_listen_port = 8853
+designated_resolver = true
   ",
     )
     .unwrap();
 
     assert_eq!(config.tls_listen_port, 8853);
+    assert!(config.designated_resolver);
+    let tls_cert = config.tls_cert.unwrap();
+    assert_eq!(tls_cert.path, Path::new("path/to/some.pkcs12"));
+    assert!(tls_cert.sni_certs.is_empty());
+    assert_eq!(tls_cert.reload_interval, 60);
+
+    let config = Config::from_toml(
+        "[tls_cert]
+path = \"dns.example.com.pem\"
+private_key = \"dns.example.com.key\"
+reload_interval = 0
+sni_certs = [
+    { path = \"doh.example.net.pem\", private_key = \"doh.example.net.key\" },
+]
+",
+    )
+    .unwrap();
+
+    let tls_cert = config.tls_cert.unwrap();
+    assert_eq!(tls_cert.reload_interval, 0);
+    assert_eq!(tls_cert.sni_certs.len(), 1);
+    assert_eq!(tls_cert.sni_certs[0].path, Path::new("doh.example.net.pem"));
     assert_eq!(
-        config.tls_cert.unwrap().path,
-        Path::new("path/to/some.pkcs12")
+        tls_cert.sni_certs[0].private_key,
+        Path::new("doh.example.net.key")
     );
 }
 
+#[test]
+#[cfg(feature = "__tls")]
+fn test_parse_client_identities() {
+    use hickory_server::server::{CertFingerprint, IdentityPattern};
+
+    let config = Config::from_toml(
+        "tls_cert = { path = \"cert.pem\", private_key = \"key.pem\", client_ca = \"ca.pem\" }
+deny_networks = [\"0.0.0.0/0\", \"::/0\"]
+deny_identities = [\"AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89\"]
+allow_identities = [\"*.devices.example.com\"]
+",
+    )
+    .unwrap();
+
+    let tls_cert = config.tls_cert.unwrap();
+    assert_eq!(tls_cert.client_ca.as_deref(), Some(Path::new("ca.pem")));
+    assert!(tls_cert.request_client_cert());
+    assert_eq!(
+        config.deny_identities,
+        vec![IdentityPattern::Fingerprint(CertFingerprint::from([
+            0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45,
+            0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01,
+            0x23, 0x45, 0x67, 0x89,
+        ]))]
+    );
+    assert_eq!(
+        config.allow_identities,
+        vec![IdentityPattern::Name("*.devices.example.com".to_owned())]
+    );
+
+    assert!(Config::from_toml("allow_identities = [\"*.\"]").is_err());
+}
+
 fn test_config(path: &str) {
     let workspace = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
     let path = PathBuf::from(workspace)
@@ -229,6 +314,7 @@ define_test_config!(dns_over_tls_rustls_and_openssl);
 define_test_config!(dns_over_tls);
 #[cfg(all(feature = "__dnssec", feature = "sqlite"))]
 define_test_config!(dnssec_with_update);
+define_test_config!(catalog_zone);
 define_test_config!(example);
 define_test_config!(ipv4_and_ipv6);
 define_test_config!(ipv4_only);
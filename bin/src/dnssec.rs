@@ -7,10 +7,8 @@
 
 //! Configuration types for all security options in hickory-dns
 
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
-use rustls_pki_types::PrivateKeyDer;
-use rustls_pki_types::pem::PemObject;
 use serde::Deserialize;
 use time::Duration;
 use tracing::info;
@@ -18,7 +16,7 @@ use tracing::info;
 use hickory_proto::rr::domain::Name;
 use hickory_proto::{
     ProtoError,
-    dnssec::{Algorithm, DnssecSigner, SigningKey, rdata::DNSKEY},
+    dnssec::{Algorithm, DnssecSigner, crypto::signing_key_from_file, rdata::DNSKEY},
     rr::domain::IntoName,
 };
 use hickory_server::zone_handler::DnssecZoneHandler;
@@ -90,7 +88,8 @@ impl KeyConfig {
         };
 
         // read the key in
-        let key = key_from_file(&self.key_path, self.algorithm)?;
+        let key =
+            signing_key_from_file(&self.key_path, self.algorithm).map_err(|e| e.to_string())?;
 
         // add the key to the zone
         // TODO: allow the duration of signatures to be customized
@@ -132,54 +131,17 @@ impl KeyConfig {
     }
 }
 
-pub fn key_from_file(path: &Path, algorithm: Algorithm) -> Result<Box<dyn SigningKey>, String> {
-    use std::fs::File;
-    use std::io::Read;
-
-    use tracing::info;
-
-    use hickory_proto::dnssec::crypto::signing_key_from_der;
-
-    info!("reading key: {path:?}");
-    let mut file =
-        File::open(path).map_err(|e| format!("error opening private key file: {path:?}: {e}"))?;
-
-    let mut buf = Vec::with_capacity(256);
-    file.read_to_end(&mut buf)
-        .map_err(|e| format!("could not read key from: {path:?}: {e}"))?;
-
-    let key = match trim_ascii_start(&buf).starts_with(b"-----BEGIN ") {
-        true => PrivateKeyDer::from_pem_slice(&buf)
-            .map_err(|e| format!("could not read pem from {}: {e}", path.display()))?,
-        false => PrivateKeyDer::try_from(&*buf)
-            .map_err(|e| format!("could not read der from {}: {e}", path.display()))?,
-    };
-
-    signing_key_from_der(&key, algorithm).map_err(|e| format!("could not decode key: {e}"))
-}
-
-// Copied from std, MSRV 1.80
-fn trim_ascii_start(mut bytes: &[u8]) -> &[u8] {
-    // Note: A pattern matching based approach (instead of indexing) allows
-    // making the function const.
-    while let [first, rest @ ..] = bytes {
-        match first.is_ascii_whitespace() {
-            true => bytes = rest,
-            false => return bytes,
-        }
-    }
-    bytes
-}
-
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
     use super::*;
 
     #[test]
     fn pkcs8_pem_key() {
         // OpenSSL 3 generates PKCS#8-encoded RSA keys by default
         // `openssl genrsa 2048`
-        key_from_file(
+        signing_key_from_file(
             Path::new("tests/test-data/rsa-2048-pkcs8.pem"),
             Algorithm::RSASHA256,
         )
@@ -191,7 +153,7 @@ mod tests {
         // OpenSSL 1 used to generate PKCS#1-encoded RSA keys by default
         // OpenSSL 3 does not anymore, but you can still generate them with ssh-keygen
         // `ssh-keygen -t rsa -b 2048 -o -a 100 -f test-data/rsa-2048-pkcs1.pem -m PEM`
-        key_from_file(
+        signing_key_from_file(
             Path::new("tests/test-data/rsa-2048-pkcs1.pem"),
             Algorithm::RSASHA256,
         )
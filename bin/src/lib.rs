@@ -1,3 +1,7 @@
+#[cfg(feature = "__tls")]
+use std::io;
+#[cfg(feature = "__tls")]
+use std::str::FromStr;
 #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,6 +16,8 @@ use clap::Parser;
 use metrics_process::Collector;
 #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
 use rustls::KeyLogFile;
+#[cfg(feature = "__tls")]
+use rustls::ServerConfig;
 #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
 use rustls::server::ResolvesServerCert;
 use socket2::{Domain, Socket, Type};
@@ -32,7 +38,14 @@ use tracing::{error, info};
 use hickory_server::proto::ProtoError;
 use hickory_server::proto::rr::rdata::opt::NSIDPayload;
 #[cfg(feature = "__tls")]
-use hickory_server::server::default_tls_server_config;
+use hickory_server::server::{
+    ClientAuth, client_cert_tls_server_config, default_tls_server_config,
+};
+#[cfg(feature = "__tls")]
+use hickory_server::{
+    proto::rr::{LowerName, Name},
+    store::designated_resolver::DesignatedResolver,
+};
 use hickory_server::{server::Server, zone_handler::Catalog};
 
 mod config;
@@ -289,15 +302,25 @@ impl DnsServer {
             user,
             group,
             zones,
+            catalog_zones,
             drop_privileges,
             #[cfg(feature = "__tls")]
             tls_cert,
             #[cfg(feature = "__https")]
             http_endpoint,
+            #[cfg(feature = "__tls")]
+            designated_resolver,
             deny_networks,
             allow_networks,
+            #[cfg(feature = "__tls")]
+            deny_identities,
+            #[cfg(feature = "__tls")]
+            allow_identities,
+            proxy_protocol_networks,
             udp_socket: udp_socket_config,
             tcp_socket: tcp_socket_config,
+            #[cfg(feature = "dnstap")]
+            dnstap,
         } = config;
 
         #[cfg(unix)]
@@ -315,6 +338,16 @@ impl DnsServer {
             catalog.set_nsid(Some(payload));
         }
 
+        #[cfg(feature = "dnstap")]
+        let tap = match &dnstap {
+            Some(config) if !validate => Some(config.open().await?),
+            _ => None,
+        };
+        #[cfg(feature = "dnstap")]
+        let upstream_tap = tap
+            .as_ref()
+            .filter(|_| dnstap.as_ref().is_some_and(|config| config.upstream));
+
         // configure our server based on the config_path
         let zone_dir = zonedir.unwrap_or(directory);
         for zone in zones {
@@ -325,21 +358,19 @@ impl DnsServer {
             #[cfg(feature = "metrics")]
             config_metrics.increment_zone_metrics(&zone);
 
-            match zone.load(&zone_dir).await {
+            match zone
+                .load(
+                    &zone_dir,
+                    #[cfg(feature = "dnstap")]
+                    upstream_tap,
+                )
+                .await
+            {
                 Ok(handlers) => catalog.upsert(zone_name.into(), handlers),
                 Err(err) => return Err(format!("could not load zone {zone_name}: {err}")),
             }
         }
 
-        if validate {
-            info!("configuration files are validated");
-            return Ok(());
-        }
-
-        // now, run the server, based on the config
-        #[cfg_attr(not(feature = "__tls"), allow(unused_mut))]
-        let mut server = Server::with_access(catalog, deny_networks, allow_networks);
-
         let mut listen_addrs = listen_addrs_ipv4
             .into_iter()
             .map(IpAddr::V4)
@@ -351,22 +382,107 @@ impl DnsServer {
             listen_addrs.push(IpAddr::V6(Ipv6Addr::UNSPECIFIED));
         }
 
+        #[cfg(feature = "__tls")]
+        match (designated_resolver, &tls_cert) {
+            (true, Some(config)) => {
+                let target = match &config.endpoint_name {
+                    Some(name) => Name::from_str(name)
+                        .map_err(|err| format!("invalid endpoint_name {name:?}: {err}"))?,
+                    None => Name::root(),
+                };
+
+                let mut designated =
+                    DesignatedResolver::new(target).with_addresses(listen_addrs.iter().copied());
+                if !disable_tls {
+                    designated = designated.with_tls(tls_port.unwrap_or(tls_listen_port));
+                }
+                #[cfg(feature = "__https")]
+                if !disable_https {
+                    designated = designated
+                        .with_https(https_port.unwrap_or(https_listen_port), &http_endpoint);
+                }
+                #[cfg(feature = "__quic")]
+                if !disable_quic {
+                    designated = designated.with_quic(quic_port.unwrap_or(quic_listen_port));
+                }
+
+                match designated.is_empty() {
+                    true => warn!("designated_resolver without TLS, HTTPS or QUIC listeners"),
+                    false => catalog.upsert(
+                        LowerName::from_str("resolver.arpa.").unwrap(),
+                        vec![Arc::new(designated.zone_handler())],
+                    ),
+                }
+            }
+            (true, None) => warn!("designated_resolver requires a tls_cert"),
+            (false, _) => {}
+        }
+
+        let catalog_zone_consumers = catalog_zones
+            .iter()
+            .map(|entry| {
+                entry
+                    .consumer(&zone_dir, catalog.handle())
+                    .map_err(|err| format!("could not load catalog zone {}: {err}", entry.zone))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if validate {
+            info!("configuration files are validated");
+            return Ok(());
+        }
+
+        for consumer in catalog_zone_consumers {
+            tokio::spawn(consumer.run());
+        }
+
+        // now, run the server, based on the config
+        let mut server = Server::with_access(catalog, deny_networks, allow_networks);
+        server.set_trusted_proxies(proxy_protocol_networks);
+        #[cfg(feature = "__tls")]
+        {
+            let client_auth = match &tls_cert {
+                Some(config) => config.client_auth(&zone_dir)?,
+                None => ClientAuth::default(),
+            };
+            server.set_client_auth(
+                client_auth
+                    .with_denied(deny_identities)
+                    .with_allowed(allow_identities),
+            );
+        }
+        #[cfg(feature = "dnstap")]
+        if let Some(tap) = tap.filter(|_| dnstap.is_some_and(|config| config.client)) {
+            server.set_dnstap(tap);
+        }
+
+        // the certificates are reloaded until the server stops
+        #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
+        let (cert_resolver, cert_watcher) = match &tls_cert {
+            Some(config) => {
+                let resolver = config.load(&zone_dir).map_err(|err| {
+                    format!(
+                        "failed to load TLS certificate from {:?}: {err}",
+                        config.path
+                    )
+                })?;
+                let watcher =
+                    tokio::spawn(config.clone().watch(zone_dir.clone(), resolver.clone()));
+                (Some(resolver as Arc<dyn ResolvesServerCert>), Some(watcher))
+            }
+            None => (None, None),
+        };
+
         let mut setup = ServerSetup {
             listen_addrs,
             server: &mut server,
             tcp_request_timeout,
             #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
-            cert_resolver: tls_cert
+            cert_resolver,
+            #[cfg(feature = "__tls")]
+            request_client_cert: tls_cert
                 .as_ref()
-                .map(|config| {
-                    config.load(&zone_dir).map_err(|err| {
-                        format!(
-                            "failed to load TLS certificate from {:?}: {err}",
-                            config.path
-                        )
-                    })
-                })
-                .transpose()?,
+                .is_some_and(|config| config.request_client_cert()),
             #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
             ssl_keylog_enabled,
             udp_socket_config,
@@ -494,6 +610,11 @@ impl DnsServer {
         #[cfg(feature = "metrics")]
         process_metrics_collector.abort();
 
+        #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
+        if let Some(cert_watcher) = cert_watcher {
+            cert_watcher.abort();
+        }
+
         Ok(())
     }
 }
@@ -504,6 +625,8 @@ struct ServerSetup<'a> {
     tcp_request_timeout: Duration,
     #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
     cert_resolver: Option<Arc<dyn ResolvesServerCert>>,
+    #[cfg(feature = "__tls")]
+    request_client_cert: bool,
     #[cfg(any(feature = "__tls", feature = "__https", feature = "__quic"))]
     ssl_keylog_enabled: bool,
     udp_socket_config: UdpSocketConfig,
@@ -511,6 +634,19 @@ struct ServerSetup<'a> {
 }
 
 impl ServerSetup<'_> {
+    /// TLS config of the listeners for `protocol`, requesting client certificates if configured
+    #[cfg(feature = "__tls")]
+    fn tls_server_config(
+        &self,
+        protocol: &[u8],
+        cert_resolver: Arc<dyn ResolvesServerCert>,
+    ) -> io::Result<ServerConfig> {
+        match self.request_client_cert {
+            true => client_cert_tls_server_config(protocol, cert_resolver),
+            false => default_tls_server_config(protocol, cert_resolver),
+        }
+    }
+
     fn udp(&mut self, port: u16) -> Result<(), String> {
         #[cfg(unix)]
         let num_sockets = self.udp_socket_config.sockets.unwrap_or(1);
@@ -586,7 +722,8 @@ impl ServerSetup<'_> {
                     .map_err(|err| format!("failed to lookup local address: {err}"))?
             );
 
-            let mut tls_config = default_tls_server_config(b"dot", cert_resolver.clone())
+            let mut tls_config = self
+                .tls_server_config(b"dot", cert_resolver.clone())
                 .map_err(|err| format!("failed to build default TLS config: {err}"))?;
             if self.ssl_keylog_enabled {
                 warn!("DoT SSL_KEYLOG_FILE support enabled");
@@ -628,7 +765,8 @@ impl ServerSetup<'_> {
                     .map_err(|err| format!("failed to lookup local address: {err}"))?
             );
 
-            let mut tls_config = default_tls_server_config(b"h2", cert_resolver.clone())
+            let mut tls_config = self
+                .tls_server_config(b"h2", cert_resolver.clone())
                 .map_err(|err| format!("failed to build default TLS config: {err}"))?;
             if self.ssl_keylog_enabled {
                 warn!("DoH SSL_KEYLOG_FILE support enabled");
@@ -668,7 +806,8 @@ impl ServerSetup<'_> {
                     .map_err(|err| format!("failed to lookup local address: {err}"))?
             );
 
-            let mut tls_config = default_tls_server_config(b"doq", cert_resolver.clone())
+            let mut tls_config = self
+                .tls_server_config(b"doq", cert_resolver.clone())
                 .map_err(|err| format!("failed to build default TLS config: {err}"))?;
             if self.ssl_keylog_enabled {
                 warn!("DoQ SSL_KEYLOG_FILE support enabled");
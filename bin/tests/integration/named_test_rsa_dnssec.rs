@@ -8,7 +8,6 @@ use std::sync::Arc;
 
 use crate::server_harness::{TestServer, query_a, query_all_dnssec};
 use futures_util::TryStreamExt;
-use hickory_dns::dnssec::key_from_file;
 use hickory_net::DnsHandle;
 use hickory_net::client::Client;
 use hickory_net::dnssec::DnssecDnsHandle;
@@ -16,7 +15,7 @@ use hickory_net::runtime::{RuntimeProvider, TokioRuntimeProvider};
 use hickory_net::tcp::TcpClientStream;
 use hickory_net::xfer::{DnsExchangeBackground, DnsMultiplexer, Protocol};
 use hickory_proto::{
-    dnssec::{Algorithm, TrustAnchors},
+    dnssec::{Algorithm, TrustAnchors, crypto::signing_key_from_file},
     op::{DnsRequestOptions, Query},
     rr::RecordType,
 };
@@ -28,7 +27,7 @@ fn confg_toml() -> &'static str {
 }
 
 fn trust_anchor(public_key_path: &Path, algorithm: Algorithm) -> Arc<TrustAnchors> {
-    let key_pair = key_from_file(public_key_path, algorithm).unwrap();
+    let key_pair = signing_key_from_file(public_key_path, algorithm).unwrap();
     let public_key = key_pair.to_public_key().unwrap();
     let mut trust_anchor = TrustAnchors::empty();
 
@@ -31,6 +31,12 @@ fn sqlite(zone_path: &Path, module: &str, test_name: &str) -> SqliteZoneHandler
         allow_update: true,
         #[cfg(feature = "__dnssec")]
         tsig_keys: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        sig0_signers: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        update_policy: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        tkey: None,
     };
 
     block_on(SqliteZoneHandler::try_from_config(
@@ -63,6 +69,12 @@ fn sqlite_update(zone_path: &Path, module: &str, test_name: &str) -> SqliteZoneH
         allow_update: true,
         #[cfg(feature = "__dnssec")]
         tsig_keys: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        sig0_signers: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        update_policy: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        tkey: None,
     };
 
     block_on(SqliteZoneHandler::try_from_config(
@@ -103,6 +115,12 @@ fn test_sqlite_memory_journal_path() {
         allow_update: true,
         #[cfg(feature = "__dnssec")]
         tsig_keys: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        sig0_signers: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        update_policy: Vec::new(),
+        #[cfg(feature = "__dnssec")]
+        tkey: None,
     };
 
     let result = block_on(SqliteZoneHandler::<TokioRuntimeProvider>::try_from_config(
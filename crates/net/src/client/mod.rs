@@ -23,10 +23,15 @@ use core::{
     time::Duration,
 };
 
+#[cfg(feature = "__tls")]
+use std::{net::SocketAddr, sync::Arc};
+
 use futures_util::{
     ready,
     stream::{Stream, StreamExt},
 };
+#[cfg(feature = "__tls")]
+use rustls::{ClientConfig, pki_types::ServerName};
 use tracing::debug;
 
 use crate::{
@@ -45,6 +50,11 @@ use crate::{
         DnsHandle, DnsMultiplexer, DnsRequestSender,
     },
 };
+#[cfg(feature = "__tls")]
+use crate::{
+    proto::op::QUERY_PADDING_BLOCK_LEN,
+    tls::{TlsClientStream, tls_client_connect},
+};
 
 #[cfg(all(feature = "__dnssec", feature = "tokio"))]
 pub(crate) mod dnssec_client;
@@ -115,6 +125,40 @@ impl<P: RuntimeProvider> Client<P> {
         )
     }
 
+    /// Connects a Client to `name_server` over TLS
+    ///
+    /// Zone transfers are then pulled over the encrypted connection (XoT), see
+    /// [`xot_client_config`](crate::tls::xot_client_config) for a suitable configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `name_server` - IP and Port of the remote DNS server
+    /// * `server_name` - The DNS name associated with the certificate of the server
+    /// * `client_config` - TLS client configuration
+    /// * `provider` - Runtime provider for the underlying TCP connection
+    #[cfg(feature = "__tls")]
+    #[allow(clippy::type_complexity)]
+    pub async fn connect_tls(
+        name_server: SocketAddr,
+        server_name: ServerName<'static>,
+        client_config: Arc<ClientConfig>,
+        provider: P,
+    ) -> Result<
+        (
+            Self,
+            DnsExchangeBackground<DnsMultiplexer<TlsClientStream<P::Tcp>>, P::Timer>,
+        ),
+        NetError,
+    > {
+        let (stream, handle) =
+            tls_client_connect(name_server, server_name, client_config, provider);
+        Ok(Self::from_sender(
+            DnsMultiplexer::new(stream.await?, handle)
+                .with_timeout(Duration::from_secs(5))
+                .with_padding_block(QUERY_PADDING_BLOCK_LEN),
+        ))
+    }
+
     /// (Re-)enable usage of EDNS for outgoing messages
     pub fn enable_edns(&mut self) {
         self.use_edns = true;
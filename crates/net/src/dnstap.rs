@@ -0,0 +1,634 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! dnstap structured logging of DNS messages, see <https://dnstap.info>
+//!
+//! Messages are encoded as `dnstap.Dnstap` protobuf messages and written as data frames of a
+//! [Frame Streams](https://github.com/farsightsec/fstrm) stream, either to a file or to a UNIX
+//! socket. A [`Dnstap`] handle is cheap to clone and never blocks: messages are dropped when the
+//! writer does not keep up.
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(unix)]
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::proto::op::{DnsRequest, DnsResponse};
+use crate::proto::rr::Name;
+use crate::proto::serialize::binary::BinEncodable;
+use crate::xfer::Protocol;
+
+/// Content type of dnstap Frame Streams
+const CONTENT_TYPE: &[u8] = b"protobuf:dnstap.Dnstap";
+/// Default number of messages buffered before dropping
+const DEFAULT_BUFFER_SIZE: usize = 4096;
+
+const CONTROL_ACCEPT: u32 = 0x01;
+const CONTROL_START: u32 = 0x02;
+const CONTROL_STOP: u32 = 0x03;
+const CONTROL_READY: u32 = 0x04;
+const CONTROL_FIELD_CONTENT_TYPE: u32 = 0x01;
+/// Maximum length of control frames, as recommended by fstrm
+const MAX_CONTROL_FRAME_LEN: usize = 512;
+
+/// Handle to a dnstap output
+///
+/// Created by [`DnstapBuilder`], see [`Dnstap::builder`].
+#[derive(Clone)]
+pub struct Dnstap(Arc<DnstapInner>);
+
+struct DnstapInner {
+    identity: Option<Vec<u8>>,
+    version: Option<Vec<u8>>,
+    sample_rate: u64,
+    zones: Vec<Name>,
+    sampled: AtomicU64,
+    sender: mpsc::Sender<Vec<u8>>,
+}
+
+impl Dnstap {
+    /// Returns a builder with the default settings: all messages are logged, and the version is
+    /// that of this library
+    pub fn builder() -> DnstapBuilder {
+        DnstapBuilder::default()
+    }
+
+    /// Whether the messages of an exchange for `name` are to be logged
+    ///
+    /// This applies the zone filter and the sample rate, it should be called once per exchange
+    /// and the decision applied to both the query and the response.
+    pub fn sample(&self, name: &Name) -> bool {
+        if !self.0.zones.is_empty() && !self.0.zones.iter().any(|zone| zone.zone_of(name)) {
+            return false;
+        }
+
+        self.0.sample_rate <= 1
+            || self
+                .0
+                .sampled
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(self.0.sample_rate)
+    }
+
+    /// Queues a message to be written
+    pub fn log(&self, message: &DnstapMessage) {
+        let frame = message.encode(self.0.identity.as_deref(), self.0.version.as_deref());
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.0.sender.try_send(frame) {
+            debug!("dnstap buffer full, dropping message");
+        }
+    }
+}
+
+/// Handles are equal if they write to the same output
+impl PartialEq for Dnstap {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Dnstap {}
+
+impl fmt::Debug for Dnstap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dnstap")
+            .field("sample_rate", &self.0.sample_rate)
+            .field("zones", &self.0.zones)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for [`Dnstap`]
+pub struct DnstapBuilder {
+    identity: Option<Vec<u8>>,
+    version: Option<Vec<u8>>,
+    sample_rate: u64,
+    zones: Vec<Name>,
+    buffer_size: usize,
+}
+
+impl DnstapBuilder {
+    /// Sets the identity of the server, typically its hostname
+    pub fn with_identity(mut self, identity: impl Into<Vec<u8>>) -> Self {
+        self.identity = Some(identity.into());
+        self
+    }
+
+    /// Sets the version of the server
+    pub fn with_version(mut self, version: impl Into<Vec<u8>>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Logs only one in every `rate` exchanges, 0 and 1 log all of them
+    pub fn with_sample_rate(mut self, rate: u32) -> Self {
+        self.sample_rate = u64::from(rate);
+        self
+    }
+
+    /// Logs only exchanges for names in or below these zones, all of them if empty
+    pub fn with_zones(mut self, zones: impl IntoIterator<Item = Name>) -> Self {
+        self.zones = zones.into_iter().collect();
+        self
+    }
+
+    /// Sets the number of messages buffered before new ones are dropped
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Writes a unidirectional Frame Stream to the file at `path`, replacing its contents
+    ///
+    /// Returns the handle and a background future writing the stream, which must be spawned. It
+    /// completes once all handles have been dropped.
+    pub async fn to_file(
+        self,
+        path: &Path,
+    ) -> io::Result<(
+        Dnstap,
+        impl Future<Output = io::Result<()>> + Send + 'static,
+    )> {
+        // a file holds a single stream, readers stop at the first STOP frame
+        let file = tokio::fs::File::create(path).await?;
+        Ok(self.to_writer(file))
+    }
+
+    /// Connects to the UNIX socket at `path` and performs the bidirectional Frame Streams handshake
+    ///
+    /// See [`DnstapBuilder::to_file`] for the returned values.
+    #[cfg(unix)]
+    pub async fn to_unix_socket(
+        self,
+        path: &Path,
+    ) -> io::Result<(
+        Dnstap,
+        impl Future<Output = io::Result<()>> + Send + 'static,
+    )> {
+        let mut stream = tokio::net::UnixStream::connect(path).await?;
+        stream
+            .write_all(&control_frame(CONTROL_READY, true))
+            .await?;
+        if read_control_frame(&mut stream).await? != CONTROL_ACCEPT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "dnstap reader did not accept the content type",
+            ));
+        }
+
+        Ok(self.to_writer(stream))
+    }
+
+    /// Writes a unidirectional Frame Stream to `writer`
+    ///
+    /// See [`DnstapBuilder::to_file`] for the returned values.
+    pub fn to_writer<W: AsyncWrite + Send + Unpin + 'static>(
+        self,
+        writer: W,
+    ) -> (
+        Dnstap,
+        impl Future<Output = io::Result<()>> + Send + 'static,
+    ) {
+        let (sender, receiver) = mpsc::channel(self.buffer_size.max(1));
+        let dnstap = Dnstap(Arc::new(DnstapInner {
+            identity: self.identity,
+            version: self.version,
+            sample_rate: self.sample_rate,
+            zones: self.zones,
+            sampled: AtomicU64::new(0),
+            sender,
+        }));
+
+        (dnstap, write_frames(writer, receiver))
+    }
+}
+
+impl Default for DnstapBuilder {
+    fn default() -> Self {
+        Self {
+            identity: None,
+            version: Some(concat!("hickory-dns ", env!("CARGO_PKG_VERSION")).into()),
+            sample_rate: 1,
+            zones: Vec::new(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+        }
+    }
+}
+
+async fn write_frames<W: AsyncWrite + Unpin>(
+    writer: W,
+    mut receiver: mpsc::Receiver<Vec<u8>>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    writer
+        .write_all(&control_frame(CONTROL_START, true))
+        .await?;
+    writer.flush().await?;
+
+    while let Some(frame) = receiver.recv().await {
+        writer.write_u32(frame.len() as u32).await?;
+        writer.write_all(&frame).await?;
+        if receiver.is_empty() {
+            writer.flush().await?;
+        }
+    }
+
+    writer
+        .write_all(&control_frame(CONTROL_STOP, false))
+        .await?;
+    writer.flush().await
+}
+
+/// Encodes a control frame, including the escape sequence
+fn control_frame(control_type: u32, content_type: bool) -> Vec<u8> {
+    let mut control = control_type.to_be_bytes().to_vec();
+    if content_type {
+        control.extend_from_slice(&CONTROL_FIELD_CONTENT_TYPE.to_be_bytes());
+        control.extend_from_slice(&(CONTENT_TYPE.len() as u32).to_be_bytes());
+        control.extend_from_slice(CONTENT_TYPE);
+    }
+
+    let mut frame = 0u32.to_be_bytes().to_vec();
+    frame.extend_from_slice(&(control.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&control);
+    frame
+}
+
+/// Reads a control frame, returning its type
+#[cfg(unix)]
+async fn read_control_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<u32> {
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+    if reader.read_u32().await? != 0 {
+        return Err(invalid("expected dnstap control frame"));
+    }
+
+    let len = reader.read_u32().await? as usize;
+    if !(4..=MAX_CONTROL_FRAME_LEN).contains(&len) {
+        return Err(invalid("invalid dnstap control frame length"));
+    }
+
+    let mut control = vec![0; len];
+    reader.read_exact(&mut control).await?;
+    Ok(u32::from_be_bytes([
+        control[0], control[1], control[2], control[3],
+    ]))
+}
+
+/// A DNS message to log, `dnstap.Message`
+#[derive(Clone, Debug)]
+pub struct DnstapMessage {
+    /// Type of the message
+    pub message_type: DnstapMessageType,
+    /// Transport the message was exchanged over
+    pub protocol: Protocol,
+    /// Address of the initiator of the query
+    pub query_address: Option<SocketAddr>,
+    /// Address of the responder
+    pub response_address: Option<SocketAddr>,
+    /// Time the query was sent or received
+    pub query_time: Option<SystemTime>,
+    /// Wire format query
+    pub query_message: Option<Vec<u8>>,
+    /// Zone the query was sent to, for resolver messages
+    pub query_zone: Option<Name>,
+    /// Time the response was sent or received
+    pub response_time: Option<SystemTime>,
+    /// Wire format response
+    pub response_message: Option<Vec<u8>>,
+}
+
+impl DnstapMessage {
+    /// Creates a message of the given type without any content
+    pub fn new(message_type: DnstapMessageType, protocol: Protocol) -> Self {
+        Self {
+            message_type,
+            protocol,
+            query_address: None,
+            response_address: None,
+            query_time: None,
+            query_message: None,
+            query_zone: None,
+            response_time: None,
+            response_message: None,
+        }
+    }
+
+    /// Encodes the `dnstap.Dnstap` protobuf message wrapping this message
+    fn encode(&self, identity: Option<&[u8]>, version: Option<&[u8]>) -> Vec<u8> {
+        let mut message = Vec::new();
+        put_varint_field(&mut message, 1, self.message_type.into());
+        if let Some(addr) = self.query_address.or(self.response_address) {
+            // INET = 1, INET6 = 2
+            put_varint_field(&mut message, 2, if addr.is_ipv4() { 1 } else { 2 });
+        }
+        put_varint_field(&mut message, 3, socket_protocol(self.protocol));
+        if let Some(addr) = self.query_address {
+            put_bytes_field(&mut message, 4, &ip_bytes(addr));
+            put_varint_field(&mut message, 6, u64::from(addr.port()));
+        }
+        if let Some(addr) = self.response_address {
+            put_bytes_field(&mut message, 5, &ip_bytes(addr));
+            put_varint_field(&mut message, 7, u64::from(addr.port()));
+        }
+        if let Some(time) = self.query_time {
+            put_time_fields(&mut message, 8, 9, time);
+        }
+        if let Some(query) = &self.query_message {
+            put_bytes_field(&mut message, 10, query);
+        }
+        if let Some(zone) = self
+            .query_zone
+            .as_ref()
+            .and_then(|zone| zone.to_bytes().ok())
+        {
+            put_bytes_field(&mut message, 11, &zone);
+        }
+        if let Some(time) = self.response_time {
+            put_time_fields(&mut message, 12, 13, time);
+        }
+        if let Some(response) = &self.response_message {
+            put_bytes_field(&mut message, 14, response);
+        }
+
+        let mut dnstap = Vec::with_capacity(message.len() + 64);
+        if let Some(identity) = identity {
+            put_bytes_field(&mut dnstap, 1, identity);
+        }
+        if let Some(version) = version {
+            put_bytes_field(&mut dnstap, 2, version);
+        }
+        put_bytes_field(&mut dnstap, 14, &message);
+        // MESSAGE = 1
+        put_varint_field(&mut dnstap, 15, 1);
+        dnstap
+    }
+}
+
+/// Type of a [`DnstapMessage`], `dnstap.Message.Type`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DnstapMessageType {
+    /// Query sent by a recursive resolver to an authoritative server
+    ResolverQuery,
+    /// Response received by a recursive resolver from an authoritative server
+    ResolverResponse,
+    /// Query received by a server from a client
+    ClientQuery,
+    /// Response sent by a server to a client
+    ClientResponse,
+    /// Query sent by a forwarder to an upstream resolver
+    ForwarderQuery,
+    /// Response received by a forwarder from an upstream resolver
+    ForwarderResponse,
+}
+
+impl From<DnstapMessageType> for u64 {
+    fn from(message_type: DnstapMessageType) -> Self {
+        match message_type {
+            DnstapMessageType::ResolverQuery => 3,
+            DnstapMessageType::ResolverResponse => 4,
+            DnstapMessageType::ClientQuery => 5,
+            DnstapMessageType::ClientResponse => 6,
+            DnstapMessageType::ForwarderQuery => 7,
+            DnstapMessageType::ForwarderResponse => 8,
+        }
+    }
+}
+
+/// Role of the exchanges with upstream servers, determining the type of their messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UpstreamRole {
+    /// Iterative queries to authoritative servers
+    Resolver,
+    /// Recursive queries to upstream resolvers
+    Forwarder,
+}
+
+/// dnstap output for the exchanges with upstream servers
+///
+/// Applied to connections with [`DnsExchange::with_dnstap`](crate::xfer::DnsExchange::with_dnstap).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UpstreamTap {
+    dnstap: Dnstap,
+    role: UpstreamRole,
+}
+
+impl UpstreamTap {
+    /// Logs exchanges with upstream servers to `dnstap` as messages of the given role
+    pub fn new(dnstap: Dnstap, role: UpstreamRole) -> Self {
+        Self { dnstap, role }
+    }
+}
+
+/// Tap of a connection to an upstream server
+pub(crate) struct ConnectionTap {
+    tap: UpstreamTap,
+    protocol: Protocol,
+    server: SocketAddr,
+}
+
+impl ConnectionTap {
+    pub(crate) fn new(tap: UpstreamTap, protocol: Protocol, server: SocketAddr) -> Self {
+        Self {
+            tap,
+            protocol,
+            server,
+        }
+    }
+
+    /// Logs the query if sampled, returning the state needed to log its response
+    pub(crate) fn query(self: &Arc<Self>, request: &DnsRequest) -> Option<PendingResponse> {
+        let root = Name::root();
+        let name = request.queries.first().map_or(&root, |query| &query.name);
+        if !self.tap.dnstap.sample(name) {
+            return None;
+        }
+
+        let (query_type, response_type) = match self.tap.role {
+            UpstreamRole::Resolver => (
+                DnstapMessageType::ResolverQuery,
+                DnstapMessageType::ResolverResponse,
+            ),
+            UpstreamRole::Forwarder => (
+                DnstapMessageType::ForwarderQuery,
+                DnstapMessageType::ForwarderResponse,
+            ),
+        };
+
+        let mut message = DnstapMessage::new(query_type, self.protocol);
+        message.response_address = Some(self.server);
+        message.query_time = Some(SystemTime::now());
+        message.query_message = request.to_vec().ok();
+        self.tap.dnstap.log(&message);
+
+        message.message_type = response_type;
+        Some(PendingResponse {
+            dnstap: self.tap.dnstap.clone(),
+            message,
+        })
+    }
+}
+
+/// A logged query awaiting its response
+pub(crate) struct PendingResponse {
+    dnstap: Dnstap,
+    message: DnstapMessage,
+}
+
+impl PendingResponse {
+    pub(crate) fn response(mut self, response: &DnsResponse) {
+        self.message.response_time = Some(SystemTime::now());
+        self.message.response_message = Some(response.as_buffer().to_vec());
+        self.dnstap.log(&self.message);
+    }
+}
+
+/// `dnstap.SocketProtocol` of a transport
+fn socket_protocol(protocol: Protocol) -> u64 {
+    match protocol {
+        Protocol::Udp => 1,
+        Protocol::Tcp => 2,
+        #[cfg(feature = "__tls")]
+        Protocol::Tls => 3,
+        #[cfg(feature = "__https")]
+        Protocol::Https => 4,
+        #[cfg(feature = "__quic")]
+        Protocol::Quic => 7,
+        #[cfg(feature = "__h3")]
+        Protocol::H3 => 4,
+    }
+}
+
+fn ip_bytes(addr: SocketAddr) -> Vec<u8> {
+    match addr {
+        SocketAddr::V4(addr) => addr.ip().octets().to_vec(),
+        SocketAddr::V6(addr) => addr.ip().octets().to_vec(),
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_varint_field(buf: &mut Vec<u8>, field: u32, value: u64) {
+    put_varint(buf, u64::from(field) << 3);
+    put_varint(buf, value);
+}
+
+fn put_bytes_field(buf: &mut Vec<u8>, field: u32, value: &[u8]) {
+    put_varint(buf, u64::from(field) << 3 | 2);
+    put_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+fn put_time_fields(buf: &mut Vec<u8>, sec_field: u32, nsec_field: u32, time: SystemTime) {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    put_varint_field(buf, sec_field, since_epoch.as_secs());
+    // fixed32
+    put_varint(buf, u64::from(nsec_field) << 3 | 5);
+    buf.extend_from_slice(&since_epoch.subsec_nanos().to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut message = DnstapMessage::new(DnstapMessageType::ClientQuery, Protocol::Udp);
+        message.query_address = Some("192.0.2.1:300".parse().unwrap());
+        message.query_time = Some(UNIX_EPOCH + Duration::new(1, 2));
+        message.query_message = Some(vec![0xAB; 2]);
+
+        #[rustfmt::skip]
+        let expected = [
+            0x0a, 3, b'n', b's', b'1',
+            0x72, 26,
+                0x08, 5,
+                0x10, 1,
+                0x18, 1,
+                0x22, 4, 192, 0, 2, 1,
+                0x30, 0xac, 0x02,
+                0x40, 1,
+                0x4d, 2, 0, 0, 0,
+                0x52, 2, 0xab, 0xab,
+            0x78, 1,
+        ];
+        assert_eq!(message.encode(Some(b"ns1"), None), expected);
+    }
+
+    #[tokio::test]
+    async fn test_frame_stream() {
+        let (writer, mut reader) = tokio::io::duplex(1024);
+        let (dnstap, background) = Dnstap::builder().with_version("v").to_writer(writer);
+        let background = tokio::spawn(background);
+
+        let message = DnstapMessage::new(DnstapMessageType::ClientResponse, Protocol::Tcp);
+        dnstap.log(&message);
+        drop(dnstap);
+        background.await.unwrap().unwrap();
+
+        let mut stream = Vec::new();
+        reader.read_to_end(&mut stream).await.unwrap();
+
+        let start = control_frame(CONTROL_START, true);
+        let frame = message.encode(None, Some(b"v"));
+        let stop = control_frame(CONTROL_STOP, false);
+        assert_eq!(stream[..start.len()], start);
+        let stream = &stream[start.len()..];
+        assert_eq!(stream[..4], (frame.len() as u32).to_be_bytes());
+        assert_eq!(stream[4..4 + frame.len()], frame);
+        assert_eq!(stream[4 + frame.len()..], stop);
+    }
+
+    #[tokio::test]
+    async fn test_file_truncated() {
+        let path = std::env::temp_dir().join(format!("dnstap-{}.fstrm", std::process::id()));
+        for _ in 0..2 {
+            let (dnstap, background) = Dnstap::builder().to_file(&path).await.unwrap();
+            drop(dnstap);
+            background.await.unwrap();
+        }
+
+        let stream = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut expected = control_frame(CONTROL_START, true);
+        expected.extend(control_frame(CONTROL_STOP, false));
+        assert_eq!(stream, expected);
+    }
+
+    #[test]
+    fn test_sample() {
+        let (dnstap, _) = Dnstap::builder()
+            .with_sample_rate(2)
+            .with_zones([Name::from_ascii("example.com.").unwrap()])
+            .to_writer(tokio::io::sink());
+
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        assert!(dnstap.sample(&name));
+        assert!(!dnstap.sample(&name));
+        assert!(dnstap.sample(&name));
+        assert!(!dnstap.sample(&Name::from_ascii("example.net.").unwrap()));
+    }
+}
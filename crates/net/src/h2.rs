@@ -30,7 +30,8 @@ use tracing::{debug, warn};
 
 use crate::error::NetError;
 use crate::http::{RequestContext, SetHeaders, Version};
-use crate::proto::op::{DnsRequest, DnsResponse};
+use crate::proto::op::{DnsRequest, DnsResponse, QUERY_PADDING_BLOCK_LEN};
+use crate::proxy::ProxyConfig;
 use crate::runtime::iocompat::AsyncIoStdAsTokio;
 use crate::runtime::{DnsTcpStream, RuntimeProvider, Spawn};
 use crate::xfer::{CONNECT_TIMEOUT, DnsExchange, DnsRequestSender, DnsResponseStream};
@@ -56,6 +57,7 @@ impl HttpsClientStream {
             bind_addr: None,
             set_headers: None,
             connect_timeout: CONNECT_TIMEOUT,
+            proxy: None,
         }
     }
 }
@@ -119,6 +121,10 @@ impl DnsRequestSender for HttpsClientStream {
 
         // per the RFC, a zero id allows for the HTTP packet to be cached better
         request.metadata.id = 0;
+        // RFC 8467: pad queries to hide their length on encrypted transports
+        if let Some(edns) = &mut request.edns {
+            edns.set_padding_block(Some(QUERY_PADDING_BLOCK_LEN));
+        }
 
         let bytes = match request.to_vec() {
             Ok(bytes) => bytes,
@@ -169,6 +175,7 @@ pub struct HttpsClientStreamBuilder<P> {
     bind_addr: Option<SocketAddr>,
     set_headers: Option<Arc<dyn SetHeaders>>,
     connect_timeout: Duration,
+    proxy: Option<ProxyConfig>,
 }
 
 impl<P: RuntimeProvider> HttpsClientStreamBuilder<P> {
@@ -190,6 +197,12 @@ impl<P: RuntimeProvider> HttpsClientStreamBuilder<P> {
         self
     }
 
+    /// Connect to the name server through a proxy
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
     /// Creates a new [`DnsExchange`] wrapping the [`HttpsClientStream`] from this builder
     pub async fn exchange(
         self,
@@ -217,8 +230,18 @@ impl<P: RuntimeProvider> HttpsClientStreamBuilder<P> {
         server_name: Arc<str>,
         path: Arc<str>,
     ) -> impl Future<Output = Result<HttpsClientStream, NetError>> + Send + 'static {
+        let tcp = match self.proxy {
+            Some(proxy) => {
+                let tcp = self
+                    .provider
+                    .connect_tcp(proxy.addr(), self.bind_addr, None);
+                Box::pin(async move { proxy.connect(tcp.await?, name_server).await })
+            }
+            None => self.provider.connect_tcp(name_server, self.bind_addr, None),
+        };
+
         connect(
-            self.provider.connect_tcp(name_server, self.bind_addr, None),
+            tcp,
             self.client_config,
             name_server,
             server_name,
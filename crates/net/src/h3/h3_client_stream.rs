@@ -27,7 +27,7 @@ use tracing::{debug, warn};
 use crate::error::NetError;
 use crate::http::{RequestContext, SetHeaders, Version};
 use crate::proto::ProtoError;
-use crate::proto::op::{DnsRequest, DnsResponse};
+use crate::proto::op::{DnsRequest, DnsResponse, QUERY_PADDING_BLOCK_LEN};
 use crate::quic::connect_quic;
 use crate::runtime::{RuntimeProvider, Spawn};
 use crate::tls::client_config;
@@ -228,6 +228,10 @@ impl DnsRequestSender for H3ClientStream {
 
         // per the RFC, a zero id allows for the HTTP packet to be cached better
         request.metadata.id = 0;
+        // RFC 8467: pad queries to hide their length on encrypted transports
+        if let Some(edns) = &mut request.edns {
+            edns.set_padding_block(Some(QUERY_PADDING_BLOCK_LEN));
+        }
 
         let bytes = match request.to_vec() {
             Ok(bytes) => bytes,
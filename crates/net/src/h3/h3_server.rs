@@ -17,11 +17,12 @@ use h3_quinn::{BidiStream, Endpoint};
 use http::Request;
 use quinn::crypto::rustls::QuicServerConfig;
 use quinn::{EndpointConfig, ServerConfig};
+use rustls::pki_types::CertificateDer;
 use rustls::server::ResolvesServerCert;
 use rustls::server::ServerConfig as TlsServerConfig;
 use rustls::version::TLS13;
 
-use crate::{error::NetError, tls::default_provider, udp::UdpSocket};
+use crate::{error::NetError, quic::peer_certificates, tls::default_provider, udp::UdpSocket};
 
 use super::ALPN_H3;
 
@@ -94,6 +95,7 @@ impl H3Server {
         let connection = connecting.await?;
         Ok(Some((
             H3Connection {
+                peer_certificates: peer_certificates(&connection),
                 connection: Connection::new(h3_quinn::Connection::new(connection))
                     .await
                     .map_err(|e| NetError::from(format!("h3 connection failed: {e}")))?,
@@ -114,9 +116,15 @@ impl H3Server {
 /// A HTTP/3 connection.
 pub struct H3Connection {
     connection: Connection<h3_quinn::Connection, Bytes>,
+    peer_certificates: Option<Vec<CertificateDer<'static>>>,
 }
 
 impl H3Connection {
+    /// Certificate chain the client authenticated with, if any
+    pub fn peer_certificates(&self) -> Option<&[CertificateDer<'static>]> {
+        self.peer_certificates.as_deref()
+    }
+
     /// Accept the next request from the client
     pub async fn accept(
         &mut self,
@@ -16,6 +16,9 @@ use tracing::debug;
 
 use crate::error::NetError;
 
+mod pool;
+pub use pool::{HttpPool, HttpPoolConfig};
+
 pub(crate) struct RequestContext {
     pub(crate) version: Version,
     pub(crate) server_name: Arc<str>,
@@ -0,0 +1,490 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Pool of HTTP connections to a name server
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+use futures_channel::oneshot;
+use futures_util::future::BoxFuture;
+use futures_util::stream::{Stream, StreamExt};
+use futures_util::task::noop_waker_ref;
+use tracing::debug;
+
+use crate::error::NetError;
+use crate::proto::op::DnsRequest;
+use crate::xfer::{DnsRequestSender, DnsResponseStream, FirstAnswer};
+
+/// Configuration of an [`HttpPool`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HttpPoolConfig {
+    /// Maximum number of connections to the name server, defaults to 1
+    pub max_connections: usize,
+    /// Maximum number of concurrent requests, i.e. HTTP streams, on each connection, at least 1,
+    /// defaults to 100
+    pub max_concurrent_streams: usize,
+    /// Time after which a connection without requests in flight is closed, defaults to 60 seconds
+    pub idle_timeout: Duration,
+    /// Time to wait before connecting again after a failed connection, doubled after each
+    /// consecutive failure, defaults to 1 second
+    pub reconnect_backoff: Duration,
+    /// Maximum time to wait before connecting again after failed connections, defaults to 60
+    /// seconds
+    pub max_reconnect_backoff: Duration,
+}
+
+impl Default for HttpPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 1,
+            max_concurrent_streams: 100,
+            idle_timeout: Duration::from_secs(60),
+            reconnect_backoff: Duration::from_secs(1),
+            max_reconnect_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// A pool of HTTP connections to a name server, for DNS over HTTP/2 and HTTP/3
+///
+/// Requests are sent on the open connection with the fewest requests in flight, below the
+/// maximum number of concurrent streams. Once all the connections reach that limit, another
+/// connection is opened, up to the maximum number of connections, otherwise requests wait for a
+/// stream to be available. Closed and idle connections are removed from the pool, and no
+/// connection is attempted during the backoff following a failed one.
+#[must_use = "futures do nothing unless polled"]
+pub struct HttpPool<S> {
+    inner: Arc<Inner<S>>,
+    is_shutdown: bool,
+}
+
+impl<S: DnsRequestSender + Clone> HttpPool<S> {
+    /// Makes a pool of the connections opened by `connect`, and waits for the first one
+    pub async fn connect(
+        config: HttpPoolConfig,
+        connect: impl Fn() -> BoxFuture<'static, Result<S, NetError>> + Send + Sync + 'static,
+    ) -> Result<Self, NetError> {
+        let pool = Self::new(config, connect);
+        pool.inner.clone().checkout().await?;
+        Ok(pool)
+    }
+
+    /// Makes a pool of the connections opened by `connect`, the first one is opened on the
+    /// first request
+    pub fn new(
+        config: HttpPoolConfig,
+        connect: impl Fn() -> BoxFuture<'static, Result<S, NetError>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                connect: Box::new(connect),
+                state: Mutex::new(State {
+                    connections: Vec::new(),
+                    connecting: 0,
+                    next_id: 0,
+                    failures: 0,
+                    retry_at: None,
+                    waiters: Vec::new(),
+                }),
+            }),
+            is_shutdown: false,
+        }
+    }
+
+    /// Returns the number of open connections
+    pub fn connections(&self) -> usize {
+        self.inner.lock().connections.len()
+    }
+}
+
+impl<S: DnsRequestSender + Clone> DnsRequestSender for HttpPool<S> {
+    fn send_message(&mut self, request: DnsRequest) -> DnsResponseStream {
+        if self.is_shutdown {
+            panic!("can not send messages after stream is shutdown")
+        }
+
+        let inner = self.inner.clone();
+        DnsResponseStream::from(Box::pin(async move {
+            let mut lease = inner.checkout().await?;
+            lease.stream.send_message(request).first_answer().await
+        }))
+    }
+
+    fn shutdown(&mut self) {
+        self.is_shutdown = true;
+    }
+
+    fn is_shutdown(&self) -> bool {
+        self.is_shutdown
+    }
+}
+
+impl<S> Stream for HttpPool<S> {
+    type Item = Result<(), NetError>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // closed connections are replaced, the pool is ready until it is shutdown
+        match self.is_shutdown {
+            true => Poll::Ready(None),
+            false => Poll::Ready(Some(Ok(()))),
+        }
+    }
+}
+
+type Connector<S> = Box<dyn Fn() -> BoxFuture<'static, Result<S, NetError>> + Send + Sync>;
+
+struct Inner<S> {
+    config: HttpPoolConfig,
+    connect: Connector<S>,
+    state: Mutex<State<S>>,
+}
+
+impl<S: DnsRequestSender + Clone> Inner<S> {
+    /// Reserves a stream on a connection, opening one if needed
+    async fn checkout(self: Arc<Self>) -> Result<Lease<S>, NetError> {
+        loop {
+            let waiter = {
+                let mut state = self.lock();
+                let now = Instant::now();
+                state.remove_unusable(now, self.config.idle_timeout);
+
+                if let Some(connection) = state
+                    .connections
+                    .iter_mut()
+                    .filter(|connection| {
+                        connection.in_flight < self.config.max_concurrent_streams.max(1)
+                    })
+                    .min_by_key(|connection| connection.in_flight)
+                {
+                    connection.in_flight += 1;
+                    return Ok(Lease {
+                        id: connection.id,
+                        stream: connection.stream.clone(),
+                        pool: self.clone(),
+                    });
+                }
+
+                let backing_off = state.retry_at.is_some_and(|retry_at| now < retry_at);
+                let open = state.connections.len() + state.connecting;
+                if open < self.config.max_connections.max(1) && !backing_off {
+                    state.connecting += 1;
+                    None
+                } else if open == 0 {
+                    return Err(NetError::from(
+                        "waiting before connecting again to the name server",
+                    ));
+                } else {
+                    let (sender, receiver) = oneshot::channel();
+                    state.waiters.push(sender);
+                    Some(receiver)
+                }
+            };
+
+            match waiter {
+                // woken up when a stream or a connection is released, or a connection is opened
+                Some(receiver) => {
+                    let _ = receiver.await;
+                }
+                None => {
+                    if let Some(lease) = self.clone().open().await? {
+                        return Ok(lease);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Opens a connection and reserves a stream on it, failing only when there is no other
+    /// connection
+    async fn open(self: Arc<Self>) -> Result<Option<Lease<S>>, NetError> {
+        let _connecting = Connecting(self.clone());
+        let result = (self.connect)().await;
+
+        let mut state = self.lock();
+        let result = match result {
+            Ok(stream) => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.failures = 0;
+                state.retry_at = None;
+                state.connections.push(Connection {
+                    id,
+                    stream: stream.clone(),
+                    in_flight: 1,
+                    last_used: Instant::now(),
+                });
+                debug!(
+                    connections = state.connections.len(),
+                    "opened HTTP connection"
+                );
+                Ok(Some(Lease {
+                    id,
+                    stream,
+                    pool: self.clone(),
+                }))
+            }
+            Err(e) => {
+                let backoff = self
+                    .config
+                    .reconnect_backoff
+                    .saturating_mul(2_u32.saturating_pow(state.failures))
+                    .min(self.config.max_reconnect_backoff);
+                state.failures = state.failures.saturating_add(1);
+                state.retry_at = Some(Instant::now() + backoff);
+                debug!(?backoff, "HTTP connection failed: {e}");
+                match state.connections.is_empty() {
+                    true => Err(e),
+                    false => Ok(None),
+                }
+            }
+        };
+
+        drop(state);
+        result
+    }
+}
+
+impl<S> Inner<S> {
+    fn lock(&self) -> MutexGuard<'_, State<S>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct State<S> {
+    connections: Vec<Connection<S>>,
+    /// Number of connections being opened
+    connecting: usize,
+    next_id: u64,
+    /// Number of consecutive failed connections
+    failures: u32,
+    /// Time before which no connection is attempted
+    retry_at: Option<Instant>,
+    waiters: Vec<oneshot::Sender<()>>,
+}
+
+impl<S: DnsRequestSender> State<S> {
+    /// Removes the closed connections, and the ones idle for `idle_timeout`
+    fn remove_unusable(&mut self, now: Instant, idle_timeout: Duration) {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        self.connections.retain_mut(|connection| {
+            if connection.in_flight == 0 && now.duration_since(connection.last_used) >= idle_timeout
+            {
+                debug!("closing idle HTTP connection");
+                return false;
+            }
+
+            match connection.stream.poll_next_unpin(&mut cx) {
+                Poll::Ready(Some(Err(e))) => {
+                    debug!("removing failed HTTP connection: {e}");
+                    false
+                }
+                Poll::Ready(None) => false,
+                Poll::Ready(Some(Ok(()))) | Poll::Pending => true,
+            }
+        });
+    }
+}
+
+impl<S> State<S> {
+    fn wake(&mut self) {
+        for waiter in self.waiters.drain(..) {
+            let _ = waiter.send(());
+        }
+    }
+}
+
+struct Connection<S> {
+    id: u64,
+    stream: S,
+    in_flight: usize,
+    last_used: Instant,
+}
+
+/// A stream reserved on a connection of the pool
+struct Lease<S> {
+    id: u64,
+    stream: S,
+    pool: Arc<Inner<S>>,
+}
+
+impl<S> Drop for Lease<S> {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock();
+        if let Some(connection) = state
+            .connections
+            .iter_mut()
+            .find(|connection| connection.id == self.id)
+        {
+            connection.in_flight -= 1;
+            connection.last_used = Instant::now();
+        }
+        state.wake();
+    }
+}
+
+/// A connection being opened
+struct Connecting<S>(Arc<Inner<S>>);
+
+impl<S> Drop for Connecting<S> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.connecting -= 1;
+        state.wake();
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::proto::op::{DnsResponse, Message, OpCode, Query};
+    use crate::proto::rr::{Name, RecordType};
+
+    /// Stream answering after 10ms, and counting the concurrent requests
+    #[derive(Clone)]
+    struct TestStream {
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl TestStream {
+        fn new() -> Self {
+            Self {
+                in_flight: Arc::new(AtomicUsize::new(0)),
+                max_in_flight: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+    }
+
+    impl DnsRequestSender for TestStream {
+        fn send_message(&mut self, request: DnsRequest) -> DnsResponseStream {
+            let (in_flight, max_in_flight) = (self.in_flight.clone(), self.max_in_flight.clone());
+            let count = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(count, Ordering::SeqCst);
+
+            DnsResponseStream::from(Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                let mut message = Message::response(request.metadata.id, OpCode::Query);
+                message.add_queries(request.queries.clone());
+                Ok(DnsResponse::from_message(message)?)
+            }))
+        }
+
+        fn shutdown(&mut self) {}
+
+        fn is_shutdown(&self) -> bool {
+            false
+        }
+    }
+
+    impl Stream for TestStream {
+        type Item = Result<(), NetError>;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(Some(Ok(())))
+        }
+    }
+
+    fn request() -> DnsRequest {
+        let query = Query::new(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+        let mut message = Message::query();
+        message.add_query(query);
+        DnsRequest::from(message)
+    }
+
+    fn pool(
+        config: HttpPoolConfig,
+        result: impl Fn() -> Result<TestStream, NetError> + Send + Sync + 'static,
+    ) -> (HttpPool<TestStream>, Arc<AtomicUsize>) {
+        let connects = Arc::new(AtomicUsize::new(0));
+        let counter = connects.clone();
+        let pool = HttpPool::new(config, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let result = result();
+            Box::pin(async move { result })
+        });
+        (pool, connects)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_streams() {
+        let stream = TestStream::new();
+        let max_in_flight = stream.max_in_flight.clone();
+        let config = HttpPoolConfig {
+            max_connections: 2,
+            max_concurrent_streams: 2,
+            ..HttpPoolConfig::default()
+        };
+        let (mut pool, connects) = pool(config, move || Ok(stream.clone()));
+
+        let responses = join_all((0..6).map(|_| pool.send_message(request()).first_answer())).await;
+        assert!(responses.iter().all(Result::is_ok));
+
+        // two connections of two streams each
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.connections(), 2);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_zero_concurrent_streams() {
+        let stream = TestStream::new();
+        let config = HttpPoolConfig {
+            max_concurrent_streams: 0,
+            ..HttpPoolConfig::default()
+        };
+        let (mut pool, connects) = pool(config, move || Ok(stream.clone()));
+
+        // treated as a single stream per connection
+        pool.send_message(request()).first_answer().await.unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout() {
+        let stream = TestStream::new();
+        let config = HttpPoolConfig {
+            idle_timeout: Duration::ZERO,
+            ..HttpPoolConfig::default()
+        };
+        let (mut pool, connects) = pool(config, move || Ok(stream.clone()));
+
+        pool.send_message(request()).first_answer().await.unwrap();
+        pool.send_message(request()).first_answer().await.unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_backoff() {
+        let config = HttpPoolConfig {
+            reconnect_backoff: Duration::from_millis(50),
+            ..HttpPoolConfig::default()
+        };
+        let (mut pool, connects) = pool(config, || Err(NetError::from("connection refused")));
+
+        assert!(pool.send_message(request()).first_answer().await.is_err());
+        // no connection is attempted during the backoff
+        assert!(pool.send_message(request()).first_answer().await.is_err());
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(pool.send_message(request()).first_answer().await.is_err());
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+    }
+}
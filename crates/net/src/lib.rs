@@ -9,6 +9,8 @@ pub mod client;
 
 #[cfg(feature = "__dnssec")]
 pub mod dnssec;
+#[cfg(feature = "dnstap")]
+pub mod dnstap;
 
 mod error;
 pub use error::{DnsError, ForwardNSData, NetError, NoRecords};
@@ -21,6 +23,7 @@ pub mod h3;
 pub mod http;
 #[cfg(feature = "mdns")]
 pub mod multicast;
+pub mod proxy;
 #[cfg(all(feature = "__quic", feature = "tokio"))]
 pub mod quic;
 pub mod runtime;
@@ -0,0 +1,461 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS-Based Service Discovery, see [RFC 6763](https://www.rfc-editor.org/rfc/rfc6763)
+
+use core::net::IpAddr;
+use core::time::Duration;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::proto::ProtoError;
+use crate::proto::op::{Message, MessageType, OpCode, Query};
+use crate::proto::rr::rdata::{PTR, SRV, TXT};
+use crate::proto::rr::{Name, RData, Record, RecordType};
+
+/// Name enumerating the service types advertised on the link, see
+/// [RFC 6763 section 9](https://www.rfc-editor.org/rfc/rfc6763#section-9)
+pub(super) const SERVICE_TYPES: &str = "_services._dns-sd._udp.local.";
+
+/// Interval between the first two queries of a browser, doubling up to [`MAX_QUERY_INTERVAL`]
+const FIRST_QUERY_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest interval between queries of a browser, see
+/// [RFC 6762 section 5.2](https://www.rfc-editor.org/rfc/rfc6762#section-5.2)
+const MAX_QUERY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// A service instance to advertise, see
+/// [RFC 6763 section 4](https://www.rfc-editor.org/rfc/rfc6763#section-4)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceInfo {
+    instance: String,
+    service_type: Name,
+    port: u16,
+    txt: Vec<String>,
+}
+
+impl ServiceInfo {
+    /// Instance named `instance` of `service_type`, listening on `port` of the responder host
+    ///
+    /// The instance name is any user friendly UTF-8 text up to 63 bytes, e.g. `Living Room
+    /// Printer`, the service type is the service and protocol labels in the `local.` domain, e.g.
+    /// `_ipp._tcp.local.`.
+    pub fn new(instance: impl Into<String>, service_type: Name, port: u16) -> Self {
+        Self {
+            instance: instance.into(),
+            service_type,
+            port,
+            txt: Vec::new(),
+        }
+    }
+
+    /// Sets the `key=value` strings of the TXT record, see
+    /// [RFC 6763 section 6](https://www.rfc-editor.org/rfc/rfc6763#section-6)
+    pub fn with_txt(mut self, txt: Vec<String>) -> Self {
+        self.txt = txt;
+        self
+    }
+
+    /// User friendly name of the instance
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    /// Type of the service, e.g. `_ipp._tcp.local.`
+    pub fn service_type(&self) -> &Name {
+        &self.service_type
+    }
+
+    /// Port the service is listening on
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// `key=value` strings of the TXT record
+    pub fn txt(&self) -> &[String] {
+        &self.txt
+    }
+
+    /// Full name of the instance, `<instance>.<service type>`
+    ///
+    /// The instance name is a single label which may contain dots and spaces.
+    pub fn instance_name(&self) -> Result<Name, ProtoError> {
+        self.service_type.prepend_label(self.instance.as_bytes())
+    }
+
+    /// The same service with the instance name suffixed with ` (<attempt>)`, used to resolve a
+    /// name conflict
+    pub(super) fn renamed(&self, attempt: u32) -> Self {
+        Self {
+            instance: format!("{} ({attempt})", self.instance),
+            ..self.clone()
+        }
+    }
+
+    /// Records advertising the service instance `name`, hosted on `host`
+    ///
+    /// The PTR records are shared, the SRV and TXT records unique to the instance and marked with
+    /// the cache-flush bit.
+    pub(super) fn records(
+        &self,
+        name: &Name,
+        host: &Name,
+        host_ttl: u32,
+        ttl: u32,
+    ) -> Result<Vec<Record>, ProtoError> {
+        let mut srv = Record::from_rdata(
+            name.clone(),
+            host_ttl,
+            RData::SRV(SRV::new(0, 0, self.port, host.clone())),
+        );
+        srv.mdns_cache_flush = true;
+
+        // an empty TXT record is a single empty string, see RFC 6763 section 6.1
+        let txt = match self.txt.is_empty() {
+            true => TXT::from_bytes(vec![&[]]),
+            false => TXT::new(self.txt.clone()),
+        };
+        let mut txt = Record::from_rdata(name.clone(), ttl, RData::TXT(txt));
+        txt.mdns_cache_flush = true;
+
+        Ok(vec![
+            Record::from_rdata(
+                self.service_type.clone(),
+                ttl,
+                RData::PTR(PTR(name.clone())),
+            ),
+            Record::from_rdata(
+                Name::from_ascii(SERVICE_TYPES)?,
+                ttl,
+                RData::PTR(PTR(self.service_type.clone())),
+            ),
+            srv,
+            txt,
+        ])
+    }
+}
+
+/// Changes of the service instances found while browsing
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ServiceEvent {
+    /// An instance was found, or its host, port, addresses or TXT data changed
+    Resolved(ResolvedService),
+    /// An instance said goodbye, or its records expired
+    Removed(Name),
+}
+
+/// A service instance resolved to the addresses of its host
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResolvedService {
+    /// Full name of the instance, `<instance>.<service type>`
+    pub name: Name,
+    /// Host name of the instance, the target of its SRV record
+    pub host: Name,
+    /// Port the service is listening on
+    pub port: u16,
+    /// Addresses of the host
+    pub addresses: Vec<IpAddr>,
+    /// `key=value` strings of the TXT record
+    pub txt: Vec<String>,
+}
+
+/// Browses for the instances of a service type, see
+/// [RFC 6763 section 4](https://www.rfc-editor.org/rfc/rfc6763#section-4)
+///
+/// Queries are sent with an exponential backoff and carry the known instances for known-answer
+/// suppression, instances found without their SRV, TXT or address records are resolved with
+/// additional queries.
+pub(super) struct Browser {
+    service_type: Name,
+    instances: HashMap<Name, Instance>,
+    hosts: HashMap<Name, Vec<IpAddr>>,
+    interval: Duration,
+    next_query: Instant,
+}
+
+impl Browser {
+    pub(super) fn new(service_type: Name, now: Instant) -> Self {
+        Self {
+            service_type,
+            instances: HashMap::new(),
+            hosts: HashMap::new(),
+            interval: FIRST_QUERY_INTERVAL,
+            next_query: now,
+        }
+    }
+
+    /// Time of the next query or expiry of an instance
+    pub(super) fn next_deadline(&self) -> Instant {
+        self.instances
+            .values()
+            .map(|instance| instance.expires)
+            .fold(self.next_query, Instant::min)
+    }
+
+    /// Removes the expired instances and builds the query due at `now`, if any
+    pub(super) fn poll(&mut self, now: Instant) -> (Option<Message>, Vec<ServiceEvent>) {
+        let mut events = Vec::new();
+        self.instances.retain(|name, instance| {
+            let expired = instance.expires <= now;
+            if expired {
+                events.push(ServiceEvent::Removed(name.clone()));
+            }
+            !expired
+        });
+
+        if now < self.next_query {
+            return (None, events);
+        }
+
+        let mut message = Message::new(0, MessageType::Query, OpCode::Query);
+        message.add_query(Query::new(self.service_type.clone(), RecordType::PTR));
+        for (name, instance) in &self.instances {
+            // known answers are only included while more than half of their TTL remains
+            let remaining = instance.expires.saturating_duration_since(now).as_secs();
+            if remaining > u64::from(instance.ttl / 2) {
+                message.add_answer(Record::from_rdata(
+                    self.service_type.clone(),
+                    remaining as u32,
+                    RData::PTR(PTR(name.clone())),
+                ));
+            }
+        }
+        message.add_queries(self.unresolved());
+
+        self.next_query = now + self.interval;
+        self.interval = (self.interval * 2).min(MAX_QUERY_INTERVAL);
+        (Some(message), events)
+    }
+
+    /// Updates the instances from the records of a response
+    pub(super) fn handle(&mut self, message: &Message, now: Instant) -> Vec<ServiceEvent> {
+        if message.metadata.message_type != MessageType::Response {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        let mut found = false;
+        for record in message.all_sections() {
+            match &record.data {
+                RData::PTR(PTR(name)) if record.name == self.service_type => {
+                    if record.ttl == 0 {
+                        if self.instances.remove(name).is_some() {
+                            events.push(ServiceEvent::Removed(name.clone()));
+                        }
+                        continue;
+                    }
+
+                    let expires = now + Duration::from_secs(u64::from(record.ttl));
+                    let instance = self.instances.entry(name.clone()).or_insert_with(|| {
+                        found = true;
+                        Instance::new(record.ttl, expires)
+                    });
+                    instance.ttl = record.ttl;
+                    instance.expires = expires;
+                }
+                RData::SRV(srv) => {
+                    if let Some(instance) = self.instances.get_mut(&record.name) {
+                        instance.srv = Some(srv.clone());
+                    }
+                }
+                RData::TXT(txt) => {
+                    if let Some(instance) = self.instances.get_mut(&record.name) {
+                        instance.txt = Some(
+                            txt.txt_data
+                                .iter()
+                                .filter(|data| !data.is_empty())
+                                .map(|data| String::from_utf8_lossy(data).into_owned())
+                                .collect(),
+                        );
+                    }
+                }
+                RData::A(_) | RData::AAAA(_) => {
+                    let Some(ip) = record.data.ip_addr() else {
+                        continue;
+                    };
+
+                    let addresses = self.hosts.entry(record.name.clone()).or_default();
+                    if record.mdns_cache_flush {
+                        // the cache-flush bit marks the complete set of addresses of the host,
+                        // RFC 6762 section 10.2 allows to keep the previous ones for a second,
+                        // they are replaced right away here
+                        addresses.retain(|addr| addr.is_ipv4() != ip.is_ipv4());
+                    }
+                    if !addresses.contains(&ip) {
+                        addresses.push(ip);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (name, instance) in &mut self.instances {
+            let Some(resolved) = instance.resolve(name, &self.hosts) else {
+                continue;
+            };
+            if instance.reported.as_ref() != Some(&resolved) {
+                instance.reported = Some(resolved.clone());
+                events.push(ServiceEvent::Resolved(resolved));
+            }
+        }
+
+        // resolve new instances right away instead of waiting for the next query
+        if found && self.unresolved().next().is_some() {
+            self.next_query = self.next_query.min(now);
+        }
+
+        events
+    }
+
+    /// Queries for the missing records of the instances found
+    fn unresolved(&self) -> impl Iterator<Item = Query> + '_ {
+        self.instances.iter().flat_map(|(name, instance)| {
+            let mut queries = Vec::new();
+            match &instance.srv {
+                None => queries.push(Query::new(name.clone(), RecordType::SRV)),
+                Some(srv) if !self.hosts.contains_key(&srv.target) => {
+                    queries.push(Query::new(srv.target.clone(), RecordType::A));
+                    queries.push(Query::new(srv.target.clone(), RecordType::AAAA));
+                }
+                Some(_) => {}
+            }
+            if instance.txt.is_none() {
+                queries.push(Query::new(name.clone(), RecordType::TXT));
+            }
+            queries
+        })
+    }
+}
+
+/// An instance found by a [`Browser`]
+struct Instance {
+    ttl: u32,
+    expires: Instant,
+    srv: Option<SRV>,
+    txt: Option<Vec<String>>,
+    reported: Option<ResolvedService>,
+}
+
+impl Instance {
+    fn new(ttl: u32, expires: Instant) -> Self {
+        Self {
+            ttl,
+            expires,
+            srv: None,
+            txt: None,
+            reported: None,
+        }
+    }
+
+    fn resolve(&self, name: &Name, hosts: &HashMap<Name, Vec<IpAddr>>) -> Option<ResolvedService> {
+        let srv = self.srv.as_ref()?;
+        Some(ResolvedService {
+            name: name.clone(),
+            host: srv.target.clone(),
+            port: srv.port,
+            addresses: hosts.get(&srv.target)?.clone(),
+            txt: self.txt.clone().unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::*;
+
+    fn service() -> ServiceInfo {
+        ServiceInfo::new(
+            "Living Room.Printer",
+            Name::from_ascii("_ipp._tcp.local.").unwrap(),
+            631,
+        )
+        .with_txt(vec!["rp=ipp/print".to_string()])
+    }
+
+    #[test]
+    fn test_instance_name() {
+        let service = service();
+        let name = service.instance_name().unwrap();
+        assert_eq!(name.num_labels(), 4);
+        assert!(Name::from_ascii("_ipp._tcp.local.").unwrap().zone_of(&name));
+
+        let renamed = service.renamed(2);
+        assert_eq!(renamed.instance(), "Living Room.Printer (2)");
+        assert_ne!(renamed.instance_name().unwrap(), name);
+    }
+
+    #[test]
+    fn test_browse() {
+        let service = service();
+        let name = service.instance_name().unwrap();
+        let host = Name::from_ascii("printer.local.").unwrap();
+        let now = Instant::now();
+        let mut browser = Browser::new(service.service_type().clone(), now);
+
+        let (query, events) = browser.poll(now);
+        let query = query.unwrap();
+        assert!(events.is_empty());
+        assert_eq!(query.queries.len(), 1);
+        assert_eq!(query.queries[0].query_type, RecordType::PTR);
+        assert!(browser.poll(now).0.is_none());
+
+        // a PTR alone is not resolved, a query for the missing records is due right away
+        let mut response = Message::response(0, OpCode::Query);
+        let records = service.records(&name, &host, 120, 4500).unwrap();
+        response.add_answer(records[0].clone());
+        assert!(browser.handle(&response, now).is_empty());
+        let (query, _) = browser.poll(now);
+        let query = query.unwrap();
+        assert_eq!(query.answers.len(), 1);
+        assert!(
+            query
+                .queries
+                .iter()
+                .any(|query| query.name == name && query.query_type == RecordType::SRV)
+        );
+
+        let mut response = Message::response(0, OpCode::Query);
+        response.add_answers(records[2..].iter().cloned());
+        response.add_additional(Record::from_rdata(
+            host.clone(),
+            120,
+            RData::from(Ipv4Addr::new(192, 0, 2, 1)),
+        ));
+        let events = browser.handle(&response, now);
+        assert_eq!(
+            events,
+            [ServiceEvent::Resolved(ResolvedService {
+                name: name.clone(),
+                host: host.clone(),
+                port: 631,
+                addresses: vec![Ipv4Addr::new(192, 0, 2, 1).into()],
+                txt: vec!["rp=ipp/print".to_string()],
+            })]
+        );
+        assert!(browser.handle(&response, now).is_empty());
+
+        // goodbye packet
+        let mut goodbye = Message::response(0, OpCode::Query);
+        let mut ptr = records[0].clone();
+        ptr.ttl = 0;
+        goodbye.add_answer(ptr);
+        assert_eq!(
+            browser.handle(&goodbye, now),
+            [ServiceEvent::Removed(name.clone())]
+        );
+
+        // expiry
+        browser.handle(&response, now);
+        response.add_answer(records[0].clone());
+        browser.handle(&response, now);
+        let (_, events) = browser.poll(now + Duration::from_secs(4500));
+        assert_eq!(events, [ServiceEvent::Removed(name)]);
+    }
+}
@@ -0,0 +1,1168 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Multicast DNS responder, see [RFC 6762](https://www.rfc-editor.org/rfc/rfc6762)
+
+use core::cmp::Ordering;
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
+use core::time::Duration;
+use std::time::Instant;
+
+use futures_channel::{mpsc, oneshot};
+use futures_util::future::BoxFuture;
+use futures_util::stream::{Stream, StreamExt};
+use tracing::{debug, info, warn};
+
+use super::dns_sd::{Browser, ServiceEvent, ServiceInfo};
+use super::mdns_stream::{MDNS_IPV4, MDNS_PORT};
+use super::{MdnsQueryType, MdnsStream};
+use crate::BufDnsStreamHandle;
+use crate::error::NetError;
+use crate::proto::ProtoError;
+use crate::proto::op::{Message, MessageType, OpCode, Query, SerialMessage};
+use crate::proto::rr::{DNSClass, Name, RData, Record, RecordType};
+use crate::xfer::dns_handle::DnsStreamHandle;
+
+/// TTL of the records containing a host name, see
+/// [RFC 6762 section 10](https://www.rfc-editor.org/rfc/rfc6762#section-10)
+const HOST_TTL: u32 = 120;
+/// TTL of the other records
+const OTHER_TTL: u32 = 75 * 60;
+/// Maximum TTL of the records in responses to legacy unicast queries, see
+/// [RFC 6762 section 6.7](https://www.rfc-editor.org/rfc/rfc6762#section-6.7)
+const LEGACY_TTL: u32 = 10;
+
+/// Number of probes sent before claiming a name, see
+/// [RFC 6762 section 8.1](https://www.rfc-editor.org/rfc/rfc6762#section-8.1)
+const PROBES: u8 = 3;
+const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+/// Delay before probing again after losing a simultaneous probe tiebreak, see
+/// [RFC 6762 section 8.2](https://www.rfc-editor.org/rfc/rfc6762#section-8.2)
+const PROBE_DEFER: Duration = Duration::from_secs(1);
+/// Delay before probing again after too many conflicts, see
+/// [RFC 6762 section 8.1](https://www.rfc-editor.org/rfc/rfc6762#section-8.1)
+const CONFLICT_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_CONFLICTS: u32 = 15;
+
+/// Number of unsolicited announcements, see
+/// [RFC 6762 section 8.3](https://www.rfc-editor.org/rfc/rfc6762#section-8.3)
+const ANNOUNCEMENTS: u8 = 2;
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Random delay in milliseconds of the responses containing only shared records, see
+/// [RFC 6762 section 6](https://www.rfc-editor.org/rfc/rfc6762#section-6)
+const SHARED_RESPONSE_DELAY_MS: core::ops::RangeInclusive<u64> = 20..=120;
+
+/// A multicast DNS responder, advertising a host and its DNS-SD services on the local link
+///
+/// The host name and each registered service instance name are claimed by probing, then
+/// announced, see [RFC 6762 section 8](https://www.rfc-editor.org/rfc/rfc6762#section-8). A name
+/// found in use by another host is renamed, `host-2.local.` for the host, `Instance (2)` for a
+/// service. Queries are answered with known-answer suppression, by multicast, or unicast to
+/// queriers asking for it and to legacy unicast queriers. Goodbye packets are sent for the
+/// unregistered services and for all records on [`MdnsResponder::shutdown`].
+///
+/// The responder binds port 5353, which is often already in use by the mDNS responder of the
+/// operating system.
+#[derive(Clone)]
+pub struct MdnsResponder {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl MdnsResponder {
+    /// Responder for `host` with `addresses` on the well-known ipv4 multicast address
+    pub async fn new_ipv4(
+        host: Name,
+        addresses: Vec<IpAddr>,
+        ipv4_if: Option<Ipv4Addr>,
+    ) -> Result<(Self, BoxFuture<'static, ()>), NetError> {
+        Self::new(*MDNS_IPV4, host, addresses, ipv4_if, None).await
+    }
+
+    /// Responder for `host` with `addresses`, e.g. `device.local.`
+    ///
+    /// # Arguments
+    ///
+    /// * `multicast_addr` - address of the mDNS multicast group
+    /// * `host` - host name to claim, the target of the SRV records of the services
+    /// * `addresses` - addresses of the host to advertise
+    /// * `ipv4_if` - address of the interface to send ipv4 multicast packets from
+    /// * `ipv6_if` - index of the interface to send ipv6 multicast packets from, required for ipv6
+    ///
+    /// # Return
+    ///
+    /// The responder and the background future answering queries, which must be spawned. The
+    /// background future completes after [`MdnsResponder::shutdown`], or once all the responders
+    /// are dropped.
+    pub async fn new(
+        multicast_addr: SocketAddr,
+        host: Name,
+        addresses: Vec<IpAddr>,
+        ipv4_if: Option<Ipv4Addr>,
+        ipv6_if: Option<u32>,
+    ) -> Result<(Self, BoxFuture<'static, ()>), NetError> {
+        // responses must be sent with an IP TTL of 255, see RFC 6762 section 11
+        let (stream, sender) = MdnsStream::new(
+            multicast_addr,
+            MdnsQueryType::Continuous,
+            Some(255),
+            ipv4_if,
+            ipv6_if,
+        );
+        let stream = stream.await?;
+
+        let (commands, receiver) = mpsc::unbounded();
+        let responder = Responder::new(host, addresses, Instant::now());
+        let background = Box::pin(run(stream, sender, receiver, responder));
+        Ok((Self { commands }, background))
+    }
+
+    /// Registers `service`, returning its instance name once claimed and announced
+    ///
+    /// The instance name differs from the one of `service` if it was in use by another host, it is
+    /// the name to unregister the service with.
+    pub async fn register(&self, service: ServiceInfo) -> Result<Name, NetError> {
+        let (sender, receiver) = oneshot::channel();
+        self.send(Command::Register(service, sender))?;
+        receiver.await.map_err(|_| stopped())?
+    }
+
+    /// Unregisters the service instance `name`, sending a goodbye packet for its records
+    pub async fn unregister(&self, name: Name) -> Result<(), NetError> {
+        let (sender, receiver) = oneshot::channel();
+        self.send(Command::Unregister(name, sender))?;
+        receiver.await.map_err(|_| stopped())?
+    }
+
+    /// Browses for the instances of `service_type`, e.g. `_ipp._tcp.local.`
+    ///
+    /// Browsing stops once the stream is dropped.
+    pub fn browse(
+        &self,
+        service_type: Name,
+    ) -> Result<impl Stream<Item = ServiceEvent> + Send + Unpin + 'static, NetError> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.send(Command::Browse(service_type, sender))?;
+        Ok(receiver)
+    }
+
+    /// Sends goodbye packets for all the records and stops the responder
+    pub async fn shutdown(&self) -> Result<(), NetError> {
+        let (sender, receiver) = oneshot::channel();
+        self.send(Command::Shutdown(sender))?;
+        receiver.await.map_err(|_| stopped())
+    }
+
+    fn send(&self, command: Command) -> Result<(), NetError> {
+        self.commands.unbounded_send(command).map_err(|_| stopped())
+    }
+}
+
+fn stopped() -> NetError {
+    NetError::from("mDNS responder stopped")
+}
+
+enum Command {
+    Register(ServiceInfo, oneshot::Sender<Result<Name, NetError>>),
+    Unregister(Name, oneshot::Sender<Result<(), NetError>>),
+    Browse(Name, mpsc::UnboundedSender<ServiceEvent>),
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// Answers queries and runs the timers of the responder and browsers until shut down
+async fn run(
+    mut stream: MdnsStream,
+    mut sender: BufDnsStreamHandle,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    mut responder: Responder,
+) {
+    let multicast_addr = stream.multicast_addr();
+    let mut send = |message: Message, dst: SocketAddr| {
+        let result = message
+            .to_vec()
+            .map_err(NetError::from)
+            .and_then(|bytes| sender.send(SerialMessage::new(bytes, dst)));
+        if let Err(error) = result {
+            warn!(%error, %dst, "failed to send mDNS message");
+        }
+    };
+
+    let mut pending = Vec::<(u64, oneshot::Sender<Result<Name, NetError>>)>::new();
+    let mut browsers = Vec::<(Browser, mpsc::UnboundedSender<ServiceEvent>)>::new();
+    let shutdown = loop {
+        let now = Instant::now();
+        for (message, dst) in responder.poll(now) {
+            send(message, dst.unwrap_or(multicast_addr));
+        }
+
+        for (id, result) in responder.take_registered() {
+            if let Some(index) = pending.iter().position(|(pending, _)| *pending == id) {
+                let (_, reply) = pending.swap_remove(index);
+                let _ = reply.send(result.map_err(NetError::from));
+            }
+        }
+
+        browsers.retain_mut(|(browser, events)| {
+            let (query, found) = browser.poll(now);
+            if let Some(query) = query {
+                send(query, multicast_addr);
+            }
+            found
+                .into_iter()
+                .all(|event| events.unbounded_send(event).is_ok())
+                && !events.is_closed()
+        });
+
+        let deadline = browsers
+            .iter()
+            .map(|(browser, _)| browser.next_deadline())
+            .chain(responder.next_deadline())
+            .min();
+        let timer = async move {
+            match deadline {
+                Some(deadline) => {
+                    tokio::time::sleep_until(tokio::time::Instant::from_std(deadline)).await
+                }
+                None => futures_util::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            command = commands.next() => match command {
+                Some(Command::Register(service, reply)) => {
+                    match responder.register(service, Instant::now()) {
+                        Ok(id) => pending.push((id, reply)),
+                        Err(error) => {
+                            let _ = reply.send(Err(error.into()));
+                        }
+                    }
+                }
+                Some(Command::Unregister(name, reply)) => {
+                    let result = match responder.unregister(&name) {
+                        Some(goodbye) => {
+                            if let Some(goodbye) = goodbye {
+                                send(goodbye, multicast_addr);
+                            }
+                            Ok(())
+                        }
+                        None => Err(NetError::from(format!("unknown service instance: {name}"))),
+                    };
+                    let _ = reply.send(result);
+                }
+                Some(Command::Browse(service_type, events)) => {
+                    browsers.push((Browser::new(service_type, Instant::now()), events));
+                }
+                Some(Command::Shutdown(reply)) => break Some(reply),
+                None => break None,
+            },
+            message = stream.next() => match message {
+                Some(Ok(message)) => {
+                    let (bytes, src) = message.into_parts();
+                    let message = match Message::from_vec(&bytes) {
+                        Ok(message) => message,
+                        Err(error) => {
+                            debug!(%error, %src, "dropping malformed mDNS message");
+                            continue;
+                        }
+                    };
+
+                    let now = Instant::now();
+                    for (reply, dst) in responder.handle(&message, src, now) {
+                        send(reply, dst.unwrap_or(multicast_addr));
+                    }
+                    for (browser, events) in &mut browsers {
+                        for event in browser.handle(&message, now) {
+                            let _ = events.unbounded_send(event);
+                        }
+                    }
+                }
+                Some(Err(error)) => warn!(%error, "failed to receive mDNS message"),
+                None => break None,
+            },
+            _ = timer => {}
+        }
+    };
+
+    if let Some(goodbye) = responder.goodbye() {
+        send(goodbye, multicast_addr);
+    }
+    info!("mDNS responder stopped");
+    if let Some(reply) = shutdown {
+        let _ = reply.send(());
+    }
+}
+
+/// State of the names claimed by the responder
+///
+/// This is driven by the messages received and the time, the messages to send are returned.
+pub(super) struct Responder {
+    host: Name,
+    addresses: Vec<IpAddr>,
+    entries: Vec<Entry>,
+    next_id: u64,
+    registered: Vec<(u64, Result<Name, ProtoError>)>,
+    /// Responses with only shared records, by the time they are due
+    delayed: Vec<(Instant, Message, Option<SocketAddr>)>,
+}
+
+impl Responder {
+    pub(super) fn new(host: Name, addresses: Vec<IpAddr>, now: Instant) -> Self {
+        let mut responder = Self {
+            host: host.clone(),
+            addresses,
+            entries: Vec::new(),
+            next_id: 0,
+            registered: Vec::new(),
+            delayed: Vec::new(),
+        };
+        if !responder.addresses.is_empty() {
+            responder.push(
+                Owner::Host {
+                    base: host.clone(),
+                    name: host,
+                },
+                now,
+            );
+        }
+        responder
+    }
+
+    /// Starts probing the instance name of `service`
+    pub(super) fn register(
+        &mut self,
+        service: ServiceInfo,
+        now: Instant,
+    ) -> Result<u64, ProtoError> {
+        let name = service.instance_name()?;
+        Ok(self.push(
+            Owner::Service {
+                base: service,
+                name,
+            },
+            now,
+        ))
+    }
+
+    /// Removes the service instance `name`, returning the goodbye packet if it was announced, or
+    /// `None` if there is no such instance
+    pub(super) fn unregister(&mut self, name: &Name) -> Option<Option<Message>> {
+        let index = self.entries.iter().position(|entry| match &entry.owner {
+            Owner::Service { name: service, .. } => service == name,
+            Owner::Host { .. } => false,
+        })?;
+
+        let entry = self.entries.remove(index);
+        Some(match entry.state {
+            State::Probing(_) => {
+                self.registered
+                    .push((entry.id, Err(ProtoError::from("service unregistered"))));
+                None
+            }
+            State::Announcing(_) | State::Announced => Some(goodbye(self.records(&entry))),
+        })
+    }
+
+    /// Goodbye packet for all the records announced
+    pub(super) fn goodbye(&self) -> Option<Message> {
+        let records = self
+            .entries
+            .iter()
+            .filter(|entry| entry.state.is_authoritative())
+            .flat_map(|entry| self.records(entry))
+            .collect::<Vec<_>>();
+        match records.is_empty() {
+            true => None,
+            false => Some(goodbye(records)),
+        }
+    }
+
+    /// Registrations completed since the last call, by id, with the instance name claimed
+    pub(super) fn take_registered(&mut self) -> Vec<(u64, Result<Name, ProtoError>)> {
+        std::mem::take(&mut self.registered)
+    }
+
+    /// Time the next probe, announcement or delayed response is due
+    pub(super) fn next_deadline(&self) -> Option<Instant> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.state != State::Announced)
+            .map(|entry| entry.deadline)
+            .chain(self.delayed.iter().map(|(deadline, _, _)| *deadline))
+            .min()
+    }
+
+    /// Probes and announcements due at `now`, to multicast, and the delayed responses due, by
+    /// multicast or unicast to the address given
+    pub(super) fn poll(&mut self, now: Instant) -> Vec<(Message, Option<SocketAddr>)> {
+        let mut messages = Vec::new();
+        self.delayed.retain(|(deadline, message, dst)| {
+            let due = *deadline <= now;
+            if due {
+                messages.push((message.clone(), *dst));
+            }
+            !due
+        });
+
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            if entry.state == State::Announced || now < entry.deadline {
+                continue;
+            }
+
+            match entry.state {
+                State::Probing(sent) if sent < PROBES => {
+                    messages.push((self.probe(entry, sent == 0), None));
+                    let entry = &mut self.entries[index];
+                    entry.state = State::Probing(sent + 1);
+                    entry.deadline = now + PROBE_INTERVAL;
+                }
+                State::Probing(_) => {
+                    let entry = &mut self.entries[index];
+                    entry.state = State::Announcing(0);
+                    entry.deadline = now;
+                    debug!(name = %entry.name(), "claimed mDNS name");
+                    if let Owner::Service { name, .. } = &entry.owner {
+                        self.registered.push((entry.id, Ok(name.clone())));
+                    }
+                    messages.push((self.announce(&self.entries[index]), None));
+                    self.advance_announcement(index, now);
+                }
+                State::Announcing(_) => {
+                    messages.push((self.announce(entry), None));
+                    self.advance_announcement(index, now);
+                }
+                State::Announced => {}
+            }
+        }
+
+        messages
+    }
+
+    /// Handles a message received from `src`, returning the replies to send by multicast, or
+    /// unicast to the address given
+    ///
+    /// Replies with only shared records are delayed, they are returned by [`Responder::poll`].
+    pub(super) fn handle(
+        &mut self,
+        message: &Message,
+        src: SocketAddr,
+        now: Instant,
+    ) -> Vec<(Message, Option<SocketAddr>)> {
+        if message.metadata.op_code != OpCode::Query || message.metadata.response_code != 0.into() {
+            return Vec::new();
+        }
+
+        match message.metadata.message_type {
+            MessageType::Query => {
+                self.tiebreak(message, now);
+                let Some((reply, dst, shared)) = self.answer(message, src) else {
+                    return Vec::new();
+                };
+
+                // other responders may answer with the same shared records, replies with unique
+                // records are sent immediately, see RFC 6762 section 6
+                if !shared {
+                    return vec![(reply, dst)];
+                }
+
+                let delay = rand::random_range(SHARED_RESPONSE_DELAY_MS);
+                self.delayed
+                    .push((now + Duration::from_millis(delay), reply, dst));
+                Vec::new()
+            }
+            MessageType::Response => {
+                self.detect_conflicts(message, now);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Defers probing the names other hosts are probing with lexicographically later records, see
+    /// [RFC 6762 section 8.2](https://www.rfc-editor.org/rfc/rfc6762#section-8.2)
+    fn tiebreak(&mut self, message: &Message, now: Instant) {
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            if !matches!(entry.state, State::Probing(_)) {
+                continue;
+            }
+
+            let name = entry.name();
+            let mut theirs = message
+                .authorities
+                .iter()
+                .filter(|record| record.name == *name)
+                .collect::<Vec<_>>();
+            if theirs.is_empty() {
+                continue;
+            }
+
+            let ours = self.records(entry);
+            let mut ours = ours
+                .iter()
+                .filter(|record| record.mdns_cache_flush && record.name == *name)
+                .collect::<Vec<_>>();
+            ours.sort_by(|a, b| tiebreak_cmp(a, b));
+            theirs.sort_by(|a, b| tiebreak_cmp(a, b));
+
+            let lost = ours
+                .iter()
+                .zip(&theirs)
+                .map(|(ours, theirs)| tiebreak_cmp(ours, theirs))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or_else(|| ours.len().cmp(&theirs.len()))
+                == Ordering::Less;
+            if lost {
+                debug!(%name, "lost simultaneous probe tiebreak, deferring");
+                let entry = &mut self.entries[index];
+                entry.state = State::Probing(0);
+                entry.deadline = now + PROBE_DEFER;
+            }
+        }
+    }
+
+    /// Answers the questions of a query for the records announced, see
+    /// [RFC 6762 section 6](https://www.rfc-editor.org/rfc/rfc6762#section-6)
+    ///
+    /// Returns the response, the unicast address to send it to if any, and whether the answers
+    /// are all shared records.
+    fn answer(
+        &self,
+        query: &Message,
+        src: SocketAddr,
+    ) -> Option<(Message, Option<SocketAddr>, bool)> {
+        let legacy = src.port() != MDNS_PORT;
+        let records = self
+            .entries
+            .iter()
+            .filter(|entry| entry.state.is_authoritative())
+            .flat_map(|entry| self.records(entry))
+            .collect::<Vec<_>>();
+
+        let mut answers = Vec::<Record>::new();
+        let mut unicast = true;
+        for question in &query.queries {
+            let before = answers.len();
+            for record in &records {
+                if !question_matches(question, record) || answers.contains(record) {
+                    continue;
+                }
+
+                // known-answer suppression, see RFC 6762 section 7.1
+                let known = query
+                    .answers
+                    .iter()
+                    .any(|known| known == record && known.ttl >= record.ttl / 2);
+                if !known {
+                    answers.push(record.clone());
+                }
+            }
+
+            if answers.len() > before && !question.mdns_unicast_response {
+                unicast = false;
+            }
+        }
+
+        if answers.is_empty() {
+            return None;
+        }
+        let shared = answers.iter().all(|answer| !answer.mdns_cache_flush);
+
+        // the SRV, TXT and address records of the instances and hosts answered, see RFC 6763
+        // section 12
+        let mut targets = answers
+            .iter()
+            .filter_map(|answer| match &answer.data {
+                RData::PTR(ptr) => Some(ptr.0.clone()),
+                RData::SRV(srv) => Some(srv.target.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let mut additionals = Vec::<Record>::new();
+        let mut index = 0;
+        while let Some(target) = targets.get(index).cloned() {
+            index += 1;
+            for record in &records {
+                if record.name != target
+                    || !matches!(
+                        record.record_type(),
+                        RecordType::SRV | RecordType::TXT | RecordType::A | RecordType::AAAA
+                    )
+                {
+                    continue;
+                }
+
+                if let RData::SRV(srv) = &record.data {
+                    if !targets.contains(&srv.target) {
+                        targets.push(srv.target.clone());
+                    }
+                }
+                if !answers.contains(record) && !additionals.contains(record) {
+                    additionals.push(record.clone());
+                }
+            }
+        }
+
+        if legacy {
+            // legacy unicast responses echo the query and carry no mDNS specific bits, see
+            // RFC 6762 section 6.7
+            let mut response = Message::response(query.metadata.id, OpCode::Query);
+            response.metadata.authoritative = true;
+            response.add_queries(query.queries.iter().cloned().map(|mut question| {
+                question.mdns_unicast_response = false;
+                question
+            }));
+            let legacy = |mut record: Record| {
+                record.mdns_cache_flush = false;
+                record.ttl = record.ttl.min(LEGACY_TTL);
+                record
+            };
+            response.add_answers(answers.into_iter().map(legacy));
+            response.add_additionals(additionals.into_iter().map(legacy));
+            return Some((response, Some(src), shared));
+        }
+
+        let mut response = Message::response(0, OpCode::Query);
+        response.metadata.authoritative = true;
+        response.add_answers(answers);
+        response.add_additionals(additionals);
+        Some((response, unicast.then_some(src), shared))
+    }
+
+    /// Renames the names being probed that other hosts answer for, and probes again the names
+    /// announced that other hosts answer with different data for, see
+    /// [RFC 6762 section 9](https://www.rfc-editor.org/rfc/rfc6762#section-9)
+    fn detect_conflicts(&mut self, message: &Message, now: Instant) {
+        let received = message
+            .answers
+            .iter()
+            .chain(&message.additionals)
+            .collect::<Vec<_>>();
+
+        let mut failed = Vec::new();
+        let mut host_renamed = false;
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            let name = entry.name().clone();
+            let ours = self.records(entry);
+            let conflict = received.iter().any(|record| {
+                record.name == name
+                    && record.ttl > 0
+                    && !ours.contains(record)
+                    && match entry.state {
+                        // any record for a name being probed is a conflict
+                        State::Probing(_) => true,
+                        // only different data for a unique record is for a name announced
+                        State::Announcing(_) | State::Announced => ours.iter().any(|ours| {
+                            ours.mdns_cache_flush
+                                && ours.record_type() == record.record_type()
+                                && ours.dns_class == record.dns_class
+                        }),
+                    }
+            });
+            if !conflict {
+                continue;
+            }
+
+            let entry = &mut self.entries[index];
+            if entry.state.is_authoritative() {
+                info!(%name, "mDNS name in conflict, probing again");
+                entry.state = State::Probing(0);
+                entry.deadline = now;
+                continue;
+            }
+
+            entry.conflicts += 1;
+            if let Err(error) = entry.rename() {
+                warn!(%name, %error, "failed to rename mDNS name in conflict");
+                failed.push(index);
+                continue;
+            }
+
+            info!(%name, renamed = %entry.name(), "mDNS name in use, renaming");
+            entry.state = State::Probing(0);
+            entry.deadline = match entry.conflicts > MAX_CONFLICTS {
+                true => now + CONFLICT_BACKOFF,
+                false => now,
+            };
+            if let Owner::Host { name, .. } = &entry.owner {
+                self.host = name.clone();
+                host_renamed = true;
+            }
+        }
+
+        for index in failed.into_iter().rev() {
+            let entry = self.entries.remove(index);
+            self.registered.push((
+                entry.id,
+                Err(ProtoError::from("no name left to resolve the conflict")),
+            ));
+        }
+
+        // the SRV records of the services announced now target the new host name
+        if host_renamed {
+            for entry in &mut self.entries {
+                if matches!(entry.owner, Owner::Service { .. }) && entry.state.is_authoritative() {
+                    entry.state = State::Announcing(0);
+                    entry.deadline = now;
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, owner: Owner, now: Instant) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        // the first probe is delayed randomly to avoid collisions after power failures, see
+        // RFC 6762 section 8.1
+        let delay = Duration::from_millis(rand::random_range(0..250));
+        self.entries.push(Entry {
+            id,
+            owner,
+            state: State::Probing(0),
+            deadline: now + delay,
+            conflicts: 0,
+        });
+        id
+    }
+
+    fn advance_announcement(&mut self, index: usize, now: Instant) {
+        let entry = &mut self.entries[index];
+        let State::Announcing(sent) = entry.state else {
+            return;
+        };
+
+        entry.state = match sent + 1 < ANNOUNCEMENTS {
+            true => State::Announcing(sent + 1),
+            false => State::Announced,
+        };
+        entry.deadline = now + ANNOUNCE_INTERVAL;
+    }
+
+    /// Query for the name of `entry` with its proposed records in the authority section
+    fn probe(&self, entry: &Entry, unicast_response: bool) -> Message {
+        let mut question = Query::new(entry.name().clone(), RecordType::ANY);
+        question.mdns_unicast_response = unicast_response;
+
+        let mut message = Message::new(0, MessageType::Query, OpCode::Query);
+        message.add_query(question);
+        message.add_authorities(
+            self.records(entry)
+                .into_iter()
+                .filter(|record| record.mdns_cache_flush)
+                .map(|mut record| {
+                    record.mdns_cache_flush = false;
+                    record
+                }),
+        );
+        message
+    }
+
+    fn announce(&self, entry: &Entry) -> Message {
+        let mut message = Message::response(0, OpCode::Query);
+        message.metadata.authoritative = true;
+        message.add_answers(self.records(entry));
+        message
+    }
+
+    /// Records of `entry`, unique records are marked with the cache-flush bit
+    fn records(&self, entry: &Entry) -> Vec<Record> {
+        match &entry.owner {
+            Owner::Host { name, .. } => self
+                .addresses
+                .iter()
+                .map(|addr| {
+                    let mut record = Record::from_rdata(name.clone(), HOST_TTL, RData::from(*addr));
+                    record.mdns_cache_flush = true;
+                    record
+                })
+                .collect(),
+            Owner::Service { base, name } => base
+                .records(name, &self.host, HOST_TTL, OTHER_TTL)
+                .unwrap_or_else(|error| {
+                    warn!(%name, %error, "invalid DNS-SD records");
+                    Vec::new()
+                }),
+        }
+    }
+}
+
+/// A host or service instance name claimed by the responder
+struct Entry {
+    id: u64,
+    owner: Owner,
+    state: State,
+    /// Time the next probe or announcement is due
+    deadline: Instant,
+    /// Number of conflicts, used for renaming and rate limiting
+    conflicts: u32,
+}
+
+impl Entry {
+    fn name(&self) -> &Name {
+        match &self.owner {
+            Owner::Host { name, .. } | Owner::Service { name, .. } => name,
+        }
+    }
+
+    /// Renames the entry after its last conflict, `host-2.local.` or `Instance (2)` after the
+    /// first one
+    fn rename(&mut self) -> Result<(), ProtoError> {
+        let attempt = self.conflicts + 1;
+        match &mut self.owner {
+            Owner::Host { base, name } => {
+                let mut label = base
+                    .iter()
+                    .next()
+                    .ok_or_else(|| ProtoError::from("empty mDNS host name"))?
+                    .to_vec();
+                label.extend_from_slice(format!("-{attempt}").as_bytes());
+                *name = base.base_name().prepend_label(label)?;
+            }
+            Owner::Service { base, name } => {
+                *name = base.renamed(attempt).instance_name()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Owner of the records of an [`Entry`], `name` is its current name, `base` the one it was
+/// registered with
+enum Owner {
+    /// The host name and addresses
+    Host { base: Name, name: Name },
+    /// A service instance
+    Service { base: ServiceInfo, name: Name },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Probing the name, with the number of probes sent
+    Probing(u8),
+    /// Announcing the records, with the number of announcements sent
+    Announcing(u8),
+    /// Answering queries for the records
+    Announced,
+}
+
+impl State {
+    /// The records may be sent in responses
+    fn is_authoritative(self) -> bool {
+        !matches!(self, Self::Probing(_))
+    }
+}
+
+/// Whether `record` answers `question`
+fn question_matches(question: &Query, record: &Record) -> bool {
+    question.name == record.name
+        && (question.query_type == RecordType::ANY || question.query_type == record.record_type())
+        && (question.query_class == DNSClass::ANY || question.query_class == record.dns_class)
+}
+
+/// Lexicographical order of records for simultaneous probe tiebreaking, by class, type, then
+/// rdata, see [RFC 6762 section 8.2](https://www.rfc-editor.org/rfc/rfc6762#section-8.2)
+fn tiebreak_cmp(a: &Record, b: &Record) -> Ordering {
+    u16::from(a.dns_class)
+        .cmp(&u16::from(b.dns_class))
+        .then_with(|| u16::from(a.record_type()).cmp(&u16::from(b.record_type())))
+        .then_with(|| a.data.cmp(&b.data))
+}
+
+/// Response with `records` with a TTL of 0, see
+/// [RFC 6762 section 10.1](https://www.rfc-editor.org/rfc/rfc6762#section-10.1)
+fn goodbye(records: Vec<Record>) -> Message {
+    let mut message = Message::response(0, OpCode::Query);
+    message.metadata.authoritative = true;
+    message.add_answers(records.into_iter().map(|mut record| {
+        record.ttl = 0;
+        record
+    }));
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv6Addr;
+
+    use super::*;
+
+    const MDNS_SRC: &str = "192.0.2.2:5353";
+
+    fn responder(now: Instant) -> Responder {
+        Responder::new(
+            Name::from_ascii("device.local.").unwrap(),
+            vec![
+                Ipv4Addr::new(192, 0, 2, 1).into(),
+                Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).into(),
+            ],
+            now,
+        )
+    }
+
+    fn service() -> ServiceInfo {
+        ServiceInfo::new(
+            "Device",
+            Name::from_ascii("_http._tcp.local.").unwrap(),
+            8080,
+        )
+    }
+
+    /// Runs the timers until no probe or announcement is due, returning the messages sent
+    fn settle(responder: &mut Responder, now: &mut Instant) -> Vec<Message> {
+        let mut messages = Vec::new();
+        while let Some(deadline) = responder.next_deadline() {
+            *now = (*now).max(deadline);
+            messages.extend(responder.poll(*now).into_iter().map(|(message, _)| message));
+        }
+        messages
+    }
+
+    /// The delayed replies sent once the maximum delay elapsed
+    fn delayed(responder: &mut Responder, now: Instant) -> Vec<(Message, Option<SocketAddr>)> {
+        responder.poll(now + Duration::from_millis(*SHARED_RESPONSE_DELAY_MS.end()))
+    }
+
+    fn query(name: &str, query_type: RecordType) -> Message {
+        let mut message = Message::new(0, MessageType::Query, OpCode::Query);
+        message.add_query(Query::new(Name::from_ascii(name).unwrap(), query_type));
+        message
+    }
+
+    #[test]
+    fn test_probe_and_announce() {
+        let mut now = Instant::now();
+        let mut responder = responder(now);
+        let id = responder.register(service(), now).unwrap();
+
+        let messages = settle(&mut responder, &mut now);
+        let (probes, announcements): (Vec<_>, Vec<_>) = messages
+            .iter()
+            .partition(|message| message.metadata.message_type == MessageType::Query);
+        assert_eq!(probes.len(), 2 * usize::from(PROBES));
+        assert_eq!(announcements.len(), 2 * usize::from(ANNOUNCEMENTS));
+
+        let probe = probes[0];
+        assert_eq!(probe.queries[0].query_type, RecordType::ANY);
+        assert!(probe.queries[0].mdns_unicast_response);
+        assert!(!probes[2].queries[0].mdns_unicast_response);
+        assert!(!probe.authorities.is_empty());
+        assert!(announcements.iter().all(|message| {
+            message.metadata.authoritative
+                && message
+                    .answers
+                    .iter()
+                    .all(|record| record.ttl > 0 && record.name.num_labels() > 0)
+        }));
+
+        let registered = responder.take_registered();
+        assert_eq!(registered.len(), 1);
+        assert_eq!(registered[0].0, id);
+        assert_eq!(
+            registered[0].1.as_ref().unwrap(),
+            &Name::from_ascii("Device._http._tcp.local.").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_answer() {
+        let mut now = Instant::now();
+        let mut responder = responder(now);
+        responder.register(service(), now).unwrap();
+        settle(&mut responder, &mut now);
+        let src = MDNS_SRC.parse().unwrap();
+
+        // browsing for the service type, with the SRV, TXT and addresses as additionals
+        let replies = responder.handle(&query("_http._tcp.local.", RecordType::PTR), src, now);
+        assert!(replies.is_empty());
+        let replies = delayed(&mut responder, now);
+        assert_eq!(replies.len(), 1);
+        let (response, dst) = &replies[0];
+        assert_eq!(*dst, None);
+        assert_eq!(response.metadata.id, 0);
+        assert!(response.queries.is_empty());
+        assert_eq!(response.answers.len(), 1);
+        let types = response
+            .additionals
+            .iter()
+            .map(|record| record.record_type())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                RecordType::SRV,
+                RecordType::TXT,
+                RecordType::A,
+                RecordType::AAAA
+            ]
+        );
+
+        // known-answer suppression
+        let mut known = query("_http._tcp.local.", RecordType::PTR);
+        known.add_answer(response.answers[0].clone());
+        assert!(responder.handle(&known, src, now).is_empty());
+        assert!(delayed(&mut responder, now).is_empty());
+        known.answers[0].ttl = OTHER_TTL / 2 - 1;
+        assert!(responder.handle(&known, src, now).is_empty());
+        assert_eq!(delayed(&mut responder, now).len(), 1);
+
+        // unicast response requested
+        let mut unicast = query("device.local.", RecordType::A);
+        unicast.queries[0].mdns_unicast_response = true;
+        let replies = responder.handle(&unicast, src, now);
+        assert_eq!(replies[0].1, Some(src));
+        assert_eq!(replies[0].0.answers.len(), 1);
+        assert!(replies[0].0.answers[0].mdns_cache_flush);
+
+        // legacy unicast
+        let legacy_src = "192.0.2.2:40000".parse().unwrap();
+        let mut legacy = query("device.local.", RecordType::ANY);
+        legacy.metadata.id = 42;
+        let replies = responder.handle(&legacy, legacy_src, now);
+        let (response, dst) = &replies[0];
+        assert_eq!(*dst, Some(legacy_src));
+        assert_eq!(response.metadata.id, 42);
+        assert_eq!(response.queries.len(), 1);
+        assert_eq!(response.answers.len(), 2);
+        assert!(
+            response
+                .answers
+                .iter()
+                .all(|record| !record.mdns_cache_flush && record.ttl <= LEGACY_TTL)
+        );
+
+        // unknown names
+        assert!(
+            responder
+                .handle(&query("other.local.", RecordType::A), src, now)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_shared_response_delay() {
+        let mut now = Instant::now();
+        let mut responder = responder(now);
+        responder.register(service(), now).unwrap();
+        settle(&mut responder, &mut now);
+        let src = MDNS_SRC.parse().unwrap();
+
+        // only shared records, sent after a random delay
+        assert!(
+            responder
+                .handle(&query("_http._tcp.local.", RecordType::PTR), src, now)
+                .is_empty()
+        );
+        let deadline = responder.next_deadline().unwrap();
+        let delay = deadline - now;
+        assert!(delay >= Duration::from_millis(*SHARED_RESPONSE_DELAY_MS.start()));
+        assert!(delay <= Duration::from_millis(*SHARED_RESPONSE_DELAY_MS.end()));
+        assert!(
+            responder
+                .poll(deadline - Duration::from_millis(1))
+                .is_empty()
+        );
+        let replies = responder.poll(deadline);
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].1, None);
+        assert_eq!(replies[0].0.answers[0].record_type(), RecordType::PTR);
+        assert_eq!(responder.next_deadline(), None);
+
+        // unique records, sent immediately
+        let replies = responder.handle(
+            &query("Device._http._tcp.local.", RecordType::SRV),
+            src,
+            now,
+        );
+        assert_eq!(replies.len(), 1);
+        assert!(replies[0].0.answers[0].mdns_cache_flush);
+        assert_eq!(responder.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_probe_conflict() {
+        let mut now = Instant::now();
+        let mut responder = responder(now);
+        let id = responder.register(service(), now).unwrap();
+        now = responder.next_deadline().unwrap();
+        responder.poll(now);
+
+        // another host answers for the names being probed
+        let mut response = Message::response(0, OpCode::Query);
+        response.add_answer(Record::from_rdata(
+            Name::from_ascii("device.local.").unwrap(),
+            HOST_TTL,
+            RData::from(Ipv4Addr::new(192, 0, 2, 99)),
+        ));
+        response.add_answer(Record::from_rdata(
+            Name::from_ascii("Device._http._tcp.local.").unwrap(),
+            OTHER_TTL,
+            RData::TXT(crate::proto::rr::rdata::TXT::new(vec!["a=b".to_string()])),
+        ));
+        responder.handle(&response, MDNS_SRC.parse().unwrap(), now);
+
+        let messages = settle(&mut responder, &mut now);
+        assert_eq!(responder.host, Name::from_ascii("device-2.local.").unwrap());
+        let registered = responder.take_registered();
+        assert_eq!(registered[0].0, id);
+        assert_eq!(
+            registered[0].1.as_ref().unwrap(),
+            &service().renamed(2).instance_name().unwrap()
+        );
+        let srv = messages
+            .iter()
+            .flat_map(|message| &message.answers)
+            .find_map(|record| match &record.data {
+                RData::SRV(srv) => Some(srv),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(srv.target, responder.host);
+    }
+
+    #[test]
+    fn test_tiebreak() {
+        let now = Instant::now();
+        let mut responder = responder(now);
+        let deadline = responder.next_deadline().unwrap();
+        responder.poll(deadline);
+        let src = MDNS_SRC.parse().unwrap();
+
+        // a simultaneous probe with earlier records loses
+        let mut probe = query("device.local.", RecordType::ANY);
+        probe.add_authority(Record::from_rdata(
+            Name::from_ascii("device.local.").unwrap(),
+            HOST_TTL,
+            RData::from(Ipv4Addr::new(192, 0, 2, 0)),
+        ));
+        responder.handle(&probe, src, deadline);
+        assert_eq!(responder.entries[0].state, State::Probing(1));
+
+        // one with later records wins
+        probe.authorities[0].data = RData::from(Ipv4Addr::new(192, 0, 2, 200));
+        responder.handle(&probe, src, deadline);
+        assert_eq!(responder.entries[0].state, State::Probing(0));
+        assert_eq!(responder.entries[0].deadline, deadline + PROBE_DEFER);
+    }
+
+    #[test]
+    fn test_goodbye() {
+        let mut now = Instant::now();
+        let mut responder = responder(now);
+        responder.register(service(), now).unwrap();
+        assert!(responder.goodbye().is_none());
+        settle(&mut responder, &mut now);
+
+        let name = Name::from_ascii("Device._http._tcp.local.").unwrap();
+        let goodbye = responder.unregister(&name).unwrap().unwrap();
+        assert_eq!(goodbye.answers.len(), 4);
+        assert!(goodbye.answers.iter().all(|record| record.ttl == 0));
+        assert!(responder.unregister(&name).is_none());
+
+        let goodbye = responder.goodbye().unwrap();
+        assert_eq!(goodbye.answers.len(), 2);
+    }
+}
@@ -370,8 +370,20 @@ impl Future for NextRandomUdpSocket {
         } else if self.mdns_query_type.bind_on_5353() {
             let addr = SocketAddr::new(self.bind_address, MDNS_PORT);
             debug!("binding sending stream to {}", addr);
-            let socket = std::net::UdpSocket::bind(addr)?;
-            let socket = self.prepare_sender(socket)?;
+            // the port is shared with the multicast socket, and other mDNS responders
+            let socket = Socket::new(
+                socket2::Domain::for_address(addr),
+                socket2::Type::DGRAM,
+                Some(socket2::Protocol::UDP),
+            )?;
+            if addr.is_ipv6() {
+                socket.set_only_v6(true)?;
+            }
+            socket.set_reuse_address(true)?;
+            #[cfg(unix)] // this is currently restricted to Unix's in socket2
+            socket.set_reuse_port(true)?;
+            socket.bind(&socket2::SockAddr::from(addr))?;
+            let socket = self.prepare_sender(std::net::UdpSocket::from(socket))?;
 
             Poll::Ready(Ok(Some(socket)))
         } else {
@@ -7,14 +7,22 @@
 
 //! Multicast protocol related components for DNS
 
+#[cfg(feature = "tokio")]
+mod dns_sd;
 #[cfg(feature = "tokio")]
 mod mdns_client_stream;
 #[cfg(feature = "tokio")]
+mod mdns_responder;
+#[cfg(feature = "tokio")]
 mod mdns_stream;
 
+#[cfg(feature = "tokio")]
+pub use self::dns_sd::{ResolvedService, ServiceEvent, ServiceInfo};
 #[cfg(feature = "tokio")]
 pub use self::mdns_client_stream::MdnsClientStream;
 #[cfg(feature = "tokio")]
+pub use self::mdns_responder::MdnsResponder;
+#[cfg(feature = "tokio")]
 pub use self::mdns_stream::{MDNS_IPV4, MDNS_IPV6, MdnsStream};
 
 /// See [rfc6762](https://tools.ietf.org/html/rfc6762#section-5) details on these different types.
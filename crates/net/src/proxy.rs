@@ -0,0 +1,399 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Tunnels to name servers through HTTP and SOCKS5 proxies
+
+use core::fmt;
+use core::net::{IpAddr, SocketAddr};
+use std::io;
+
+use data_encoding::BASE64;
+use futures_io::{AsyncRead, AsyncWrite};
+use futures_util::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Maximum length of the response of an HTTP proxy to a CONNECT request
+const MAX_HTTP_RESPONSE_LEN: usize = 8_192;
+
+/// A proxy to tunnel TCP connections to the name servers through
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields, rename_all = "snake_case", tag = "type")
+)]
+#[non_exhaustive]
+pub enum ProxyConfig {
+    /// HTTP proxy, tunnelling with the CONNECT method, see
+    /// [RFC 9110 section 9.3.6](https://www.rfc-editor.org/rfc/rfc9110#section-9.3.6)
+    Http {
+        /// Address of the proxy
+        addr: SocketAddr,
+        /// Credentials for the Basic authentication to the proxy
+        #[cfg_attr(feature = "serde", serde(default))]
+        credentials: Option<ProxyCredentials>,
+    },
+    /// SOCKS5 proxy, see [RFC 1928](https://www.rfc-editor.org/rfc/rfc1928)
+    Socks5 {
+        /// Address of the proxy
+        addr: SocketAddr,
+        /// Credentials for the username/password authentication to the proxy, see
+        /// [RFC 1929](https://www.rfc-editor.org/rfc/rfc1929)
+        #[cfg_attr(feature = "serde", serde(default))]
+        credentials: Option<ProxyCredentials>,
+    },
+}
+
+impl ProxyConfig {
+    /// Address of the proxy, to connect to instead of the name server
+    pub fn addr(&self) -> SocketAddr {
+        match self {
+            Self::Http { addr, .. } | Self::Socks5 { addr, .. } => *addr,
+        }
+    }
+
+    /// Opens a tunnel to `target` over `stream`, connected to the proxy
+    ///
+    /// The stream is returned once the proxy relays the data to and from `target`.
+    pub async fn connect<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut stream: S,
+        target: SocketAddr,
+    ) -> io::Result<S> {
+        match self {
+            Self::Http { credentials, .. } => {
+                http_connect(&mut stream, target, credentials.as_ref()).await?
+            }
+            Self::Socks5 { credentials, .. } => {
+                socks5_connect(&mut stream, target, credentials.as_ref()).await?
+            }
+        }
+
+        debug!(proxy = %self.addr(), %target, "proxy tunnel established");
+        Ok(stream)
+    }
+}
+
+/// Credentials to authenticate to a proxy
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub struct ProxyCredentials {
+    /// User name
+    pub username: String,
+    /// Password
+    pub password: String,
+}
+
+impl ProxyCredentials {
+    /// Credentials of `username` with `password`
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl fmt::Debug for ProxyCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyCredentials")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+async fn http_connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    target: SocketAddr,
+    credentials: Option<&ProxyCredentials>,
+) -> io::Result<()> {
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if let Some(credentials) = credentials {
+        let basic = format!("{}:{}", credentials.username, credentials.password);
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            BASE64.encode(basic.as_bytes())
+        ));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    // read byte by byte, the data following the response belongs to the tunnel
+    let mut response = Vec::with_capacity(128);
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_HTTP_RESPONSE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "HTTP proxy response is too long",
+            ));
+        }
+
+        let mut byte = [0];
+        stream.read_exact(&mut byte).await?;
+        response.push(byte[0]);
+    }
+
+    let status_line = String::from_utf8_lossy(&response);
+    let status_line = status_line.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') && status.len() == 3 => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("HTTP proxy refused the tunnel to {target}: {status_line}"),
+        )),
+    }
+}
+
+const SOCKS5_VERSION: u8 = 5;
+const SOCKS5_NO_AUTHENTICATION: u8 = 0;
+const SOCKS5_USERNAME_PASSWORD: u8 = 2;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS5_CONNECT: u8 = 1;
+const SOCKS5_IPV4: u8 = 1;
+const SOCKS5_DOMAIN_NAME: u8 = 3;
+const SOCKS5_IPV6: u8 = 4;
+
+async fn socks5_connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    target: SocketAddr,
+    credentials: Option<&ProxyCredentials>,
+) -> io::Result<()> {
+    let method = match credentials {
+        Some(_) => SOCKS5_USERNAME_PASSWORD,
+        None => SOCKS5_NO_AUTHENTICATION,
+    };
+    stream.write_all(&[SOCKS5_VERSION, 1, method]).await?;
+    stream.flush().await?;
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+    match reply {
+        [SOCKS5_VERSION, selected] if selected == method => {}
+        [SOCKS5_VERSION, SOCKS5_NO_ACCEPTABLE_METHOD] => {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "SOCKS5 proxy did not accept the authentication method",
+            ));
+        }
+        _ => return Err(invalid_reply()),
+    }
+
+    if let Some(credentials) = credentials {
+        let (username, password) = (
+            credentials.username.as_bytes(),
+            credentials.password.as_bytes(),
+        );
+        let (Ok(username_len), Ok(password_len)) =
+            (u8::try_from(username.len()), u8::try_from(password.len()))
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "SOCKS5 username and password are limited to 255 bytes",
+            ));
+        };
+
+        // RFC 1929: the sub-negotiation has its own version
+        let mut request = vec![1, username_len];
+        request.extend_from_slice(username);
+        request.push(password_len);
+        request.extend_from_slice(password);
+        stream.write_all(&request).await?;
+        stream.flush().await?;
+
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "SOCKS5 proxy rejected the credentials",
+            ));
+        }
+    }
+
+    let mut request = vec![SOCKS5_VERSION, SOCKS5_CONNECT, 0];
+    match target.ip() {
+        IpAddr::V4(ip) => {
+            request.push(SOCKS5_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            request.push(SOCKS5_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+    }
+    request.extend_from_slice(&target.port().to_be_bytes());
+    stream.write_all(&request).await?;
+    stream.flush().await?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+    let [SOCKS5_VERSION, status, 0, address_type] = reply else {
+        return Err(invalid_reply());
+    };
+
+    if status != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("SOCKS5 proxy refused the tunnel to {target}: error {status}"),
+        ));
+    }
+
+    // skip the bound address and port
+    let address_len = match address_type {
+        SOCKS5_IPV4 => 4,
+        SOCKS5_IPV6 => 16,
+        SOCKS5_DOMAIN_NAME => {
+            let mut len = [0];
+            stream.read_exact(&mut len).await?;
+            usize::from(len[0])
+        }
+        _ => return Err(invalid_reply()),
+    };
+    let mut bound = vec![0; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+fn invalid_reply() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid SOCKS5 proxy reply")
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, duplex};
+
+    use super::*;
+    use crate::runtime::iocompat::AsyncIoTokioAsStd;
+
+    const TARGET: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53)), 443);
+    const PROXY: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 3128);
+
+    #[tokio::test]
+    async fn test_http_connect() {
+        let (client, mut proxy) = duplex(1024);
+        let server = tokio::spawn(async move {
+            let mut request = vec![0; 1024];
+            let len = proxy.read(&mut request).await.unwrap();
+            proxy
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\ntunnel")
+                .await
+                .unwrap();
+            String::from_utf8(request[..len].to_vec()).unwrap()
+        });
+
+        let config = ProxyConfig::Http {
+            addr: PROXY,
+            credentials: Some(ProxyCredentials::new("user", "secret")),
+        };
+        let mut stream = config
+            .connect(AsyncIoTokioAsStd(client), TARGET)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            server.await.unwrap(),
+            "CONNECT 192.0.2.53:443 HTTP/1.1\r\nHost: 192.0.2.53:443\r\n\
+             Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n\r\n"
+        );
+
+        // the data following the response is left to the tunnel
+        let mut data = [0; 6];
+        futures_util::AsyncReadExt::read_exact(&mut stream, &mut data)
+            .await
+            .unwrap();
+        assert_eq!(&data, b"tunnel");
+    }
+
+    #[tokio::test]
+    async fn test_http_refused() {
+        let (client, mut proxy) = duplex(1024);
+        tokio::spawn(async move {
+            let mut request = vec![0; 1024];
+            let len = proxy.read(&mut request).await.unwrap();
+            assert!(request[..len].starts_with(b"CONNECT "));
+            proxy
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let config = ProxyConfig::Http {
+            addr: PROXY,
+            credentials: None,
+        };
+        let Err(error) = config.connect(AsyncIoTokioAsStd(client), TARGET).await else {
+            panic!("expected the proxy to refuse the connection");
+        };
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect() {
+        let (client, mut proxy) = duplex(1024);
+        let server = tokio::spawn(async move {
+            let mut greeting = [0; 3];
+            proxy.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, 2]);
+            proxy.write_all(&[5, 2]).await.unwrap();
+
+            let mut auth = [0; 13];
+            proxy.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x06secret");
+            proxy.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0; 10];
+            proxy.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, [5, 1, 0, 1, 192, 0, 2, 53, 1, 187]);
+            proxy
+                .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0x1f, 0x90])
+                .await
+                .unwrap();
+        });
+
+        let config = ProxyConfig::Socks5 {
+            addr: PROXY,
+            credentials: Some(ProxyCredentials::new("user", "secret")),
+        };
+        config
+            .connect(AsyncIoTokioAsStd(client), TARGET)
+            .await
+            .unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_socks5_refused() {
+        let (client, mut proxy) = duplex(1024);
+        tokio::spawn(async move {
+            let mut greeting = [0; 3];
+            proxy.read_exact(&mut greeting).await.unwrap();
+            proxy.write_all(&[5, 0]).await.unwrap();
+
+            let mut request = [0; 10];
+            proxy.read_exact(&mut request).await.unwrap();
+            // connection refused by destination host
+            proxy
+                .write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+        });
+
+        let config = ProxyConfig::Socks5 {
+            addr: PROXY,
+            credentials: None,
+        };
+        let Err(error) = config.connect(AsyncIoTokioAsStd(client), TARGET).await else {
+            panic!("expected the proxy to refuse the connection");
+        };
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+    }
+}
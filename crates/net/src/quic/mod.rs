@@ -15,6 +15,8 @@ mod quic_stream;
 #[cfg(feature = "__h3")]
 pub(crate) use self::quic_client_stream::connect_quic;
 pub use self::quic_client_stream::{QuicClientStream, QuicClientStreamBuilder};
+#[cfg(feature = "__h3")]
+pub(crate) use self::quic_server::peer_certificates;
 pub use self::quic_server::{QuicServer, QuicStreams};
 pub use self::quic_stream::{DoqErrorCode, QuicStream};
 
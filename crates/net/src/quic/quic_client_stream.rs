@@ -24,7 +24,7 @@ use tokio::time::timeout;
 
 use crate::{
     error::NetError,
-    proto::op::{DnsRequest, DnsResponse},
+    proto::op::{DnsRequest, DnsResponse, QUERY_PADDING_BLOCK_LEN},
     quic::quic_stream::{DoqErrorCode, QuicStream},
     runtime::{RuntimeProvider, Spawn},
     tls::client_config,
@@ -163,11 +163,16 @@ impl DnsRequestSender for QuicClientStream {
     ///    that is in use.  When forwarding a DNS message from another transport
     ///    over DoQ, the Message ID MUST be set to 0.
     /// ```
-    fn send_message(&mut self, request: DnsRequest) -> DnsResponseStream {
+    fn send_message(&mut self, mut request: DnsRequest) -> DnsResponseStream {
         if self.is_shutdown {
             panic!("can not send messages after stream is shutdown")
         }
 
+        // RFC 8467: pad queries to hide their length on encrypted transports
+        if let Some(edns) = &mut request.edns {
+            edns.set_padding_block(Some(QUERY_PADDING_BLOCK_LEN));
+        }
+
         Box::pin(Self::inner_send(self.quic_connection.clone(), request)).into()
     }
 
@@ -11,6 +11,7 @@ use std::sync::Arc;
 
 use quinn::crypto::rustls::QuicServerConfig;
 use quinn::{Connection, Endpoint, ServerConfig};
+use rustls::pki_types::CertificateDer;
 use rustls::server::ResolvesServerCert;
 use rustls::server::ServerConfig as TlsServerConfig;
 use rustls::version::TLS13;
@@ -19,7 +20,7 @@ use crate::{error::NetError, tls::default_provider, udp::UdpSocket};
 
 use super::{
     quic_config,
-    quic_stream::{self, QuicStream},
+    quic_stream::{self, DoqErrorCode, QuicStream},
 };
 
 /// A DNS-over-QUIC Server, see QuicClientStream for the client counterpart
@@ -117,4 +118,23 @@ impl QuicStreams {
             Err(e) => Some(Err(NetError::from(e))),
         }
     }
+
+    /// Closes the connection with the error `code`
+    pub fn close(&self, code: DoqErrorCode) {
+        self.connection.close(code.into(), b"");
+    }
+
+    /// Certificate chain the client authenticated with, if any
+    pub fn peer_certificates(&self) -> Option<Vec<CertificateDer<'static>>> {
+        peer_certificates(&self.connection)
+    }
+}
+
+/// Certificate chain the peer of `connection` authenticated with, if any
+pub(crate) fn peer_certificates(connection: &Connection) -> Option<Vec<CertificateDer<'static>>> {
+    connection
+        .peer_identity()?
+        .downcast::<Vec<CertificateDer<'static>>>()
+        .ok()
+        .map(|chain| *chain)
 }
@@ -10,12 +10,11 @@
 use std::{future::Future, net::SocketAddr, sync::Arc, time::Duration};
 
 use futures_util::future::BoxFuture;
-#[cfg(not(feature = "rustls-platform-verifier"))]
-use rustls::RootCertStore;
 use rustls::{
-    ClientConfig,
+    ClientConfig, RootCertStore,
     crypto::{self, CryptoProvider},
-    pki_types::ServerName,
+    pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+    version::TLS13,
 };
 #[cfg(feature = "rustls-platform-verifier")]
 use rustls_platform_verifier::BuilderVerifierExt;
@@ -25,6 +24,7 @@ use tracing::debug;
 
 use crate::{
     error::NetError,
+    proto::op::QUERY_PADDING_BLOCK_LEN,
     runtime::{
         DnsTcpStream, RuntimeProvider, Spawn,
         iocompat::{AsyncIoStdAsTokio, AsyncIoTokioAsStd},
@@ -75,7 +75,9 @@ pub async fn tls_exchange<P: RuntimeProvider<Tcp = S>, S: DnsTcpStream>(
         Arc::new(config),
     );
 
-    let mut multiplexer = DnsMultiplexer::new(future.await?, sender).with_timeout(timeout);
+    let mut multiplexer = DnsMultiplexer::new(future.await?, sender)
+        .with_timeout(timeout)
+        .with_padding_block(QUERY_PADDING_BLOCK_LEN);
     if let Some(max) = max_active_requests {
         multiplexer = multiplexer.with_max_active_requests(max);
     }
@@ -239,6 +241,28 @@ pub fn client_config() -> Result<ClientConfig, rustls::Error> {
     Ok(builder.with_no_client_auth())
 }
 
+/// Make a new [`ClientConfig`] for zone transfers over TLS (XoT), see
+/// [RFC 9103](https://www.rfc-editor.org/rfc/rfc9103)
+///
+/// XoT requires TLS 1.3 and the `dot` ALPN token. Primaries generally authenticate secondaries
+/// with a client certificate, which is presented when `client_auth` is given as the certificate
+/// chain and its private key.
+pub fn xot_client_config(
+    roots: RootCertStore,
+    client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+) -> Result<ClientConfig, rustls::Error> {
+    let builder = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_protocol_versions(&[&TLS13])?
+        .with_root_certificates(roots);
+
+    let mut config = match client_auth {
+        Some((cert_chain, key)) => builder.with_client_auth_cert(cert_chain, key)?,
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = vec![b"dot".to_vec()];
+    Ok(config)
+}
+
 /// Instantiate a new [`CryptoProvider`] for use with rustls
 #[cfg(all(feature = "tls-aws-lc-rs", not(feature = "tls-ring")))]
 pub fn default_provider() -> CryptoProvider {
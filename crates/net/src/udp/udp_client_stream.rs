@@ -17,9 +17,9 @@ use futures_util::{FutureExt, Stream, StreamExt, pin_mut, stream::FuturesUnorder
 use tracing::{debug, trace, warn};
 
 use crate::error::NetError;
-use crate::proto::op::{DEFAULT_RETRY_FLOOR, DnsRequest, DnsResponse, Message, SerialMessage};
 #[cfg(feature = "__dnssec")]
-use crate::proto::rr::TSigner;
+use crate::proto::op::MessageSigner;
+use crate::proto::op::{DEFAULT_RETRY_FLOOR, DnsRequest, DnsResponse, Message, SerialMessage};
 use crate::runtime::{DnsUdpSocket, RuntimeProvider, Spawn, Time};
 use crate::udp::MAX_RECEIVE_BUFFER_SIZE;
 use crate::udp::udp_stream::NextRandomUdpSocket;
@@ -36,7 +36,7 @@ pub struct UdpClientStream<P> {
     timeout: Duration,
     is_shutdown: bool,
     #[cfg(feature = "__dnssec")]
-    signer: Option<TSigner>,
+    signer: Option<Arc<dyn MessageSigner>>,
     bind_addr: Option<SocketAddr>,
     avoid_local_ports: Arc<HashSet<u16>>,
     os_port_selection: bool,
@@ -124,7 +124,7 @@ struct UdpRequest<P> {
     request: DnsRequest,
     provider: P,
     #[cfg(feature = "__dnssec")]
-    signer: Option<TSigner>,
+    signer: Option<Arc<dyn MessageSigner>>,
     #[cfg(feature = "__dnssec")]
     now: u64,
     bind_addr: Option<SocketAddr>,
@@ -166,7 +166,7 @@ impl<P: RuntimeProvider> Request for UdpRequest<P> {
         let mut verifier = None;
         #[cfg(feature = "__dnssec")]
         if let Some(signer) = &self.signer {
-            match request.finalize(signer, self.now) {
+            match request.finalize(&**signer, self.now) {
                 Ok(answer_verifier) => verifier = answer_verifier,
                 Err(e) => {
                     debug!("could not sign message: {}", e);
@@ -339,7 +339,7 @@ pub struct UdpClientStreamBuilder<P> {
     name_server: SocketAddr,
     timeout: Option<Duration>,
     #[cfg(feature = "__dnssec")]
-    signer: Option<TSigner>,
+    signer: Option<Arc<dyn MessageSigner>>,
     bind_addr: Option<SocketAddr>,
     avoid_local_ports: Arc<HashSet<u16>>,
     os_port_selection: bool,
@@ -357,11 +357,11 @@ impl<P: RuntimeProvider> UdpClientStreamBuilder<P> {
 
     /// Sets the message finalizer to be applied to queries.
     #[cfg(feature = "__dnssec")]
-    pub fn with_signer(self, signer: Option<TSigner>) -> Self {
+    pub fn with_signer(self, signer: Option<impl MessageSigner + 'static>) -> Self {
         Self {
             name_server: self.name_server,
             timeout: self.timeout,
-            signer,
+            signer: signer.map(|signer| Arc::new(signer) as Arc<dyn MessageSigner>),
             bind_addr: self.bind_addr,
             avoid_local_ports: self.avoid_local_ports,
             os_port_selection: self.os_port_selection,
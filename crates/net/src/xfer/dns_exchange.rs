@@ -11,15 +11,21 @@ use core::future::Future;
 use core::marker::PhantomData;
 use core::pin::Pin;
 use core::task::{Context, Poll};
+#[cfg(feature = "dnstap")]
+use std::{net::SocketAddr, sync::Arc};
 
 use futures_channel::mpsc;
 use futures_util::stream::{Peekable, Stream, StreamExt};
 use tracing::debug;
 
+#[cfg(feature = "dnstap")]
+use crate::dnstap::{ConnectionTap, PendingResponse, UpstreamTap};
 use crate::error::NetError;
 use crate::proto::op::{DnsRequest, DnsResponse};
 use crate::runtime::RuntimeProvider;
 use crate::runtime::Time;
+#[cfg(feature = "dnstap")]
+use crate::xfer::Protocol;
 use crate::xfer::dns_handle::DnsHandle;
 use crate::xfer::{
     BufDnsRequestStreamHandle, DEFAULT_STREAM_BUFFER_SIZE, DnsRequestSender, DnsResponseReceiver,
@@ -33,6 +39,8 @@ use crate::xfer::{
 #[must_use = "futures do nothing unless polled"]
 pub struct DnsExchange<P> {
     sender: BufDnsRequestStreamHandle<P>,
+    #[cfg(feature = "dnstap")]
+    tap: Option<Arc<ConnectionTap>>,
 }
 
 impl<P: RuntimeProvider> DnsExchange<P> {
@@ -58,6 +66,8 @@ impl<P: RuntimeProvider> DnsExchange<P> {
                     sender,
                     _phantom: PhantomData,
                 },
+                #[cfg(feature = "dnstap")]
+                tap: None,
             },
             DnsExchangeBackground {
                 io_stream: stream,
@@ -68,10 +78,21 @@ impl<P: RuntimeProvider> DnsExchange<P> {
     }
 }
 
+impl<P> DnsExchange<P> {
+    /// Logs the exchanges with `server` over this connection to dnstap
+    #[cfg(feature = "dnstap")]
+    pub fn with_dnstap(mut self, tap: UpstreamTap, protocol: Protocol, server: SocketAddr) -> Self {
+        self.tap = Some(Arc::new(ConnectionTap::new(tap, protocol, server)));
+        self
+    }
+}
+
 impl<P: Clone> Clone for DnsExchange<P> {
     fn clone(&self) -> Self {
         Self {
             sender: self.sender.clone(),
+            #[cfg(feature = "dnstap")]
+            tap: self.tap.clone(),
         }
     }
 }
@@ -81,7 +102,11 @@ impl<P: RuntimeProvider> DnsHandle for DnsExchange<P> {
     type Runtime = P;
 
     fn send(&self, request: DnsRequest) -> Self::Response {
+        #[cfg(feature = "dnstap")]
+        let tap = self.tap.as_ref().and_then(|tap| tap.query(&request));
         DnsExchangeSend {
+            #[cfg(feature = "dnstap")]
+            tap,
             result: self.sender.send(request),
             _sender: self.sender.clone(), // TODO: this shouldn't be necessary, currently the presence of Senders is what allows the background to track current users, it generally is dropped right after send, this makes sure that there is at least one active after send
         }
@@ -91,6 +116,8 @@ impl<P: RuntimeProvider> DnsHandle for DnsExchange<P> {
 /// A Stream that will resolve to Responses after sending the request
 #[must_use = "futures do nothing unless polled"]
 pub struct DnsExchangeSend<P> {
+    #[cfg(feature = "dnstap")]
+    tap: Option<PendingResponse>,
     result: DnsResponseReceiver,
     _sender: BufDnsRequestStreamHandle<P>,
 }
@@ -100,7 +127,15 @@ impl<P: Unpin> Stream for DnsExchangeSend<P> {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         // as long as there is no result, poll the exchange
-        self.result.poll_next_unpin(cx)
+        let result = self.result.poll_next_unpin(cx);
+        #[cfg(feature = "dnstap")]
+        if let Poll::Ready(Some(Ok(response))) = &result {
+            if let Some(tap) = self.tap.take() {
+                tap.response(response);
+            }
+        }
+
+        result
     }
 }
 
@@ -14,6 +14,8 @@ use core::{
 };
 use std::collections::{HashMap, hash_map::Entry};
 use std::io;
+#[cfg(feature = "__dnssec")]
+use std::sync::Arc;
 
 use futures_channel::mpsc;
 use futures_util::{
@@ -29,7 +31,7 @@ use super::{
 };
 use crate::proto::op::{DnsRequest, DnsResponse, SerialMessage};
 #[cfg(feature = "__dnssec")]
-use crate::proto::rr::{TSigVerifier, TSigner};
+use crate::proto::{op::MessageSigner, rr::TSigVerifier};
 use crate::{DnsStreamHandle, error::NetError, runtime::Time};
 
 struct ActiveRequest {
@@ -91,8 +93,9 @@ pub struct DnsMultiplexer<S> {
     stream_handle: BufDnsStreamHandle,
     active_requests: HashMap<u16, ActiveRequest>,
     max_active_requests: usize,
+    padding_block: Option<u16>,
     #[cfg(feature = "__dnssec")]
-    signer: Option<TSigner>,
+    signer: Option<Arc<dyn MessageSigner>>,
     is_shutdown: bool,
 }
 
@@ -115,6 +118,7 @@ impl<S: DnsClientStream> DnsMultiplexer<S> {
             stream_handle,
             active_requests: HashMap::default(),
             max_active_requests: 32,
+            padding_block: None,
             #[cfg(feature = "__dnssec")]
             signer: None,
             is_shutdown: false,
@@ -137,10 +141,19 @@ impl<S: DnsClientStream> DnsMultiplexer<S> {
         self
     }
 
-    /// Specify an optional signer to TSIG authenticate requests.
+    /// Pad requests with EDNS to a multiple of `block` octets.
+    ///
+    /// This should only be used on encrypted streams, see
+    /// [`QUERY_PADDING_BLOCK_LEN`](crate::proto::op::QUERY_PADDING_BLOCK_LEN).
+    pub fn with_padding_block(mut self, block: u16) -> Self {
+        self.padding_block = Some(block);
+        self
+    }
+
+    /// Specify an optional signer to TSIG or SIG(0) authenticate requests.
     #[cfg(feature = "__dnssec")]
-    pub fn with_signer(mut self, signer: TSigner) -> Self {
-        self.signer = Some(signer);
+    pub fn with_signer(mut self, signer: impl MessageSigner + 'static) -> Self {
+        self.signer = Some(Arc::new(signer));
         self
     }
 
@@ -216,13 +229,16 @@ impl<S: DnsClientStream> DnsRequestSender for DnsMultiplexer<S> {
 
         let (mut request, _) = request.into_parts();
         request.metadata.id = query_id;
+        if let (Some(block), Some(edns)) = (self.padding_block, &mut request.edns) {
+            edns.set_padding_block(Some(block));
+        }
 
         #[cfg(feature = "__dnssec")]
         let mut verifier = None;
         #[cfg(feature = "__dnssec")]
         if let Some(signer) = &self.signer {
             if signer.should_sign_message(&request) {
-                match request.finalize(signer, S::Time::current_time()) {
+                match request.finalize(&**signer, S::Time::current_time()) {
                     Ok(answer_verifier) => verifier = answer_verifier,
                     Err(e) => {
                         debug!("could not sign message: {}", e);
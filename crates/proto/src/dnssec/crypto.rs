@@ -1,6 +1,7 @@
-use alloc::{borrow::Cow, boxed::Box, sync::Arc, vec::Vec};
+use alloc::{borrow::Cow, boxed::Box, format, sync::Arc, vec::Vec};
+use std::{fs, path::Path};
 
-use rustls_pki_types::{PrivateKeyDer, PrivatePkcs1KeyDer, PrivatePkcs8KeyDer};
+use rustls_pki_types::{PrivateKeyDer, PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, pem::PemObject};
 
 use super::{
     Algorithm, DigestType, DnsSecError, DnsSecResult, PublicKey, PublicKeyBuf, SigningKey, TBS,
@@ -12,7 +13,10 @@ use super::{
     },
     rsa_public_key::RSAPublicKey,
 };
-use crate::{ProtoError, error::ProtoResult, serialize::binary::DecodeError};
+use crate::{
+    ProtoError, error::ProtoResult, rr::rdata::zonemd::HashAlgorithm,
+    serialize::binary::DecodeError,
+};
 
 /// Decode private key
 pub fn signing_key_from_der(
@@ -33,6 +37,24 @@ pub fn signing_key_from_der(
     }
 }
 
+/// Read a PEM or DER encoded private key from the file at `path`
+pub fn signing_key_from_file(
+    path: &Path,
+    algorithm: Algorithm,
+) -> DnsSecResult<Box<dyn SigningKey>> {
+    let buf =
+        fs::read(path).map_err(|e| format!("could not read key from {}: {e}", path.display()))?;
+
+    let key = match buf.trim_ascii_start().starts_with(b"-----BEGIN ") {
+        true => PrivateKeyDer::from_pem_slice(&buf)
+            .map_err(|e| format!("could not read pem from {}: {e}", path.display()))?,
+        false => PrivateKeyDer::try_from(&*buf)
+            .map_err(|e| format!("could not read der from {}: {e}", path.display()))?,
+    };
+
+    signing_key_from_der(&key, algorithm)
+}
+
 pub(super) fn decode_public_key<'a>(
     public_key: &'a [u8],
     algorithm: Algorithm,
@@ -439,6 +461,11 @@ impl Digest {
     pub fn new(bytes: &[u8], r#type: DigestType) -> Result<Self, DecodeError> {
         Ok(Self(digest::digest(r#type.try_into()?, bytes)))
     }
+
+    /// Hashes the `bytes` with the hash algorithm of a ZONEMD record.
+    pub fn zonemd(bytes: &[u8], hash_algorithm: HashAlgorithm) -> Result<Self, DecodeError> {
+        Ok(Self(digest::digest(hash_algorithm.try_into()?, bytes)))
+    }
 }
 
 fn hash_iter<'a>(
@@ -471,10 +498,20 @@ impl TryFrom<DigestType> for &'static digest::Algorithm {
     }
 }
 
+impl TryFrom<HashAlgorithm> for &'static digest::Algorithm {
+    type Error = DecodeError;
+
+    fn try_from(value: HashAlgorithm) -> Result<&'static digest::Algorithm, Self::Error> {
+        match value {
+            HashAlgorithm::SHA384 => Ok(&digest::SHA384),
+            HashAlgorithm::SHA512 => Ok(&digest::SHA512),
+            HashAlgorithm::Unassigned(other) => Err(DecodeError::UnknownDigestAlgorithm(other)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use rustls_pki_types::pem::PemObject;
-
     use super::*;
     use crate::dnssec::test_utils::{hash_test, public_key_test};
 
@@ -561,6 +598,18 @@ mod tests {
         signing_key_from_der(&key_der, Algorithm::RSASHA256).unwrap();
     }
 
+    #[test]
+    fn test_signing_key_from_file() {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test-data");
+        signing_key_from_file(
+            &test_data.join("rsa-2048-private-key-1.pk8"),
+            Algorithm::RSASHA256,
+        )
+        .unwrap();
+        signing_key_from_file(&test_data.join("rsa-2048-pkcs1.pem"), Algorithm::RSASHA256).unwrap();
+        assert!(signing_key_from_file(&test_data.join("root.key"), Algorithm::RSASHA256).is_err());
+    }
+
     /// Regression test for https://github.com/hickory-dns/hickory-dns/issues/3690.
     ///
     /// Per RFC 5155 §2, DNSSEC algorithm 7 (RSASHA1NSEC3SHA1) uses the same RSA/SHA-1
@@ -17,7 +17,10 @@ use thiserror::Error;
 
 use crate::dnssec::crypto::Digest;
 use crate::error::ProtoError;
-use crate::rr::{Name, RData, Record, rdata::tsig::TsigAlgorithm};
+use crate::rr::{
+    Name, RData, Record,
+    rdata::tsig::{TsigAlgorithm, TsigError},
+};
 use crate::serialize::binary::{BinEncodable, BinEncoder, DecodeError, NameEncoding};
 
 mod algorithm;
@@ -352,6 +355,10 @@ pub enum DnsSecError {
     /// Tsig key verification failed
     #[error("Tsig key wrong key error")]
     TsigWrongKey,
+
+    /// The server refused a TKEY key negotiation
+    #[error("TKEY error: {0:?}")]
+    TkeyError(TsigError),
 }
 
 impl From<String> for DnsSecError {
@@ -379,6 +386,7 @@ impl Clone for DnsSecError {
             RingUnspecified(_r) => RingUnspecified(ring_like::Unspecified),
             TsigUnsupportedMacAlgorithm(alg) => TsigUnsupportedMacAlgorithm(alg.clone()),
             TsigWrongKey => TsigWrongKey,
+            TkeyError(error) => TkeyError(*error),
         }
     }
 }
@@ -424,10 +432,12 @@ pub(crate) use ring_impl as ring_like;
 #[cfg(feature = "dnssec-aws-lc-rs")]
 #[cfg_attr(feature = "dnssec-ring", allow(unused_imports))]
 pub(crate) mod aws_lc_rs_impl {
+    use alloc::vec::Vec;
+
     pub(crate) use aws_lc_rs::{
-        digest,
+        agreement, digest,
         error::{KeyRejected, Unspecified},
-        hmac,
+        hkdf, hmac,
         rand::SystemRandom,
         rsa::PublicKeyComponents,
         signature::{
@@ -436,14 +446,30 @@ pub(crate) mod aws_lc_rs_impl {
             RSA_PKCS1_SHA512, RsaKeyPair,
         },
     };
+
+    /// X25519 key agreement of our ephemeral private key with the public key of the peer
+    // ring takes precedence when both backends are enabled
+    #[cfg_attr(feature = "dnssec-ring", expect(dead_code))]
+    pub(crate) fn x25519_agree(
+        private_key: agreement::EphemeralPrivateKey,
+        peer_public_key: &[u8],
+    ) -> Result<Vec<u8>, Unspecified> {
+        let peer_public_key =
+            agreement::UnparsedPublicKey::new(&agreement::X25519, peer_public_key);
+        agreement::agree_ephemeral(private_key, peer_public_key, Unspecified, |secret| {
+            Ok(secret.to_vec())
+        })
+    }
 }
 
 #[cfg(feature = "dnssec-ring")]
 pub(crate) mod ring_impl {
+    use alloc::vec::Vec;
+
     pub(crate) use ring::{
-        digest,
+        agreement, digest,
         error::{KeyRejected, Unspecified},
-        hmac,
+        hkdf, hmac,
         rand::SystemRandom,
         rsa::PublicKeyComponents,
         signature::{
@@ -452,6 +478,16 @@ pub(crate) mod ring_impl {
             RSA_PKCS1_SHA512, RsaKeyPair,
         },
     };
+
+    /// X25519 key agreement of our ephemeral private key with the public key of the peer
+    pub(crate) fn x25519_agree(
+        private_key: agreement::EphemeralPrivateKey,
+        peer_public_key: &[u8],
+    ) -> Result<Vec<u8>, Unspecified> {
+        let peer_public_key =
+            agreement::UnparsedPublicKey::new(&agreement::X25519, peer_public_key);
+        agreement::agree_ephemeral(private_key, &peer_public_key, |secret| secret.to_vec())
+    }
 }
 
 #[cfg(test)]
@@ -11,9 +11,13 @@ use core::time::Duration;
 
 use super::{DnsSecResult, SigningKey};
 use crate::{
-    dnssec::{TBS, rdata::DNSKEY},
+    dnssec::{
+        TBS,
+        rdata::{DNSKEY, SIG, SigInput},
+    },
     error::{ProtoError, ProtoResult},
-    rr::Name,
+    op::{Message, MessageSignature, MessageSigner},
+    rr::{DNSClass, Name, Record, RecordType, SerialNumber, TSigVerifier},
     serialize::binary::{BinEncodable, BinEncoder},
 };
 
@@ -374,6 +378,47 @@ impl DnssecSigner {
     }
 }
 
+/// Signs messages with SIG(0), [RFC 2931](https://tools.ietf.org/html/rfc2931)
+///
+/// The `signer_name` must be the owner name of the KEY record with the public key, which the
+/// receiver uses to verify the signature.
+impl MessageSigner for DnssecSigner {
+    fn sign_message(
+        &self,
+        message: &Message,
+        current_time: u64,
+    ) -> ProtoResult<(MessageSignature, Option<TSigVerifier>)> {
+        let current_time = current_time as u32;
+        let input = SigInput {
+            // "Such a SIG is identified by having a "type covered" field of zero."
+            type_covered: RecordType::ZERO,
+            algorithm: self.key.algorithm(),
+            num_labels: 0,
+            original_ttl: 0,
+            sig_expiration: SerialNumber(current_time.wrapping_add(SIG0_FUDGE)),
+            sig_inception: SerialNumber(current_time.wrapping_sub(SIG0_FUDGE)),
+            key_tag: self.calculate_key_tag()?,
+            signer_name: self.signer_name.clone(),
+        };
+
+        let tbs = TBS::from_sig0(&input, &message.to_vec()?)?;
+        let sig = SIG {
+            input,
+            sig: self.sign(&tbs)?,
+        };
+
+        // "For all SIG(0) RRs, the owner name, class, TTL, and original TTL, are meaningless."
+        // They are set to the root, ANY and zero as is customary.
+        let mut record = Record::from_rdata(Name::root(), 0, sig);
+        record.dns_class = DNSClass::ANY;
+
+        Ok((MessageSignature::Sig0(Box::new(record)), None))
+    }
+}
+
+/// Seconds a SIG(0) signature is valid before and after the time of signing, to allow for clock skew
+const SIG0_FUDGE: u32 = 300;
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -384,6 +429,7 @@ mod tests {
     use crate::dnssec::{
         Algorithm, PublicKey, SigningKey, TBS, crypto::RsaSigningKey, rdata::SigInput,
     };
+    use crate::op::{OpCode, Query};
     use crate::rr::rdata::{CNAME, NS};
     use crate::rr::{DNSClass, Name, RData, Record, RecordType, SerialNumber};
 
@@ -443,6 +489,55 @@ mod tests {
         assert!(pub_key.verify(tbs.as_ref(), &sig).is_ok());
     }
 
+    #[test]
+    fn test_sign_and_verify_message_sig0() {
+        let key =
+            RsaSigningKey::from_pkcs8(&PrivatePkcs8KeyDer::from(RSA_KEY), Algorithm::RSASHA256)
+                .unwrap();
+        let pub_key = key.to_public_key().unwrap();
+        let key_name = Name::parse("host.example.com.", None).unwrap();
+        let signer = DnssecSigner::new(
+            DNSKEY::from_key(&pub_key),
+            Box::new(key),
+            key_name.clone(),
+            Duration::from_secs(300),
+        );
+
+        let mut message = Message::query();
+        message.metadata.op_code = OpCode::Update;
+        message.add_query(Query::new(
+            Name::parse("example.com.", None).unwrap(),
+            RecordType::SOA,
+        ));
+        message.finalize(&signer, 1_000_000).unwrap();
+
+        let bytes = message.to_vec().unwrap();
+        let (tbs, sig) = TBS::from_sig0_message(&bytes).unwrap();
+        assert_eq!(sig.name, Name::root());
+        assert_eq!(sig.data.input().type_covered, RecordType::ZERO);
+        assert_eq!(sig.data.input().signer_name, key_name);
+        assert_eq!(
+            sig.data.input().sig_inception,
+            SerialNumber(1_000_000 - 300)
+        );
+
+        let pub_key = signer.key().to_public_key().unwrap();
+        assert!(pub_key.verify(tbs.as_ref(), sig.data.sig()).is_ok());
+
+        // the message id is covered by the signature
+        let mut tampered = bytes.clone();
+        tampered[0] ^= 0xff;
+        let (tbs, sig) = TBS::from_sig0_message(&tampered).unwrap();
+        assert!(pub_key.verify(tbs.as_ref(), sig.data.sig()).is_err());
+
+        let decoded = Message::from_vec(&bytes).unwrap();
+        assert!(matches!(
+            decoded.signature(),
+            Some(MessageSignature::Sig0(_))
+        ));
+        assert_eq!(decoded.additionals.len(), 0);
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_calculate_key_tag_pem() {
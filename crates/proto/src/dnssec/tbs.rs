@@ -7,13 +7,14 @@
 
 //! hash functions for DNSSEC operations
 
-use alloc::{borrow::ToOwned, vec::Vec};
+use alloc::{borrow::ToOwned, boxed::Box, vec::Vec};
 
-use super::rdata::sig::SigInput;
+use super::rdata::sig::{SIG, SigInput};
 use crate::{
     error::{ProtoError, ProtoResult},
+    op::{Header, Message, MessageSignature, Query},
     rr::{DNSClass, Name, Record},
-    serialize::binary::{BinEncodable, BinEncoder, NameEncoding},
+    serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, NameEncoding},
 };
 
 /// Data To Be Signed.
@@ -42,6 +43,82 @@ impl TBS {
         Self::new(name, dns_class, input, records)
     }
 
+    /// Returns the to-be-signed serialization of a SIG(0) signed message.
+    ///
+    /// [RFC 2931](https://tools.ietf.org/html/rfc2931#section-3), DNS SIG(0), September 2000
+    ///
+    /// ```text
+    ///       data = RDATA | request - SIG(0)
+    ///
+    ///    where "|" is concatenation and RDATA is the RDATA of the SIG(0) being
+    ///    calculated less the signature itself.
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - the `SigInput` of the SIG(0) record
+    /// * `message` - the wire format of the message without the SIG(0) record
+    pub fn from_sig0(input: &SigInput, message: &[u8]) -> ProtoResult<Self> {
+        let mut buf = Vec::with_capacity(message.len() + 64);
+        let mut encoder = BinEncoder::new(&mut buf);
+        input.emit(&mut encoder)?;
+        encoder.emit_slice(message)?;
+        Ok(Self(buf))
+    }
+
+    /// Splits a SIG(0) signed message into its to-be-signed serialization and the SIG(0) record.
+    ///
+    /// The SIG(0) record must be the last record of the additional section, it is removed from
+    /// the message and the additional count of the header decremented.
+    pub fn from_sig0_message(message: &[u8]) -> ProtoResult<(Self, Box<Record<SIG>>)> {
+        let mut decoder = BinDecoder::new(message);
+        let Header {
+            metadata,
+            mut counts,
+        } = Header::read(&mut decoder)?;
+
+        // Separate out the final additional SIG(0) record.
+        if counts.additionals > 0 {
+            counts.additionals -= 1;
+        } else {
+            return Err(ProtoError::from("missing SIG(0) from signed message"));
+        }
+
+        let start_data = message.len() - decoder.len();
+
+        for _ in 0..counts.queries {
+            Query::read(&mut decoder)?;
+        }
+
+        let count = (counts.answers + counts.authorities) as usize;
+        let (_, _, sig) = Message::read_records(&mut decoder, count, false, metadata.op_code)?;
+        if sig.is_some() {
+            return Err(ProtoError::from(
+                "signature record before additional section",
+            ));
+        }
+
+        let count = counts.additionals as usize;
+        let (_, _, sig) = Message::read_records(&mut decoder, count, true, metadata.op_code)?;
+        if sig.is_some() {
+            return Err(ProtoError::from("signature record is not the last record"));
+        }
+
+        let end_data = message.len() - decoder.len();
+
+        let (_, _, sig) = Message::read_records(&mut decoder, 1, true, metadata.op_code)?;
+        let Some(MessageSignature::Sig0(sig)) = sig else {
+            return Err(ProtoError::from("SIG(0) record not found"));
+        };
+
+        let mut unsigned = Vec::with_capacity(end_data);
+        let mut encoder = BinEncoder::new(&mut unsigned);
+        Header { metadata, counts }.emit(&mut encoder)?;
+        encoder.emit_slice(&message[start_data..end_data])?;
+
+        Ok((Self::from_sig0(&sig.data.input, &unsigned)?, sig))
+    }
+
     /// Returns the to-be-signed serialization of the given record set.
     ///
     /// # Arguments
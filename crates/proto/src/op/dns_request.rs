@@ -38,6 +38,9 @@ pub struct DnsRequestOptions {
     pub max_request_depth: usize,
     /// set recursion desired (or not) for any requests
     pub recursion_desired: bool,
+    /// Set the AD bit, asking for the authentic data status of the answers, see
+    /// [RFC 6840 section 5.7](https://www.rfc-editor.org/rfc/rfc6840#section-5.7)
+    pub authentic_data: bool,
     /// Randomize case of query name, and check that the response matches, for spoofing resistance.
     #[cfg(feature = "std")]
     pub case_randomization: bool,
@@ -56,6 +59,7 @@ impl Default for DnsRequestOptions {
             edns_payload_len: DEFAULT_MAX_PAYLOAD_LEN,
             edns_set_dnssec_ok: false,
             recursion_desired: true,
+            authentic_data: false,
             #[cfg(feature = "std")]
             case_randomization: false,
             // We use the default value for the retry interval floor here as a good starting point.
@@ -90,6 +94,7 @@ impl DnsRequest {
 
         message.queries.push(query);
         message.metadata.recursion_desired = options.recursion_desired;
+        message.metadata.authentic_data = options.authentic_data;
 
         if options.use_edns {
             message
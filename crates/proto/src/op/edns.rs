@@ -41,6 +41,9 @@ pub struct Edns {
     max_payload: u16,
 
     options: OPT,
+    // block length to pad the message to, not part of the wire format
+    #[cfg_attr(feature = "serde", serde(default))]
+    padding_block: Option<u16>,
 }
 
 impl Default for Edns {
@@ -51,6 +54,7 @@ impl Default for Edns {
             flags: EdnsFlags::default(),
             max_payload: 512,
             options: OPT::default(),
+            padding_block: None,
         }
     }
 }
@@ -101,6 +105,11 @@ impl Edns {
         &mut self.options
     }
 
+    /// Block length the message is padded to, if any
+    pub fn padding_block(&self) -> Option<u16> {
+        self.padding_block
+    }
+
     /// Set the high order bits for the result code.
     pub fn set_rcode_high(&mut self, rcode_high: u8) -> &mut Self {
         self.rcode_high = rcode_high;
@@ -155,6 +164,42 @@ impl Edns {
         self.max_payload = max_payload.max(512);
         self
     }
+
+    /// Pad messages carrying this EDNS to a multiple of `block` octets
+    ///
+    /// A Padding option is added when the message is encoded, so that the length of the message
+    /// up to and including the OPT record is a multiple of `block`. Signatures following the OPT
+    /// record are not accounted for. See [`QUERY_PADDING_BLOCK_LEN`] and
+    /// [`RESPONSE_PADDING_BLOCK_LEN`] for the block lengths recommended by
+    /// [RFC 8467](https://www.rfc-editor.org/rfc/rfc8467#section-4.1).
+    pub fn set_padding_block(&mut self, block: Option<u16>) -> &mut Self {
+        self.padding_block = block.filter(|block| *block > 1);
+        self
+    }
+
+    /// Returns a copy of self with a Padding option bringing a message of `message_len` octets,
+    /// without the OPT record, to a multiple of the padding block
+    pub(crate) fn padded(&self, message_len: usize) -> Self {
+        let mut edns = self.clone();
+        let Some(block) = self.padding_block else {
+            return edns;
+        };
+
+        edns.options.remove(EdnsCode::Padding);
+        // root name, type, class, TTL and RDLENGTH
+        let opt_len = 11
+            + edns
+                .options
+                .as_ref()
+                .iter()
+                .map(|(_, option)| 4 + usize::from(option.len()))
+                .sum::<usize>();
+        let unpadded = message_len + opt_len + 4;
+        let block = usize::from(block);
+        let padding = (block - unpadded % block) % block;
+        edns.options.insert(EdnsOption::Padding(padding as u16)); // cast safety: padding < block
+        edns
+    }
 }
 
 // FIXME: this should be a TryFrom
@@ -187,6 +232,7 @@ impl<'a> From<&'a Record> for Edns {
             flags,
             max_payload,
             options,
+            padding_block: None,
         }
     }
 }
@@ -284,6 +330,16 @@ impl From<EdnsFlags> for u16 {
     }
 }
 
+/// Block length for padding queries over encrypted transports
+///
+/// See [RFC 8467, section 4.1](https://www.rfc-editor.org/rfc/rfc8467#section-4.1).
+pub const QUERY_PADDING_BLOCK_LEN: u16 = 128;
+
+/// Block length for padding responses over encrypted transports
+///
+/// See [RFC 8467, section 4.1](https://www.rfc-editor.org/rfc/rfc8467#section-4.1).
+pub const RESPONSE_PADDING_BLOCK_LEN: u16 = 468;
+
 /// Default maximum payload length for EDNS messages.
 ///
 /// Per 2020 DNS flag day, default to 1232 bytes.
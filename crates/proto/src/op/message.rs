@@ -20,11 +20,14 @@ use tracing::debug;
 use tracing::warn;
 
 #[cfg(feature = "__dnssec")]
-use crate::dnssec::{DnssecIter, rdata::DNSSECRData};
+use crate::dnssec::{
+    DnssecIter,
+    rdata::{DNSSECRData, SIG},
+};
 #[cfg(any(feature = "std", feature = "no-std-rand"))]
 use crate::random;
 #[cfg(feature = "__dnssec")]
-use crate::rr::{TSigVerifier, TSigner};
+use crate::rr::TSigVerifier;
 use crate::{
     error::{ProtoError, ProtoResult},
     op::{Edns, Header, HeaderCounts, MessageType, Metadata, OpCode, Query, ResponseCode},
@@ -90,8 +93,8 @@ pub struct Message {
     pub authorities: Vec<Record>,
     /// Records which may be helpful in using the records in the other sections
     pub additionals: Vec<Record>,
-    /// TSIG signature for the message, if any
-    pub signature: Option<Box<Record<TSIG>>>,
+    /// TSIG or SIG(0) signature for the message, if any
+    pub signature: Option<MessageSignature>,
     /// [RFC 6891, EDNS(0) Extensions, April 2013](https://tools.ietf.org/html/rfc6891#section-6.1.1)
     ///
     /// ```text
@@ -327,13 +330,13 @@ impl Message {
         self
     }
 
-    /// Set the TSIG signature record for the message.
+    /// Set the TSIG or SIG(0) signature record for the message.
     ///
     /// This must be used only after all records have been associated. Generally this will be
     /// handled by the client and not need to be used directly
     #[cfg(feature = "__dnssec")]
-    pub fn set_signature(&mut self, sig: Box<Record<TSIG>>) -> &mut Self {
-        self.signature = Some(sig);
+    pub fn set_signature(&mut self, sig: impl Into<MessageSignature>) -> &mut Self {
+        self.signature = Some(sig.into());
         self
     }
 
@@ -385,12 +388,12 @@ impl Message {
     /// # Return value
     ///
     /// the signature over the message, if any
-    pub fn signature(&self) -> Option<&Record<TSIG>> {
-        self.signature.as_deref()
+    pub fn signature(&self) -> Option<&MessageSignature> {
+        self.signature.as_ref()
     }
 
     /// Remove signatures from the Message
-    pub fn take_signature(&mut self) -> Option<Box<Record<TSIG>>> {
+    pub fn take_signature(&mut self) -> Option<MessageSignature> {
         self.signature.take()
     }
 
@@ -408,11 +411,11 @@ impl Message {
     /// # Returns
     ///
     /// This returns a tuple of first standard Records, then a possibly associated Edns, and then
-    /// finally a `Record<TSIG>` if applicable.
+    /// finally a TSIG or SIG(0) `MessageSignature` if applicable.
     ///
-    /// A `Record<TSIG>` record is only valid when found in the additional data section.
+    /// A TSIG or SIG(0) record is only valid when found in the additional data section.
     /// Further, it must always be the last record in that section. It is not possible to have
-    /// multiple TSIG records.
+    /// multiple signature records.
     ///
     /// RFC 8945 §5.1 says:
     ///  "This TSIG record MUST be the only TSIG RR in the message and MUST be the last record in
@@ -424,7 +427,7 @@ impl Message {
         count: usize,
         is_additional: bool,
         op: OpCode,
-    ) -> Result<(Vec<Record>, Option<Edns>, Option<Box<Record<TSIG>>>), DecodeError> {
+    ) -> Result<(Vec<Record>, Option<Edns>, Option<MessageSignature>), DecodeError> {
         let mut records: Vec<Record> = Vec::with_capacity(count);
         let mut edns: Option<Edns> = None;
         let mut sig = None;
@@ -458,24 +461,31 @@ impl Message {
                 continue;
             }
 
-            match record.data {
+            match &record.data {
+                // SIG(0) covers no type, see RFC 2931 section 3
                 #[cfg(feature = "__dnssec")]
-                RData::DNSSEC(DNSSECRData::SIG(_)) => {
-                    warn!(
-                        "message was SIG(0) signed, but support for SIG(0) message authentication was removed from hickory-dns"
-                    );
-                    records.push(record);
+                RData::DNSSEC(DNSSECRData::SIG(rdata))
+                    if rdata.input().type_covered == RecordType::ZERO =>
+                {
+                    sig = Some(MessageSignature::Sig0(Box::new(
+                        record
+                            .map(|data| match data {
+                                RData::DNSSEC(DNSSECRData::SIG(sig)) => Some(sig),
+                                _ => None,
+                            })
+                            .unwrap(/* match arm ensures correct type */),
+                    )))
                 }
                 #[cfg(feature = "__dnssec")]
                 RData::TSIG(_) => {
-                    sig = Some(Box::new(
+                    sig = Some(MessageSignature::Tsig(Box::new(
                         record
                             .map(|data| match data {
                                 RData::TSIG(tsig) => Some(tsig),
                                 _ => None,
                             })
                             .unwrap(/* match arm ensures correct type */),
-                    ))
+                    )))
                 }
                 RData::Update0(RecordType::OPT) | RData::OPT(_) => {
                     if edns.is_some() {
@@ -516,9 +526,9 @@ impl Message {
     ///
     /// Subsequent to calling this, the Message should not change.
     #[cfg(feature = "__dnssec")]
-    pub fn finalize(
+    pub fn finalize<S: MessageSigner + ?Sized>(
         &mut self,
-        finalizer: &TSigner,
+        finalizer: &S,
         inception_time: u64,
     ) -> ProtoResult<Option<TSigVerifier>> {
         debug!("finalizing message: {:?}", self);
@@ -530,6 +540,86 @@ impl Message {
     }
 }
 
+/// A TSIG or SIG(0) signature over a whole message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub enum MessageSignature {
+    /// [RFC 8945](https://tools.ietf.org/html/rfc8945) TSIG, a shared secret HMAC signature
+    Tsig(Box<Record<TSIG>>),
+    /// [RFC 2931](https://tools.ietf.org/html/rfc2931) SIG(0), a public key signature
+    #[cfg(feature = "__dnssec")]
+    Sig0(Box<Record<SIG>>),
+}
+
+impl MessageSignature {
+    /// Returns the TSIG record, if this is a TSIG signature
+    pub fn tsig(&self) -> Option<&Record<TSIG>> {
+        match self {
+            Self::Tsig(tsig) => Some(tsig),
+            #[cfg(feature = "__dnssec")]
+            Self::Sig0(_) => None,
+        }
+    }
+
+    /// Returns the SIG record, if this is a SIG(0) signature
+    #[cfg(feature = "__dnssec")]
+    pub fn sig0(&self) -> Option<&Record<SIG>> {
+        match self {
+            Self::Sig0(sig) => Some(sig),
+            Self::Tsig(_) => None,
+        }
+    }
+}
+
+impl From<Box<Record<TSIG>>> for MessageSignature {
+    fn from(tsig: Box<Record<TSIG>>) -> Self {
+        Self::Tsig(tsig)
+    }
+}
+
+#[cfg(feature = "__dnssec")]
+impl From<Box<Record<SIG>>> for MessageSignature {
+    fn from(sig: Box<Record<SIG>>) -> Self {
+        Self::Sig0(sig)
+    }
+}
+
+impl BinEncodable for MessageSignature {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        match self {
+            Self::Tsig(tsig) => tsig.emit(encoder),
+            #[cfg(feature = "__dnssec")]
+            Self::Sig0(sig) => sig.emit(encoder),
+        }
+    }
+}
+
+/// Types which sign messages before they are sent, e.g. TSIG or SIG(0) signers
+#[cfg(feature = "__dnssec")]
+pub trait MessageSigner: Send + Sync {
+    /// Signs the message, returning the signature and a verifier for the response, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the message to sign, without a signature
+    /// * `current_time` - the current time, in seconds since the Unix epoch
+    fn sign_message(
+        &self,
+        message: &Message,
+        current_time: u64,
+    ) -> ProtoResult<(MessageSignature, Option<TSigVerifier>)>;
+
+    /// Returns true if the message should be signed, by default updates, notifies, zone
+    /// transfers and TKEY key negotiations are signed
+    fn should_sign_message(&self, message: &Message) -> bool {
+        [OpCode::Update, OpCode::Notify].contains(&message.op_code)
+            || message.queries.iter().any(|q| {
+                [RecordType::AXFR, RecordType::IXFR, RecordType::TKEY].contains(&q.query_type)
+            })
+    }
+}
+
 impl Deref for Message {
     type Target = Metadata;
 
@@ -585,7 +675,7 @@ pub fn emit_message_parts<Q, A, N, D>(
     authorities: &mut N,
     additionals: &mut D,
     edns: Option<&Edns>,
-    signature: Option<&Record<TSIG>>,
+    signature: Option<&MessageSignature>,
     encoder: &mut BinEncoder<'_>,
 ) -> ProtoResult<Header>
 where
@@ -594,6 +684,7 @@ where
     N: EmitAndCount,
     D: EmitAndCount,
 {
+    let start = encoder.offset;
     let place = encoder.place::<Header>()?;
 
     let query_count = queries.emit(encoder)?;
@@ -603,7 +694,7 @@ where
     let authority_count = count_was_truncated(authorities.emit(encoder))?;
     let mut additional_count = count_was_truncated(additionals.emit(encoder))?;
 
-    if let Some(mut edns) = edns.cloned() {
+    if let Some(mut edns) = edns.map(|edns| edns.padded(encoder.offset - start)) {
         // need to commit the error code
         edns.set_rcode_high(metadata.response_code.high());
 
@@ -663,7 +754,7 @@ impl BinEncodable for Message {
             &mut self.authorities.iter(),
             &mut self.additionals.iter(),
             self.edns.as_ref(),
-            self.signature.as_deref(),
+            self.signature.as_ref(),
             encoder,
         )?;
 
@@ -763,11 +854,20 @@ impl fmt::Display for Message {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "__dnssec")]
+    use crate::dnssec::{
+        Algorithm,
+        rdata::{SIG, SigInput},
+    };
+    #[cfg(feature = "std")]
+    use crate::op::QUERY_PADDING_BLOCK_LEN;
+    #[cfg(feature = "__dnssec")]
+    use crate::rr::SerialNumber;
     use crate::rr::rdata::A;
     #[cfg(feature = "std")]
     use crate::rr::rdata::OPT;
     #[cfg(feature = "std")]
-    use crate::rr::rdata::opt::{ClientSubnet, EdnsCode, EdnsOption};
+    use crate::rr::rdata::opt::{ClientSubnet, EdnsCode, EdnsOption, TcpKeepalive};
     #[cfg(feature = "__dnssec")]
     use crate::rr::rdata::{TSIG, tsig::TsigAlgorithm};
     use crate::rr::{Name, RData};
@@ -868,6 +968,35 @@ mod tests {
         Message::read(&mut decoder).unwrap()
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_padding_block() {
+        for name in [
+            "a.",
+            "www.example.com.",
+            "a.very.long.name.below.example.com.",
+        ] {
+            let mut message = Message::query();
+            message.add_query(Query::new(Name::from_ascii(name).unwrap(), RecordType::A));
+            let mut edns = Edns::new();
+            edns.options_mut()
+                .insert(EdnsOption::Keepalive(TcpKeepalive::default()));
+            edns.set_padding_block(Some(QUERY_PADDING_BLOCK_LEN));
+            message.edns = Some(edns);
+
+            let bytes = message.to_vec().unwrap();
+            assert_eq!(bytes.len() % usize::from(QUERY_PADDING_BLOCK_LEN), 0);
+
+            let got = Message::from_vec(&bytes).unwrap();
+            let edns = got.edns.expect("edns not found");
+            assert!(matches!(
+                edns.option(EdnsCode::Padding),
+                Some(EdnsOption::Padding(_))
+            ));
+            assert!(edns.option(EdnsCode::Keepalive).is_some());
+        }
+    }
+
     #[test]
     fn test_legit_message() {
         #[rustfmt::skip]
@@ -1242,11 +1371,36 @@ mod tests {
         assert!(error.contains("record after TSIG or SIG(0)"));
     }
 
-    #[expect(clippy::type_complexity)]
+    #[cfg(all(feature = "std", feature = "__dnssec"))]
+    #[test]
+    fn test_read_records_sig_covering_type() {
+        let a_record = Record::from_rdata(
+            Name::from_labels(vec!["example", "com"]).unwrap(),
+            300,
+            RData::A(A::new(127, 0, 0, 1)),
+        );
+        let sig_record = Record::from_rdata(
+            Name::from_labels(vec!["example", "com"]).unwrap(),
+            300,
+            fake_sig(RecordType::A),
+        );
+        let (records, edns, signature) =
+            encode_and_read_records(vec![a_record.clone(), sig_record.clone()], true).unwrap();
+        assert_eq!(records, [a_record.clone(), sig_record]);
+        assert!(edns.is_none());
+        assert!(signature.is_none());
+
+        let sig0_record = Record::from_rdata(Name::root(), 0, fake_sig(RecordType::ZERO));
+        let (records, _, signature) =
+            encode_and_read_records(vec![a_record.clone(), sig0_record], true).unwrap();
+        assert_eq!(records, [a_record]);
+        assert!(matches!(signature, Some(MessageSignature::Sig0(_))));
+    }
+
     fn encode_and_read_records(
         records: Vec<Record>,
         is_additional: bool,
-    ) -> ProtoResult<(Vec<Record>, Option<Edns>, Option<Box<Record<TSIG>>>)> {
+    ) -> ProtoResult<(Vec<Record>, Option<Edns>, Option<MessageSignature>)> {
         let mut bytes = Vec::new();
         let mut encoder = BinEncoder::new(&mut bytes);
         encoder.emit_iter(records.iter())?;
@@ -1270,4 +1424,21 @@ mod tests {
             vec![],
         ))
     }
+
+    #[cfg(feature = "__dnssec")]
+    fn fake_sig(type_covered: RecordType) -> RData {
+        RData::DNSSEC(DNSSECRData::SIG(SIG {
+            input: SigInput {
+                type_covered,
+                algorithm: Algorithm::ED25519,
+                num_labels: 0,
+                original_ttl: 0,
+                sig_expiration: SerialNumber(2),
+                sig_inception: SerialNumber(1),
+                key_tag: 5,
+                signer_name: Name::from_labels(vec!["example", "com"]).unwrap(),
+            },
+            sig: vec![0; 64],
+        }))
+    }
 }
@@ -8,10 +8,12 @@
 use alloc::{boxed::Box, vec::Vec};
 use core::ops::Deref;
 
-use super::{Edns, EmitAndCount, Header, LowerQuery, Message, Metadata, emit_message_parts};
+use super::{
+    Edns, EmitAndCount, Header, LowerQuery, Message, MessageSignature, Metadata, emit_message_parts,
+};
 use crate::{
     error::ProtoError,
-    rr::{Record, rdata::TSIG},
+    rr::Record,
     serialize::binary::{
         BinDecodable, BinDecoder, BinEncodable, BinEncoder, DecodeError, NameEncoding,
     },
@@ -32,8 +34,8 @@ pub struct MessageRequest {
     pub authorities: Vec<Record>,
     /// Records which may be helpful in using the records in the other sections
     pub additionals: Vec<Record>,
-    /// TSIG signature for the message, if any
-    pub signature: Option<Box<Record<TSIG>>>,
+    /// TSIG or SIG(0) signature for the message, if any
+    pub signature: Option<MessageSignature>,
     /// [RFC 6891, EDNS(0) Extensions, April 2013](https://tools.ietf.org/html/rfc6891#section-6.1.1)
     ///
     /// ```text
@@ -155,7 +157,7 @@ impl BinEncodable for MessageRequest {
             &mut self.authorities.iter(),
             &mut self.additionals.iter(),
             self.edns.as_ref(),
-            self.signature.as_deref(),
+            self.signature.as_ref(),
             encoder,
         )?;
 
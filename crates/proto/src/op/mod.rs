@@ -26,7 +26,9 @@ mod dns_response;
 pub use dns_response::DnsResponse;
 
 mod edns;
-pub use edns::{DEFAULT_MAX_PAYLOAD_LEN, Edns, EdnsFlags};
+pub use edns::{
+    DEFAULT_MAX_PAYLOAD_LEN, Edns, EdnsFlags, QUERY_PADDING_BLOCK_LEN, RESPONSE_PADDING_BLOCK_LEN,
+};
 
 mod header;
 pub use header::{Flags, Header, HeaderCounts, MessageType, Metadata};
@@ -35,7 +37,9 @@ mod lower_query;
 pub use lower_query::LowerQuery;
 
 mod message;
-pub use message::{EmitAndCount, Message, emit_message_parts};
+#[cfg(feature = "__dnssec")]
+pub use message::MessageSigner;
+pub use message::{EmitAndCount, Message, MessageSignature, emit_message_parts};
 
 mod message_request;
 pub use message_request::{MessageRequest, Queries, QueriesEmitAndCount};
@@ -16,8 +16,8 @@ use crate::{
     rr::{DNSClass, Name, RData, RecordSet, RecordType, rdata::SOA},
 };
 use crate::{
-    op::{Message, Query},
-    rr::{Record, rdata::TSIG},
+    op::{Message, MessageSignature, Query},
+    rr::Record,
 };
 
 /// To reduce errors in using the Message struct as an Update, this will do the call throughs
@@ -69,7 +69,7 @@ pub trait UpdateMessage: Debug {
     /// Return the message's signature (if any)
     ///
     /// This is used to authenticate update messages.
-    fn signature(&self) -> Option<&Record<TSIG>>;
+    fn signature(&self) -> Option<&MessageSignature>;
 }
 
 /// to reduce errors in using the Message struct as an Update, this will do the call throughs
@@ -127,7 +127,7 @@ impl UpdateMessage for Message {
         &self.additionals
     }
 
-    fn signature(&self) -> Option<&Record<TSIG>> {
+    fn signature(&self) -> Option<&MessageSignature> {
         self.signature()
     }
 }
@@ -611,7 +611,7 @@ pub trait UpdateRequest {
     fn additionals(&self) -> &[Record];
 
     /// Signature for verifying the Message
-    fn signature(&self) -> Option<&Record<TSIG>>;
+    fn signature(&self) -> Option<&MessageSignature>;
 }
 
 impl UpdateRequest for MessageRequest {
@@ -636,7 +636,7 @@ impl UpdateRequest for MessageRequest {
         &self.additionals
     }
 
-    fn signature(&self) -> Option<&Record<TSIG>> {
-        self.signature.as_deref()
+    fn signature(&self) -> Option<&MessageSignature> {
+        self.signature.as_ref()
     }
 }
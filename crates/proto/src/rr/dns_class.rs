@@ -63,7 +63,11 @@ impl FromStr for DNSClass {
             "HS" => Ok(Self::HS),
             "NONE" => Ok(Self::NONE),
             "ANY" | "*" => Ok(Self::ANY),
-            _ => Err(DecodeError::UnknownDnsClassStr(str.to_owned())),
+            // RFC 3597 generic class mnemonic, e.g. `CLASS32`
+            _ => match str.strip_prefix("CLASS").map(u16::from_str) {
+                Some(Ok(code)) => Ok(Self::from(code)),
+                _ => Err(DecodeError::UnknownDnsClassStr(str.to_owned())),
+            },
         }
     }
 }
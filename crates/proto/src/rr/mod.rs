@@ -45,6 +45,11 @@ pub use rr_set::{RecordSet, RrsetRecords};
 pub(crate) mod serial_number;
 pub use serial_number::SerialNumber;
 
+#[cfg(feature = "__dnssec")]
+mod tkey;
+#[cfg(feature = "__dnssec")]
+pub use tkey::{TKeyExchange, accept_tkey, tkey_error, tkey_record};
+
 mod tsig;
 #[cfg(feature = "__dnssec")]
 pub use tsig::TSigVerifier;
@@ -28,9 +28,11 @@ pub mod soa;
 pub mod srv;
 pub mod sshfp;
 pub mod svcb;
+pub mod tkey;
 pub mod tlsa;
 pub mod tsig;
 pub mod txt;
+pub mod zonemd;
 
 pub use self::a::A;
 pub use self::aaaa::AAAA;
@@ -50,6 +52,8 @@ pub use self::soa::SOA;
 pub use self::srv::SRV;
 pub use self::sshfp::SSHFP;
 pub use self::svcb::SVCB;
+pub use self::tkey::TKEY;
 pub use self::tlsa::TLSA;
 pub use self::tsig::TSIG;
 pub use self::txt::TXT;
+pub use self::zonemd::ZONEMD;
@@ -8,11 +8,12 @@
 //! option record for passing protocol options between the client and server
 #![allow(clippy::use_self)]
 
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 use core::fmt;
 use core::hash::{Hash, Hasher};
 use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use core::str::FromStr;
+use core::time::Duration;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -483,6 +484,15 @@ pub enum EdnsOption {
     /// [RFC 5001, DNS Name Server Identifier (NSID) Option](https://tools.ietf.org/html/rfc5001)
     NSID(NSIDPayload),
 
+    /// [RFC 7828, The edns-tcp-keepalive EDNS0 Option](https://tools.ietf.org/html/rfc7828)
+    Keepalive(TcpKeepalive),
+
+    /// [RFC 7830, The EDNS(0) Padding Option](https://tools.ietf.org/html/rfc7830), the number
+    /// of padding octets
+    ///
+    /// Padding is emitted as zero octets, the content of received padding is ignored.
+    Padding(u16),
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16, Vec<u8>),
 }
@@ -495,7 +505,9 @@ impl EdnsOption {
             EdnsOption::DAU(algorithms) => algorithms.len(),
             EdnsOption::Subnet(subnet) => subnet.len(),
             EdnsOption::NSID(payload) => payload.as_ref().len() as u16, // cast safety: NSIDPayload size is constrained.
-            EdnsOption::Unknown(_, data) => data.len() as u16,          // TODO: should we verify?
+            EdnsOption::Keepalive(keepalive) => keepalive.len(),
+            EdnsOption::Padding(len) => *len,
+            EdnsOption::Unknown(_, data) => data.len() as u16, // TODO: should we verify?
         }
     }
 
@@ -506,6 +518,8 @@ impl EdnsOption {
             EdnsOption::DAU(algorithms) => algorithms.is_empty(),
             EdnsOption::Subnet(subnet) => subnet.is_empty(),
             EdnsOption::NSID(payload) => payload.as_ref().is_empty(),
+            EdnsOption::Keepalive(keepalive) => keepalive.len() == 0,
+            EdnsOption::Padding(len) => *len == 0,
             EdnsOption::Unknown(_, data) => data.is_empty(),
         }
     }
@@ -518,6 +532,8 @@ impl BinEncodable for EdnsOption {
             EdnsOption::DAU(algorithms) => algorithms.emit(encoder),
             EdnsOption::Subnet(subnet) => subnet.emit(encoder),
             EdnsOption::NSID(payload) => encoder.emit_slice(payload.as_ref()),
+            EdnsOption::Keepalive(keepalive) => keepalive.emit(encoder),
+            EdnsOption::Padding(len) => encoder.emit_slice(&vec![0; usize::from(*len)]),
             EdnsOption::Unknown(_, data) => encoder.emit_slice(data), // gah, clone needed or make a crazy api.
         }
     }
@@ -533,6 +549,13 @@ impl<'a> TryFrom<(EdnsCode, &'a [u8])> for EdnsOption {
             EdnsCode::DAU => Self::DAU(value.1.into()),
             EdnsCode::Subnet => Self::Subnet(value.1.try_into()?),
             EdnsCode::NSID => Self::NSID(value.1.try_into()?),
+            EdnsCode::Keepalive => Self::Keepalive(value.1.try_into()?),
+            EdnsCode::Padding => Self::Padding(u16::try_from(value.1.len()).map_err(|_| {
+                DecodeError::IncorrectRDataLengthRead {
+                    read: value.1.len(),
+                    len: u16::MAX as usize,
+                }
+            })?),
             _ => Self::Unknown(value.0.into(), value.1.to_vec()),
         })
     }
@@ -547,6 +570,10 @@ impl<'a> TryFrom<&'a EdnsOption> for Vec<u8> {
             EdnsOption::DAU(algorithms) => algorithms.into(),
             EdnsOption::Subnet(subnet) => subnet.try_into()?,
             EdnsOption::NSID(payload) => payload.as_ref().to_vec(),
+            EdnsOption::Keepalive(keepalive) => keepalive
+                .timeout
+                .map_or_else(Vec::new, |timeout| timeout.to_be_bytes().to_vec()),
+            EdnsOption::Padding(len) => vec![0; usize::from(*len)],
             EdnsOption::Unknown(_, data) => data.clone(), // gah, clone needed or make a crazy api.
         })
     }
@@ -559,6 +586,8 @@ impl<'a> From<&'a EdnsOption> for EdnsCode {
             EdnsOption::DAU(..) => Self::DAU,
             EdnsOption::Subnet(..) => Self::Subnet,
             EdnsOption::NSID(..) => Self::NSID,
+            EdnsOption::Keepalive(..) => Self::Keepalive,
+            EdnsOption::Padding(..) => Self::Padding,
             EdnsOption::Unknown(code, _) => (*code).into(),
         }
     }
@@ -844,6 +873,69 @@ impl AsRef<[u8]> for NSIDPayload {
     }
 }
 
+/// The edns-tcp-keepalive option payload
+///
+/// Sent without a timeout in queries, to signal that the client wants to keep the connection
+/// open, and with the idle timeout of the server in responses.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct TcpKeepalive {
+    /// Idle timeout, in units of 100 milliseconds
+    timeout: Option<u16>,
+}
+
+impl TcpKeepalive {
+    /// Construct a keepalive option with an optional idle timeout
+    ///
+    /// The timeout is rounded down to the resolution of 100 milliseconds and saturates at
+    /// `u16::MAX` units, about 109 minutes.
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout: timeout
+                .map(|timeout| u16::try_from(timeout.as_millis() / 100).unwrap_or(u16::MAX)),
+        }
+    }
+
+    /// The idle timeout, if any
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+            .map(|timeout| Duration::from_millis(u64::from(timeout) * 100))
+    }
+
+    fn len(&self) -> u16 {
+        match self.timeout {
+            Some(_) => 2,
+            None => 0,
+        }
+    }
+}
+
+impl BinEncodable for TcpKeepalive {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        match self.timeout {
+            Some(timeout) => timeout.emit(encoder),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for TcpKeepalive {
+    type Error = DecodeError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        match *value {
+            [] => Ok(Self { timeout: None }),
+            [high, low] => Ok(Self {
+                timeout: Some(u16::from_be_bytes([high, low])),
+            }),
+            _ => Err(DecodeError::IncorrectRDataLengthRead {
+                read: value.len(),
+                len: 2,
+            }),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -898,7 +990,10 @@ mod tests {
                 EdnsCode::Cookie,
                 EdnsOption::Unknown(10, vec![0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f]),
             ),
-            (EdnsCode::Keepalive, EdnsOption::Unknown(11, vec![])),
+            (
+                EdnsCode::Keepalive,
+                EdnsOption::Keepalive(TcpKeepalive::default()),
+            ),
         ];
         let options = OPT::new(options);
         assert_eq!(opt, options);
@@ -977,6 +1072,36 @@ mod tests {
         assert_eq!(payload_in, payload_out);
     }
 
+    #[test]
+    fn test_keepalive_roundtrip() {
+        let keepalive = TcpKeepalive::new(Some(Duration::from_millis(12_345)));
+        assert_eq!(keepalive.timeout(), Some(Duration::from_millis(12_300)));
+
+        let option = EdnsOption::Keepalive(keepalive);
+        let bytes = Vec::<u8>::try_from(&option).unwrap();
+        assert_eq!(bytes, [0x00, 0x7b]);
+        assert_eq!(
+            EdnsOption::try_from((EdnsCode::Keepalive, bytes.as_slice())).unwrap(),
+            option
+        );
+
+        let err = TcpKeepalive::try_from([0x00].as_slice()).unwrap_err();
+        assert!(matches!(err, DecodeError::IncorrectRDataLengthRead { .. }));
+    }
+
+    #[test]
+    fn test_padding_roundtrip() {
+        let option = EdnsOption::Padding(3);
+        let mut buf = Vec::new();
+        let mut encoder = BinEncoder::new(&mut buf);
+        option.emit(&mut encoder).unwrap();
+        assert_eq!(buf, [0, 0, 0]);
+
+        // the content of padding is ignored
+        let decoded = EdnsOption::try_from((EdnsCode::Padding, [1, 2, 3].as_slice())).unwrap();
+        assert_eq!(decoded, option);
+    }
+
     #[test]
     fn test_eq_and_hash() {
         let options_1 = OPT::new(vec![
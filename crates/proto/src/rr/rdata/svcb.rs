@@ -260,6 +260,7 @@ fn parse_value(key: SvcParamKey, value: Option<&str>) -> Result<SvcParamValue, P
         SvcParamKey::Ipv4Hint => parse_ipv4_hint(value),
         SvcParamKey::Ipv6Hint => parse_ipv6_hint(value),
         SvcParamKey::EchConfigList => parse_ech_config(value),
+        SvcParamKey::DohPath => parse_doh_path(value),
         SvcParamKey::Key(_) => parse_unknown(value),
         SvcParamKey::Key65535 | SvcParamKey::Unknown(_) => Err(ParseError::Message(
             "Bad Key type or unsupported, see generic key option, e.g. key1234",
@@ -409,6 +410,20 @@ fn parse_ech_config(value: Option<&str>) -> Result<SvcParamValue, ParseError> {
     )))
 }
 
+/// [RFC 9461 Service Binding Mapping for DNS Servers, Nov 2023](https://datatracker.ietf.org/doc/html/rfc9461#section-5)
+///
+/// ```text
+///   The value of the "dohpath" SvcParamValue is a URI Template [RFC6570]
+///   that MUST be relative (i.e., it MUST NOT begin with a scheme).  It
+///   MUST be encoded as a UTF-8 string with no escapes.
+/// ```
+fn parse_doh_path(value: Option<&str>) -> Result<SvcParamValue, ParseError> {
+    let value = value.ok_or(ParseError::Message("expected a URI template for dohpath"))?;
+
+    let value = parse_char_data(value)?;
+    Ok(SvcParamValue::DohPath(value))
+}
+
 ///  [RFC 9460 SVCB and HTTPS Resource Records, Nov 2023](https://datatracker.ietf.org/doc/html/rfc9460#section-2.1)
 ///
 /// ```text
@@ -516,6 +531,9 @@ where
 ///    |           |                 | hints          |Section  |          |
 ///    |           |                 |                |7.3      |          |
 ///    +-----------+-----------------+----------------+---------+----------+
+///    |     7     | dohpath         | DNS over HTTPS |RFC 9461 |IETF      |
+///    |           |                 | path template  |         |          |
+///    +-----------+-----------------+----------------+---------+----------+
 ///    |65280-65534| N/A             | Reserved for   |RFC 9460 |IETF      |
 ///    |           |                 | Private Use    |         |          |
 ///    +-----------+-----------------+----------------+---------+----------+
@@ -552,6 +570,9 @@ pub enum SvcParamKey {
     /// IPv6 address hints
     #[cfg_attr(feature = "serde", serde(rename = "ipv6hint"))]
     Ipv6Hint,
+    /// URI template of DNS over HTTPS queries
+    #[cfg_attr(feature = "serde", serde(rename = "dohpath"))]
+    DohPath,
     /// Private Use
     Key(u16),
     /// Reserved ("Invalid key")
@@ -570,6 +591,7 @@ impl From<u16> for SvcParamKey {
             4 => Self::Ipv4Hint,
             5 => Self::EchConfigList,
             6 => Self::Ipv6Hint,
+            7 => Self::DohPath,
             65280..=65534 => Self::Key(val),
             65535 => Self::Key65535,
             _ => Self::Unknown(val),
@@ -587,6 +609,7 @@ impl From<SvcParamKey> for u16 {
             SvcParamKey::Ipv4Hint => 4,
             SvcParamKey::EchConfigList => 5,
             SvcParamKey::Ipv6Hint => 6,
+            SvcParamKey::DohPath => 7,
             SvcParamKey::Key(val) => val,
             SvcParamKey::Key65535 => 65535,
             SvcParamKey::Unknown(val) => val,
@@ -620,6 +643,7 @@ impl fmt::Display for SvcParamKey {
             Self::Ipv4Hint => f.write_str("ipv4hint")?,
             Self::EchConfigList => f.write_str("ech")?,
             Self::Ipv6Hint => f.write_str("ipv6hint")?,
+            Self::DohPath => f.write_str("dohpath")?,
             Self::Key(val) => write!(f, "key{val}")?,
             Self::Key65535 => f.write_str("key65535")?,
             Self::Unknown(val) => write!(f, "unknown{val}")?,
@@ -650,6 +674,7 @@ impl FromStr for SvcParamKey {
             "ipv4hint" => Self::Ipv4Hint,
             "ech" => Self::EchConfigList,
             "ipv6hint" => Self::Ipv6Hint,
+            "dohpath" => Self::DohPath,
             "key65535" => Self::Key65535,
             _ => parse_unknown_key(s)?,
         };
@@ -763,6 +788,16 @@ pub enum SvcParamValue {
     /// See `IpHint`
     #[cfg_attr(feature = "serde", serde(rename = "ipv6hint"))]
     Ipv6Hint(IpHint<AAAA>),
+    /// [RFC 9461 Service Binding Mapping for DNS Servers, Nov 2023](https://datatracker.ietf.org/doc/html/rfc9461#section-5)
+    ///
+    /// ```text
+    ///   DNS servers can use the "dohpath" SvcParamKey to indicate that they
+    ///   support DoH and the URI Template to use for DoH requests.
+    /// ```
+    ///
+    /// The template, e.g. `/dns-query{?dns}`, is relative to the target name and port of the record.
+    #[cfg_attr(feature = "serde", serde(rename = "dohpath"))]
+    DohPath(String),
     /// Unparsed network data. Refer to documents on the associated key value
     ///
     /// This will be left as is when read off the wire, and encoded in bas64
@@ -807,6 +842,11 @@ impl SvcParamValue {
             SvcParamKey::Ipv4Hint => Self::Ipv4Hint(IpHint::<A>::read(&mut decoder)?),
             SvcParamKey::EchConfigList => Self::EchConfigList(EchConfigList::read(&mut decoder)?),
             SvcParamKey::Ipv6Hint => Self::Ipv6Hint(IpHint::<AAAA>::read(&mut decoder)?),
+            // The wire format of the SvcParamValue is the template as UTF-8, without escapes.
+            SvcParamKey::DohPath => {
+                let template = decoder.read_vec(len)?.unverified(/*validated as UTF-8*/);
+                Self::DohPath(String::from_utf8(template)?)
+            }
             SvcParamKey::Key(_) | SvcParamKey::Key65535 | SvcParamKey::Unknown(_) => {
                 Self::Unknown(Unknown::read(&mut decoder)?)
             }
@@ -832,6 +872,7 @@ impl BinEncodable for SvcParamValue {
             Self::Ipv4Hint(ip_hint) => ip_hint.emit(encoder)?,
             Self::EchConfigList(ech_config) => ech_config.emit(encoder)?,
             Self::Ipv6Hint(ip_hint) => ip_hint.emit(encoder)?,
+            Self::DohPath(template) => encoder.emit_slice(template.as_bytes())?,
             Self::Unknown(unknown) => unknown.emit(encoder)?,
         }
 
@@ -854,6 +895,7 @@ impl fmt::Display for SvcParamValue {
             Self::Ipv4Hint(ip_hint) => write!(f, "{ip_hint}")?,
             Self::EchConfigList(ech_config) => write!(f, "{ech_config}")?,
             Self::Ipv6Hint(ip_hint) => write!(f, "{ip_hint}")?,
+            Self::DohPath(template) => f.write_str(template)?,
             Self::Unknown(unknown) => write!(f, "{unknown}")?,
         }
 
@@ -1513,6 +1555,7 @@ mod tests {
         assert_eq!(SvcParamKey::Ipv4Hint, 4.into());
         assert_eq!(SvcParamKey::EchConfigList, 5.into());
         assert_eq!(SvcParamKey::Ipv6Hint, 6.into());
+        assert_eq!(SvcParamKey::DohPath, 7.into());
         assert_eq!(SvcParamKey::Key(65280), 65280.into());
         assert_eq!(SvcParamKey::Key(65534), 65534.into());
         assert_eq!(SvcParamKey::Key65535, 65535.into());
@@ -1528,6 +1571,7 @@ mod tests {
         assert_eq!(u16::from(SvcParamKey::Ipv4Hint), 4);
         assert_eq!(u16::from(SvcParamKey::EchConfigList), 5);
         assert_eq!(u16::from(SvcParamKey::Ipv6Hint), 6);
+        assert_eq!(u16::from(SvcParamKey::DohPath), 7);
         assert_eq!(u16::from(SvcParamKey::Key(65280)), 65280);
         assert_eq!(u16::from(SvcParamKey::Key(65534)), 65534);
         assert_eq!(u16::from(SvcParamKey::Key65535), 65535);
@@ -1575,6 +1619,20 @@ mod tests {
                 ),
             ],
         ));
+        test_encode_decode(SVCB::new(
+            1,
+            Name::from_utf8("dns.example.com.").unwrap(),
+            vec![
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["h2".to_string()])),
+                ),
+                (
+                    SvcParamKey::DohPath,
+                    SvcParamValue::DohPath("/dns-query{?dns}".to_string()),
+                ),
+            ],
+        ));
     }
 
     #[test]
@@ -1722,6 +1780,23 @@ mod tests {
         assert_eq!(svcb, svcb_display);
     }
 
+    #[test]
+    fn test_parse_doh_path() {
+        let svcb: SVCB = parse_record(
+            "_dns.resolver.arpa. 7200 IN SVCB 1 doh.example.net. alpn=h2 dohpath=/dns-query{?dns}",
+        );
+
+        let (key, value) = &svcb.svc_params[1];
+        assert_eq!(*key, SvcParamKey::DohPath);
+        assert_eq!(
+            *value,
+            SvcParamValue::DohPath("/dns-query{?dns}".to_owned())
+        );
+
+        let display = format!("_dns.resolver.arpa. 7200 IN SVCB {svcb}");
+        assert_eq!(svcb, parse_record::<SVCB>(&display));
+    }
+
     /// sanity check for https
     #[test]
     fn test_parsing_https() {
@@ -0,0 +1,327 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TKEY for establishing secret keys for TSIG
+#![allow(clippy::use_self)]
+
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::tsig::{TsigAlgorithm, TsigError};
+use crate::{
+    error::{ProtoError, ProtoResult},
+    rr::{RData, RecordData, RecordDataDecodable, RecordType, rdata::sshfp},
+    serialize::binary::{
+        BinDecodable, BinDecoder, BinEncodable, BinEncoder, DecodeError, RDataEncoding,
+    },
+};
+
+/// [RFC 2930, Secret Key Establishment for DNS](https://tools.ietf.org/html/rfc2930#section-2)
+///
+/// ```text
+/// 2. The TKEY Resource Record
+///
+///    The TKEY resource record (RR) has the structure given below.  Its RR
+///    type code is 249.
+///
+///       Field       Type         Comment
+///       -----       ----         -------
+///
+///       NAME         domain       see description below
+///       TTYPE        u_int16_t    TKEY = 249
+///       CLASS        u_int16_t    ignored, SHOULD be 255 (ANY)
+///       TTL          u_int32_t    ignored, SHOULD be zero
+///       RDLEN        u_int16_t    size of RDATA
+///       RDATA:
+///          Algorithm:   domain
+///          Inception:   u_int32_t
+///          Expiration:  u_int32_t
+///          Mode:        u_int16_t
+///          Error:       u_int16_t
+///          Key Size:    u_int16_t
+///          Key Data:    octet-stream
+///          Other Size:  u_int16_t
+///          Other Data:  octet-stream  undefined by this specification
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[non_exhaustive]
+pub struct TKEY {
+    /// The algorithm of the key being established
+    pub algorithm: TsigAlgorithm,
+
+    /// Start of the validity period of the key, in seconds since the Unix epoch
+    pub inception: u32,
+
+    /// End of the validity period of the key, in seconds since the Unix epoch
+    pub expiration: u32,
+
+    /// The general scheme for establishing the key
+    pub mode: TkeyMode,
+
+    /// The TKEY error RCODE, in responses
+    pub error: Option<TsigError>,
+
+    /// Key exchange data, the meaning of which depends on the `mode`
+    pub key: Vec<u8>,
+
+    /// Additional data, undefined by RFC 2930
+    pub other: Vec<u8>,
+}
+
+impl TKEY {
+    /// Constructs a new TKEY
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the TSIG algorithm of the key being established
+    /// * `inception` - start of the validity period of the key
+    /// * `expiration` - end of the validity period of the key
+    /// * `mode` - the scheme for establishing the key
+    /// * `error` - the TKEY error RCODE, `None` in requests
+    /// * `key` - key exchange data
+    /// * `other` - additional data, generally empty
+    pub fn new(
+        algorithm: TsigAlgorithm,
+        inception: u32,
+        expiration: u32,
+        mode: TkeyMode,
+        error: Option<TsigError>,
+        key: Vec<u8>,
+        other: Vec<u8>,
+    ) -> Self {
+        Self {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key,
+            other,
+        }
+    }
+}
+
+impl BinEncodable for TKEY {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        let mut encoder = encoder.with_rdata_behavior(RDataEncoding::Other);
+        self.algorithm.emit(&mut encoder)?;
+        self.inception.emit(&mut encoder)?;
+        self.expiration.emit(&mut encoder)?;
+        u16::from(self.mode).emit(&mut encoder)?;
+
+        match self.error {
+            None => 0,
+            Some(err) => u16::from(err),
+        }
+        .emit(&mut encoder)?;
+
+        match u16::try_from(self.key.len()) {
+            Ok(key_len) => key_len.emit(&mut encoder)?,
+            Err(_) => return Err(ProtoError::from("invalid key, longer than 65535 B in TKEY")),
+        }
+        encoder.emit_slice(&self.key)?;
+
+        match u16::try_from(self.other.len()) {
+            Ok(other_len) => other_len.emit(&mut encoder)?,
+            Err(_) => {
+                return Err(ProtoError::from(
+                    "invalid other_buffer, longer than 65535 B in TKEY",
+                ));
+            }
+        }
+        encoder.emit_slice(&self.other)?;
+        Ok(())
+    }
+}
+
+impl<'r> RecordDataDecodable<'r> for TKEY {
+    fn read_data(decoder: &mut BinDecoder<'r>) -> Result<Self, DecodeError> {
+        let end_idx = decoder.len().checked_add(decoder.index()).ok_or_else(||
+            // no legal message is long enough to trigger this
+            DecodeError::IncorrectRDataLengthRead {
+                read: decoder.index(),
+                len: decoder.index(),
+            })?;
+
+        let algorithm = TsigAlgorithm::read(decoder)?;
+        let inception = decoder.read_u32()?.unverified(/*valid as any u32*/);
+        let expiration = decoder.read_u32()?.unverified(/*valid as any u32*/);
+        let mode = TkeyMode::from(decoder.read_u16()?.unverified(/*valid as any u16*/));
+        let error = match decoder.read_u16()?.unverified(/*valid as any u16*/) {
+            0 => None,
+            code => Some(TsigError::from(code)),
+        };
+        let key_size = decoder
+            .read_u16()?
+            .verify_unwrap(|&size| decoder.index() + size as usize + 2 /* u16 */ <= end_idx)
+            .map_err(|size| DecodeError::IncorrectRDataLengthRead {
+                read: end_idx - decoder.index(),
+                len: size as usize + 2,
+            })?;
+        let key =
+            decoder.read_vec(key_size as usize)?.unverified(/*valid as any vec of the right size*/);
+        let other_size = decoder
+            .read_u16()?
+            .verify_unwrap(|&size| decoder.index() + size as usize == end_idx)
+            .map_err(|size| DecodeError::IncorrectRDataLengthRead {
+                read: end_idx - decoder.index(),
+                len: size as usize,
+            })?;
+        let other = decoder.read_vec(other_size as usize)?.unverified(/*valid as any vec of the right size*/);
+
+        Ok(Self {
+            algorithm,
+            inception,
+            expiration,
+            mode,
+            error,
+            key,
+            other,
+        })
+    }
+}
+
+impl RecordData for TKEY {
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::TKEY(tkey) => Some(tkey),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::TKEY
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::TKEY(self)
+    }
+}
+
+// Does not appear to have a normalized text representation
+impl fmt::Display for TKEY {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{algorithm} {inception} {expiration} {mode} {error} {key} {other}",
+            algorithm = self.algorithm,
+            inception = self.inception,
+            expiration = self.expiration,
+            mode = u16::from(self.mode),
+            error = self.error.map(Into::into).unwrap_or(0),
+            key = sshfp::HEX.encode(&self.key),
+            other = sshfp::HEX.encode(&self.other),
+        )
+    }
+}
+
+/// The scheme used to establish a key
+///
+/// [RFC 2930, Secret Key Establishment for DNS](https://tools.ietf.org/html/rfc2930#section-2.5)
+///
+/// ```text
+///       Value    Description
+///       -----    -----------
+///        0        - reserved, see section 7
+///        1       server assignment
+///        2       Diffie-Hellman exchange
+///        3       GSS-API negotiation
+///        4       resolver assignment
+///        5       key deletion
+///       6-65534   - available, see section 7
+///       65535     - reserved, see section 7
+/// ```
+///
+/// Modes 0xFFF0 through 0xFFFE are for private use (section 7), Hickory DNS uses 0xFFF0 for its
+/// X25519 key agreement, see [`TkeyMode::X25519HkdfSha256`].
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum TkeyMode {
+    /// The server generates the key
+    ServerAssignment,
+    /// The key is agreed on with a Diffie-Hellman exchange
+    DiffieHellman,
+    /// The key is negotiated with GSS-API
+    GssApi,
+    /// The resolver generates the key
+    ResolverAssignment,
+    /// The key is deleted
+    KeyDeletion,
+    /// Private use mode 0xFFF0: the key is agreed on with X25519 and derived with HKDF-SHA256
+    ///
+    /// This is not the Diffie-Hellman exchange of RFC 2930, and is only understood by Hickory DNS.
+    X25519HkdfSha256,
+    /// A reserved or unassigned mode
+    Unknown(u16),
+}
+
+impl From<u16> for TkeyMode {
+    fn from(value: u16) -> Self {
+        match value {
+            1 => Self::ServerAssignment,
+            2 => Self::DiffieHellman,
+            3 => Self::GssApi,
+            4 => Self::ResolverAssignment,
+            5 => Self::KeyDeletion,
+            0xFFF0 => Self::X25519HkdfSha256,
+            mode => Self::Unknown(mode),
+        }
+    }
+}
+
+impl From<TkeyMode> for u16 {
+    fn from(value: TkeyMode) -> Self {
+        match value {
+            TkeyMode::ServerAssignment => 1,
+            TkeyMode::DiffieHellman => 2,
+            TkeyMode::GssApi => 3,
+            TkeyMode::ResolverAssignment => 4,
+            TkeyMode::KeyDeletion => 5,
+            TkeyMode::X25519HkdfSha256 => 0xFFF0,
+            TkeyMode::Unknown(mode) => mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_tkey() {
+        let rdata = TKEY::new(
+            TsigAlgorithm::HmacSha256,
+            1_700_000_000,
+            1_700_003_600,
+            TkeyMode::X25519HkdfSha256,
+            Some(TsigError::BadMode),
+            vec![0xAB; 32],
+            vec![1, 2, 3],
+        );
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).expect("failed to emit tkey");
+        let bytes = encoder.into_bytes();
+
+        let mut decoder = BinDecoder::new(bytes);
+        let read_rdata = TKEY::read_data(&mut decoder).expect("failed to read back");
+        assert_eq!(rdata, read_rdata);
+    }
+
+    #[test]
+    fn test_tkey_mode_values() {
+        assert_eq!(u16::from(TkeyMode::DiffieHellman), 2);
+        assert_eq!(u16::from(TkeyMode::X25519HkdfSha256), 0xFFF0);
+        assert_eq!(TkeyMode::from(0xFFF0), TkeyMode::X25519HkdfSha256);
+        assert_eq!(TkeyMode::from(0xFFF1), TkeyMode::Unknown(0xFFF1));
+    }
+}
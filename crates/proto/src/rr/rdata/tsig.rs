@@ -19,7 +19,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "__dnssec")]
 use crate::dnssec::{DnsSecError, ring_like::hmac};
 #[cfg(feature = "__dnssec")]
-use crate::op::{Header, Message, Query};
+use crate::op::{Header, Message, MessageSignature, Query};
 #[cfg(feature = "__dnssec")]
 use crate::rr::tsig::TSigner;
 use crate::{
@@ -658,9 +658,10 @@ impl BinDecodable<'_> for TsigAlgorithm {
     }
 }
 
-/// A TSIG RR error rcode
+/// A TSIG or TKEY RR error rcode
 ///
-/// See <https://www.rfc-editor.org/rfc/rfc8945.html#section-3>
+/// See <https://www.rfc-editor.org/rfc/rfc8945.html#section-3> and
+/// <https://www.rfc-editor.org/rfc/rfc2930.html#section-2.6>
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[derive(Debug, Eq, PartialEq, PartialOrd, Copy, Clone, Hash)]
 pub enum TsigError {
@@ -670,6 +671,12 @@ pub enum TsigError {
     BadKey,
     /// Bad signature time
     BadTime,
+    /// Bad TKEY mode
+    BadMode,
+    /// Duplicate TKEY key name
+    BadName,
+    /// TKEY algorithm not supported
+    BadAlg,
     /// Bad truncated request MAC
     BadTrunc,
     /// An unknown error
@@ -682,6 +689,9 @@ impl From<u16> for TsigError {
             16 => Self::BadSig,
             17 => Self::BadKey,
             18 => Self::BadTime,
+            19 => Self::BadMode,
+            20 => Self::BadName,
+            21 => Self::BadAlg,
             22 => Self::BadTrunc,
             code => Self::Unknown(code),
         }
@@ -694,6 +704,9 @@ impl From<TsigError> for u16 {
             TsigError::BadSig => 16,
             TsigError::BadKey => 17,
             TsigError::BadTime => 18,
+            TsigError::BadMode => 19,
+            TsigError::BadName => 20,
+            TsigError::BadAlg => 21,
             TsigError::BadTrunc => 22,
             TsigError::Unknown(code) => code,
         }
@@ -784,7 +797,7 @@ pub fn signed_bitmessage_to_buf(
 
     // Read the TSIG signature record.
     let (_, _, sig) = Message::read_records(&mut decoder, 1, true, metadata.op_code)?;
-    let Some(tsig_rr) = sig else {
+    let Some(MessageSignature::Tsig(tsig_rr)) = sig else {
         return Err(ProtoError::from("TSIG signature record not found"));
     };
 
@@ -0,0 +1,498 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! ZONEMD records for message digests of DNS zones
+#![allow(clippy::use_self)]
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "__dnssec")]
+use crate::{
+    dnssec::{crypto::Digest, rdata::DNSSECRData},
+    error::ProtoError,
+    rr::{Name, RecordSet},
+    serialize::binary::NameEncoding,
+};
+use crate::{
+    error::ProtoResult,
+    rr::{RData, RecordData, RecordDataDecodable, RecordType},
+    serialize::{
+        binary::{BinDecoder, BinEncodable, BinEncoder, DecodeError},
+        txt::ParseError,
+    },
+};
+
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-2)
+///
+/// ```text
+/// 2.2.  ZONEMD RDATA Wire Format
+///
+///    The ZONEMD RDATA wire format is encoded as follows:
+///
+///                         1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                             Serial                            |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |    Scheme     |Hash Algorithm |                               |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               |
+///    |                             Digest                            |
+///    /                                                               /
+///    /                                                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[non_exhaustive]
+pub struct ZONEMD {
+    /// The serial of the SOA of the zone the digest was computed for
+    pub serial: u32,
+
+    /// The scheme by which the zone is collated for the digest
+    pub scheme: Scheme,
+
+    /// The hash algorithm of the digest
+    pub hash_algorithm: HashAlgorithm,
+
+    /// The digest of the zone
+    pub digest: Vec<u8>,
+}
+
+impl ZONEMD {
+    /// Creates a new ZONEMD record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `serial` - the serial of the SOA of the zone.
+    /// * `scheme` - the collation scheme of the digest.
+    /// * `hash_algorithm` - the hash algorithm of the digest.
+    /// * `digest` - the digest of the zone.
+    pub fn new(
+        serial: u32,
+        scheme: Scheme,
+        hash_algorithm: HashAlgorithm,
+        digest: Vec<u8>,
+    ) -> Self {
+        Self {
+            serial,
+            scheme,
+            hash_algorithm,
+            digest,
+        }
+    }
+
+    /// Computes the ZONEMD of the zone, with the [`Scheme::Simple`] scheme
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the apex of the zone
+    /// * `serial` - the serial of the SOA of the zone
+    /// * `hash_algorithm` - the hash algorithm of the digest
+    /// * `rrsets` - all RecordSets of the zone, including their RRSIGs
+    #[cfg(feature = "__dnssec")]
+    pub fn from_zone<'a>(
+        origin: &Name,
+        serial: u32,
+        hash_algorithm: HashAlgorithm,
+        rrsets: impl IntoIterator<Item = &'a RecordSet>,
+    ) -> ProtoResult<Self> {
+        let digest = simple_digest(origin, hash_algorithm, rrsets)?;
+        Ok(Self::new(serial, Scheme::Simple, hash_algorithm, digest))
+    }
+
+    /// Verifies this ZONEMD against the zone
+    ///
+    /// Returns an error if the scheme or hash algorithm is not supported, or if the digest does
+    /// not match the zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the apex of the zone
+    /// * `serial` - the serial of the SOA of the zone
+    /// * `rrsets` - all RecordSets of the zone, including their RRSIGs
+    #[cfg(feature = "__dnssec")]
+    pub fn verify<'a>(
+        &self,
+        origin: &Name,
+        serial: u32,
+        rrsets: impl IntoIterator<Item = &'a RecordSet>,
+    ) -> ProtoResult<()> {
+        if self.serial != serial {
+            return Err(ProtoError::from(format!(
+                "ZONEMD serial {} does not match SOA serial {serial}",
+                self.serial
+            )));
+        }
+
+        if self.scheme != Scheme::Simple {
+            return Err(ProtoError::from(format!(
+                "unsupported ZONEMD scheme: {}",
+                u8::from(self.scheme)
+            )));
+        }
+
+        if simple_digest(origin, self.hash_algorithm, rrsets)? != self.digest {
+            return Err(ProtoError::from("ZONEMD digest does not match the zone"));
+        }
+
+        Ok(())
+    }
+
+    /// Parse the RData from a set of Tokens
+    ///
+    /// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-2.3)
+    ///
+    /// ```text
+    /// 2.3.  ZONEMD Presentation Format
+    ///
+    ///    The presentation format of the RDATA portion is as follows:
+    ///
+    ///    *  The Serial field MUST be represented as an unsigned decimal
+    ///       integer.
+    ///
+    ///    *  The Scheme field MUST be represented as an unsigned decimal
+    ///       integer.
+    ///
+    ///    *  The Hash Algorithm field MUST be represented as an unsigned
+    ///       decimal integer.
+    ///
+    ///    *  The Digest MUST be represented as a sequence of case-insensitive
+    ///       hexadecimal digits.  Whitespace is allowed within the hexadecimal
+    ///       text.
+    /// ```
+    pub(crate) fn from_tokens<'i, I: Iterator<Item = &'i str>>(
+        mut tokens: I,
+    ) -> Result<Self, ParseError> {
+        fn missing_field(field: &str) -> ParseError {
+            ParseError::Msg(format!("ZONEMD {field} field missing"))
+        }
+
+        let serial = tokens
+            .next()
+            .ok_or_else(|| missing_field("serial"))?
+            .parse::<u32>()?;
+        let scheme = tokens
+            .next()
+            .ok_or_else(|| missing_field("scheme"))?
+            .parse::<u8>()?;
+        let hash_algorithm = tokens
+            .next()
+            .ok_or_else(|| missing_field("hash algorithm"))?
+            .parse::<u8>()?;
+
+        let digest = tokens.collect::<String>();
+        if digest.is_empty() {
+            return Err(missing_field("digest"));
+        }
+        let digest = data_encoding::HEXUPPER_PERMISSIVE.decode(digest.as_bytes())?;
+
+        Ok(Self::new(
+            serial,
+            scheme.into(),
+            hash_algorithm.into(),
+            digest,
+        ))
+    }
+}
+
+/// Computes the digest of the zone with the SIMPLE scheme of
+/// [RFC 8976, section 3.3](https://tools.ietf.org/html/rfc8976#section-3.3)
+///
+/// All records within the zone, including glue, occluded data and DNSSEC records, are hashed in
+/// their canonical form and order, with RRsets of the same owner ordered by their numeric type.
+/// Duplicate records are only included once. The apex ZONEMD RRset and the RRSIGs covering it
+/// are excluded.
+#[cfg(feature = "__dnssec")]
+fn simple_digest<'a>(
+    origin: &Name,
+    hash_algorithm: HashAlgorithm,
+    rrsets: impl IntoIterator<Item = &'a RecordSet>,
+) -> ProtoResult<Vec<u8>> {
+    let mut records = Vec::new();
+    for rrset in rrsets {
+        if !origin.zone_of(rrset.name()) {
+            continue;
+        }
+
+        let is_apex = rrset.name() == origin;
+        if is_apex && rrset.record_type() == RecordType::ZONEMD {
+            continue;
+        }
+
+        for record in rrset.records_without_rrsigs().chain(rrset.rrsigs()) {
+            if let RData::DNSSEC(DNSSECRData::RRSIG(rrsig)) = &record.data {
+                if is_apex && rrsig.input().type_covered == RecordType::ZONEMD {
+                    continue;
+                }
+            }
+
+            let mut rdata = Vec::new();
+            let mut encoder = BinEncoder::new(&mut rdata);
+            encoder.canonical_form = true;
+            encoder.name_encoding = NameEncoding::Uncompressed;
+            record.data.emit(&mut encoder)?;
+
+            records.push((record, u16::from(record.record_type()), rdata));
+        }
+    }
+
+    // canonical order of owner names, RR types and RDATA
+    records.sort_by(|(a, a_type, a_rdata), (b, b_type, b_rdata)| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a_type.cmp(b_type))
+            .then_with(|| a_rdata.cmp(b_rdata))
+    });
+    records.dedup_by(|(a, a_type, a_rdata), (b, b_type, b_rdata)| {
+        a.name == b.name && a_type == b_type && a.dns_class == b.dns_class && a_rdata == b_rdata
+    });
+
+    let mut buf = Vec::new();
+    let mut encoder = BinEncoder::new(&mut buf);
+    encoder.name_encoding = NameEncoding::UncompressedLowercase;
+    for (record, record_type, rdata) in &records {
+        // RR = owner | type | class | TTL | RDATA length | RDATA
+        record.name.emit(&mut encoder)?;
+        record_type.emit(&mut encoder)?;
+        record.dns_class.emit(&mut encoder)?;
+        record.ttl.emit(&mut encoder)?;
+        let length = u16::try_from(rdata.len())
+            .map_err(|_| ProtoError::from("RDATA length exceeds u16::MAX"))?;
+        length.emit(&mut encoder)?;
+        encoder.emit_slice(rdata)?;
+    }
+
+    Ok(Digest::zonemd(&buf, hash_algorithm)?.as_ref().to_vec())
+}
+
+/// The collation scheme of the ZONEMD digest
+///
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-5.2)
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Scheme {
+    /// Simple ZONEMD collation
+    Simple,
+
+    /// Unassigned or private use value
+    Unassigned(u8),
+}
+
+impl From<u8> for Scheme {
+    fn from(scheme: u8) -> Self {
+        match scheme {
+            1 => Self::Simple,
+            _ => Self::Unassigned(scheme),
+        }
+    }
+}
+
+impl From<Scheme> for u8 {
+    fn from(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::Simple => 1,
+            Scheme::Unassigned(scheme) => scheme,
+        }
+    }
+}
+
+/// The hash algorithm of the ZONEMD digest
+///
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-5.3)
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum HashAlgorithm {
+    /// SHA-384
+    SHA384,
+
+    /// SHA-512
+    SHA512,
+
+    /// Unassigned or private use value
+    Unassigned(u8),
+}
+
+impl From<u8> for HashAlgorithm {
+    fn from(alg: u8) -> Self {
+        match alg {
+            1 => Self::SHA384,
+            2 => Self::SHA512,
+            _ => Self::Unassigned(alg),
+        }
+    }
+}
+
+impl From<HashAlgorithm> for u8 {
+    fn from(alg: HashAlgorithm) -> Self {
+        match alg {
+            HashAlgorithm::SHA384 => 1,
+            HashAlgorithm::SHA512 => 2,
+            HashAlgorithm::Unassigned(alg) => alg,
+        }
+    }
+}
+
+impl BinEncodable for ZONEMD {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        self.serial.emit(encoder)?;
+        u8::from(self.scheme).emit(encoder)?;
+        u8::from(self.hash_algorithm).emit(encoder)?;
+        encoder.emit_slice(&self.digest)
+    }
+}
+
+impl<'r> RecordDataDecodable<'r> for ZONEMD {
+    fn read_data(decoder: &mut BinDecoder<'r>) -> Result<Self, DecodeError> {
+        let serial = decoder.read_u32()?.unverified();
+        let scheme = decoder.read_u8()?.unverified().into();
+        let hash_algorithm = decoder.read_u8()?.unverified().into();
+        let digest = decoder.read_vec_to_end().unverified();
+        Ok(Self::new(serial, scheme, hash_algorithm, digest))
+    }
+}
+
+impl RecordData for ZONEMD {
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::ZONEMD(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::ZONEMD
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::ZONEMD(self)
+    }
+}
+
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-2.3)
+///
+/// ```text
+/// 2.4.  ZONEMD RR Example
+///
+///    example.com. 86400 IN ZONEMD 2018031500 1 1 (
+///        FEBE3D4CE2EC2FFA4BA99D46CD69D6D29711E55217057BEE
+///        7EB1A7B641A47BA7FED2DD5B97AE499FAFA4F22C6BD647DE )
+/// ```
+impl fmt::Display for ZONEMD {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{serial} {scheme} {alg} {digest}",
+            serial = self.serial,
+            scheme = u8::from(self.scheme),
+            alg = u8::from(self.hash_algorithm),
+            digest = data_encoding::HEXUPPER.encode(&self.digest),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let rdata = ZONEMD::new(
+            2018031500,
+            Scheme::Simple,
+            HashAlgorithm::SHA384,
+            vec![0xFE, 0xBE, 0x3D, 0x4C],
+        );
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).expect("failed to emit ZONEMD");
+        let bytes = encoder.into_bytes();
+        assert_eq!(
+            bytes,
+            &[0x78, 0x48, 0xB7, 0x8C, 1, 1, 0xFE, 0xBE, 0x3D, 0x4C]
+        );
+
+        let mut decoder = BinDecoder::new(bytes);
+        let read_rdata = ZONEMD::read_data(&mut decoder).expect("failed to read ZONEMD");
+        assert_eq!(read_rdata, rdata);
+    }
+
+    #[test]
+    fn test_parse_display() {
+        let tokens = ["2018031500", "1", "2", "FEBE3D4C", "e2ec2ffa"];
+        let rdata = ZONEMD::from_tokens(tokens.into_iter()).unwrap();
+        assert_eq!(rdata.serial, 2018031500);
+        assert_eq!(rdata.scheme, Scheme::Simple);
+        assert_eq!(rdata.hash_algorithm, HashAlgorithm::SHA512);
+        assert_eq!(rdata.to_string(), "2018031500 1 2 FEBE3D4CE2EC2FFA");
+
+        assert!(ZONEMD::from_tokens(["2018031500", "1", "1"].into_iter()).is_err());
+    }
+
+    #[cfg(all(feature = "__dnssec", feature = "std"))]
+    #[test]
+    fn test_simple_zone_digest() {
+        use crate::serialize::txt::Parser;
+
+        // RFC 8976, Appendix A.1
+        let zone = r#"
+example.      86400  IN  SOA     ns1 admin 2018031900 (
+                                 1800 900 604800 86400 )
+              86400  IN  NS      ns1
+              86400  IN  NS      ns2
+              86400  IN  ZONEMD  2018031900 1 1 (
+                                 c68090d90a7aed71
+                                 6bc459f9340e3d7c
+                                 1370d4d24b7e2fc3
+                                 a1ddc0b9a87153b9
+                                 a9713b3c9ae5cc27
+                                 777f98b8e730044c )
+ns1           3600   IN  A       203.0.113.63
+ns2           3600   IN  AAAA    2001:db8::63
+"#;
+
+        let origin = Name::from_ascii("example.").unwrap();
+        let (_, records) = Parser::new(zone, None, Some(origin.clone()))
+            .parse()
+            .unwrap();
+        let zonemd = records
+            .values()
+            .find(|rrset| rrset.record_type() == RecordType::ZONEMD)
+            .and_then(|rrset| rrset.records_without_rrsigs().next())
+            .and_then(|record| ZONEMD::try_borrow(&record.data))
+            .cloned()
+            .unwrap();
+
+        zonemd
+            .verify(&origin, 2018031900, records.values())
+            .unwrap();
+        assert_eq!(
+            &ZONEMD::from_zone(&origin, 2018031900, HashAlgorithm::SHA384, records.values())
+                .unwrap(),
+            &zonemd
+        );
+
+        assert!(
+            zonemd
+                .verify(&origin, 2018031901, records.values())
+                .is_err()
+        );
+        let mut records = records;
+        records.retain(|key, _| key.record_type != RecordType::AAAA);
+        assert!(
+            zonemd
+                .verify(&origin, 2018031900, records.values())
+                .is_err()
+        );
+    }
+}
@@ -8,7 +8,10 @@
 //! record data enum variants
 #![allow(deprecated, clippy::use_self)] // allows us to deprecate RData types
 
-use alloc::vec::Vec;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 #[cfg(test)]
 use core::convert::From;
 use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -26,7 +29,7 @@ use crate::{
         Name, RecordData, RecordDataDecodable,
         rdata::{
             A, AAAA, ANAME, CAA, CERT, CNAME, CSYNC, HINFO, HTTPS, MX, NAPTR, NS, NULL, OPENPGPKEY,
-            OPT, PTR, SMIMEA, SOA, SRV, SSHFP, SVCB, TLSA, TSIG, TXT,
+            OPT, PTR, SMIMEA, SOA, SRV, SSHFP, SVCB, TKEY, TLSA, TSIG, TXT, ZONEMD,
         },
         record_type::RecordType,
     },
@@ -682,6 +685,22 @@ pub enum RData {
     /// ```
     TLSA(TLSA),
 
+    /// [RFC 2930, Secret Key Establishment for DNS](https://tools.ietf.org/html/rfc2930#section-2)
+    ///
+    /// ```text
+    ///       RDATA:
+    ///          Algorithm:   domain
+    ///          Inception:   u_int32_t
+    ///          Expiration:  u_int32_t
+    ///          Mode:        u_int16_t
+    ///          Error:       u_int16_t
+    ///          Key Size:    u_int16_t
+    ///          Key Data:    octet-stream
+    ///          Other Size:  u_int16_t
+    ///          Other Data:  octet-stream  undefined by this specification
+    /// ```
+    TKEY(TKEY),
+
     /// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-4.2)
     ///
     /// ```text
@@ -812,6 +831,9 @@ pub enum RData {
     /// ```
     TXT(TXT),
 
+    /// [RFC 8976](https://tools.ietf.org/html/rfc8976) Message Digest for DNS Zones
+    ZONEMD(ZONEMD),
+
     /// A DNSSEC- or SIG(0)- specific record. See `DNSSECRData` for details.
     ///
     /// These types are in `DNSSECRData` to make them easy to disable when
@@ -871,9 +893,11 @@ impl RData {
             Self::SRV(..) => RecordType::SRV,
             Self::SSHFP(..) => RecordType::SSHFP,
             Self::SVCB(..) => RecordType::SVCB,
+            Self::TKEY(..) => RecordType::TKEY,
             Self::TLSA(..) => RecordType::TLSA,
             Self::TSIG(..) => RecordType::TSIG,
             Self::TXT(..) => RecordType::TXT,
+            Self::ZONEMD(..) => RecordType::ZONEMD,
             #[cfg(feature = "__dnssec")]
             Self::DNSSEC(rdata) => DNSSECRData::to_record_type(rdata),
             Self::Unknown { code, .. } => *code,
@@ -995,6 +1019,10 @@ impl RData {
                 trace!("reading SVCB");
                 SVCB::read_data(&mut decoder).map(Self::SVCB)
             }
+            RecordType::TKEY => {
+                trace!("reading TKEY");
+                TKEY::read_data(&mut decoder).map(Self::TKEY)
+            }
             RecordType::TLSA => {
                 trace!("reading TLSA");
                 TLSA::read_data(&mut decoder).map(Self::TLSA)
@@ -1007,6 +1035,10 @@ impl RData {
                 trace!("reading TXT");
                 TXT::read_data(&mut decoder).map(Self::TXT)
             }
+            RecordType::ZONEMD => {
+                trace!("reading ZONEMD");
+                ZONEMD::read_data(&mut decoder).map(Self::ZONEMD)
+            }
             #[cfg(feature = "__dnssec")]
             r if r.is_dnssec() => DNSSECRData::read(&mut decoder, record_type).map(Self::DNSSEC),
             record_type => {
@@ -1054,6 +1086,12 @@ impl RData {
         tokens: I,
         origin: Option<&Name>,
     ) -> Result<Self, ParseError> {
+        let mut tokens = tokens.peekable();
+        if tokens.peek() == Some(&"\\#") {
+            tokens.next();
+            return Self::from_generic_tokens(record_type, tokens);
+        }
+
         let rdata = match record_type {
             RecordType::A => Self::A(A::from_tokens(tokens)?),
             RecordType::AAAA => Self::AAAA(AAAA::from_tokens(tokens)?),
@@ -1085,6 +1123,7 @@ impl RData {
             RecordType::SVCB => Self::SVCB(SVCB::from_tokens(tokens)?),
             RecordType::TLSA => Self::TLSA(TLSA::from_tokens(tokens)?),
             RecordType::TXT => Self::TXT(TXT::from_tokens(tokens)?),
+            RecordType::ZONEMD => Self::ZONEMD(ZONEMD::from_tokens(tokens)?),
             RecordType::SIG => return Err(ParseError::from("parsing SIG doesn't make sense")),
             RecordType::DNSKEY => {
                 return Err(ParseError::from("DNSKEY should be dynamically generated"));
@@ -1112,6 +1151,9 @@ impl RData {
             RecordType::RRSIG => {
                 return Err(ParseError::from("RRSIG should be dynamically generated"));
             }
+            RecordType::TKEY => {
+                return Err(ParseError::from("TKEY is only used during key negotiation"));
+            }
             RecordType::TSIG => return Err(ParseError::from("TSIG is only used during AXFR")),
             #[allow(deprecated)]
             RecordType::ZERO => Self::ZERO,
@@ -1123,6 +1165,41 @@ impl RData {
 
         Ok(rdata)
     }
+
+    /// Parse the [RFC 3597](https://tools.ietf.org/html/rfc3597#section-5) generic encoding of
+    /// the RData, `\# <length> <hex data>`, the leading `\#` having already been consumed.
+    ///
+    /// This is accepted for every record type, including those that have no presentation format
+    /// parser of their own.
+    fn from_generic_tokens<'i, I: Iterator<Item = &'i str>>(
+        record_type: RecordType,
+        mut tokens: I,
+    ) -> Result<Self, ParseError> {
+        if record_type.is_any()
+            || matches!(
+                record_type,
+                RecordType::AXFR | RecordType::IXFR | RecordType::OPT
+            )
+        {
+            return Err(ParseError::UnsupportedRecordType(record_type));
+        }
+
+        let length = tokens
+            .next()
+            .ok_or_else(|| ParseError::MissingToken("generic rdata length".to_string()))?
+            .parse::<usize>()?;
+
+        let hex = tokens.collect::<String>();
+        let data = data_encoding::HEXUPPER_PERMISSIVE.decode(hex.as_bytes())?;
+        if data.len() != length {
+            return Err(ParseError::Msg(format!(
+                "generic rdata length {length} does not match data length {}",
+                data.len()
+            )));
+        }
+
+        Ok(Self::read(BinDecoder::new(&data), record_type)?)
+    }
 }
 
 impl BinEncodable for RData {
@@ -1217,9 +1294,11 @@ impl BinEncodable for RData {
             Self::SRV(srv) => srv.emit(encoder),
             Self::SSHFP(sshfp) => sshfp.emit(encoder),
             Self::SVCB(svcb) => svcb.emit(encoder),
+            Self::TKEY(tkey) => tkey.emit(encoder),
             Self::TLSA(tlsa) => tlsa.emit(encoder),
             Self::TSIG(tsig) => tsig.emit(encoder),
             Self::TXT(txt) => txt.emit(encoder),
+            Self::ZONEMD(zonemd) => zonemd.emit(encoder),
             #[cfg(feature = "__dnssec")]
             Self::DNSSEC(rdata) => rdata.emit(encoder),
             Self::Unknown { rdata, .. } => rdata.emit(encoder),
@@ -1280,9 +1359,11 @@ impl fmt::Display for RData {
             Self::SRV(srv) => w(f, srv),
             Self::SSHFP(sshfp) => w(f, sshfp),
             Self::SVCB(svcb) => w(f, svcb),
+            Self::TKEY(tkey) => w(f, tkey),
             Self::TLSA(tlsa) => w(f, tlsa),
             Self::TSIG(tsig) => w(f, tsig),
             Self::TXT(txt) => w(f, txt),
+            Self::ZONEMD(zonemd) => w(f, zonemd),
             #[cfg(feature = "__dnssec")]
             Self::DNSSEC(rdata) => w(f, rdata),
             Self::Unknown { rdata, .. } => w(f, rdata),
@@ -1541,9 +1622,11 @@ mod tests {
             RData::SRV(..) => RecordType::SRV,
             RData::SSHFP(..) => RecordType::SSHFP,
             RData::SVCB(..) => RecordType::SVCB,
+            RData::TKEY(..) => RecordType::TKEY,
             RData::TLSA(..) => RecordType::TLSA,
             RData::TSIG(..) => RecordType::TSIG,
             RData::TXT(..) => RecordType::TXT,
+            RData::ZONEMD(..) => RecordType::ZONEMD,
             #[cfg(feature = "__dnssec")]
             RData::DNSSEC(rdata) => rdata.to_record_type(),
             RData::Unknown { code, .. } => *code,
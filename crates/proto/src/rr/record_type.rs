@@ -110,7 +110,8 @@ pub enum RecordType {
     /// [RFC 9460](https://tools.ietf.org/html/rfc9460) DNS SVCB and HTTPS RRs
     SVCB,
     //  TA,         // 32768 N/A DNSSEC Trust Authorities
-    //  TKEY,       // 249 RFC 2930 Secret key record
+    /// [RFC 2930](https://tools.ietf.org/html/rfc2930) Secret Key Establishment for DNS
+    TKEY,
     /// [RFC 6698](https://tools.ietf.org/html/rfc6698) TLSA certificate association
     TLSA,
     /// [RFC 8945](https://tools.ietf.org/html/rfc8945) Transaction Signature
@@ -122,6 +123,8 @@ pub enum RecordType {
 
     /// This corresponds to a record type of 0, unspecified
     ZERO,
+    /// [RFC 8976](https://tools.ietf.org/html/rfc8976) Message Digest for DNS Zones
+    ZONEMD,
 }
 
 impl RecordType {
@@ -182,6 +185,7 @@ impl RecordType {
                 | Self::NSEC3PARAM
                 | Self::RRSIG
                 | Self::SIG
+                | Self::TKEY
                 | Self::TSIG
         )
     }
@@ -248,9 +252,15 @@ impl FromStr for RecordType {
             "SVCB" => Ok(Self::SVCB),
             "TLSA" => Ok(Self::TLSA),
             "TXT" => Ok(Self::TXT),
+            "ZONEMD" => Ok(Self::ZONEMD),
+            "TKEY" => Ok(Self::TKEY),
             "TSIG" => Ok(Self::TSIG),
             "ANY" | "*" => Ok(Self::ANY),
-            _ => Err(DecodeError::UnknownRecordTypeStr(str.to_owned())),
+            // RFC 3597 generic type mnemonic, e.g. `TYPE65534`
+            _ => match str.strip_prefix("TYPE").map(u16::from_str) {
+                Some(Ok(code)) => Ok(Self::from(code)),
+                _ => Err(DecodeError::UnknownRecordTypeStr(str.to_owned())),
+            },
         }
     }
 }
@@ -302,9 +312,11 @@ impl From<u16> for RecordType {
             44 => Self::SSHFP,
             64 => Self::SVCB,
             52 => Self::TLSA,
+            249 => Self::TKEY,
             250 => Self::TSIG,
             16 => Self::TXT,
             0 => Self::ZERO,
+            63 => Self::ZONEMD,
             // all unknown record types
             _ => Self::Unknown(value),
         }
@@ -379,9 +391,11 @@ impl From<RecordType> for &'static str {
             RecordType::SSHFP => "SSHFP",
             RecordType::SVCB => "SVCB",
             RecordType::TLSA => "TLSA",
+            RecordType::TKEY => "TKEY",
             RecordType::TSIG => "TSIG",
             RecordType::TXT => "TXT",
             RecordType::ZERO => "ZERO",
+            RecordType::ZONEMD => "ZONEMD",
             RecordType::Unknown(_) => "Unknown",
         }
     }
@@ -434,9 +448,11 @@ impl From<RecordType> for u16 {
             RecordType::SSHFP => 44,
             RecordType::SVCB => 64,
             RecordType::TLSA => 52,
+            RecordType::TKEY => 249,
             RecordType::TSIG => 250,
             RecordType::TXT => 16,
             RecordType::ZERO => 0,
+            RecordType::ZONEMD => 63,
             RecordType::Unknown(code) => code,
         }
     }
@@ -545,6 +561,7 @@ mod tests {
             "SSHFP",
             "TLSA",
             "TXT",
+            "ZONEMD",
             "ANY",
             "AXFR",
         ];
@@ -561,6 +578,7 @@ mod tests {
             "NSEC3PARAM",
             "RRSIG",
             "SIG",
+            "TKEY",
             "TSIG",
         ];
         #[cfg(not(feature = "__dnssec"))]
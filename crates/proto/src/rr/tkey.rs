@@ -0,0 +1,288 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Hickory-DNS implementation of Secret Key Establishment for DNS (TKEY)
+//! [RFC 2930](https://www.rfc-editor.org/rfc/rfc2930) September 2000
+//!
+//! Current deviations from RFC in implementation as of 2025-06-01
+//!
+//! - Only key deletion and a private use key agreement mode are supported, the Diffie-Hellman
+//!   exchange (mode 2) is not, so keys can only be negotiated between Hickory DNS peers.
+//! - The key agreement, [`TkeyMode::X25519HkdfSha256`] (mode 0xFFF0), uses X25519 instead of the
+//!   KEY records of RFC 2539, the raw public keys of both parties are carried in the key data
+//!   field of the TKEY records.
+//! - The shared TSIG key is derived with HKDF-SHA256 instead of the MD5 construction of
+//!   RFC 2930 section 4.1. The HKDF salt is the concatenation of the resolver and server public
+//!   keys, the info is the key name in canonical wire format.
+//! - Time checking is not performed in the TKEY implementation but by the caller.
+
+use alloc::vec::Vec;
+
+use super::rdata::tkey::{TKEY, TkeyMode};
+use super::rdata::tsig::{TsigAlgorithm, TsigError};
+use crate::dnssec::{
+    DnsSecError,
+    ring_like::{SystemRandom, agreement, hkdf, x25519_agree},
+};
+use crate::op::{Message, Query};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType, TSigner};
+use crate::serialize::binary::BinEncodable;
+
+/// Length of the TSIG keys derived from a key agreement
+const DERIVED_KEY_LEN: usize = 32;
+
+/// The resolver side of an X25519 TKEY exchange
+///
+/// The request built by [`TKeyExchange::request`] must be signed, with SIG(0) or an existing
+/// TSIG key, for the server to accept it. The server answers with its own public key, from which
+/// [`TKeyExchange::finish`] derives the shared TSIG key.
+pub struct TKeyExchange {
+    key_name: Name,
+    algorithm: TsigAlgorithm,
+    private_key: agreement::EphemeralPrivateKey,
+    public_key: Vec<u8>,
+}
+
+impl TKeyExchange {
+    /// Generates an ephemeral key pair to negotiate a key named `key_name`
+    ///
+    /// # Arguments
+    ///
+    /// * `key_name` - name of the key to establish, generally below the zone of the server
+    /// * `algorithm` - the TSIG algorithm the established key will be used with
+    pub fn new(mut key_name: Name, algorithm: TsigAlgorithm) -> Result<Self, DnsSecError> {
+        if !algorithm.supported() {
+            return Err(DnsSecError::TsigUnsupportedMacAlgorithm(algorithm));
+        }
+
+        let private_key =
+            agreement::EphemeralPrivateKey::generate(&agreement::X25519, &SystemRandom::new())?;
+        let public_key = private_key.compute_public_key()?.as_ref().to_vec();
+
+        key_name.set_fqdn(true);
+        Ok(Self {
+            key_name,
+            algorithm,
+            private_key,
+            public_key,
+        })
+    }
+
+    /// Name of the key being established
+    pub fn key_name(&self) -> &Name {
+        &self.key_name
+    }
+
+    /// Builds the TKEY query requesting a key valid from `inception` to `expiration`
+    ///
+    /// The server may shorten the validity period, see [`TKeyExchange::finish`].
+    pub fn request(&self, inception: u32, expiration: u32) -> Message {
+        let tkey = TKEY::new(
+            self.algorithm.clone(),
+            inception,
+            expiration,
+            TkeyMode::X25519HkdfSha256,
+            None,
+            self.public_key.clone(),
+            Vec::new(),
+        );
+
+        let mut message = Message::query();
+        message.metadata.recursion_desired = false;
+        message.add_query(Query::new(self.key_name.clone(), RecordType::TKEY));
+        message.add_additional(tkey_record(self.key_name.clone(), tkey));
+        message
+    }
+
+    /// Derives the negotiated key from the TKEY answer of the server
+    ///
+    /// Returns the signer for the new key, along with the expiration time granted by the server.
+    pub fn finish(self, response: &Message, fudge: u16) -> Result<(TSigner, u32), DnsSecError> {
+        let tkey = response
+            .answers
+            .iter()
+            .filter(|record| record.name == self.key_name)
+            .find_map(|record| match &record.data {
+                RData::TKEY(tkey) => Some(tkey),
+                _ => None,
+            })
+            .ok_or(DnsSecError::Message("no TKEY in response"))?;
+
+        if let Some(error) = tkey.error {
+            return Err(DnsSecError::TkeyError(error));
+        }
+
+        if tkey.mode != TkeyMode::X25519HkdfSha256 || tkey.algorithm != self.algorithm {
+            return Err(DnsSecError::Message("unexpected TKEY mode or algorithm"));
+        }
+
+        let shared_secret = x25519_agree(self.private_key, &tkey.key)?;
+        let key = derive_key(&shared_secret, &self.public_key, &tkey.key, &self.key_name)?;
+        let signer = TSigner::new(key, self.algorithm, self.key_name, fudge)?;
+        Ok((signer, tkey.expiration))
+    }
+}
+
+/// The server side of an X25519 TKEY exchange
+///
+/// Answers the TKEY `request` for the key `key_name`, granting the key from `inception` to
+/// `expiration`. Returns the TKEY for the answer section of the response, with the public key of
+/// the server, and the signer for the negotiated key.
+///
+/// A `TsigError` is returned for unsupported modes, algorithms or malformed public keys, it is
+/// meant to be sent back in the error field of the TKEY answer, see [`tkey_error`].
+pub fn accept_tkey(
+    key_name: &Name,
+    request: &TKEY,
+    inception: u32,
+    expiration: u32,
+    fudge: u16,
+) -> Result<(TKEY, TSigner), TsigError> {
+    if request.mode != TkeyMode::X25519HkdfSha256 {
+        return Err(TsigError::BadMode);
+    }
+
+    if !request.algorithm.supported() {
+        return Err(TsigError::BadAlg);
+    }
+
+    let private_key =
+        agreement::EphemeralPrivateKey::generate(&agreement::X25519, &SystemRandom::new())
+            .map_err(|_| TsigError::BadKey)?;
+    let public_key = private_key
+        .compute_public_key()
+        .map_err(|_| TsigError::BadKey)?
+        .as_ref()
+        .to_vec();
+
+    let shared_secret = x25519_agree(private_key, &request.key).map_err(|_| TsigError::BadKey)?;
+    let key = derive_key(&shared_secret, &request.key, &public_key, key_name)
+        .map_err(|_| TsigError::BadKey)?;
+    let signer = TSigner::new(key, request.algorithm.clone(), key_name.clone(), fudge)
+        .map_err(|_| TsigError::BadAlg)?;
+
+    let response = TKEY::new(
+        request.algorithm.clone(),
+        inception,
+        expiration,
+        TkeyMode::X25519HkdfSha256,
+        None,
+        public_key,
+        Vec::new(),
+    );
+
+    Ok((response, signer))
+}
+
+/// Echoes the TKEY `request` with `error` set, for the answer section of an error response
+pub fn tkey_error(request: &TKEY, error: TsigError) -> TKEY {
+    TKEY::new(
+        request.algorithm.clone(),
+        request.inception,
+        request.expiration,
+        request.mode,
+        Some(error),
+        Vec::new(),
+        Vec::new(),
+    )
+}
+
+/// Builds a TKEY record, with class ANY and a TTL of zero as recommended by RFC 2930 section 2
+pub fn tkey_record(key_name: Name, tkey: TKEY) -> Record {
+    let mut record = Record::from_rdata(key_name, 0, RData::TKEY(tkey));
+    record.dns_class = DNSClass::ANY;
+    record
+}
+
+fn derive_key(
+    shared_secret: &[u8],
+    resolver_public_key: &[u8],
+    server_public_key: &[u8],
+    key_name: &Name,
+) -> Result<Vec<u8>, DnsSecError> {
+    let mut salt = Vec::with_capacity(resolver_public_key.len() + server_public_key.len());
+    salt.extend_from_slice(resolver_public_key);
+    salt.extend_from_slice(server_public_key);
+    let info = key_name.to_lowercase().to_bytes()?;
+
+    let mut key = vec![0; DERIVED_KEY_LEN];
+    hkdf::Salt::new(hkdf::HKDF_SHA256, &salt)
+        .extract(shared_secret)
+        .expand(&[&info], DerivedKeyLen)?
+        .fill(&mut key)?;
+    Ok(key)
+}
+
+struct DerivedKeyLen;
+
+impl hkdf::KeyType for DerivedKeyLen {
+    fn len(&self) -> usize {
+        DERIVED_KEY_LEN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_tkey_exchange() {
+        let key_name = Name::from_str("client.example.com.").unwrap();
+        let exchange = TKeyExchange::new(key_name.clone(), TsigAlgorithm::HmacSha256).unwrap();
+        let request = exchange.request(1_700_000_000, 1_700_003_600);
+
+        let RData::TKEY(tkey) = &request.additionals[0].data else {
+            panic!("expected TKEY in additionals");
+        };
+        let (answer, server_signer) =
+            accept_tkey(&key_name, tkey, 1_700_000_000, 1_700_001_800, 300).unwrap();
+
+        let mut response = request.clone();
+        response.additionals.clear();
+        response.add_answer(tkey_record(key_name.clone(), answer));
+
+        let (client_signer, expiration) = exchange.finish(&response, 300).unwrap();
+        assert_eq!(expiration, 1_700_001_800);
+        assert_eq!(client_signer.key(), server_signer.key());
+        assert_eq!(client_signer.key().len(), DERIVED_KEY_LEN);
+        assert_eq!(client_signer.signer_name(), &key_name);
+    }
+
+    #[test]
+    fn test_tkey_bad_mode() {
+        let key_name = Name::from_str("client.example.com.").unwrap();
+        let mut request = TKEY::new(
+            TsigAlgorithm::HmacSha256,
+            0,
+            3600,
+            TkeyMode::ServerAssignment,
+            None,
+            vec![0; 32],
+            Vec::new(),
+        );
+        assert_eq!(
+            accept_tkey(&key_name, &request, 0, 3600, 300).err(),
+            Some(TsigError::BadMode)
+        );
+
+        // the RFC 2930 Diffie-Hellman exchange is not implemented
+        request.mode = TkeyMode::DiffieHellman;
+        assert_eq!(
+            accept_tkey(&key_name, &request, 0, 3600, 300).err(),
+            Some(TsigError::BadMode)
+        );
+
+        request.mode = TkeyMode::X25519HkdfSha256;
+        request.key = vec![0; 7];
+        assert_eq!(
+            accept_tkey(&key_name, &request, 0, 3600, 300).err(),
+            Some(TsigError::BadKey)
+        );
+    }
+}
@@ -36,7 +36,7 @@ use super::rdata::tsig::{
 #[cfg(feature = "__dnssec")]
 use crate::error::{ProtoError, ProtoResult};
 #[cfg(feature = "__dnssec")]
-use crate::op::DnsResponse;
+use crate::op::{DnsResponse, MessageSignature, MessageSigner};
 use crate::op::{Message, OpCode};
 #[cfg(feature = "__dnssec")]
 use crate::rr::Record;
@@ -255,10 +255,9 @@ impl TSigner {
     /// Returns true if the `TSigner` should sign the given `Message`
     pub fn should_sign_message(&self, message: &Message) -> bool {
         [OpCode::Update, OpCode::Notify].contains(&message.op_code)
-            || message
-                .queries
-                .iter()
-                .any(|q| [RecordType::AXFR, RecordType::IXFR].contains(&q.query_type))
+            || message.queries.iter().any(|q| {
+                [RecordType::AXFR, RecordType::IXFR, RecordType::TKEY].contains(&q.query_type)
+            })
     }
 
     /// Verify the message is correctly signed
@@ -390,6 +389,22 @@ impl TSigner {
     }
 }
 
+#[cfg(feature = "__dnssec")]
+impl MessageSigner for TSigner {
+    fn sign_message(
+        &self,
+        message: &Message,
+        current_time: u64,
+    ) -> ProtoResult<(MessageSignature, Option<TSigVerifier>)> {
+        let (tsig, verifier) = Self::sign_message(self, message, current_time)?;
+        Ok((MessageSignature::Tsig(tsig), verifier))
+    }
+
+    fn should_sign_message(&self, message: &Message) -> bool {
+        Self::should_sign_message(self, message)
+    }
+}
+
 /// A verifier for TSIG-signed DNS responses.
 ///
 /// This struct maintains the state necessary to verify a chain of TSIG-signed
@@ -522,7 +537,7 @@ mod tests {
         let (mut question, signer) = get_message_and_signer();
 
         let other_name = Name::from_ascii("other_name.").unwrap();
-        let Some(mut signature) = question.take_signature() else {
+        let Some(MessageSignature::Tsig(mut signature)) = question.take_signature() else {
             panic!("should have TSIG signed");
         };
         signature.name = other_name;
@@ -34,6 +34,9 @@ mod zone_lex;
 pub(crate) use zone_lex::Lexer;
 pub use zone_lex::Token;
 
+mod zone_writer;
+pub use zone_writer::ZoneWriter;
+
 /// parses the string following the rules from:
 ///  <https://tools.ietf.org/html/rfc2308> (NXCaching RFC) and
 ///  <https://www.zytrax.com/books/dns/apa/time.html>
@@ -239,6 +239,7 @@ impl TryFrom<LexToken> for Token {
     fn try_from(token: LexToken) -> Result<Self, Self::Error> {
         let token = match token {
             LexToken::At
+            | LexToken::Generate
             | LexToken::Include
             | LexToken::Origin
             | LexToken::Ttl
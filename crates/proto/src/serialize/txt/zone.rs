@@ -8,15 +8,17 @@
 use alloc::{
     borrow::Cow,
     collections::btree_map::{BTreeMap, Entry},
-    string::String,
+    string::{String, ToString},
     vec::Vec,
 };
-use core::{mem, str::FromStr};
+use core::{fmt::Write, mem, str::FromStr};
 use std::{
     fs,
     path::{Path, PathBuf},
 };
 
+#[cfg(feature = "__dnssec")]
+use crate::dnssec::rdata::DNSSECRData;
 use crate::{
     rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey},
     serialize::txt::{
@@ -125,6 +127,20 @@ use crate::{
 /// ;               Semicolon is used to start a comment; the remainder of
 ///                 the line is ignored.
 /// ```
+///
+/// In addition to the entries of RFC 1035, the `$TTL` entry of
+/// [RFC 2308](https://tools.ietf.org/html/rfc2308#section-4) and BIND's `$GENERATE` entry are
+/// supported:
+///
+/// ```text
+/// $GENERATE <start>-<stop>[/<step>] <lhs> [<TTL>] [<class>] <type> <rhs>
+/// ```
+///
+/// which creates one record for each value in the range. In `<lhs>` and `<rhs>` every `$` is
+/// replaced by the current value, `${offset[,width[,base]]}` adds `offset` to the value and
+/// formats it zero padded to `width` in the `base` `d`, `o`, `x`, `X`, or as reversed nibbles
+/// with `n` or `N`. A literal `$` is written as `\$`. All `$GENERATE` entries of a zone,
+/// including those of `$INCLUDE` files, may create at most 65536 records in total.
 pub struct Parser<'a> {
     lexers: Vec<(Lexer<'a>, Option<PathBuf>)>,
     origin: Option<Name>,
@@ -168,6 +184,7 @@ impl<'a> Parser<'a> {
 
                         match t {
                             // if Dollar, then $INCLUDE or $ORIGIN
+                            Token::Generate => State::Generate(Vec::new()),
                             Token::Include => State::Include(None),
                             Token::Origin => State::Origin,
                             Token::Ttl => State::Ttl,
@@ -197,6 +214,21 @@ impl<'a> Parser<'a> {
                         }
                         _ => return Err(ParseError::UnexpectedToken(t)),
                     },
+                    State::Generate(mut parts) => match t {
+                        Token::EOL => {
+                            cx.generate(parts)?;
+                            State::StartLine
+                        }
+                        Token::CharData(part) => {
+                            parts.push(part);
+                            State::Generate(parts)
+                        }
+                        Token::List(list) => {
+                            parts.extend(list);
+                            State::Generate(parts)
+                        }
+                        _ => return Err(ParseError::UnexpectedToken(t)),
+                    },
                     State::Origin => {
                         match t {
                             Token::CharData(data) => {
@@ -314,8 +346,10 @@ impl<'a> Parser<'a> {
             }
 
             // Extra flush at the end for the case of missing endline
-            if let State::Record(record_parts) = mem::replace(&mut state, State::StartLine) {
-                cx.insert(record_parts)?;
+            match mem::replace(&mut state, State::StartLine) {
+                State::Record(record_parts) => cx.insert(record_parts)?,
+                State::Generate(parts) => cx.generate(parts)?,
+                _ => (),
             }
 
             stack -= 1;
@@ -324,6 +358,8 @@ impl<'a> Parser<'a> {
 
         //
         // build the Authority and return.
+        #[cfg(feature = "__dnssec")]
+        cx.attach_rrsigs();
         let origin = cx
             .origin
             .ok_or(ParseError::Message("$ORIGIN was not specified"))?;
@@ -362,6 +398,10 @@ struct Context {
     current_name: Option<Name>,
     rtype: Option<RecordType>,
     ttl: Ttl,
+    /// Number of records created by `$GENERATE` entries so far
+    generated: u32,
+    #[cfg(feature = "__dnssec")]
+    rrsigs: Vec<Record>,
 }
 
 impl Context {
@@ -373,6 +413,9 @@ impl Context {
             current_name: None,
             rtype: None,
             ttl: Ttl::default(),
+            generated: 0,
+            #[cfg(feature = "__dnssec")]
+            rrsigs: Vec::new(),
         }
     }
 
@@ -410,6 +453,14 @@ impl Context {
         let mut record = Record::from_rdata(name, ttl, rdata);
         record.dns_class = self.class;
 
+        // signatures are attached to the RecordSet they cover once the whole zone has been read,
+        //  inserting a record into a RecordSet clears its signatures
+        #[cfg(feature = "__dnssec")]
+        if let RData::DNSSEC(DNSSECRData::RRSIG(_)) = &record.data {
+            self.rrsigs.push(record);
+            return Ok(());
+        }
+
         // add to the map
         let entry = self.records.entry(RrKey::new(
             LowerName::new(&record.name),
@@ -429,6 +480,103 @@ impl Context {
 
         Ok(())
     }
+
+    /// Insert the records of a `$GENERATE` entry
+    fn generate(&mut self, parts: Vec<String>) -> ParseResult<()> {
+        let mut parts = parts.into_iter();
+        let range = parts
+            .next()
+            .ok_or_else(|| ParseError::MissingToken("$GENERATE range".to_string()))
+            .and_then(|range| GenerateRange::from_str(&range))?;
+
+        self.generated = self.generated.saturating_add(range.len());
+        if self.generated > MAX_GENERATE_RECORDS {
+            return Err(ParseError::Msg(format!(
+                "$GENERATE entries exceed {MAX_GENERATE_RECORDS} records in total"
+            )));
+        }
+        let lhs = parts
+            .next()
+            .ok_or_else(|| ParseError::MissingToken("$GENERATE lhs".to_string()))?;
+
+        // [<TTL>] [<class>] <type>, in any order as for records
+        let mut ttl = None;
+        let mut class = self.class;
+        let rtype = loop {
+            let mut data = parts
+                .next()
+                .ok_or_else(|| ParseError::MissingToken("$GENERATE type".to_string()))?;
+
+            if let Ok(parsed) = parse_ttl(&data) {
+                ttl = Some(parsed);
+                continue;
+            }
+
+            data.make_ascii_uppercase();
+            match DNSClass::from_str(&data) {
+                Ok(parsed) => class = parsed,
+                Err(_) => break RecordType::from_str(&data)?,
+            }
+        };
+
+        let rhs = parts.collect::<Vec<_>>();
+        if rhs.is_empty() {
+            return Err(ParseError::MissingToken("$GENERATE rhs".to_string()));
+        }
+
+        // the generated records don't change the owner or class of the following records
+        let current_name = self.current_name.take();
+        let current_class = mem::replace(&mut self.class, class);
+        self.rtype = Some(rtype);
+
+        for value in range.values() {
+            self.current_name = Some(Name::parse(
+                &generate_text(&lhs, value)?,
+                self.origin.as_ref(),
+            )?);
+            if ttl.is_some() {
+                self.ttl.this = ttl;
+            }
+
+            let record_parts = rhs
+                .iter()
+                .map(|part| generate_text(part, value))
+                .collect::<ParseResult<Vec<_>>>()?;
+            self.insert(record_parts)?;
+        }
+
+        self.current_name = current_name;
+        self.class = current_class;
+        Ok(())
+    }
+
+    /// Attach all parsed RRSIGs to the RecordSets they cover
+    ///
+    /// Signatures without a matching RecordSet are kept in a RecordSet of their own.
+    #[cfg(feature = "__dnssec")]
+    fn attach_rrsigs(&mut self) {
+        for rrsig in mem::take(&mut self.rrsigs) {
+            let RData::DNSSEC(DNSSECRData::RRSIG(sig)) = &rrsig.data else {
+                continue;
+            };
+
+            let name = LowerName::new(&rrsig.name);
+            match self
+                .records
+                .get_mut(&RrKey::new(name.clone(), sig.input().type_covered))
+            {
+                Some(rrset) => rrset.insert_rrsig(rrsig),
+                None => match self.records.entry(RrKey::new(name, RecordType::RRSIG)) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(RecordSet::from(rrsig));
+                    }
+                    Entry::Occupied(mut entry) => {
+                        entry.get_mut().insert(rrsig, 0);
+                    }
+                },
+            }
+        }
+    }
 }
 
 enum State {
@@ -437,9 +585,145 @@ enum State {
     Ttl,          // $TTL <time>
     Record(Vec<String>),
     Include(Option<String>), // $INCLUDE <filename>
+    Generate(Vec<String>),   // $GENERATE <range> <lhs> [<TTL>] [<class>] <type> <rhs>
     Origin,
 }
 
+/// The `<start>-<stop>[/<step>]` range of a `$GENERATE` entry
+struct GenerateRange {
+    start: u32,
+    stop: u32,
+    step: u32,
+}
+
+impl GenerateRange {
+    fn values(&self) -> impl Iterator<Item = u32> {
+        (self.start..=self.stop).step_by(self.step as usize)
+    }
+
+    /// Number of values in the range
+    fn len(&self) -> u32 {
+        ((self.stop - self.start) / self.step).saturating_add(1)
+    }
+}
+
+impl FromStr for GenerateRange {
+    type Err = ParseError;
+
+    fn from_str(range: &str) -> ParseResult<Self> {
+        let (range, step) = match range.split_once('/') {
+            Some((range, step)) => (range, u32::from_str(step)?),
+            None => (range, 1),
+        };
+
+        let (start, stop) = range
+            .split_once('-')
+            .ok_or_else(|| ParseError::Msg(format!("invalid $GENERATE range: {range}")))?;
+        let (start, stop) = (u32::from_str(start)?, u32::from_str(stop)?);
+
+        if start > stop || step == 0 {
+            return Err(ParseError::Msg(format!(
+                "invalid $GENERATE range: {start}-{stop}/{step}"
+            )));
+        }
+
+        Ok(Self { start, stop, step })
+    }
+}
+
+/// Substitutes the `$` and `${offset,width,base}` references of a `$GENERATE` template
+fn generate_text(template: &str, value: u32) -> ParseResult<String> {
+    let mut text = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some('$') => text.push('$'),
+                Some(escaped) => {
+                    text.push('\\');
+                    text.push(escaped);
+                }
+                None => text.push('\\'),
+            },
+            '$' if chars.as_str().starts_with('{') => {
+                let (modifiers, rest) = chars.as_str()[1..].split_once('}').ok_or_else(|| {
+                    ParseError::Msg(format!("unclosed $GENERATE modifier in {template}"))
+                })?;
+                write_generated(&mut text, value, modifiers)?;
+                chars = rest.chars();
+            }
+            '$' => write_generated(&mut text, value, "")?,
+            ch => text.push(ch),
+        }
+    }
+
+    Ok(text)
+}
+
+/// Writes the value as described by the `offset[,width[,base]]` modifiers
+fn write_generated(text: &mut String, value: u32, modifiers: &str) -> ParseResult<()> {
+    let mut parts = modifiers.split(',');
+    let offset = match parts.next() {
+        Some("") | None => 0,
+        Some(offset) => i64::from_str(offset)?,
+    };
+    let width = match parts.next() {
+        Some(width) => usize::from_str(width)?,
+        None => 0,
+    };
+    let base = parts.next().unwrap_or("d");
+    if parts.next().is_some() || width > MAX_GENERATE_WIDTH {
+        return Err(ParseError::Msg(format!(
+            "invalid $GENERATE modifier: {{{modifiers}}}"
+        )));
+    }
+
+    let value = u32::try_from(i64::from(value) + offset).map_err(|_| {
+        ParseError::Msg(format!(
+            "$GENERATE offset {offset} moves {value} out of range"
+        ))
+    })?;
+
+    match base {
+        "d" => write!(text, "{value:0width$}"),
+        "o" => write!(text, "{value:0width$o}"),
+        "x" => write!(text, "{value:0width$x}"),
+        "X" => write!(text, "{value:0width$X}"),
+        "n" | "N" => {
+            // reversed nibbles separated by dots as for ip6.arpa names, padded with zero nibbles
+            //  until `width` characters, including the dots, are written
+            let mut value = value;
+            let mut width = width;
+            loop {
+                let nibble = char::from_digit(value & 0xf, 16).expect("nibble is a hex digit");
+                text.push(match base {
+                    "N" => nibble.to_ascii_uppercase(),
+                    _ => nibble,
+                });
+                value >>= 4;
+                width = width.saturating_sub(1);
+
+                if width > 0 || value != 0 {
+                    text.push('.');
+                    width = width.saturating_sub(1);
+                }
+
+                if width == 0 && value == 0 {
+                    break Ok(());
+                }
+            }
+        }
+        _ => return Err(ParseError::Msg(format!("invalid $GENERATE base: {base}"))),
+    }
+    .map_err(|_| ParseError::Message("failed to write $GENERATE value"))
+}
+
+/// Max number of records created by all $GENERATE entries of a zone
+const MAX_GENERATE_RECORDS: u32 = 65_536;
+
+/// Max width of a $GENERATE value, no label can be longer
+const MAX_GENERATE_WIDTH: usize = 255;
+
 /// Max traversal depth for $INCLUDE files
 const MAX_INCLUDE_LEVEL: usize = 256;
 
@@ -470,4 +754,118 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn test_generate() {
+        let zone_data = r#"$ORIGIN 2.0.192.in-addr.arpa.
+$TTL 3600
+@ IN SOA ns.example. hostmaster.example. 1 7200 900 1209600 300
+$GENERATE 1-254 $ PTR host-$.example.
+$GENERATE 10-20/5 ${100,4,d}.hosts.example. 60 IN A 192.0.2.$
+$GENERATE 0-1 ${0,3,x} TXT ${10,2,X}-\$ ; comment
+www A 192.0.2.99
+"#;
+
+        let (_, records) = Parser::new(zone_data, None, None).parse().unwrap();
+        let origin = Name::from_str("2.0.192.in-addr.arpa.").unwrap();
+
+        let ptrs = records
+            .iter()
+            .filter(|(key, _)| key.record_type == RecordType::PTR)
+            .collect::<Vec<_>>();
+        assert_eq!(ptrs.len(), 254);
+
+        let ptr = &records[&RrKey::new(
+            Name::from_str("17.2.0.192.in-addr.arpa.").unwrap().into(),
+            RecordType::PTR,
+        )];
+        assert_eq!(ptr.ttl(), 3600);
+        assert_eq!(
+            ptr.records_without_rrsigs()
+                .next()
+                .unwrap()
+                .data
+                .to_string(),
+            "host-17.example."
+        );
+
+        for (owner, ip) in [("0110", 10), ("0115", 15), ("0120", 20)] {
+            let name = Name::from_str(owner)
+                .unwrap()
+                .append_domain(&Name::from_str("hosts.example.").unwrap())
+                .unwrap();
+            let a = &records[&RrKey::new(name.into(), RecordType::A)];
+            assert_eq!(a.ttl(), 60);
+            assert_eq!(
+                a.records_without_rrsigs().next().unwrap().data.to_string(),
+                format!("192.0.2.{ip}")
+            );
+        }
+
+        let txt = &records[&RrKey::new(
+            Name::from_str("001")
+                .unwrap()
+                .append_domain(&origin)
+                .unwrap()
+                .into(),
+            RecordType::TXT,
+        )];
+        assert_eq!(
+            txt.records_without_rrsigs()
+                .next()
+                .unwrap()
+                .data
+                .to_string(),
+            "0B-$"
+        );
+
+        // the owner of the record following $GENERATE is not inherited from it
+        assert!(
+            records.contains_key(&RrKey::new(
+                Name::from_str("www")
+                    .unwrap()
+                    .append_domain(&origin)
+                    .unwrap()
+                    .into(),
+                RecordType::A,
+            ))
+        );
+    }
+
+    #[test]
+    fn test_generate_nibbles() {
+        let mut text = String::new();
+        write_generated(&mut text, 0x1234, "0,0,n").unwrap();
+        assert_eq!(text, "4.3.2.1");
+
+        text.clear();
+        write_generated(&mut text, 0xab, "0,7,N").unwrap();
+        assert_eq!(text, "B.A.0.0");
+
+        text.clear();
+        write_generated(&mut text, 10, "-2,3,o").unwrap();
+        assert_eq!(text, "010");
+    }
+
+    #[test]
+    fn test_generate_errors() {
+        for generate in [
+            "$GENERATE 1-0 $ A 192.0.2.1",
+            "$GENERATE 1-10/0 $ A 192.0.2.1",
+            "$GENERATE 0-4294967295 $ A 192.0.2.1",
+            "$GENERATE 1-10 ${-2} A 192.0.2.1",
+            "$GENERATE 1-10 ${0,3,z} A 192.0.2.1",
+            "$GENERATE 1-10 ${0,3 A 192.0.2.1",
+            "$GENERATE 1-10 $ A",
+            "$GENERATE a-b $ A 192.0.2.1",
+            // the limit applies to the total of all entries
+            "$GENERATE 1-40000 a$ A 192.0.2.1\n$GENERATE 1-40000 b$ A 192.0.2.1",
+        ] {
+            let zone_data = format!("$ORIGIN example.com.\n$TTL 60\n{generate}\n");
+            assert!(
+                Parser::new(zone_data, None, None).parse().is_err(),
+                "{generate} should fail"
+            );
+        }
+    }
 }
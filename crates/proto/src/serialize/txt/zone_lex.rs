@@ -57,6 +57,13 @@ impl<'a> Lexer<'a> {
                         }
                         // white space at the start of line is a Blank
                         Some(ch) if ch.is_whitespace() => self.state = State::Blank,
+                        // control entries are only recognized at the start of a line, elsewhere
+                        //  `$` is part of the data, e.g. in `$GENERATE` templates
+                        Some('$') => {
+                            self.txt.next();
+                            char_data = Some(String::new());
+                            self.state = State::Dollar;
+                        }
                         Some(_) => self.state = State::RestOfLine,
                         None => {
                             self.state = State::EOF;
@@ -72,11 +79,6 @@ impl<'a> Lexer<'a> {
                             self.state = State::List;
                         }
                         Some(ch @ ')') => return Err(LexerError::IllegalCharacter(ch)),
-                        Some('$') => {
-                            self.txt.next();
-                            char_data = Some(String::new());
-                            self.state = State::Dollar;
-                        }
                         Some('\r') | Some('\n') => {
                             self.state = State::EOL;
                         }
@@ -156,6 +158,7 @@ impl<'a> Lexer<'a> {
                             })?;
 
                             return Ok(Some(match dollar.as_str() {
+                                "GENERATE" => Token::Generate,
                                 "INCLUDE" => Token::Include,
                                 "ORIGIN" => Token::Origin,
                                 "TTL" => Token::Ttl,
@@ -353,6 +356,7 @@ pub(crate) enum State {
 
 /// Tokens emited from each Lexer pass
 #[derive(Eq, PartialEq, Debug, Clone)]
+#[non_exhaustive]
 pub enum Token {
     /// only if the first part of the line
     Blank,
@@ -362,6 +366,8 @@ pub enum Token {
     CharData(String),
     /// @
     At,
+    /// $GENERATE
+    Generate,
     /// $INCLUDE
     Include,
     /// $ORIGIN
@@ -557,6 +563,10 @@ mod lex_test {
             Token::Origin
         );
         assert_eq!(next_token(&mut Lexer::new("$TTL")).unwrap(), Token::Ttl);
+        assert_eq!(
+            next_token(&mut Lexer::new("$GENERATE")).unwrap(),
+            Token::Generate
+        );
         assert_eq!(next_token(&mut Lexer::new("\n")), Some(Token::EOL));
         assert_eq!(next_token(&mut Lexer::new("\r\n")), Some(Token::EOL));
     }
@@ -0,0 +1,416 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Serialization of records to the zone file format
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+use crate::{
+    rr::{DNSClass, Name, RData, Record, RecordSet, RecordType},
+    serialize::binary::BinEncodable,
+};
+
+/// Writes records in the zone file (master file) format of
+/// [RFC 1035, section 5](https://tools.ietf.org/html/rfc1035#section-5)
+///
+/// The output is canonical: it starts with an `$ORIGIN` entry followed by the SOA of the zone,
+/// then all other RecordSets ordered by name and type, with RRSIGs following the RecordSet they
+/// cover. Every record carries an explicit TTL and class, and consecutive records of the same
+/// owner leave the owner blank. Names within the origin are written relative to it.
+///
+/// Record data which can not be read back by the [`Parser`](super::Parser) from its
+/// presentation format, e.g. DNSSEC records or TXT records with binary data, is written in the
+/// generic `\# <length> <hex>` encoding of [RFC 3597](https://tools.ietf.org/html/rfc3597#section-5),
+/// so any set of records written by the `ZoneWriter` parses back to the same records.
+///
+/// ```
+/// use std::str::FromStr;
+///
+/// use hickory_proto::rr::{Name, RData, Record, RecordSet, rdata::A};
+/// use hickory_proto::serialize::txt::ZoneWriter;
+///
+/// let origin = Name::from_str("example.com.").unwrap();
+/// let www = Name::from_str("www.example.com.").unwrap();
+/// let rrset = RecordSet::from(Record::from_rdata(www, 3600, RData::A(A::new(127, 0, 0, 1))));
+///
+/// let mut zone = String::new();
+/// ZoneWriter::new(origin).write(&mut zone, [&rrset]).unwrap();
+/// assert_eq!(zone, "$ORIGIN example.com.\nwww\t3600\tIN\tA\t127.0.0.1\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ZoneWriter {
+    origin: Name,
+    relative_names: bool,
+}
+
+impl ZoneWriter {
+    /// Returns a new writer for the zone with the specified origin
+    pub fn new(mut origin: Name) -> Self {
+        origin.set_fqdn(true);
+        Self {
+            origin,
+            relative_names: true,
+        }
+    }
+
+    /// Sets whether names within the origin are written relative to it, the default is `true`
+    pub fn with_relative_names(mut self, relative_names: bool) -> Self {
+        self.relative_names = relative_names;
+        self
+    }
+
+    /// Writes all records, including RRSIGs, of the RecordSets to `out`
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - destination for the zone text
+    /// * `rrsets` - the RecordSets of the zone, e.g. the values of the map returned by the
+    ///   [`Parser`](super::Parser), in any order
+    pub fn write<'r, W: Write>(
+        &self,
+        out: &mut W,
+        rrsets: impl IntoIterator<Item = &'r RecordSet>,
+    ) -> fmt::Result {
+        let mut rrsets = rrsets.into_iter().collect::<Vec<_>>();
+        rrsets.sort_by(|a, b| {
+            let key = |rrset: &RecordSet| {
+                (
+                    *rrset.name() != self.origin,
+                    rrset.record_type() != RecordType::SOA,
+                )
+            };
+
+            key(a)
+                .cmp(&key(b))
+                .then_with(|| a.name().cmp(b.name()))
+                .then_with(|| u16::from(a.record_type()).cmp(&u16::from(b.record_type())))
+        });
+
+        writeln!(out, "$ORIGIN {}", self.origin.to_ascii())?;
+
+        let mut last_owner: Option<&Name> = None;
+        for rrset in rrsets {
+            let records = rrset.records_without_rrsigs().chain(rrset.rrsigs());
+            for record in records {
+                match last_owner {
+                    Some(owner) if owner.eq_case(&record.name) => (),
+                    // `@` is only recognized in place of an owner name
+                    _ if self.relative_names && record.name == self.origin => {
+                        out.write_char('@')?
+                    }
+                    _ => self.write_name(out, &record.name)?,
+                }
+
+                last_owner = Some(&record.name);
+                self.write_record(out, record)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes everything following the owner name of the record
+    fn write_record<W: Write>(&self, out: &mut W, record: &Record) -> fmt::Result {
+        write!(out, "\t{}\t", record.ttl)?;
+
+        match record.dns_class {
+            class @ (DNSClass::OPT(_) | DNSClass::Unknown(_)) => {
+                write!(out, "CLASS{}", u16::from(class))?
+            }
+            class => write!(out, "{class}")?,
+        }
+
+        match record.record_type() {
+            RecordType::Unknown(code) => write!(out, "\tTYPE{code}\t")?,
+            record_type => write!(out, "\t{record_type}\t")?,
+        }
+
+        match self.presentation(&record.data) {
+            Some(text) => out.write_str(&text)?,
+            None => write_generic(out, &record.data)?,
+        }
+
+        out.write_char('\n')
+    }
+
+    /// Returns the presentation format of the RData, if it can be parsed back to the same RData
+    fn presentation(&self, rdata: &RData) -> Option<String> {
+        let mut text = String::new();
+        match rdata {
+            RData::ANAME(name) => self.write_name(&mut text, name).ok()?,
+            RData::CNAME(name) => self.write_name(&mut text, name).ok()?,
+            RData::NS(name) => self.write_name(&mut text, name).ok()?,
+            RData::PTR(name) => self.write_name(&mut text, name).ok()?,
+            RData::MX(mx) => {
+                write!(text, "{} ", mx.preference).ok()?;
+                self.write_name(&mut text, &mx.exchange).ok()?;
+            }
+            RData::SRV(srv) => {
+                write!(text, "{} {} {} ", srv.priority, srv.weight, srv.port).ok()?;
+                self.write_name(&mut text, &srv.target).ok()?;
+            }
+            RData::SOA(soa) => {
+                if soa.refresh < 0 || soa.retry < 0 || soa.expire < 0 {
+                    return None;
+                }
+
+                self.write_name(&mut text, &soa.mname).ok()?;
+                text.push(' ');
+                self.write_name(&mut text, &soa.rname).ok()?;
+                write!(
+                    text,
+                    " {} {} {} {} {}",
+                    soa.serial, soa.refresh, soa.retry, soa.expire, soa.minimum
+                )
+                .ok()?;
+            }
+            RData::HINFO(hinfo) => write_character_strings(&mut text, [&*hinfo.cpu, &*hinfo.os])?,
+            RData::TXT(txt) if !txt.txt_data.is_empty() => {
+                write_character_strings(&mut text, txt.txt_data.iter().map(AsRef::as_ref))?
+            }
+            RData::TXT(_) => return None,
+            rdata => {
+                // only use the Display form if it parses back to the same data
+                write!(text, "{rdata}").ok()?;
+                match RData::try_from_str(rdata.record_type(), &text) {
+                    Ok(parsed) if parsed == *rdata => (),
+                    _ => return None,
+                }
+            }
+        }
+
+        Some(text)
+    }
+
+    fn write_name<W: Write>(&self, out: &mut W, name: &Name) -> fmt::Result {
+        let labels = name.iter().len();
+        let origin_labels = self.origin.iter().len();
+        if !self.relative_names || labels == origin_labels || !self.origin.zone_of(name) {
+            return out.write_str(&name.to_ascii());
+        }
+
+        let mut relative =
+            Name::from_labels(name.iter().take(labels - origin_labels)).map_err(|_| fmt::Error)?;
+        relative.set_fqdn(false);
+        out.write_str(&relative.to_ascii())
+    }
+}
+
+/// Writes the quoted `<character-string>`s, returns `None` if they are not printable text
+fn write_character_strings<'a>(
+    out: &mut String,
+    strings: impl IntoIterator<Item = &'a [u8]>,
+) -> Option<()> {
+    for (i, string) in strings.into_iter().enumerate() {
+        let string = core::str::from_utf8(string).ok()?;
+        if i > 0 {
+            out.push(' ');
+        }
+
+        out.push('"');
+        for ch in string.chars() {
+            match ch {
+                '"' | '\\' => {
+                    out.push('\\');
+                    out.push(ch);
+                }
+                ch if ch.is_control() => return None,
+                ch => out.push(ch),
+            }
+        }
+        out.push('"');
+    }
+
+    Some(())
+}
+
+/// Writes the RFC 3597 generic encoding of the RData, `\# <length> <hex>`
+fn write_generic<W: Write>(out: &mut W, rdata: &RData) -> fmt::Result {
+    let data = rdata.to_bytes().map_err(|_| fmt::Error)?;
+    write!(out, "\\# {}", data.len())?;
+    if !data.is_empty() {
+        write!(out, " {}", data_encoding::HEXUPPER.encode(&data))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use core::str::FromStr;
+
+    use super::*;
+    use crate::{
+        rr::{
+            RrKey,
+            rdata::{HINFO, MX, NULL, TXT},
+        },
+        serialize::txt::Parser,
+    };
+
+    const ZONE: &str = r#"
+$ORIGIN example.com.
+$TTL 3600
+@       IN  SOA  ns1 hostmaster.example.net. ( 2024010101 7200 900 1209600 300 )
+        IN  NS   ns1
+        IN  NS   ns.example.net.
+        IN  MX   10 mail
+        IN  TXT  "v=spf1 -all" "second \"quoted\" string" ""
+        IN  CAA  0 issue "ca.example.net"
+ns1     IN  A    192.0.2.1
+        IN  AAAA 2001:db8::1
+mail    60  IN   A 192.0.2.2
+www     IN  CNAME example.com.
+*.wild  IN  TXT  "wildcard; not a comment"
+_sip._tcp IN SRV 0 5 5060 sip.example.net.
+alias   IN  ANAME www
+host    IN  HINFO "Generic PC" "Some OS"
+sshfp   IN  SSHFP 1 1 C93DBCF3AC0C63F3A0A4A8AF13A1F3C18B0D5F3F
+_443._tcp IN TLSA 3 1 1 0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF
+svc     IN  SVCB 1 . alpn=h2 port=8443
+unknown IN  TYPE65534 \# 4 0A000001
+"#;
+
+    fn parse(zone: &str) -> (Name, BTreeMap<RrKey, RecordSet>) {
+        Parser::new(zone, None, None)
+            .parse()
+            .expect("failed to parse zone")
+    }
+
+    fn write(origin: Name, records: &BTreeMap<RrKey, RecordSet>) -> String {
+        let mut out = String::new();
+        ZoneWriter::new(origin)
+            .write(&mut out, records.values())
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (origin, records) = parse(ZONE);
+        let written = write(origin.clone(), &records);
+
+        let (written_origin, parsed) = parse(&written);
+        assert_eq!(written_origin, origin);
+        assert_eq!(parsed, records, "written zone:\n{written}");
+
+        // writing is stable
+        assert_eq!(write(origin, &parsed), written);
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let (origin, records) = parse(ZONE);
+        let written = write(origin, &records);
+        let mut lines = written.lines();
+
+        assert_eq!(lines.next(), Some("$ORIGIN example.com."));
+        assert_eq!(
+            lines.next(),
+            Some("@\t3600\tIN\tSOA\tns1 hostmaster.example.net. 2024010101 7200 900 1209600 300")
+        );
+        assert_eq!(lines.next(), Some("\t3600\tIN\tNS\tns1"));
+        assert_eq!(lines.next(), Some("\t3600\tIN\tNS\tns.example.net."));
+        assert_eq!(lines.next(), Some("\t3600\tIN\tMX\t10 mail"));
+        assert_eq!(
+            lines.next(),
+            Some("\t3600\tIN\tTXT\t\"v=spf1 -all\" \"second \\\"quoted\\\" string\" \"\"")
+        );
+
+        assert!(written.contains("\nmail\t60\tIN\tA\t192.0.2.2\n"));
+        assert!(written.contains("\nwww\t3600\tIN\tCNAME\texample.com.\n"));
+        assert!(written.contains("\n_sip._tcp\t3600\tIN\tSRV\t0 5 5060 sip.example.net.\n"));
+        assert!(written.contains("\nunknown\t3600\tIN\tTYPE65534\t\\# 4 0A000001\n"));
+    }
+
+    #[test]
+    fn test_absolute_names() {
+        let (origin, records) = parse(ZONE);
+        let mut written = String::new();
+        ZoneWriter::new(origin)
+            .with_relative_names(false)
+            .write(&mut written, records.values())
+            .unwrap();
+
+        assert!(written.contains("\nwww.example.com.\t3600\tIN\tCNAME\texample.com.\n"));
+        assert_eq!(parse(&written).1, records);
+    }
+
+    #[test]
+    fn test_generic_fallback() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let name = Name::from_str("binary.example.com.").unwrap();
+
+        let mut records = BTreeMap::new();
+        let mut insert = |rdata: RData| {
+            let record = Record::from_rdata(name.clone(), 300, rdata);
+            let key = RrKey::new(name.clone().into(), record.record_type());
+            records.insert(key, RecordSet::from(record));
+        };
+
+        insert(RData::TXT(TXT::from_bytes(vec![
+            b"\xff\x00binary",
+            b"line\nbreak",
+        ])));
+        insert(RData::HINFO(HINFO::from_bytes(
+            b"tab\tcpu".to_vec().into_boxed_slice(),
+            b"os".to_vec().into_boxed_slice(),
+        )));
+        insert(RData::NULL(NULL::with(vec![1, 2, 3])));
+        insert(RData::MX(MX::new(
+            1,
+            Name::from_str("mail.other.example.").unwrap(),
+        )));
+
+        let written = write(origin.clone(), &records);
+        assert!(written.contains("\tTXT\t\\# "), "{written}");
+        assert!(written.contains("\tHINFO\t\\# "), "{written}");
+        assert!(written.contains("\tNULL\t\\# 3 010203"), "{written}");
+        assert!(written.contains("\tMX\t1 mail.other.example."), "{written}");
+
+        let (_, parsed) = parse(&written);
+        assert_eq!(parsed, records);
+    }
+
+    #[cfg(feature = "__dnssec")]
+    #[test]
+    fn test_rrsigs_round_trip() {
+        use crate::dnssec::{
+            Algorithm,
+            rdata::{DNSSECRData, RRSIG, SigInput},
+        };
+        use crate::rr::SerialNumber;
+
+        let (origin, mut records) = parse(ZONE);
+        let www = Name::from_str("www.example.com.").unwrap();
+        let input = SigInput {
+            type_covered: RecordType::CNAME,
+            algorithm: Algorithm::ED25519,
+            num_labels: 3,
+            original_ttl: 3600,
+            sig_expiration: SerialNumber(1_700_086_400),
+            sig_inception: SerialNumber(1_700_000_000),
+            key_tag: 12345,
+            signer_name: origin.clone(),
+        };
+        let rrsig = Record::from_rdata(
+            www.clone(),
+            3600,
+            RData::DNSSEC(DNSSECRData::RRSIG(RRSIG::from_sig(input, vec![0xAB; 64]))),
+        );
+        records
+            .get_mut(&RrKey::new(www.into(), RecordType::CNAME))
+            .unwrap()
+            .insert_rrsig(rrsig);
+
+        let written = write(origin, &records);
+        assert!(written.contains("\tRRSIG\t\\# "), "{written}");
+        assert_eq!(parse(&written).1, records);
+    }
+}
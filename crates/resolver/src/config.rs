@@ -39,6 +39,9 @@ use tracing::{debug, info};
 use crate::name_server_pool::NameServerTransportState;
 #[cfg(any(feature = "__https", feature = "__h3"))]
 use crate::net::http::DEFAULT_DNS_QUERY_PATH;
+#[cfg(any(feature = "__https", feature = "__h3"))]
+use crate::net::http::HttpPoolConfig;
+use crate::net::proxy::ProxyConfig;
 use crate::net::xfer::{CONNECT_TIMEOUT, Protocol};
 use crate::proto::access_control::{AccessControlSet, AccessControlSetBuilder};
 use crate::proto::op::DEFAULT_MAX_PAYLOAD_LEN;
@@ -57,6 +60,9 @@ pub struct ResolverConfig {
     pub search: Vec<Name>,
     /// Name servers to use for resolution
     pub name_servers: Vec<NameServerConfig>,
+    /// Name servers to use instead for the names in specific domains
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub routes: Vec<DomainRoute>,
 }
 
 impl ResolverConfig {
@@ -69,6 +75,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.udp_and_tcp().collect(),
+            routes: vec![],
         }
     }
 
@@ -82,6 +89,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.tls().collect(),
+            routes: vec![],
         }
     }
 
@@ -95,6 +103,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.https().collect(),
+            routes: vec![],
         }
     }
 
@@ -108,6 +117,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.quic().collect(),
+            routes: vec![],
         }
     }
 
@@ -121,6 +131,7 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: config.h3().collect(),
+            routes: vec![],
         }
     }
 
@@ -140,6 +151,7 @@ impl ResolverConfig {
             domain,
             search,
             name_servers,
+            routes: vec![],
         }
     }
 
@@ -190,10 +202,60 @@ impl ResolverConfig {
     pub fn name_servers(&self) -> &[NameServerConfig] {
         &self.name_servers
     }
+
+    /// Send the queries for names in `domain` to `name_servers` instead of the default ones
+    ///
+    /// When several routes match a name, the one with the longest domain is used.
+    pub fn add_route(&mut self, domain: Name, name_servers: Vec<NameServerConfig>) {
+        self.routes.push(DomainRoute {
+            domain,
+            name_servers,
+        });
+    }
+
+    /// Send the reverse queries for addresses in `network` to `name_servers`
+    ///
+    /// This routes the `in-addr.arpa.` or `ip6.arpa.` zones covering the network. Prefixes that
+    /// are not on an octet, or nibble for IPv6, boundary are split into the zones they span, e.g.
+    /// `172.16.0.0/12` into `16.172.in-addr.arpa.` through `31.172.in-addr.arpa.`.
+    pub fn add_reverse_route(&mut self, network: IpNet, name_servers: Vec<NameServerConfig>) {
+        let label_bits = match network {
+            IpNet::V4(_) => 8,
+            IpNet::V6(_) => 4,
+        };
+        let zone_labels = network.prefix_len().div_ceil(label_bits);
+        // the subnets are only empty for an invalid prefix length, which IpNet rules out
+        let Ok(subnets) = network.subnets(zone_labels * label_bits) else {
+            return;
+        };
+
+        for subnet in subnets {
+            // keep the network labels and the `in-addr.arpa` or `ip6.arpa` suffix
+            let domain = Name::from(subnet.network()).trim_to(zone_labels as usize + 2);
+            self.add_route(domain, name_servers.clone());
+        }
+    }
+
+    /// Returns the routes of names to name servers
+    pub fn routes(&self) -> &[DomainRoute] {
+        &self.routes
+    }
+}
+
+/// Name servers resolving the names in a domain, see [`ResolverConfig::add_route`]
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DomainRoute {
+    /// The domain, including all of its subdomains, e.g. `corp.example.com.` or
+    /// `10.in-addr.arpa.`
+    pub domain: Name,
+    /// Name servers to use for resolution of the names in the domain
+    pub name_servers: Vec<NameServerConfig>,
 }
 
 /// Configuration for the NameServer
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -331,7 +393,7 @@ fn default_trust_negative_responses() -> bool {
 }
 
 /// Configuration for a connection to a nameserver
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 #[non_exhaustive]
 pub struct ConnectionConfig {
@@ -341,6 +403,8 @@ pub struct ConnectionConfig {
     pub protocol: ProtocolConfig,
     /// The client address (IP and port) to use for connecting to the server
     pub bind_addr: Option<SocketAddr>,
+    /// The proxy to connect to the server through, only supported for HTTPS (HTTP/2)
+    pub proxy: Option<ProxyConfig>,
 }
 
 impl ConnectionConfig {
@@ -391,8 +455,17 @@ impl ConnectionConfig {
             port: protocol.default_port(),
             protocol,
             bind_addr: None,
+            proxy: None,
         }
     }
+
+    /// Connect to the server through a proxy, only supported for HTTPS (HTTP/2)
+    ///
+    /// Connecting with any other protocol fails when a proxy is set.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -406,6 +479,8 @@ impl<'de> Deserialize<'de> for ConnectionConfig {
             protocol: ProtocolConfig,
             #[serde(default)]
             bind_addr: Option<SocketAddr>,
+            #[serde(default)]
+            proxy: Option<ProxyConfig>,
         }
 
         let parts = OptionalParts::deserialize(deserializer)?;
@@ -413,6 +488,7 @@ impl<'de> Deserialize<'de> for ConnectionConfig {
             port: parts.port.unwrap_or_else(|| parts.protocol.default_port()),
             protocol: parts.protocol,
             bind_addr: parts.bind_addr,
+            proxy: parts.proxy,
         })
     }
 }
@@ -512,6 +588,11 @@ pub struct ResolverOpts {
         serde(default = "default_timeout", with = "duration")
     )]
     pub timeout: Duration,
+    /// Timeout of a request to each name server, when it should differ from `timeout`
+    ///
+    /// `timeout` then only bounds the time spent on a request across all the name servers.
+    #[cfg_attr(feature = "serde", serde(with = "duration_opt"))]
+    pub server_timeout: Option<Duration>,
     /// Number of retries after lookup failure before giving up. Defaults to 2
     #[cfg_attr(feature = "serde", serde(default = "default_attempts"))]
     pub attempts: usize,
@@ -573,6 +654,14 @@ pub struct ResolverOpts {
     pub try_tcp_on_error: bool,
     /// The server ordering strategy that the resolver should use.
     pub server_ordering_strategy: ServerOrderingStrategy,
+    /// Whether to trust the authentic data (AD) bit in the responses of the name servers.
+    ///
+    /// With `Some(true)` the AD bit is requested from the name servers and trusted, only enable
+    /// this when the name servers validate DNSSEC and the path to them is secure, see
+    /// [RFC 6840 section 5.7](https://www.rfc-editor.org/rfc/rfc6840#section-5.7). With
+    /// `Some(false)` the AD bit is cleared from the responses, as for a resolv.conf without the
+    /// `trust-ad` option. Defaults to `None`, passing the AD bit of the responses through.
+    pub trust_ad: Option<bool>,
     /// Request upstream recursive resolvers to not perform any recursion.
     ///
     /// This is true by default, disabling this is useful for requesting single records, but may prevent successful resolution.
@@ -623,6 +712,41 @@ pub struct ResolverOpts {
         serde(default = "default_connect_timeout", with = "duration")
     )]
     pub connect_timeout: Duration,
+    /// Maximum number of HTTPS (HTTP/2) and HTTP/3 connections to each name server.
+    ///
+    /// Another connection is opened when all the connections to the name server reach
+    /// `http_max_concurrent_streams`. Defaults to 1.
+    #[cfg_attr(feature = "serde", serde(default = "default_http_max_connections"))]
+    #[cfg(any(feature = "__https", feature = "__h3"))]
+    pub http_max_connections: usize,
+    /// Maximum number of concurrent requests on each HTTPS (HTTP/2) and HTTP/3 connection.
+    ///
+    /// Defaults to 100, the minimum that servers should allow.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "default_http_max_concurrent_streams")
+    )]
+    #[cfg(any(feature = "__https", feature = "__h3"))]
+    pub http_max_concurrent_streams: usize,
+    /// Time after which an HTTPS (HTTP/2) or HTTP/3 connection without requests is closed.
+    ///
+    /// Defaults to 60s.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "default_http_idle_timeout", with = "duration")
+    )]
+    #[cfg(any(feature = "__https", feature = "__h3"))]
+    pub http_idle_timeout: Duration,
+    /// Time to wait before connecting again to a name server over HTTPS (HTTP/2) or HTTP/3 after
+    /// a failed connection.
+    ///
+    /// The time doubles after each consecutive failure, up to 60s. Defaults to 1s.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "default_http_reconnect_backoff", with = "duration")
+    )]
+    #[cfg(any(feature = "__https", feature = "__h3"))]
+    pub http_reconnect_backoff: Duration,
     /// Report the IP of the name server for query result metrics.
     ///
     /// When `false`, metrics are aggregated under a static "aggregate" address label to avoid
@@ -634,6 +758,22 @@ pub struct ResolverOpts {
 }
 
 impl ResolverOpts {
+    /// Timeout of a request to a single name server
+    pub(crate) fn server_timeout(&self) -> Duration {
+        self.server_timeout.unwrap_or(self.timeout)
+    }
+
+    /// Configuration of the pools of HTTPS (HTTP/2) and HTTP/3 connections
+    #[cfg(any(feature = "__https", feature = "__h3"))]
+    pub(crate) fn http_pool_config(&self) -> HttpPoolConfig {
+        let mut config = HttpPoolConfig::default();
+        config.max_connections = self.http_max_connections;
+        config.max_concurrent_streams = self.http_max_concurrent_streams;
+        config.idle_timeout = self.http_idle_timeout;
+        config.reconnect_backoff = self.http_reconnect_backoff;
+        config
+    }
+
     pub(crate) fn answer_address_filter(&self) -> AccessControlSet {
         let name = "resolver_answer_filter";
         AccessControlSetBuilder::new(name)
@@ -653,6 +793,7 @@ impl Default for ResolverOpts {
         Self {
             ndots: default_ndots(),
             timeout: default_timeout(),
+            server_timeout: None,
             attempts: default_attempts(),
             edns0: true,
             #[cfg(feature = "__dnssec")]
@@ -672,6 +813,7 @@ impl Default for ResolverOpts {
 
             try_tcp_on_error: false,
             server_ordering_strategy: ServerOrderingStrategy::default(),
+            trust_ad: None,
             recursion_desired: default_recursion_desired(),
             avoid_local_udp_ports: Arc::default(),
             os_port_selection: false,
@@ -681,6 +823,14 @@ impl Default for ResolverOpts {
             deny_answers: vec![],
             edns_payload_len: default_edns_payload_len(),
             connect_timeout: default_connect_timeout(),
+            #[cfg(any(feature = "__https", feature = "__h3"))]
+            http_max_connections: default_http_max_connections(),
+            #[cfg(any(feature = "__https", feature = "__h3"))]
+            http_max_concurrent_streams: default_http_max_concurrent_streams(),
+            #[cfg(any(feature = "__https", feature = "__h3"))]
+            http_idle_timeout: default_http_idle_timeout(),
+            #[cfg(any(feature = "__https", feature = "__h3"))]
+            http_reconnect_backoff: default_http_reconnect_backoff(),
             #[cfg(feature = "metrics")]
             enable_per_name_server_metrics: false,
         }
@@ -699,6 +849,26 @@ fn default_connect_timeout() -> Duration {
     CONNECT_TIMEOUT
 }
 
+#[cfg(any(feature = "__https", feature = "__h3"))]
+fn default_http_max_connections() -> usize {
+    HttpPoolConfig::default().max_connections
+}
+
+#[cfg(any(feature = "__https", feature = "__h3"))]
+fn default_http_max_concurrent_streams() -> usize {
+    HttpPoolConfig::default().max_concurrent_streams
+}
+
+#[cfg(any(feature = "__https", feature = "__h3"))]
+fn default_http_idle_timeout() -> Duration {
+    HttpPoolConfig::default().idle_timeout
+}
+
+#[cfg(any(feature = "__https", feature = "__h3"))]
+fn default_http_reconnect_backoff() -> Duration {
+    HttpPoolConfig::default().reconnect_backoff
+}
+
 fn default_attempts() -> usize {
     2
 }
@@ -1154,6 +1324,7 @@ mod tests {
         let json = serde_json::from_str::<ResolverOpts>("{}").unwrap();
         assert_eq!(code.ndots, json.ndots);
         assert_eq!(code.timeout, json.timeout);
+        assert_eq!(code.server_timeout, json.server_timeout);
         assert_eq!(code.attempts, json.attempts);
         assert_eq!(code.edns0, json.edns0);
         #[cfg(feature = "__dnssec")]
@@ -1170,15 +1341,47 @@ mod tests {
         assert_eq!(code.try_tcp_on_error, json.try_tcp_on_error);
         assert_eq!(code.recursion_desired, json.recursion_desired);
         assert_eq!(code.server_ordering_strategy, json.server_ordering_strategy);
+        assert_eq!(code.trust_ad, json.trust_ad);
         assert_eq!(code.avoid_local_udp_ports, json.avoid_local_udp_ports);
         assert_eq!(code.os_port_selection, json.os_port_selection);
         assert_eq!(code.case_randomization, json.case_randomization);
         assert_eq!(code.trust_anchor, json.trust_anchor);
         assert_eq!(code.connect_timeout, json.connect_timeout);
+        #[cfg(any(feature = "__https", feature = "__h3"))]
+        {
+            assert_eq!(code.http_max_connections, json.http_max_connections);
+            assert_eq!(
+                code.http_max_concurrent_streams,
+                json.http_max_concurrent_streams
+            );
+            assert_eq!(code.http_idle_timeout, json.http_idle_timeout);
+            assert_eq!(code.http_reconnect_backoff, json.http_reconnect_backoff);
+        }
         #[cfg(feature = "metrics")]
         assert_eq!(
             code.enable_per_name_server_metrics,
             json.enable_per_name_server_metrics
         );
     }
+
+    #[test]
+    fn test_add_reverse_route() {
+        let mut config = ResolverConfig::from_name_servers(vec![]);
+        let name_servers = vec![NameServerConfig::udp(IpAddr::from([10, 0, 0, 53]))];
+        config.add_reverse_route("10.0.0.0/8".parse().unwrap(), name_servers.clone());
+        config.add_reverse_route("172.16.0.0/12".parse().unwrap(), name_servers.clone());
+        config.add_reverse_route("fd00::/7".parse().unwrap(), name_servers);
+
+        let domains = config
+            .routes()
+            .iter()
+            .map(|route| route.domain.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(domains.len(), 1 + 16 + 2);
+        assert_eq!(domains[0], "10.in-addr.arpa.");
+        assert_eq!(domains[1], "16.172.in-addr.arpa.");
+        assert_eq!(domains[16], "31.172.in-addr.arpa.");
+        assert_eq!(domains[17], "c.f.ip6.arpa.");
+        assert_eq!(domains[18], "d.f.ip6.arpa.");
+    }
 }
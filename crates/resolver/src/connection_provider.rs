@@ -6,6 +6,8 @@
 // copied, modified, or distributed except according to those terms.
 
 use std::future::Future;
+#[cfg(feature = "__h3")]
+use std::io;
 use std::marker::Unpin;
 use std::net::{IpAddr, SocketAddr};
 #[cfg(feature = "__quic")]
@@ -14,8 +16,12 @@ use std::pin::Pin;
 #[cfg(any(feature = "__tls", feature = "__https"))]
 use std::sync::Arc;
 
+#[cfg(any(feature = "__https", feature = "__h3"))]
+use futures_util::future::BoxFuture;
 #[cfg(feature = "__https")]
 use hickory_net::h2::HttpsClientStream;
+#[cfg(feature = "__h3")]
+use parking_lot::Mutex;
 #[cfg(feature = "__tls")]
 use rustls::DigitallySignedStruct;
 #[cfg(feature = "__tls")]
@@ -33,6 +39,12 @@ use crate::net::h3::H3ClientStream;
 use crate::net::quic::QuicClientStream;
 #[cfg(feature = "__tls")]
 use crate::net::tls::{client_config, default_provider, tls_exchange};
+#[cfg(any(feature = "__https", feature = "__h3"))]
+use crate::net::{
+    http::{HttpPool, HttpPoolConfig},
+    runtime::Spawn,
+    xfer::DnsRequestSender,
+};
 use crate::{
     config::{ConnectionConfig, ProtocolConfig},
     name_server_pool::PoolContext,
@@ -78,11 +90,23 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
         config: &ConnectionConfig,
         cx: &PoolContext,
     ) -> Result<Self::FutureConn, NetError> {
+        #[cfg(feature = "__https")]
+        let proxied = matches!(config.protocol, ProtocolConfig::Https { .. });
+        #[cfg(not(feature = "__https"))]
+        let proxied = false;
+        if config.proxy.is_some() && !proxied {
+            return Err(NetError::from(format!(
+                "proxies are only supported for HTTPS, not {}",
+                config.protocol.to_protocol()
+            )));
+        }
+
         let remote_addr = SocketAddr::new(ip, config.port);
-        match (&config.protocol, self.quic_binder()) {
+        let conn: Result<Self::FutureConn, NetError> = match (&config.protocol, self.quic_binder())
+        {
             (ProtocolConfig::Udp, _) => {
                 let (timeout, os_port_selection, avoid_local_udp_ports, bind_addr, provider) = (
-                    cx.options.timeout,
+                    cx.options.server_timeout(),
                     cx.options.os_port_selection,
                     cx.options.avoid_local_udp_ports.clone(),
                     config.bind_addr,
@@ -102,7 +126,7 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
                 remote_addr,
                 config.bind_addr,
                 cx.options.connect_timeout,
-                cx.options.timeout,
+                cx.options.server_timeout(),
                 Some(cx.options.max_active_requests),
                 self.clone(),
             ))),
@@ -120,7 +144,7 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
                     config.bind_addr,
                     server_name,
                     cx.tls.clone(),
-                    cx.options.timeout,
+                    cx.options.server_timeout(),
                     cx.options.connect_timeout,
                     Some(cx.options.max_active_requests),
                     self.clone(),
@@ -134,10 +158,18 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
                 if let Some(bind_addr) = config.bind_addr {
                     builder.bind_addr(bind_addr);
                 }
-                Ok(Box::pin(builder.exchange(
-                    remote_addr,
-                    server_name.clone(),
-                    path.clone(),
+                if let Some(proxy) = &config.proxy {
+                    builder = builder.proxy(proxy.clone());
+                }
+
+                let (server_name, path) = (server_name.clone(), path.clone());
+                Ok(Box::pin(http_exchange(
+                    cx.options.http_pool_config(),
+                    move || {
+                        let builder = builder.clone();
+                        Box::pin(builder.build(remote_addr, server_name.clone(), path.clone()))
+                    },
+                    self.clone(),
                 )))
             }
 
@@ -174,19 +206,38 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
                     SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
                 });
 
-                Ok(Box::pin(
-                    H3ClientStream::builder()
-                        .crypto_config(cx.tls.clone())
-                        .disable_grease(*disable_grease)
-                        .connect_timeout(cx.options.connect_timeout)
-                        .exchange(
-                            binder.bind_quic(bind_addr, remote_addr)?,
-                            remote_addr,
-                            server_name.clone(),
-                            path.clone(),
-                            self.clone(),
-                        ),
-                ))
+                let builder = H3ClientStream::builder()
+                    .crypto_config(cx.tls.clone())
+                    .disable_grease(*disable_grease)
+                    .connect_timeout(cx.options.connect_timeout);
+
+                // each connection of the pool is bound to a new socket
+                let socket = binder.bind_quic(bind_addr, remote_addr)?;
+                let (server_name, path) = (server_name.clone(), path.clone());
+                let (provider, socket) = (self.clone(), Mutex::new(Some(socket)));
+                Ok(Box::pin(http_exchange(
+                    cx.options.http_pool_config(),
+                    move || {
+                        let socket = match socket.lock().take() {
+                            Some(socket) => Ok(socket),
+                            None => match provider.quic_binder() {
+                                Some(binder) => binder.bind_quic(bind_addr, remote_addr),
+                                None => {
+                                    Err(io::Error::other("runtime provider does not support QUIC"))
+                                }
+                            },
+                        };
+
+                        let builder = builder.clone();
+                        let (server_name, path) = (server_name.clone(), path.clone());
+                        Box::pin(async move {
+                            builder
+                                .build_with_future(socket?, remote_addr, server_name, path)
+                                .await
+                        })
+                    },
+                    self.clone(),
+                )))
             }
             #[cfg(feature = "__quic")]
             (ProtocolConfig::Quic { .. }, None) => {
@@ -196,7 +247,17 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
             (ProtocolConfig::H3 { .. }, None) => {
                 Err(NetError::from("runtime provider does not support QUIC"))
             }
+        };
+
+        #[cfg(feature = "dnstap")]
+        if let Some(tap) = cx.dnstap.clone() {
+            let (conn, protocol) = (conn?, config.protocol.to_protocol());
+            return Ok(Box::pin(async move {
+                Ok(conn.await?.with_dnstap(tap, protocol, remote_addr))
+            }));
         }
+
+        conn
     }
 
     fn runtime_provider(&self) -> &Self::RuntimeProvider {
@@ -204,6 +265,19 @@ impl<P: RuntimeProvider> ConnectionProvider for P {
     }
 }
 
+/// Creates a [`DnsExchange`] over a pool of the HTTP connections opened by `connect`
+#[cfg(any(feature = "__https", feature = "__h3"))]
+async fn http_exchange<P: RuntimeProvider, S: DnsRequestSender + Clone>(
+    config: HttpPoolConfig,
+    connect: impl Fn() -> BoxFuture<'static, Result<S, NetError>> + Send + Sync + 'static,
+    provider: P,
+) -> Result<DnsExchange<P>, NetError> {
+    let pool = HttpPool::connect(config, connect).await?;
+    let (exchange, bg) = DnsExchange::from_stream(pool);
+    provider.create_handle().spawn_bg(bg);
+    Ok(exchange)
+}
+
 /// TLS configuration for the connection provider.
 pub struct TlsConfig {
     /// The TLS configuration to use for secure connections.
@@ -455,6 +529,30 @@ mod tests {
         assert_ne!(response.iter().count(), 0);
     }
 
+    #[test]
+    fn test_proxy_unsupported_protocol() {
+        use std::net::{IpAddr, SocketAddr};
+
+        use crate::config::{ConnectionConfig, ResolverOpts};
+        use crate::connection_provider::{ConnectionProvider, TlsConfig};
+        use crate::name_server_pool::PoolContext;
+        use crate::net::proxy::ProxyConfig;
+
+        let proxy = ProxyConfig::Socks5 {
+            addr: SocketAddr::from(([127, 0, 0, 1], 1080)),
+            credentials: None,
+        };
+        let cx = PoolContext::new(ResolverOpts::default(), TlsConfig::new().unwrap());
+        let provider = TokioRuntimeProvider::default();
+        let ip = IpAddr::from([127, 0, 0, 1]);
+
+        // a proxy is never silently bypassed
+        let udp = ConnectionConfig::udp().with_proxy(proxy.clone());
+        assert!(provider.new_connection(ip, &udp, &cx).is_err());
+        let tcp = ConnectionConfig::tcp().with_proxy(proxy);
+        assert!(provider.new_connection(ip, &tcp, &cx).is_err());
+    }
+
     #[cfg(feature = "__tls")]
     #[tokio::test]
     async fn test_google_tls() {
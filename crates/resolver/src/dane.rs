@@ -0,0 +1,436 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Verification of TLS server certificates with TLSA records, see
+//! [RFC 6698](https://www.rfc-editor.org/rfc/rfc6698) and
+//! [RFC 7671](https://www.rfc-editor.org/rfc/rfc7671)
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(all(feature = "dnssec-aws-lc-rs", not(feature = "dnssec-ring")))]
+use aws_lc_rs::digest::{SHA256, SHA512, digest};
+#[cfg(feature = "dnssec-ring")]
+use ring::digest::{SHA256, SHA512, digest};
+use rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, Error, RootCertStore, SignatureScheme,
+    client::{
+        WebPkiServerVerifier,
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    },
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use tracing::debug;
+use webpki::EndEntityCert;
+
+use crate::net::tls::default_provider;
+use crate::proto::rr::rdata::{
+    TLSA,
+    tlsa::{CertUsage, Matching, Selector},
+};
+
+/// Verifies the certificate of a TLS server with its TLSA records
+///
+/// The records must have been validated with DNSSEC, see [`Resolver::dane_verifier`]. The
+/// certificate is accepted if it matches any of the records:
+///
+/// - DANE-EE, the end entity certificate matches, its name and validity are not checked;
+/// - DANE-TA, a certificate of the chain matches, and the end entity certificate is valid for
+///   the server name with that certificate as trust anchor;
+/// - PKIX-EE and PKIX-TA, the end entity or a trust anchor certificate matches as for DANE-EE
+///   and DANE-TA, and the end entity certificate is also accepted by the PKIX verifier, if any,
+///   see [`DaneVerifier::with_pkix`]. The trust anchor of a PKIX-TA record is either in the
+///   chain, or the record contains the full certificate.
+///
+/// [`Resolver::dane_verifier`]: crate::Resolver::dane_verifier
+#[derive(Clone)]
+pub struct DaneVerifier {
+    records: Vec<TLSA>,
+    pkix: Option<Arc<dyn ServerCertVerifier>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl DaneVerifier {
+    /// Verifier of the certificates matching `records`
+    ///
+    /// Records with unknown usages, selectors or matching types are ignored.
+    pub fn new(records: impl IntoIterator<Item = TLSA>) -> Self {
+        Self {
+            records: records.into_iter().filter(is_usable).collect(),
+            pkix: None,
+            provider: Arc::new(default_provider()),
+        }
+    }
+
+    /// Verifier for the PKIX-TA and PKIX-EE records, without it these records never match
+    pub fn with_pkix(mut self, verifier: Arc<dyn ServerCertVerifier>) -> Self {
+        self.pkix = Some(verifier);
+        self
+    }
+
+    /// Crypto provider for the verification of signatures, the default one of the TLS feature
+    /// otherwise
+    pub fn with_provider(mut self, provider: Arc<CryptoProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Returns the usable records
+    pub fn records(&self) -> &[TLSA] {
+        &self.records
+    }
+
+    /// Makes a new [`ClientConfig`] verifying the server certificates with this verifier
+    pub fn client_config(self) -> Result<ClientConfig, Error> {
+        let provider = self.provider.clone();
+        Ok(ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(self))
+            .with_no_client_auth())
+    }
+
+    fn verify_trust_anchor(
+        &self,
+        anchor: &CertificateDer<'_>,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let mut roots = RootCertStore::empty();
+        roots.add(anchor.clone().into_owned())?;
+        let verifier =
+            WebPkiServerVerifier::builder_with_provider(Arc::new(roots), self.provider.clone())
+                .build()
+                .map_err(|e| Error::General(e.to_string()))?;
+
+        verifier.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+}
+
+impl ServerCertVerifier for DaneVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let pkix = || match &self.pkix {
+            Some(pkix) => pkix
+                .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+                .is_ok(),
+            None => false,
+        };
+
+        for record in &self.records {
+            let verified = match record.cert_usage {
+                CertUsage::DaneEe => matches(record, end_entity),
+                CertUsage::DaneTa => intermediates.iter().any(|anchor| {
+                    matches(record, anchor)
+                        && self
+                            .verify_trust_anchor(
+                                anchor,
+                                end_entity,
+                                intermediates,
+                                server_name,
+                                ocsp_response,
+                                now,
+                            )
+                            .is_ok()
+                }),
+                CertUsage::PkixEe => matches(record, end_entity) && pkix(),
+                // the trust anchor may be left out of the chain if the record has it in full
+                CertUsage::PkixTa => {
+                    let full = (record.selector == Selector::Full
+                        && record.matching == Matching::Raw)
+                        .then(|| CertificateDer::from(record.cert_data.as_slice()));
+                    intermediates.iter().chain(&full).any(|anchor| {
+                        matches(record, anchor)
+                            && self
+                                .verify_trust_anchor(
+                                    anchor,
+                                    end_entity,
+                                    intermediates,
+                                    server_name,
+                                    ocsp_response,
+                                    now,
+                                )
+                                .is_ok()
+                    }) && pkix()
+                }
+                CertUsage::Unassigned(_) | CertUsage::Private => false,
+            };
+
+            if verified {
+                debug!(
+                    usage = ?record.cert_usage,
+                    selector = ?record.selector,
+                    matching = ?record.matching,
+                    "certificate matches TLSA record"
+                );
+                return Ok(ServerCertVerified::assertion());
+            }
+        }
+
+        Err(Error::InvalidCertificate(
+            CertificateError::ApplicationVerificationFailure,
+        ))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+impl fmt::Debug for DaneVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DaneVerifier")
+            .field("records", &self.records)
+            .field("pkix", &self.pkix.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Whether the usage, selector and matching type of the record are known
+fn is_usable(record: &TLSA) -> bool {
+    !matches!(
+        record.cert_usage,
+        CertUsage::Unassigned(_) | CertUsage::Private
+    ) && matches!(record.selector, Selector::Full | Selector::Spki)
+        && matches!(
+            record.matching,
+            Matching::Raw | Matching::Sha256 | Matching::Sha512
+        )
+}
+
+/// Whether the selected content of `cert` matches the association data of the record
+fn matches(record: &TLSA, cert: &CertificateDer<'_>) -> bool {
+    let selected = match record.selector {
+        Selector::Full => Cow::Borrowed(cert.as_ref()),
+        Selector::Spki => match EndEntityCert::try_from(cert) {
+            Ok(cert) => Cow::Owned(cert.subject_public_key_info().as_ref().to_vec()),
+            Err(e) => {
+                debug!("invalid certificate: {e}");
+                return false;
+            }
+        },
+        Selector::Unassigned(_) | Selector::Private => return false,
+    };
+
+    match record.matching {
+        Matching::Raw => *selected == *record.cert_data,
+        Matching::Sha256 => digest(&SHA256, &selected).as_ref() == record.cert_data,
+        Matching::Sha512 => digest(&SHA512, &selected).as_ref() == record.cert_data,
+        Matching::Unassigned(_) | Matching::Private => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rustls::pki_types::pem::PemObject;
+
+    use super::*;
+
+    /// The test certificates are valid from 2025-09-25 to 2026-09-25
+    fn now() -> UnixTime {
+        UnixTime::since_unix_epoch(Duration::from_secs(1_767_225_600))
+    }
+
+    fn cert(name: &str) -> CertificateDer<'static> {
+        let server_path = env!("CARGO_MANIFEST_DIR");
+        CertificateDer::from_pem_file(format!("{server_path}/../../tests/test-data/{name}"))
+            .unwrap()
+    }
+
+    fn verify(verifier: &DaneVerifier, intermediates: &[CertificateDer<'_>]) -> bool {
+        let name = ServerName::try_from("ns.example.com").unwrap();
+        verifier
+            .verify_server_cert(&cert("cert.pem"), intermediates, &name, &[], now())
+            .is_ok()
+    }
+
+    /// SHA-256 of the SPKI of the end entity certificate, from
+    /// `openssl x509 -pubkey | openssl pkey -pubin -outform DER | openssl dgst -sha256`
+    const SPKI_SHA256: [u8; 32] = [
+        0xbf, 0x41, 0xce, 0x20, 0x29, 0x67, 0x9f, 0x67, 0x8f, 0x08, 0xd5, 0x11, 0xb5, 0x01, 0x27,
+        0x8e, 0xd4, 0x85, 0x60, 0x2a, 0xe8, 0x58, 0x0f, 0x25, 0x2f, 0x1c, 0x91, 0x05, 0xb2, 0xb5,
+        0xac, 0xef,
+    ];
+
+    #[test]
+    fn test_dane_ee() {
+        let spki = TLSA::new(
+            CertUsage::DaneEe,
+            Selector::Spki,
+            Matching::Sha256,
+            SPKI_SHA256.to_vec(),
+        );
+        assert!(verify(&DaneVerifier::new([spki]), &[]));
+
+        let full = TLSA::new(
+            CertUsage::DaneEe,
+            Selector::Full,
+            Matching::Raw,
+            cert("cert.pem").to_vec(),
+        );
+        assert!(verify(&DaneVerifier::new([full]), &[]));
+
+        let other = TLSA::new(
+            CertUsage::DaneEe,
+            Selector::Full,
+            Matching::Sha512,
+            digest(&SHA512, &cert("ca.pem")).as_ref().to_vec(),
+        );
+        assert!(!verify(&DaneVerifier::new([other]), &[]));
+    }
+
+    #[test]
+    fn test_dane_ta() {
+        let ca = cert("ca.pem");
+        let record = TLSA::new(
+            CertUsage::DaneTa,
+            Selector::Full,
+            Matching::Sha256,
+            digest(&SHA256, &ca).as_ref().to_vec(),
+        );
+        let verifier = DaneVerifier::new([record]);
+
+        assert!(verify(&verifier, &[ca]));
+        // the trust anchor must be in the chain
+        assert!(!verify(&verifier, &[]));
+
+        // the end entity certificate is not valid for another name
+        let name = ServerName::try_from("other.example.com").unwrap();
+        assert!(
+            verifier
+                .verify_server_cert(&cert("cert.pem"), &[cert("ca.pem")], &name, &[], now())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pkix() {
+        let record = TLSA::new(
+            CertUsage::PkixEe,
+            Selector::Spki,
+            Matching::Sha256,
+            SPKI_SHA256.to_vec(),
+        );
+        // without a PKIX verifier, PKIX records never match
+        assert!(!verify(&DaneVerifier::new([record.clone()]), &[]));
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert("ca.pem")).unwrap();
+        let pkix = WebPkiServerVerifier::builder_with_provider(
+            Arc::new(roots),
+            Arc::new(default_provider()),
+        )
+        .build()
+        .unwrap();
+        assert!(verify(&DaneVerifier::new([record]).with_pkix(pkix), &[]));
+    }
+
+    #[test]
+    fn test_pkix_ta() {
+        let ca = cert("ca.pem");
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.clone()).unwrap();
+        let pkix = WebPkiServerVerifier::builder_with_provider(
+            Arc::new(roots),
+            Arc::new(default_provider()),
+        )
+        .build()
+        .unwrap();
+
+        // the root is not sent by the server, the record has it in full
+        let full = TLSA::new(
+            CertUsage::PkixTa,
+            Selector::Full,
+            Matching::Raw,
+            ca.to_vec(),
+        );
+        assert!(verify(
+            &DaneVerifier::new([full.clone()]).with_pkix(pkix.clone()),
+            &[]
+        ));
+        assert!(!verify(&DaneVerifier::new([full]), &[]));
+
+        // otherwise it must be in the chain
+        let digest = TLSA::new(
+            CertUsage::PkixTa,
+            Selector::Full,
+            Matching::Sha256,
+            digest(&SHA256, &ca).as_ref().to_vec(),
+        );
+        let verifier = DaneVerifier::new([digest]).with_pkix(pkix.clone());
+        assert!(verify(&verifier, &[ca]));
+        assert!(!verify(&verifier, &[]));
+
+        // the end entity certificate is no trust anchor
+        let end_entity = TLSA::new(
+            CertUsage::PkixTa,
+            Selector::Full,
+            Matching::Raw,
+            cert("cert.pem").to_vec(),
+        );
+        assert!(!verify(
+            &DaneVerifier::new([end_entity]).with_pkix(pkix),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn test_unusable() {
+        let record = TLSA::new(
+            CertUsage::Private,
+            Selector::Full,
+            Matching::Raw,
+            cert("cert.pem").to_vec(),
+        );
+        let verifier = DaneVerifier::new([record]);
+        assert!(verifier.records().is_empty());
+        assert!(!verify(&verifier, &[]));
+    }
+}
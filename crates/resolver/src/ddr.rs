@@ -0,0 +1,402 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Discovery of Designated Resolvers (DDR), see [RFC 9462](https://www.rfc-editor.org/rfc/rfc9462)
+
+use std::{net::IpAddr, sync::Arc};
+
+use tracing::{debug, warn};
+
+use crate::{
+    Resolver,
+    config::{ConnectionConfig, NameServerConfig, ResolveHosts, ResolverConfig, ResolverOpts},
+    connection_provider::ConnectionProvider,
+    net::NetError,
+    proto::rr::{
+        Name, RData, Record, RecordType,
+        rdata::svcb::{SvcParamKey, SvcParamValue},
+    },
+};
+
+/// Discovers the designated resolvers of the unencrypted name server `server`
+///
+/// The returned name servers only contain the encrypted connections which were verified: their
+/// certificate is valid for the IP address of `server` and they answered a query. The result is
+/// empty if `server` has no designated resolvers, or none of them could be verified.
+pub(crate) async fn designated_resolvers<P: ConnectionProvider>(
+    server: &NameServerConfig,
+    options: &ResolverOpts,
+    tls: &rustls::ClientConfig,
+    provider: &P,
+) -> Vec<NameServerConfig> {
+    let resolver = match probe_resolver(server.clone(), options, tls, provider) {
+        Ok(resolver) => resolver,
+        Err(error) => {
+            warn!(ip = %server.ip, %error, "failed to build resolver for discovery");
+            return Vec::new();
+        }
+    };
+    let lookup = match resolver.lookup(resolver_arpa(), RecordType::SVCB).await {
+        Ok(lookup) => lookup,
+        Err(error) => {
+            debug!(ip = %server.ip, %error, "no designated resolvers");
+            return Vec::new();
+        }
+    };
+
+    let mut verified = Vec::new();
+    for mut designated in designated(server.ip, lookup.answers()) {
+        if designated.addresses.is_empty() {
+            match resolver.lookup_ip(designated.target.clone()).await {
+                Ok(lookup) => designated.addresses = lookup.iter().collect(),
+                Err(error) => {
+                    debug!(target = %designated.target, %error, "failed to resolve designated resolver");
+                    continue;
+                }
+            }
+        }
+
+        for ip in designated.addresses {
+            let mut connections = Vec::new();
+            for connection in &designated.connections {
+                let candidate = NameServerConfig::new(
+                    ip,
+                    server.trust_negative_responses,
+                    vec![connection.clone()],
+                );
+
+                let probe = match probe_resolver(candidate, options, tls, provider) {
+                    Ok(probe) => probe,
+                    Err(error) => {
+                        warn!(%ip, %error, "failed to build resolver for designated resolver");
+                        continue;
+                    }
+                };
+                match probe.lookup(resolver_arpa(), RecordType::SVCB).await {
+                    Ok(_) => connections.push(connection.clone()),
+                    Err(error) => warn!(
+                        %ip,
+                        protocol = %connection.protocol.to_protocol(),
+                        %error,
+                        "failed to verify designated resolver"
+                    ),
+                }
+            }
+
+            if !connections.is_empty() {
+                debug!(unencrypted = %server.ip, %ip, "using designated resolver");
+                verified.push(NameServerConfig::new(
+                    ip,
+                    server.trust_negative_responses,
+                    connections,
+                ));
+            }
+        }
+    }
+
+    verified
+}
+
+/// Resolver sending all queries to `server`, without cache or hosts
+fn probe_resolver<P: ConnectionProvider>(
+    server: NameServerConfig,
+    options: &ResolverOpts,
+    tls: &rustls::ClientConfig,
+    provider: &P,
+) -> Result<Resolver<P>, NetError> {
+    let mut options = options.clone();
+    options.use_hosts_file = ResolveHosts::Never;
+    options.cache_size = 0;
+
+    Resolver::builder_with_config(
+        ResolverConfig::from_name_servers(vec![server]),
+        provider.clone(),
+    )
+    .with_options(options)
+    .with_tls_config(tls.clone())
+    .build()
+}
+
+/// The name of the SVCB records describing the designated resolvers, see RFC 9462 section 4
+fn resolver_arpa() -> Name {
+    Name::from_ascii("_dns.resolver.arpa.").unwrap()
+}
+
+/// A designated resolver, as advertised by an SVCB record
+#[derive(Debug)]
+struct Designated {
+    target: Name,
+    addresses: Vec<IpAddr>,
+    connections: Vec<ConnectionConfig>,
+}
+
+/// Designated resolvers advertised by the SVCB records in `answers`, ordered by priority
+///
+/// The connections use the IP address of the unencrypted resolver, `server`, as TLS server name,
+/// as [RFC 9462 section 4.2](https://www.rfc-editor.org/rfc/rfc9462#section-4.2) requires the
+/// certificate of the designated resolver to be valid for this address.
+fn designated(server: IpAddr, answers: &[Record]) -> Vec<Designated> {
+    let mut records = answers
+        .iter()
+        .filter_map(|record| match &record.data {
+            // AliasMode records are not used for DDR, see RFC 9461 section 2
+            RData::SVCB(svcb) if svcb.svc_priority > 0 => Some(svcb),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    records.sort_by_key(|svcb| svcb.svc_priority);
+
+    let server_name = Arc::<str>::from(server.to_string());
+    let mut designated = Vec::new();
+    for svcb in records {
+        let mut alpns = &[][..];
+        let mut port = None;
+        let mut doh_path = None;
+        let mut addresses = Vec::new();
+        let mut supported = true;
+        for (key, value) in &svcb.svc_params {
+            match value {
+                SvcParamValue::Mandatory(mandatory) => {
+                    supported = mandatory.0.iter().all(|key| {
+                        matches!(
+                            key,
+                            SvcParamKey::Alpn
+                                | SvcParamKey::NoDefaultAlpn
+                                | SvcParamKey::Port
+                                | SvcParamKey::Ipv4Hint
+                                | SvcParamKey::Ipv6Hint
+                                | SvcParamKey::DohPath
+                        )
+                    })
+                }
+                SvcParamValue::Alpn(alpn) => alpns = &alpn.0,
+                SvcParamValue::Port(value) => port = Some(*value),
+                SvcParamValue::Ipv4Hint(hint) => {
+                    addresses.extend(hint.0.iter().map(|a| IpAddr::V4(a.0)))
+                }
+                SvcParamValue::Ipv6Hint(hint) => {
+                    addresses.extend(hint.0.iter().map(|aaaa| IpAddr::V6(aaaa.0)))
+                }
+                SvcParamValue::DohPath(template) => doh_path = doh_path_from_template(template),
+                _ => debug!(%key, "ignoring unsupported SVCB parameter"),
+            }
+        }
+
+        if !supported {
+            debug!(target = %svcb.target_name, "SVCB record with unsupported mandatory keys");
+            continue;
+        }
+
+        let connections = alpns
+            .iter()
+            .filter_map(|alpn| connection(alpn, &server_name, doh_path.as_ref()))
+            .map(|mut connection| {
+                if let Some(port) = port {
+                    connection.port = port;
+                }
+                connection
+            })
+            .collect::<Vec<_>>();
+        if connections.is_empty() {
+            continue;
+        }
+
+        // the root target name denotes the owner, which is the unencrypted resolver itself here
+        if addresses.is_empty() && svcb.target_name.is_root() {
+            addresses.push(server);
+        }
+
+        designated.push(Designated {
+            target: svcb.target_name.clone(),
+            addresses,
+            connections,
+        });
+    }
+
+    designated
+}
+
+/// The connection for the ALPN identifier `alpn`, if supported
+///
+/// DNS over HTTPS is only supported for `dohpath` templates of the form `/path{?dns}`.
+#[cfg_attr(
+    not(any(feature = "__https", feature = "__h3")),
+    allow(unused_variables)
+)]
+fn connection(
+    alpn: &str,
+    server_name: &Arc<str>,
+    doh_path: Option<&Arc<str>>,
+) -> Option<ConnectionConfig> {
+    match alpn {
+        "dot" => Some(ConnectionConfig::tls(server_name.clone())),
+        #[cfg(feature = "__https")]
+        "h2" => {
+            doh_path.map(|path| ConnectionConfig::https(server_name.clone(), Some(path.clone())))
+        }
+        #[cfg(feature = "__quic")]
+        "doq" => Some(ConnectionConfig::quic(server_name.clone())),
+        #[cfg(feature = "__h3")]
+        "h3" => doh_path.map(|path| ConnectionConfig::h3(server_name.clone(), Some(path.clone()))),
+        _ => None,
+    }
+}
+
+/// The request path of a `dohpath` URI template of the form `/path{?dns}`
+fn doh_path_from_template(template: &str) -> Option<Arc<str>> {
+    let path = template.strip_suffix("{?dns}")?;
+    match path.starts_with('/') && !path.contains(['{', '}', '?']) {
+        true => Some(Arc::from(path)),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+    use crate::config::ProtocolConfig;
+    use crate::proto::rr::rdata::{
+        A, AAAA, SVCB,
+        svcb::{Alpn, IpHint, Mandatory},
+    };
+
+    const SERVER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53));
+
+    fn record(priority: u16, target: &str, params: Vec<(SvcParamKey, SvcParamValue)>) -> Record {
+        Record::from_rdata(
+            resolver_arpa(),
+            300,
+            RData::SVCB(SVCB::new(
+                priority,
+                Name::from_ascii(target).unwrap(),
+                params,
+            )),
+        )
+    }
+
+    fn alpn(ids: &[&str]) -> (SvcParamKey, SvcParamValue) {
+        (
+            SvcParamKey::Alpn,
+            SvcParamValue::Alpn(Alpn(ids.iter().map(|id| id.to_string()).collect())),
+        )
+    }
+
+    #[test]
+    fn test_designated() {
+        let answers = [
+            record(
+                2,
+                "dns.example.net.",
+                vec![
+                    alpn(&["dot"]),
+                    (SvcParamKey::Port, SvcParamValue::Port(8853)),
+                    (
+                        SvcParamKey::Ipv4Hint,
+                        SvcParamValue::Ipv4Hint(IpHint(vec![A::new(192, 0, 2, 1)])),
+                    ),
+                    (
+                        SvcParamKey::Ipv6Hint,
+                        SvcParamValue::Ipv6Hint(IpHint(vec![AAAA::from(Ipv6Addr::LOCALHOST)])),
+                    ),
+                ],
+            ),
+            record(0, "alias.example.net.", vec![]),
+            record(1, ".", vec![alpn(&["dot", "unknown"])]),
+        ];
+
+        let designated = designated(SERVER, &answers);
+        assert_eq!(designated.len(), 2);
+
+        assert!(designated[0].target.is_root());
+        assert_eq!(designated[0].addresses, [SERVER]);
+        assert_eq!(designated[0].connections.len(), 1);
+        assert_eq!(designated[0].connections[0].port, 853);
+        assert_eq!(
+            designated[0].connections[0].protocol,
+            ProtocolConfig::Tls {
+                server_name: Arc::from("192.0.2.53")
+            }
+        );
+
+        assert_eq!(
+            designated[1].target,
+            Name::from_ascii("dns.example.net.").unwrap()
+        );
+        assert_eq!(
+            designated[1].addresses,
+            [
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::LOCALHOST)
+            ]
+        );
+        assert_eq!(designated[1].connections[0].port, 8853);
+    }
+
+    #[test]
+    fn test_designated_unsupported() {
+        let answers = [
+            record(1, "dns.example.net.", vec![alpn(&["unknown"])]),
+            record(
+                1,
+                "dns.example.net.",
+                vec![
+                    (
+                        SvcParamKey::Mandatory,
+                        SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::EchConfigList])),
+                    ),
+                    alpn(&["dot"]),
+                ],
+            ),
+        ];
+
+        let designated = designated(SERVER, &answers);
+        assert!(designated.is_empty());
+    }
+
+    #[cfg(feature = "__https")]
+    #[test]
+    fn test_designated_https() {
+        let answers = [
+            record(1, "dns.example.net.", vec![alpn(&["h2"])]),
+            record(
+                2,
+                "dns.example.net.",
+                vec![
+                    alpn(&["h2"]),
+                    (
+                        SvcParamKey::DohPath,
+                        SvcParamValue::DohPath("/q{?dns}".to_string()),
+                    ),
+                ],
+            ),
+        ];
+
+        let designated = designated(SERVER, &answers);
+        assert_eq!(designated.len(), 1);
+        assert!(designated[0].addresses.is_empty());
+        assert_eq!(
+            designated[0].connections[0].protocol,
+            ProtocolConfig::Https {
+                server_name: Arc::from("192.0.2.53"),
+                path: Arc::from("/q"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_doh_path_from_template() {
+        assert_eq!(
+            doh_path_from_template("/dns-query{?dns}").as_deref(),
+            Some("/dns-query")
+        );
+        assert_eq!(doh_path_from_template("/dns-query"), None);
+        assert_eq!(doh_path_from_template("dns-query{?dns}"), None);
+        assert_eq!(doh_path_from_template("/{x}/q{?dns}"), None);
+    }
+}
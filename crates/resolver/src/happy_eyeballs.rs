@@ -0,0 +1,376 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TCP connections racing the addresses of a host, see
+//! [RFC 8305 Happy Eyeballs Version 2](https://www.rfc-editor.org/rfc/rfc8305)
+
+use std::collections::VecDeque;
+use std::future::{Future, poll_fn};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Duration;
+
+use futures_util::{StreamExt, future::Ready, stream::FuturesUnordered};
+use tokio::net::TcpStream;
+use tokio::time::{Sleep, sleep};
+use tracing::debug;
+
+use crate::{
+    config::LookupIpStrategy,
+    connection_provider::ConnectionProvider,
+    lookup::Lookup,
+    net::NetError,
+    proto::rr::{IntoName, RData, RecordType},
+    resolver::Resolver,
+};
+
+/// Connects to a host over TCP, racing its IPv6 and IPv4 addresses
+///
+/// The AAAA and A records are queried concurrently. Connections start as soon as the AAAA
+/// records are received, or after the resolution delay once the A records are. The attempts
+/// alternate between the address families, starting with IPv6, and a new attempt starts every
+/// connection attempt delay or as soon as the previous one fails. The first established
+/// connection wins, the other attempts are cancelled.
+#[derive(Clone, Copy, Debug)]
+pub struct HappyEyeballs {
+    resolution_delay: Duration,
+    connection_attempt_delay: Duration,
+}
+
+impl HappyEyeballs {
+    /// Happy Eyeballs with the delays recommended by RFC 8305, 50ms and 250ms
+    pub fn new() -> Self {
+        Self {
+            resolution_delay: Duration::from_millis(50),
+            connection_attempt_delay: Duration::from_millis(250),
+        }
+    }
+
+    /// Time to wait for the AAAA records once the A records are received
+    pub fn with_resolution_delay(mut self, delay: Duration) -> Self {
+        self.resolution_delay = delay;
+        self
+    }
+
+    /// Time to wait for a connection attempt before starting the next one
+    ///
+    /// RFC 8305 recommends at least 100ms, and at most 2s.
+    pub fn with_connection_attempt_delay(mut self, delay: Duration) -> Self {
+        self.connection_attempt_delay = delay;
+        self
+    }
+
+    /// Connects to `port` of `host`, which may be an IP address, with the addresses from
+    /// `resolver`
+    ///
+    /// Only the address families allowed by the [`LookupIpStrategy`] of the resolver are queried.
+    pub async fn connect<P: ConnectionProvider>(
+        &self,
+        resolver: &Resolver<P>,
+        host: impl IntoName,
+        port: u16,
+    ) -> io::Result<TcpStream> {
+        if let Some(ip) = host.to_ip() {
+            return TcpStream::connect(SocketAddr::new(ip, port)).await;
+        }
+
+        let name = host.into_name().map_err(io::Error::other)?;
+        let (ipv6, ipv4) = match resolver.options().ip_strategy {
+            LookupIpStrategy::Ipv4Only => (false, true),
+            LookupIpStrategy::Ipv6Only => (true, false),
+            _ => (true, true),
+        };
+
+        let aaaa = ipv6.then(|| resolver.lookup(name.clone(), RecordType::AAAA));
+        let a = ipv4.then(|| resolver.lookup(name, RecordType::A));
+        self.race(aaaa, a, Addresses::default(), port).await
+    }
+
+    /// Connects to the targets of the service at `service`, e.g. `_imap._tcp.example.com.`,
+    /// in the order of [`Resolver::srv_targets`]
+    ///
+    /// The addresses of each target are raced, and the next target is only attempted once all
+    /// the connections to the previous one failed.
+    pub async fn connect_srv<P: ConnectionProvider>(
+        &self,
+        resolver: &Resolver<P>,
+        service: impl IntoName,
+    ) -> io::Result<TcpStream> {
+        let targets = resolver
+            .srv_targets(service)
+            .await
+            .map_err(io::Error::other)?;
+
+        let mut error = None;
+        for target in targets {
+            debug!(target = %target.target, port = target.port, "connecting to service target");
+            let mut addresses = Addresses::default();
+            for ip in target.addresses {
+                addresses.push(ip);
+            }
+
+            match self
+                .race(NO_LOOKUP, NO_LOOKUP, addresses, target.port)
+                .await
+            {
+                Ok(stream) => return Ok(stream),
+                Err(e) => error = Some(e),
+            }
+        }
+
+        Err(error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "service is not available")))
+    }
+
+    /// Races the connections to `addresses`, and to the results of the `aaaa` and `a` lookups
+    async fn race<F: Future<Output = Result<Lookup, NetError>>>(
+        &self,
+        aaaa: Option<F>,
+        a: Option<F>,
+        mut addresses: Addresses,
+        port: u16,
+    ) -> io::Result<TcpStream> {
+        let mut aaaa = aaaa.map(Box::pin);
+        let mut a = a.map(Box::pin);
+        let mut resolution_delay = None::<Pin<Box<Sleep>>>;
+        let mut attempt_delay = None::<Pin<Box<Sleep>>>;
+        let mut attempts = FuturesUnordered::new();
+        // connections start once the AAAA records are received, or the resolution delay elapsed
+        let mut started = aaaa.is_none();
+        let mut error = None;
+
+        loop {
+            if started && attempt_delay.is_none() {
+                if let Some(ip) = addresses.next() {
+                    let addr = SocketAddr::new(ip, port);
+                    debug!(%addr, "attempting connection");
+                    attempts.push(async move { (addr, TcpStream::connect(addr).await) });
+                    attempt_delay = Some(Box::pin(sleep(self.connection_attempt_delay)));
+                }
+            }
+
+            if aaaa.is_none() && a.is_none() && attempts.is_empty() && addresses.is_empty() {
+                return Err(error.unwrap_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "no addresses to connect to")
+                }));
+            }
+
+            let event = poll_fn(|cx| {
+                if let Poll::Ready(Some((addr, result))) = attempts.poll_next_unpin(cx) {
+                    return Poll::Ready(Event::Connected(addr, result));
+                }
+
+                if let Some(lookup) = aaaa.as_mut() {
+                    if let Poll::Ready(result) = lookup.as_mut().poll(cx) {
+                        aaaa = None;
+                        return Poll::Ready(Event::Resolved(result));
+                    }
+                }
+
+                if let Some(lookup) = a.as_mut() {
+                    if let Poll::Ready(result) = lookup.as_mut().poll(cx) {
+                        a = None;
+                        return Poll::Ready(Event::Resolved(result));
+                    }
+                }
+
+                if let Some(delay) = resolution_delay.as_mut() {
+                    if delay.as_mut().poll(cx).is_ready() {
+                        resolution_delay = None;
+                        return Poll::Ready(Event::ResolutionDelay);
+                    }
+                }
+
+                if let Some(delay) = attempt_delay.as_mut() {
+                    if delay.as_mut().poll(cx).is_ready() {
+                        attempt_delay = None;
+                        return Poll::Ready(Event::AttemptDelay);
+                    }
+                }
+
+                Poll::Pending
+            })
+            .await;
+
+            match event {
+                Event::Connected(addr, Ok(stream)) => {
+                    debug!(%addr, "connected");
+                    return Ok(stream);
+                }
+                Event::Connected(addr, Err(e)) => {
+                    debug!(%addr, "connection failed: {e}");
+                    error = Some(e);
+                    // the next attempt starts right away
+                    attempt_delay = None;
+                }
+                Event::Resolved(result) => {
+                    match result {
+                        Ok(lookup) => addresses.extend(lookup),
+                        Err(e) => error = Some(io::Error::other(e)),
+                    }
+
+                    match (aaaa.is_none(), started) {
+                        (true, _) => started = true,
+                        (false, false) => {
+                            resolution_delay = Some(Box::pin(sleep(self.resolution_delay)))
+                        }
+                        (false, true) => {}
+                    }
+                }
+                Event::ResolutionDelay => started = true,
+                Event::AttemptDelay => {}
+            }
+        }
+    }
+}
+
+impl Default for HappyEyeballs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lookup for the address families that are not queried
+const NO_LOOKUP: Option<Ready<Result<Lookup, NetError>>> = None;
+
+enum Event {
+    Resolved(Result<Lookup, NetError>),
+    ResolutionDelay,
+    AttemptDelay,
+    Connected(SocketAddr, io::Result<TcpStream>),
+}
+
+/// Addresses to attempt, alternating between the address families
+#[derive(Default)]
+struct Addresses {
+    ipv6: VecDeque<IpAddr>,
+    ipv4: VecDeque<IpAddr>,
+    /// Whether the last address was an IPv6 one
+    last_ipv6: bool,
+}
+
+impl Addresses {
+    fn extend(&mut self, lookup: Lookup) {
+        for record in lookup.answers() {
+            match &record.data {
+                RData::AAAA(aaaa) => self.push(IpAddr::V6(aaaa.0)),
+                RData::A(a) => self.push(IpAddr::V4(a.0)),
+                _ => {}
+            }
+        }
+    }
+
+    fn push(&mut self, ip: IpAddr) {
+        match ip {
+            IpAddr::V6(_) => self.ipv6.push_back(ip),
+            IpAddr::V4(_) => self.ipv4.push_back(ip),
+        }
+    }
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let ip = match self.last_ipv6 {
+            true => self.ipv4.pop_front().or_else(|| self.ipv6.pop_front()),
+            false => self.ipv6.pop_front().or_else(|| self.ipv4.pop_front()),
+        }?;
+
+        self.last_ipv6 = ip.is_ipv6();
+        Some(ip)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ipv6.is_empty() && self.ipv4.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::Arc;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::config::{GOOGLE, ResolverConfig};
+    use crate::hosts::Hosts;
+    use crate::net::runtime::TokioRuntimeProvider;
+    use crate::proto::op::Query;
+    use crate::proto::rr::{
+        Name,
+        rdata::{A, AAAA},
+    };
+
+    #[test]
+    fn test_addresses() {
+        let mut addresses = Addresses::default();
+        addresses.ipv4.extend([
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 3)),
+        ]);
+        addresses.ipv6.extend([
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        ]);
+
+        let order = std::iter::from_fn(|| addresses.next()).collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 3)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // the IPv6 attempt is refused, and the IPv4 one succeeds
+        let name = Name::from_ascii("service.example.com.").unwrap();
+        let mut hosts = Hosts::default();
+        hosts.insert(
+            name.clone(),
+            RecordType::AAAA,
+            Lookup::from_rdata(
+                Query::new(name.clone(), RecordType::AAAA),
+                RData::AAAA(AAAA(Ipv6Addr::LOCALHOST)),
+            ),
+        );
+        hosts.insert(
+            name.clone(),
+            RecordType::A,
+            Lookup::from_rdata(
+                Query::new(name.clone(), RecordType::A),
+                RData::A(A(Ipv4Addr::LOCALHOST)),
+            ),
+        );
+
+        let mut resolver = Resolver::builder_with_config(
+            ResolverConfig::udp_and_tcp(&GOOGLE),
+            TokioRuntimeProvider::default(),
+        )
+        .build()
+        .unwrap();
+        resolver.set_hosts(Arc::new(hosts));
+
+        let stream = HappyEyeballs::new()
+            .connect(&resolver, name, port)
+            .await
+            .unwrap();
+        assert_eq!(
+            stream.peer_addr().unwrap(),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+        );
+    }
+}
@@ -222,7 +222,7 @@ impl Hosts {
 }
 
 #[cfg(unix)]
-fn hosts_path() -> &'static str {
+pub(crate) fn hosts_path() -> &'static str {
     "/etc/hosts"
 }
 
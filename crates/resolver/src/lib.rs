@@ -187,7 +187,13 @@ pub use hickory_proto as proto;
 pub mod caching_client;
 pub mod config;
 mod connection_provider;
+#[cfg(all(feature = "__tls", feature = "__dnssec"))]
+pub mod dane;
+#[cfg(feature = "__tls")]
+mod ddr;
 pub use connection_provider::{ConnectionProvider, TlsConfig};
+#[cfg(feature = "tokio")]
+pub mod happy_eyeballs;
 mod hosts;
 pub use hosts::Hosts;
 pub mod lookup;
@@ -208,6 +214,8 @@ pub use resolver::LookupFuture;
 pub use resolver::TokioResolver;
 pub use resolver::{Resolver, ResolverBuilder};
 mod cache;
+pub mod service_binding;
+pub mod srv;
 pub use cache::{MAX_TTL, ResponseCache, TtlBounds, TtlConfig};
 #[cfg(feature = "metrics")]
 pub mod metrics;
@@ -12,11 +12,16 @@ use std::{
     time::{Duration, Instant},
 };
 
+#[cfg(feature = "__dnssec")]
+use crate::proto::{
+    dnssec::{Proof, Proven},
+    rr::RecordType,
+};
 use crate::{
     cache::MAX_TTL,
     proto::{
         op::{Message, OpCode, Query},
-        rr::{RData, Record},
+        rr::{RData, Record, RecordData},
     },
 };
 
@@ -99,6 +104,61 @@ impl Lookup {
         &self.message.additionals
     }
 
+    /// Returns an iterator over the record data of type `T` in the answer records.
+    ///
+    /// ```
+    /// use hickory_resolver::lookup::Lookup;
+    /// use hickory_resolver::proto::op::Query;
+    /// use hickory_resolver::proto::rr::{Name, RData, RecordType, rdata::MX};
+    ///
+    /// let name = Name::from_ascii("example.com.").unwrap();
+    /// let lookup = Lookup::from_rdata(
+    ///     Query::new(name, RecordType::MX),
+    ///     RData::MX(MX::new(10, Name::from_ascii("mail.example.com.").unwrap())),
+    /// );
+    ///
+    /// let mx = lookup.iter::<MX>().next().unwrap();
+    /// assert_eq!(mx.preference, 10);
+    /// ```
+    pub fn iter<'a, T: RecordData + 'a>(&'a self) -> impl Iterator<Item = &'a T> {
+        self.answers()
+            .iter()
+            .filter_map(|record| T::try_borrow(&record.data))
+    }
+
+    /// Returns an iterator over the record data of type `T` in the answer records, with the
+    /// DNSSEC proof of each record.
+    #[cfg(feature = "__dnssec")]
+    pub fn proven_iter<'a, T: RecordData + 'a>(&'a self) -> impl Iterator<Item = Proven<&'a T>> {
+        self.answers().iter().filter_map(|record| {
+            T::try_borrow(&record.data).map(|data| Proven::new(record.proof, data))
+        })
+    }
+
+    /// Returns the DNSSEC proof of the answers as a whole.
+    ///
+    /// This is [`Proof::Bogus`] if any answer is bogus, otherwise the weakest proof of the
+    /// answers, so [`Proof::Secure`] only if all of them are secure. Signatures are not taken
+    /// into account, and a lookup without any answer is [`Proof::Indeterminate`].
+    #[cfg(feature = "__dnssec")]
+    pub fn proof(&self) -> Proof {
+        let mut proofs = self
+            .answers()
+            .iter()
+            .filter(|record| record.record_type() != RecordType::RRSIG)
+            .map(|record| record.proof)
+            .peekable();
+
+        if proofs.peek().is_none() {
+            return Proof::Indeterminate;
+        }
+
+        proofs.fold(Proof::Secure, |lookup, proof| match (lookup, proof) {
+            (Proof::Bogus, _) | (_, Proof::Bogus) => Proof::Bogus,
+            _ => lookup.min(proof),
+        })
+    }
+
     /// Returns the `Instant` at which this `Lookup` is no longer valid.
     pub fn valid_until(&self) -> Instant {
         self.valid_until
@@ -199,6 +259,79 @@ mod tests {
         assert_eq!(lookup.next(), None);
     }
 
+    #[test]
+    #[cfg(feature = "__dnssec")]
+    fn test_proof() {
+        use hickory_proto::dnssec::Proof;
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let record = |ip, proof| {
+            let mut record = Record::from_rdata(name.clone(), 80, RData::A(A::new(127, 0, 0, ip)));
+            record.proof = proof;
+            record
+        };
+        let lookup = |proofs: &[Proof]| {
+            Lookup::new_with_max_ttl(
+                Query::new(name.clone(), RecordType::A),
+                proofs.iter().map(|proof| record(1, *proof)),
+            )
+        };
+
+        assert_eq!(lookup(&[]).proof(), Proof::Indeterminate);
+        assert_eq!(lookup(&[Proof::Secure]).proof(), Proof::Secure);
+        assert_eq!(
+            lookup(&[Proof::Secure, Proof::Insecure]).proof(),
+            Proof::Insecure
+        );
+        assert_eq!(
+            lookup(&[Proof::Indeterminate, Proof::Secure]).proof(),
+            Proof::Indeterminate
+        );
+        assert_eq!(
+            lookup(&[Proof::Indeterminate, Proof::Bogus, Proof::Secure]).proof(),
+            Proof::Bogus
+        );
+
+        let lookup = Lookup::new_with_max_ttl(
+            Query::new(name.clone(), RecordType::A),
+            [record(1, Proof::Secure), record(2, Proof::Bogus)],
+        );
+        let proven = lookup
+            .proven_iter::<A>()
+            .map(|proven| proven.into_parts())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            proven,
+            [
+                (Proof::Secure, &A::new(127, 0, 0, 1)),
+                (Proof::Bogus, &A::new(127, 0, 0, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iter() {
+        let name = Name::from_str("example.com.").unwrap();
+        let lookup = Lookup::new_with_max_ttl(
+            Query::new(name.clone(), RecordType::A),
+            [
+                Record::from_rdata(name.clone(), 80, RData::A(A::new(127, 0, 0, 1))),
+                Record::from_rdata(
+                    name.clone(),
+                    80,
+                    RData::NS(NS(Name::from_str("ns1.example.com.").unwrap())),
+                ),
+                Record::from_rdata(name, 80, RData::A(A::new(127, 0, 0, 2))),
+            ],
+        );
+
+        assert_eq!(
+            lookup.iter::<A>().collect::<Vec<_>>(),
+            [&A::new(127, 0, 0, 1), &A::new(127, 0, 0, 2)]
+        );
+        assert_eq!(lookup.iter::<NS>().count(), 1);
+    }
+
     #[test]
     fn test_extend_answers_preserves_sections() {
         // Create a message with records in different sections
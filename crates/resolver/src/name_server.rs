@@ -382,6 +382,10 @@ impl<P: ConnectionProvider> NameServer<P> {
         })
     }
 
+    pub(crate) fn config(&self) -> &NameServerConfig {
+        &self.config
+    }
+
     pub(crate) fn trust_negative_responses(&self) -> bool {
         self.config.trust_negative_responses
     }
@@ -1080,6 +1084,7 @@ mod tests {
                 port: server_addr.port(),
                 protocol: ProtocolConfig::Udp,
                 bind_addr: None,
+                proxy: None,
             }],
         };
 
@@ -5,6 +5,7 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::cmp::Reverse;
 use std::collections::{HashMap, VecDeque};
 use std::net::IpAddr;
 use std::pin::Pin;
@@ -22,7 +23,7 @@ use futures_util::{
     Future, FutureExt,
     future::{BoxFuture, Shared},
 };
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -32,8 +33,13 @@ use tracing::{debug, error};
 
 #[cfg(any(feature = "__tls", feature = "__quic"))]
 use crate::config::OpportunisticEncryptionConfig;
+#[cfg(feature = "dnstap")]
+use crate::net::dnstap::UpstreamTap;
 use crate::{
-    config::{NameServerConfig, OpportunisticEncryption, ResolverOpts, ServerOrderingStrategy},
+    config::{
+        DomainRoute, NameServerConfig, OpportunisticEncryption, ResolverOpts,
+        ServerOrderingStrategy,
+    },
     connection_provider::{ConnectionProvider, TlsConfig},
     name_server::{ConnectionPolicy, NameServer},
     net::{
@@ -90,7 +96,10 @@ impl<P: ConnectionProvider> NameServerPool<P> {
     pub fn from_nameservers(servers: Vec<Arc<NameServer<P>>>, cx: Arc<PoolContext>) -> Self {
         Self {
             state: Arc::new(PoolState {
-                servers,
+                servers: RwLock::new(Servers {
+                    default: servers,
+                    routes: Vec::new(),
+                }),
                 cx,
                 next: AtomicUsize::new(0),
             }),
@@ -120,6 +129,52 @@ impl<P: ConnectionProvider> NameServerPool<P> {
         }
     }
 
+    /// Replaces the default name servers of the pool, shared with its clones
+    ///
+    /// Name servers whose configuration is unchanged are kept, with their connections and
+    /// statistics. Returns whether the name servers changed.
+    pub fn set_name_servers(
+        &self,
+        servers: impl IntoIterator<Item = NameServerConfig>,
+        conn_provider: P,
+    ) -> bool {
+        let mut current = self.state.servers.write();
+        let servers = current.reuse(servers, &self.state.cx, &conn_provider);
+        let changed = !same_servers(&servers, &current.default);
+        current.default = servers;
+        changed
+    }
+
+    /// Replaces the name servers used for the names in specific domains, see
+    /// [`NameServerPool::set_name_servers`]
+    ///
+    /// Queries for names outside of all the domains go to the default name servers, the others
+    /// to the name servers of the longest matching domain. Returns whether the routes changed.
+    pub fn set_routes(
+        &self,
+        routes: impl IntoIterator<Item = DomainRoute>,
+        conn_provider: P,
+    ) -> bool {
+        let mut current = self.state.servers.write();
+        let mut routes = routes
+            .into_iter()
+            .map(|route| {
+                let servers = current.reuse(route.name_servers, &self.state.cx, &conn_provider);
+                (route.domain, servers)
+            })
+            .collect::<Vec<_>>();
+        routes.sort_by_key(|(domain, _)| Reverse(domain.num_labels()));
+
+        let changed = routes.len() != current.routes.len()
+            || routes.iter().zip(current.routes.iter()).any(
+                |((new_domain, new), (old_domain, old))| {
+                    new_domain != old_domain || !same_servers(new, old)
+                },
+            );
+        current.routes = routes;
+        changed
+    }
+
     /// Returns the pool's options.
     pub fn context(&self) -> &Arc<PoolContext> {
         &self.state.cx
@@ -150,6 +205,7 @@ impl<P: ConnectionProvider> DnsHandle for NameServerPool<P> {
     fn send(&self, request: DnsRequest) -> Self::Response {
         let state = self.state.clone();
         let acs = self.state.cx.answer_address_filter.clone();
+        let clear_ad = self.state.cx.options.trust_ad == Some(false);
         let active_requests = self.active_requests.clone();
 
         Box::pin(once(async move {
@@ -197,9 +253,11 @@ impl<P: ConnectionProvider> DnsHandle for NameServerPool<P> {
             let response = lookup.await;
             let mut response = response?;
 
-            if acs.allows_all() {
+            let untrusted_ad = response.authentic_data && clear_ad;
+            if acs.allows_all() && !untrusted_ad {
                 return Ok(response);
             }
+            response.authentic_data &= !clear_ad;
 
             let answer_filter = |record: &Record| {
                 let ip = match &record.data {
@@ -244,14 +302,15 @@ impl<P: ConnectionProvider> DnsHandle for NameServerPool<P> {
 }
 
 struct PoolState<P: ConnectionProvider> {
-    servers: Vec<Arc<NameServer<P>>>,
+    servers: RwLock<Servers<P>>,
     cx: Arc<PoolContext>,
     next: AtomicUsize,
 }
 
 impl<P: ConnectionProvider> PoolState<P> {
     async fn try_send(&self, request: DnsRequest) -> Result<DnsResponse, NetError> {
-        let mut servers = self.servers.clone();
+        let name = request.queries.first().map(|query| &query.name);
+        let mut servers = self.servers.read().for_name(name).to_vec();
         match self.cx.options.server_ordering_strategy {
             // select the highest priority connection
             //   reorder the connections based on current view...
@@ -435,6 +494,52 @@ fn most_specific(previous: NetError, current: NetError) -> NetError {
     previous
 }
 
+/// The default name servers of a pool, and those of the domains routed elsewhere
+struct Servers<P: ConnectionProvider> {
+    default: Vec<Arc<NameServer<P>>>,
+    /// Ordered by decreasing number of labels, so that the first match is the longest
+    routes: Vec<(Name, Vec<Arc<NameServer<P>>>)>,
+}
+
+impl<P: ConnectionProvider> Servers<P> {
+    fn for_name(&self, name: Option<&Name>) -> &[Arc<NameServer<P>>] {
+        name.and_then(|name| self.routes.iter().find(|(domain, _)| domain.zone_of(name)))
+            .map_or(&self.default, |(_, servers)| servers)
+    }
+
+    /// Name servers for `configs`, sharing the existing ones with the same configuration
+    fn reuse(
+        &self,
+        configs: impl IntoIterator<Item = NameServerConfig>,
+        cx: &PoolContext,
+        conn_provider: &P,
+    ) -> Vec<Arc<NameServer<P>>> {
+        let existing = self
+            .default
+            .iter()
+            .chain(self.routes.iter().flat_map(|(_, servers)| servers));
+
+        configs
+            .into_iter()
+            .map(
+                |config| match existing.clone().find(|server| *server.config() == config) {
+                    Some(server) => server.clone(),
+                    None => Arc::new(NameServer::new(
+                        [],
+                        config,
+                        &cx.options,
+                        conn_provider.clone(),
+                    )),
+                },
+            )
+            .collect()
+    }
+}
+
+fn same_servers<P: ConnectionProvider>(a: &[Arc<NameServer<P>>], b: &[Arc<NameServer<P>>]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Arc::ptr_eq(a, b))
+}
+
 /// Sorts servers by their decayed SRTT for query-statistics-based ordering.
 ///
 /// Uses `sort_by_cached_key` to evaluate each server's decayed SRTT exactly
@@ -465,6 +570,9 @@ pub struct PoolContext {
     pub transport_state: AsyncMutex<NameServerTransportState>,
     /// Answer address filter
     pub answer_address_filter: AccessControlSet,
+    /// dnstap output for the exchanges with name servers
+    #[cfg(feature = "dnstap")]
+    pub dnstap: Option<UpstreamTap>,
 }
 
 impl PoolContext {
@@ -479,6 +587,8 @@ impl PoolContext {
             opportunistic_probe_budget: AtomicU8::default(),
             opportunistic_encryption: OpportunisticEncryption::default(),
             transport_state: AsyncMutex::new(NameServerTransportState::default()),
+            #[cfg(feature = "dnstap")]
+            dnstap: None,
         }
     }
 
@@ -495,6 +605,13 @@ impl PoolContext {
         self
     }
 
+    /// Log the exchanges with name servers to dnstap
+    #[cfg(feature = "dnstap")]
+    pub fn with_dnstap(mut self, dnstap: Option<UpstreamTap>) -> Self {
+        self.dnstap = dnstap;
+        self
+    }
+
     /// Enables opportunistic encryption with default configuration
     #[cfg(any(feature = "__tls", feature = "__quic"))]
     pub fn with_opportunistic_encryption(mut self) -> Self {
@@ -1171,6 +1288,43 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_trust_ad() {
+        subscribe();
+
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        let query_name = Name::from_str("example.com.").unwrap();
+        for (trust_ad, authentic_data) in [(None, true), (Some(true), true), (Some(false), false)] {
+            let handler = MockNetworkHandler::new(vec![MockRecord::a(ip, &query_name, ip)])
+                .with_mutation(Box::new(|_, _, response| {
+                    response.metadata.authentic_data = true
+                }));
+            let provider = MockProvider::new(handler);
+            let opts = ResolverOpts {
+                trust_ad,
+                ..ResolverOpts::default()
+            };
+            let pool = NameServerPool::from_config(
+                [NameServerConfig::udp(ip)],
+                Arc::new(PoolContext::new(opts, TlsConfig::new().unwrap())),
+                provider,
+            );
+
+            let response = pool
+                .lookup(
+                    Query::new(query_name.clone(), RecordType::A),
+                    DnsRequestOptions::default(),
+                )
+                .first_answer()
+                .await
+                .unwrap();
+            assert_eq!(
+                response.metadata.authentic_data, authentic_data,
+                "trust_ad: {trust_ad:?}"
+            );
+        }
+    }
+
     /// Regression test: when a server times out, its server-level SRTT should be penalized
     /// so that it gets deprioritized in future pool ordering.
     #[tokio::test]
@@ -1574,6 +1728,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_routes() {
+        let default = IpAddr::from([10, 0, 0, 1]);
+        let corp = IpAddr::from([10, 0, 0, 2]);
+        let lab = IpAddr::from([10, 0, 0, 3]);
+        let provider = MockProvider::new(MockNetworkHandler::new(vec![]));
+        let pool = NameServerPool::from_config(
+            [NameServerConfig::udp(default)],
+            Arc::new(PoolContext::new(
+                ResolverOpts::default(),
+                TlsConfig::new().unwrap(),
+            )),
+            provider.clone(),
+        );
+
+        let mut config = ResolverConfig::from_name_servers(vec![]);
+        config.add_route(
+            Name::from_ascii("corp.example.com.").unwrap(),
+            vec![NameServerConfig::udp(corp)],
+        );
+        config.add_route(
+            Name::from_ascii("lab.corp.example.com.").unwrap(),
+            vec![NameServerConfig::udp(lab), NameServerConfig::udp(default)],
+        );
+        assert!(pool.set_routes(config.routes.clone(), provider.clone()));
+        assert!(!pool.set_routes(config.routes, provider));
+
+        let servers = pool.state.servers.read();
+        let route = |name: &str| {
+            let name = Name::from_ascii(name).unwrap();
+            servers
+                .for_name(Some(&name))
+                .iter()
+                .map(|server| server.ip())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(route("www.example.com."), [default]);
+        assert_eq!(route("CORP.example.com."), [corp]);
+        assert_eq!(route("www.corp.example.com."), [corp]);
+        assert_eq!(route("www.lab.corp.example.com."), [lab, default]);
+        assert!(Arc::ptr_eq(
+            &servers.for_name(None)[0],
+            &servers.routes[0].1[1]
+        ));
+    }
+
+    #[test]
+    fn test_set_name_servers() {
+        let ip1 = IpAddr::from([10, 0, 0, 1]);
+        let ip2 = IpAddr::from([10, 0, 0, 2]);
+        let pool = NameServerPool::from_config(
+            [NameServerConfig::udp(ip1), NameServerConfig::udp(ip2)],
+            Arc::new(PoolContext::new(
+                ResolverOpts::default(),
+                TlsConfig::new().unwrap(),
+            )),
+            MockProvider::new(MockNetworkHandler::new(vec![])),
+        );
+        let provider = MockProvider::new(MockNetworkHandler::new(vec![]));
+        let first = pool.state.servers.read().default[0].clone();
+
+        assert!(!pool.set_name_servers(
+            [NameServerConfig::udp(ip1), NameServerConfig::udp(ip2)],
+            provider.clone(),
+        ));
+        assert!(Arc::ptr_eq(&pool.state.servers.read().default[0], &first));
+
+        assert!(pool.set_name_servers([NameServerConfig::udp(ip1)], provider.clone()));
+        let servers = pool.state.servers.read().default.clone();
+        assert_eq!(servers.len(), 1);
+        assert!(Arc::ptr_eq(&servers[0], &first));
+
+        assert!(pool.set_name_servers([NameServerConfig::udp(ip2)], provider));
+        assert_eq!(pool.state.servers.read().default[0].ip(), ip2);
+    }
+
     /// Regression test: `sort_servers_by_query_statistics` must not panic when
     /// SRTT values are concurrently modified.
     ///
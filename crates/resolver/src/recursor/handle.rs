@@ -90,6 +90,8 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
             opportunistic_encryption,
             edns_payload_len,
             qname_minimization,
+            #[cfg(feature = "dnstap")]
+            dnstap,
         } = options;
 
         let avoid_local_udp_ports = Arc::new(avoid_local_udp_ports);
@@ -118,6 +120,10 @@ impl<P: ConnectionProvider> RecursorDnsHandle<P> {
                 .deny(deny_answers.iter()) // no recommend default filters
                 .build()?,
         );
+        #[cfg(feature = "dnstap")]
+        {
+            pool_context = pool_context.with_dnstap(dnstap);
+        }
         pool_context.opportunistic_encryption = opportunistic_encryption;
         if let Some(state) = encrypted_transport_state {
             pool_context = pool_context.with_transport_state(state);
@@ -27,6 +27,8 @@ use tracing::warn;
 
 #[cfg(all(feature = "__dnssec", feature = "metrics"))]
 use crate::metrics::recursor::RecursorMetrics;
+#[cfg(feature = "dnstap")]
+use crate::net::dnstap::UpstreamTap;
 #[cfg(feature = "tokio")]
 use crate::net::runtime::TokioRuntimeProvider;
 #[cfg(feature = "serde")]
@@ -638,6 +640,11 @@ pub struct RecursorOptions {
     /// Configure QNAME minimization.
     #[cfg_attr(feature = "serde", serde(default))]
     pub qname_minimization: QNameMinimization,
+
+    /// dnstap output for the exchanges with name servers, see [`UpstreamTap`]
+    #[cfg(feature = "dnstap")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub dnstap: Option<UpstreamTap>,
 }
 
 impl Default for RecursorOptions {
@@ -657,6 +664,8 @@ impl Default for RecursorOptions {
             opportunistic_encryption: OpportunisticEncryption::default(),
             edns_payload_len: default_edns_payload_len(),
             qname_minimization: QNameMinimization::default(),
+            #[cfg(feature = "dnstap")]
+            dnstap: None,
         }
     }
 }
@@ -7,21 +7,33 @@
 
 //! Structs for creating and using a Resolver
 use std::fmt;
+#[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
+#[cfg(feature = "system-config")]
+use std::fs;
 use std::future::Future;
+#[cfg(feature = "__tls")]
+use std::mem;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU8;
 use std::task::{Context, Poll};
+#[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
+#[cfg(feature = "system-config")]
+use std::time::{Duration, SystemTime};
 
 use futures_util::{
     FutureExt, Stream,
     future::{self, BoxFuture},
     lock::Mutex as AsyncMutex,
 };
+use parking_lot::RwLock;
 use tracing::debug;
+#[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
+#[cfg(feature = "system-config")]
+use tracing::warn;
 
-#[cfg(feature = "__tls")]
-use crate::connection_provider::TlsConfig;
+#[cfg(feature = "dnstap")]
+use crate::net::dnstap::UpstreamTap;
 #[cfg(feature = "tokio")]
 use crate::net::runtime::TokioRuntimeProvider;
 use crate::{
@@ -42,6 +54,19 @@ use crate::{
         rr::domain::usage::ONION,
         rr::{IntoName, Name, RData, Record, RecordType},
     },
+    service_binding::{self, ServiceEndpoint},
+    srv::{self, SrvTargets},
+};
+#[cfg(feature = "__tls")]
+use crate::{config::ProtocolConfig, connection_provider::TlsConfig, ddr};
+#[cfg(all(feature = "__tls", feature = "__dnssec"))]
+use crate::{dane::DaneVerifier, proto::rr::rdata::TLSA};
+#[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
+#[cfg(feature = "system-config")]
+use crate::{
+    hosts::hosts_path,
+    net::runtime::{RuntimeProvider, Time},
+    system_conf::RESOLV_CONF,
 };
 #[cfg(feature = "__dnssec")]
 use crate::{net::dnssec::DnssecDnsHandle, proto::dnssec::TrustAnchors};
@@ -93,10 +118,17 @@ impl TokioResolver {
 /// should be spawned on the same executor.
 #[derive(Clone)]
 pub struct Resolver<P: ConnectionProvider> {
-    domain: Option<Name>,
-    search: Vec<Name>,
     context: Arc<PoolContext>,
+    pool: NameServerPool<P>,
+    provider: P,
     client_cache: CachingClient<LookupEither<P>>,
+    local: Arc<RwLock<LocalConfig>>,
+}
+
+/// Search domains and hosts, shared by the clones of a [`Resolver`] and replaced on reload
+struct LocalConfig {
+    domain: Option<Name>,
+    search: Vec<Name>,
     hosts: Arc<Hosts>,
 }
 
@@ -132,12 +164,107 @@ impl<R: ConnectionProvider> Resolver<R> {
             nsec3_soft_iteration_limit: None,
             #[cfg(feature = "__dnssec")]
             nsec3_hard_iteration_limit: None,
+            #[cfg(feature = "dnstap")]
+            dnstap: None,
         }
     }
 
-    /// Customizes the static hosts used in this resolver.
+    /// Customizes the static hosts used in this resolver and its clones.
     pub fn set_hosts(&mut self, hosts: Arc<Hosts>) {
-        self.hosts = hosts;
+        self.local.write().hosts = hosts;
+    }
+
+    /// Replaces the domain, search list, name servers and routes used by this resolver and its
+    /// clones.
+    ///
+    /// Name servers whose configuration is unchanged keep their connections and statistics. The
+    /// cache is only cleared when the set of name servers changed.
+    pub fn update_config(&self, config: ResolverConfig) {
+        self.apply_config(config, true);
+    }
+
+    /// Replaces the domain, search list and name servers, and the routes if `replace_routes` is
+    /// set, clearing the cache when the name servers changed
+    fn apply_config(&self, config: ResolverConfig, replace_routes: bool) {
+        let ResolverConfig {
+            domain,
+            search,
+            name_servers,
+            routes,
+        } = config;
+
+        {
+            let mut local = self.local.write();
+            local.domain = domain;
+            local.search = search;
+        }
+
+        let servers_changed = self
+            .pool
+            .set_name_servers(name_servers, self.provider.clone());
+        let routes_changed = replace_routes && self.pool.set_routes(routes, self.provider.clone());
+        if servers_changed || routes_changed {
+            debug!("name servers changed, clearing the cache");
+            self.clear_cache();
+        }
+    }
+
+    /// Reads the operating system's configuration and hosts file again, see
+    /// [`Resolver::update_config`].
+    ///
+    /// The options and routes of the resolver are kept, as the system configuration has no
+    /// routes, and the hosts file is only read when [`ResolverOpts::use_hosts_file`] allows it.
+    #[cfg(any(unix, target_os = "windows"))]
+    #[cfg(feature = "system-config")]
+    pub fn reload_system_conf(&self) -> Result<(), NetError> {
+        let (config, _) = super::system_conf::read_system_conf()?;
+        let hosts = match self.context.options.use_hosts_file {
+            ResolveHosts::Always | ResolveHosts::Auto => match Hosts::from_system() {
+                Ok(hosts) => Some(hosts),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some(Hosts::default()),
+                Err(e) => return Err(e.into()),
+            },
+            ResolveHosts::Never => None,
+        };
+
+        if let Some(hosts) = hosts {
+            self.local.write().hosts = Arc::new(hosts);
+        }
+        self.apply_config(config, false);
+        Ok(())
+    }
+
+    /// Watches `/etc/resolv.conf` and the hosts file, and reloads them when they change.
+    ///
+    /// The files are checked every `interval`. The returned future never completes, it should be
+    /// spawned on the runtime and dropped to stop watching.
+    #[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
+    #[cfg(feature = "system-config")]
+    pub fn watch_system_conf(
+        &self,
+        interval: Duration,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let resolver = self.clone();
+        async move {
+            let mut versions = system_conf_versions();
+            loop {
+                <R::RuntimeProvider as RuntimeProvider>::Timer::delay_for(interval).await;
+
+                let current = system_conf_versions();
+                if current == versions {
+                    continue;
+                }
+
+                // a failed reload, e.g. of a partially written file, is retried on the next check
+                match resolver.reload_system_conf() {
+                    Ok(()) => {
+                        debug!("reloaded the system configuration");
+                        versions = current;
+                    }
+                    Err(e) => warn!("failed to reload the system configuration: {e}"),
+                }
+            }
+        }
     }
 
     /// Generic lookup for any RecordType
@@ -171,12 +298,13 @@ impl<R: ConnectionProvider> Resolver<R> {
         L: From<Lookup> + Send + Sync + 'static,
     {
         let names = self.build_names(name);
+        let hosts = self.local.read().hosts.clone();
         LookupFuture::lookup_with_hosts(
             names,
             record_type,
             options,
             self.client_cache.clone(),
-            self.hosts.clone(),
+            hosts,
         )
         .await
         .map(L::from)
@@ -223,7 +351,7 @@ impl<R: ConnectionProvider> Resolver<R> {
         };
 
         let names = self.build_names(name);
-        let hosts = self.hosts.clone();
+        let hosts = self.local.read().hosts.clone();
 
         LookupIpFuture::lookup(
             names,
@@ -271,8 +399,9 @@ impl<R: ConnectionProvider> Resolver<R> {
         } else {
             // Otherwise we have to build the search list
             // Note: the vec is built in reverse order of precedence, for stack semantics
+            let local = self.local.read();
             let mut names =
-                Vec::<Name>::with_capacity(1 /*FQDN*/ + 1 /*DOMAIN*/ + self.search.len());
+                Vec::<Name>::with_capacity(1 /*FQDN*/ + 1 /*DOMAIN*/ + local.search.len());
 
             // if not meeting ndots, we always do the raw name in the final lookup, or it's a localhost...
             let raw_name_first: bool =
@@ -285,7 +414,7 @@ impl<R: ConnectionProvider> Resolver<R> {
                 names.push(fqdn);
             }
 
-            for search in self.search.iter().rev() {
+            for search in local.search.iter().rev() {
                 let name_search = name.clone().append_domain(search);
 
                 match name_search {
@@ -301,7 +430,7 @@ impl<R: ConnectionProvider> Resolver<R> {
                 }
             }
 
-            if let Some(domain) = &self.domain {
+            if let Some(domain) = &local.domain {
                 let name_search = name.clone().append_domain(domain);
 
                 match name_search {
@@ -340,6 +469,81 @@ impl<R: ConnectionProvider> Resolver<R> {
     lookup_fn!(tlsa_lookup, RecordType::TLSA);
     lookup_fn!(txt_lookup, RecordType::TXT);
     lookup_fn!(cert_lookup, RecordType::CERT);
+    lookup_fn!(svcb_lookup, RecordType::SVCB);
+    lookup_fn!(https_lookup, RecordType::HTTPS);
+
+    /// Resolves the endpoints to connect to for HTTPS at `host` and `port`, from its HTTPS
+    /// records, see [RFC 9460 section 9](https://www.rfc-editor.org/rfc/rfc9460#section-9)
+    ///
+    /// AliasMode records are followed, the endpoints of the ServiceMode records are ordered by
+    /// priority with their port, ALPN, ECH and address parameters applied, and the endpoint of
+    /// `host` itself comes last, unless all the other endpoints require Encrypted Client Hello.
+    /// Endpoints without any address are left out.
+    pub async fn https_endpoints(
+        &self,
+        host: impl IntoName,
+        port: u16,
+    ) -> Result<Vec<ServiceEndpoint>, NetError> {
+        let host = host.into_name()?;
+        let query = match port {
+            443 => host.clone(),
+            _ => Name::from_ascii(format!("_{port}._https"))?.append_domain(&host)?,
+        };
+
+        service_binding::endpoints(self, query, RecordType::HTTPS, host, port, &["http/1.1"]).await
+    }
+
+    /// Resolves the endpoints to connect to for the service of `scheme` at `host` and `port`,
+    /// from the SVCB records at `_{port}._{scheme}.{host}`, see [`Resolver::https_endpoints`]
+    pub async fn svcb_endpoints(
+        &self,
+        host: impl IntoName,
+        port: u16,
+        scheme: &str,
+    ) -> Result<Vec<ServiceEndpoint>, NetError> {
+        let host = host.into_name()?;
+        let query = Name::from_ascii(format!("_{port}._{scheme}"))?.append_domain(&host)?;
+        service_binding::endpoints(self, query, RecordType::SVCB, host, port, &[]).await
+    }
+
+    /// Resolves the targets of the service at `service`, e.g. `_imaps._tcp.example.com.`, from
+    /// its SRV records, see [RFC 2782](https://www.rfc-editor.org/rfc/rfc2782)
+    ///
+    /// The targets are ordered by priority, then randomly by weight, with their addresses taken
+    /// from the additional section of the response or looked up. There is no target when the
+    /// service is not available, i.e. its only record has the root as target.
+    pub async fn srv_targets(&self, service: impl IntoName) -> Result<SrvTargets, NetError> {
+        let lookup = self.srv_lookup(service).await?;
+        srv::targets(self, lookup).await
+    }
+
+    /// Makes a verifier of the certificate of the TLS server at `host` and `port`, from the TLSA
+    /// records at `_{port}._{protocol}.{host}`, see [RFC 6698](https://www.rfc-editor.org/rfc/rfc6698)
+    ///
+    /// The records must be proven secure with DNSSEC, which requires the validation to be enabled
+    /// in the [`ResolverOpts`], and at least one of them must be usable.
+    #[cfg(all(feature = "__tls", feature = "__dnssec"))]
+    pub async fn dane_verifier(
+        &self,
+        host: impl IntoName,
+        port: u16,
+        protocol: &str,
+    ) -> Result<DaneVerifier, NetError> {
+        let name =
+            Name::from_ascii(format!("_{port}._{protocol}"))?.append_domain(&host.into_name()?)?;
+        let lookup = self.tlsa_lookup(name.clone()).await?;
+
+        let proof = lookup.proof();
+        if !proof.is_secure() {
+            return Err(format!("TLSA records of {name} are not secure: {proof}").into());
+        }
+
+        let verifier = DaneVerifier::new(lookup.iter::<TLSA>().cloned());
+        match verifier.records().is_empty() {
+            true => Err(format!("no usable TLSA records at {name}").into()),
+            false => Ok(verifier),
+        }
+    }
 
     /// Flushes/Removes all entries from the cache
     pub fn clear_cache(&self) {
@@ -350,6 +554,7 @@ impl<R: ConnectionProvider> Resolver<R> {
     pub(crate) fn request_options(&self) -> DnsRequestOptions {
         let mut request_opts = DnsRequestOptions::default();
         request_opts.recursion_desired = self.context.options.recursion_desired;
+        request_opts.authentic_data = self.context.options.trust_ad == Some(true);
         request_opts.use_edns = self.context.options.edns0;
         request_opts.edns_payload_len = self.context.options.edns_payload_len;
         request_opts.case_randomization = self.context.options.case_randomization;
@@ -375,6 +580,16 @@ impl<P: ConnectionProvider> fmt::Debug for Resolver<P> {
     }
 }
 
+/// Modification time and length of `/etc/resolv.conf` and the hosts file, to detect changes
+#[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
+#[cfg(feature = "system-config")]
+fn system_conf_versions() -> [Option<(SystemTime, u64)>; 2] {
+    [RESOLV_CONF, hosts_path()].map(|path| {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    })
+}
+
 /// Different lookup options for the lookup attempts and validation
 #[derive(Clone)]
 enum LookupEither<P: ConnectionProvider> {
@@ -422,6 +637,8 @@ pub struct ResolverBuilder<P> {
     nsec3_soft_iteration_limit: Option<u16>,
     #[cfg(feature = "__dnssec")]
     nsec3_hard_iteration_limit: Option<u16>,
+    #[cfg(feature = "dnstap")]
+    dnstap: Option<UpstreamTap>,
 }
 
 impl<P: ConnectionProvider> ResolverBuilder<P> {
@@ -475,6 +692,52 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
         self
     }
 
+    /// Upgrade the unencrypted name servers to their designated resolvers.
+    ///
+    /// Name servers only configured with UDP and TCP are asked for the `_dns.resolver.arpa.` SVCB
+    /// records advertising their DNS-over-TLS, -HTTPS and -QUIC endpoints, see
+    /// [RFC 9462](https://www.rfc-editor.org/rfc/rfc9462). A name server is replaced by its
+    /// designated resolvers if they present a certificate valid for the IP address of the name
+    /// server (verified discovery) and answer a query, otherwise it is kept as is.
+    #[cfg(feature = "__tls")]
+    pub async fn with_designated_resolvers(mut self) -> Result<Self, NetError> {
+        let tls = match &self.tls {
+            Some(config) => config.clone(),
+            None => TlsConfig::new()?.config,
+        };
+
+        let mut name_servers = Vec::with_capacity(self.config.name_servers.len());
+        for server in mem::take(&mut self.config.name_servers) {
+            let unencrypted = server.connections.iter().all(|connection| {
+                matches!(
+                    connection.protocol,
+                    ProtocolConfig::Udp | ProtocolConfig::Tcp
+                )
+            });
+            if !unencrypted {
+                name_servers.push(server);
+                continue;
+            }
+
+            let designated =
+                ddr::designated_resolvers(&server, &self.options, &tls, &self.provider).await;
+            match designated.is_empty() {
+                true => name_servers.push(server),
+                false => name_servers.extend(designated),
+            }
+        }
+
+        self.config.name_servers = name_servers;
+        Ok(self)
+    }
+
+    /// Log the exchanges with name servers to dnstap.
+    #[cfg(feature = "dnstap")]
+    pub fn with_dnstap(mut self, dnstap: UpstreamTap) -> Self {
+        self.dnstap = Some(dnstap);
+        self
+    }
+
     /// Set maximum limits on NSEC3 additional iterations.
     ///
     /// See [RFC 9276](https://www.rfc-editor.org/rfc/rfc9276.html). Signed
@@ -500,6 +763,7 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
                     domain,
                     search,
                     name_servers,
+                    routes,
                 },
             mut options,
             provider,
@@ -513,6 +777,8 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
             nsec3_hard_iteration_limit,
             opportunistic_encryption,
             encrypted_transport_state,
+            #[cfg(feature = "dnstap")]
+            dnstap,
         } = self;
 
         #[cfg(feature = "__dnssec")]
@@ -535,11 +801,14 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
             ),
             opportunistic_encryption,
             transport_state: AsyncMutex::new(encrypted_transport_state),
+            #[cfg(feature = "dnstap")]
+            dnstap,
         });
 
-        let pool = NameServerPool::from_config(name_servers, context.clone(), provider);
+        let pool = NameServerPool::from_config(name_servers, context.clone(), provider.clone());
+        pool.set_routes(routes, provider.clone());
 
-        let client = RetryDnsHandle::new(pool, context.options.attempts);
+        let client = RetryDnsHandle::new(pool.clone(), context.options.attempts);
 
         #[cfg(feature = "__dnssec")]
         let either = if context.options.validate {
@@ -568,11 +837,15 @@ impl<P: ConnectionProvider> ResolverBuilder<P> {
         });
 
         Ok(Resolver {
-            domain,
-            search,
             context,
+            pool,
+            provider,
             client_cache,
-            hosts,
+            local: Arc::new(RwLock::new(LocalConfig {
+                domain,
+                search,
+                hosts,
+            })),
         })
     }
 }
@@ -1228,7 +1501,7 @@ mod tests {
     #[cfg(feature = "__dnssec")]
     use super::testing::{sec_lookup_fails_test, sec_lookup_test};
     use super::*;
-    use crate::config::{CLOUDFLARE, GOOGLE, ResolverConfig, ResolverOpts};
+    use crate::config::{CLOUDFLARE, GOOGLE, NameServerConfig, ResolverConfig, ResolverOpts};
     use crate::net::DnsError;
     use crate::net::xfer::DnsExchange;
     use crate::proto::op::{DnsRequest, DnsResponse, Message};
@@ -1423,6 +1696,45 @@ mod tests {
         assert_eq!(name_list.first(), Some(&fqdn));
     }
 
+    #[test]
+    fn test_update_config() {
+        let handle = TokioRuntimeProvider::default();
+        let resolver = Resolver::builder_with_config(ResolverConfig::udp_and_tcp(&GOOGLE), handle)
+            .build()
+            .unwrap();
+        let clone = resolver.clone();
+
+        let name = Name::from_ascii("foo").unwrap();
+        let fqdn = Name::from_ascii("foo.example.com.").unwrap();
+        assert!(!clone.build_names(name.clone()).contains(&fqdn));
+
+        let mut config = ResolverConfig::udp_and_tcp(&CLOUDFLARE);
+        config.add_search(Name::from_ascii("example.com.").unwrap());
+        resolver.update_config(config);
+        assert!(clone.build_names(name).contains(&fqdn));
+    }
+
+    #[test]
+    fn test_system_config_keeps_routes() {
+        let handle = TokioRuntimeProvider::default();
+        let mut config = ResolverConfig::udp_and_tcp(&GOOGLE);
+        config.add_route(
+            Name::from_ascii("corp.example.com.").unwrap(),
+            vec![NameServerConfig::udp(IpAddr::from([10, 0, 0, 2]))],
+        );
+        let routes = config.routes().to_vec();
+        let resolver = Resolver::builder_with_config(config, handle.clone())
+            .build()
+            .unwrap();
+
+        // a reloaded system configuration has no routes
+        resolver.apply_config(ResolverConfig::udp_and_tcp(&CLOUDFLARE), false);
+        assert!(!resolver.pool.set_routes(routes.clone(), handle.clone()));
+
+        resolver.update_config(ResolverConfig::udp_and_tcp(&CLOUDFLARE));
+        assert!(resolver.pool.set_routes(routes, handle));
+    }
+
     #[test]
     fn test_build_names_onion() {
         let handle = TokioRuntimeProvider::default();
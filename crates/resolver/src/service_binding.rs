@@ -0,0 +1,602 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Connection endpoints of a service, from its SVCB or HTTPS records, see
+//! [RFC 9460 SVCB and HTTPS Resource Records](https://www.rfc-editor.org/rfc/rfc9460)
+
+use std::net::IpAddr;
+
+use futures_util::future::join_all;
+use tracing::debug;
+
+use crate::{
+    connection_provider::ConnectionProvider,
+    lookup::Lookup,
+    net::NetError,
+    proto::rr::{
+        Name, RData, Record, RecordType,
+        rdata::svcb::{EchConfigList, SVCB, SvcParamKey, SvcParamValue},
+    },
+    resolver::Resolver,
+};
+
+/// Maximum number of AliasMode records followed, see RFC 9460 section 3
+const MAX_ALIASES: usize = 8;
+
+/// An endpoint of a service, connections should be attempted in the order of the endpoints
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ServiceEndpoint {
+    /// SvcPriority of the record, or `None` for the fallback to the origin host
+    pub priority: Option<u16>,
+    /// The effective target name
+    pub target: Name,
+    /// Port to connect to
+    pub port: u16,
+    /// Protocols to negotiate with ALPN, in order of preference
+    ///
+    /// This includes the default protocol of the scheme, unless the record disables it.
+    pub alpn: Vec<String>,
+    /// Configuration for TLS Encrypted Client Hello
+    pub ech_config_list: Option<EchConfigList>,
+    /// Addresses of the target, or the hints of the record if they could not be resolved
+    pub addresses: Vec<IpAddr>,
+}
+
+impl ServiceEndpoint {
+    fn fallback(host: Name, port: u16, default_alpn: &[&str]) -> Self {
+        Self {
+            priority: None,
+            target: host,
+            port,
+            alpn: default_alpn.iter().map(|alpn| (*alpn).to_owned()).collect(),
+            ech_config_list: None,
+            addresses: Vec::new(),
+        }
+    }
+}
+
+/// Resolves the endpoints of the service at `host` and `port`
+///
+/// `query` is the name of the SVCB or HTTPS records of `record_type`, which are followed through
+/// AliasMode records. The endpoints of the ServiceMode records are ordered by priority, followed
+/// by the fallback to `host` unless all the records require Encrypted Client Hello. An AliasMode
+/// record to the root means that the service is not available, no endpoint is returned.
+pub(crate) async fn endpoints<P: ConnectionProvider>(
+    resolver: &Resolver<P>,
+    mut query: Name,
+    record_type: RecordType,
+    host: Name,
+    port: u16,
+    default_alpn: &[&str],
+) -> Result<Vec<ServiceEndpoint>, NetError> {
+    let mut endpoints = Vec::new();
+    for _ in 0..=MAX_ALIASES {
+        let answers = match resolver
+            .inner_lookup::<Lookup>(query.clone(), record_type, resolver.request_options())
+            .await
+        {
+            Ok(lookup) => lookup.answers().to_vec(),
+            Err(e) if e.is_no_records_found() => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        match records(&answers, record_type, port, default_alpn) {
+            Records::Alias(target) if target.is_root() => return Ok(Vec::new()),
+            Records::Alias(target) => {
+                debug!(%query, %target, "following SVCB alias");
+                query = target;
+            }
+            Records::Service(service) => {
+                endpoints = service;
+                break;
+            }
+            Records::None => break,
+        }
+    }
+
+    // clients must not fall back to connections without ECH if all the endpoints offer it
+    let fallback = endpoints.is_empty()
+        || endpoints
+            .iter()
+            .any(|endpoint| endpoint.ech_config_list.is_none());
+    if fallback {
+        endpoints.push(ServiceEndpoint::fallback(host, port, default_alpn));
+    }
+
+    let resolved = join_all(
+        endpoints
+            .iter()
+            .map(|endpoint| resolver.lookup_ip(endpoint.target.clone())),
+    )
+    .await;
+
+    let mut error = None;
+    for (endpoint, lookup) in endpoints.iter_mut().zip(resolved) {
+        match lookup {
+            Ok(lookup) => endpoint.addresses = lookup.iter().collect(),
+            // keep the hints, if any
+            Err(e) => error = Some(e),
+        }
+    }
+
+    endpoints.retain(|endpoint| !endpoint.addresses.is_empty());
+    match (endpoints.is_empty(), error) {
+        (true, Some(e)) => Err(e),
+        _ => Ok(endpoints),
+    }
+}
+
+/// The SVCB or HTTPS records of a name
+#[derive(Debug, PartialEq, Eq)]
+enum Records {
+    /// The target of an AliasMode record
+    Alias(Name),
+    /// The endpoints of the ServiceMode records, with the hints as addresses
+    Service(Vec<ServiceEndpoint>),
+    None,
+}
+
+fn records(
+    answers: &[Record],
+    record_type: RecordType,
+    port: u16,
+    default_alpn: &[&str],
+) -> Records {
+    let mut records = answers
+        .iter()
+        .filter_map(|record| match (&record.data, record_type) {
+            (RData::SVCB(svcb), RecordType::SVCB) => Some((&record.name, svcb)),
+            (RData::HTTPS(https), RecordType::HTTPS) => Some((&record.name, &https.0)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // AliasMode records take precedence over ServiceMode ones, see RFC 9460 section 2.4.1
+    if let Some((_, alias)) = records.iter().find(|(_, svcb)| svcb.svc_priority == 0) {
+        return Records::Alias(alias.target_name.clone());
+    }
+
+    if records.is_empty() {
+        return Records::None;
+    }
+
+    // shuffle the records of equal priority, for load balancing
+    records.sort_by_cached_key(|(_, svcb)| (svcb.svc_priority, rand::random::<u32>()));
+    Records::Service(
+        records
+            .into_iter()
+            .filter_map(|(owner, svcb)| endpoint(owner, svcb, port, default_alpn))
+            .collect(),
+    )
+}
+
+fn endpoint(
+    owner: &Name,
+    svcb: &SVCB,
+    port: u16,
+    default_alpn: &[&str],
+) -> Option<ServiceEndpoint> {
+    let target = match svcb.target_name.is_root() {
+        true => owner.clone(),
+        false => svcb.target_name.clone(),
+    };
+
+    let mut endpoint = ServiceEndpoint {
+        priority: Some(svcb.svc_priority),
+        target,
+        port,
+        alpn: Vec::new(),
+        ech_config_list: None,
+        addresses: Vec::new(),
+    };
+    let mut default_alpn = default_alpn;
+    for (key, value) in &svcb.svc_params {
+        match value {
+            SvcParamValue::Mandatory(mandatory) => {
+                if let Some(key) = mandatory.0.iter().find(|key| !supported(key)) {
+                    debug!(target = %endpoint.target, %key, "SVCB record with unsupported mandatory key");
+                    return None;
+                }
+            }
+            SvcParamValue::Alpn(alpn) => endpoint.alpn.extend(alpn.0.iter().cloned()),
+            SvcParamValue::NoDefaultAlpn => default_alpn = &[],
+            SvcParamValue::Port(value) => endpoint.port = *value,
+            SvcParamValue::Ipv4Hint(hint) => endpoint
+                .addresses
+                .extend(hint.0.iter().map(|a| IpAddr::V4(a.0))),
+            SvcParamValue::Ipv6Hint(hint) => endpoint
+                .addresses
+                .extend(hint.0.iter().map(|aaaa| IpAddr::V6(aaaa.0))),
+            SvcParamValue::EchConfigList(ech) => endpoint.ech_config_list = Some(ech.clone()),
+            _ => debug!(%key, "ignoring unsupported SVCB parameter"),
+        }
+    }
+
+    // the default protocol is the least preferred, see RFC 9460 section 7.1.1
+    for alpn in default_alpn {
+        if !endpoint.alpn.iter().any(|a| a == alpn) {
+            endpoint.alpn.push((*alpn).to_owned());
+        }
+    }
+
+    Some(endpoint)
+}
+
+fn supported(key: &SvcParamKey) -> bool {
+    matches!(
+        key,
+        SvcParamKey::Alpn
+            | SvcParamKey::NoDefaultAlpn
+            | SvcParamKey::Port
+            | SvcParamKey::Ipv4Hint
+            | SvcParamKey::Ipv6Hint
+            | SvcParamKey::EchConfigList
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::Arc;
+
+    use test_support::{MockHandler, MockProvider, subscribe};
+
+    use super::*;
+    use crate::config::{NameServerConfig, ResolverConfig};
+    use crate::hosts::Hosts;
+    use crate::net::xfer::Protocol;
+    use crate::proto::op::{Message, OpCode, Query, ResponseCode};
+    use crate::proto::rr::rdata::{
+        A, AAAA, HTTPS,
+        svcb::{Alpn, IpHint, Mandatory},
+    };
+
+    fn https(
+        name: &str,
+        priority: u16,
+        target: &str,
+        params: Vec<(SvcParamKey, SvcParamValue)>,
+    ) -> Record {
+        let svcb = SVCB::new(priority, Name::from_ascii(target).unwrap(), params);
+        Record::from_rdata(
+            Name::from_ascii(name).unwrap(),
+            300,
+            RData::HTTPS(HTTPS(svcb)),
+        )
+    }
+
+    #[test]
+    fn test_alias() {
+        let answers = [
+            https("example.com.", 1, ".", vec![]),
+            https("example.com.", 0, "svc.example.net.", vec![]),
+        ];
+        assert_eq!(
+            records(&answers, RecordType::HTTPS, 443, &["http/1.1"]),
+            Records::Alias(Name::from_ascii("svc.example.net.").unwrap())
+        );
+        assert_eq!(records(&answers, RecordType::SVCB, 443, &[]), Records::None);
+    }
+
+    #[test]
+    fn test_service() {
+        let answers = [
+            https(
+                "example.com.",
+                2,
+                "svc.example.net.",
+                vec![(SvcParamKey::Port, SvcParamValue::Port(8443))],
+            ),
+            https(
+                "example.com.",
+                1,
+                ".",
+                vec![
+                    (
+                        SvcParamKey::Alpn,
+                        SvcParamValue::Alpn(Alpn(vec!["h3".to_owned(), "h2".to_owned()])),
+                    ),
+                    (
+                        SvcParamKey::Ipv4Hint,
+                        SvcParamValue::Ipv4Hint(IpHint(vec![A(Ipv4Addr::new(192, 0, 2, 1))])),
+                    ),
+                    (
+                        SvcParamKey::EchConfigList,
+                        SvcParamValue::EchConfigList(EchConfigList(vec![1, 2, 3])),
+                    ),
+                    (
+                        SvcParamKey::Ipv6Hint,
+                        SvcParamValue::Ipv6Hint(IpHint(vec![AAAA(Ipv6Addr::LOCALHOST)])),
+                    ),
+                ],
+            ),
+            https(
+                "example.com.",
+                3,
+                "unsupported.example.net.",
+                vec![(
+                    SvcParamKey::Mandatory,
+                    SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Key(42)])),
+                )],
+            ),
+        ];
+
+        let Records::Service(endpoints) = records(&answers, RecordType::HTTPS, 443, &["http/1.1"])
+        else {
+            panic!("expected ServiceMode records");
+        };
+        assert_eq!(endpoints.len(), 2);
+
+        let first = &endpoints[0];
+        assert_eq!(first.priority, Some(1));
+        assert_eq!(first.target, Name::from_ascii("example.com.").unwrap());
+        assert_eq!(first.port, 443);
+        assert_eq!(first.alpn, ["h3", "h2", "http/1.1"]);
+        assert_eq!(first.ech_config_list, Some(EchConfigList(vec![1, 2, 3])));
+        assert_eq!(
+            first.addresses,
+            [
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::LOCALHOST)
+            ]
+        );
+
+        let second = &endpoints[1];
+        assert_eq!(second.priority, Some(2));
+        assert_eq!(second.target, Name::from_ascii("svc.example.net.").unwrap());
+        assert_eq!(second.port, 8443);
+        assert_eq!(second.alpn, ["http/1.1"]);
+        assert!(second.addresses.is_empty());
+    }
+
+    #[test]
+    fn test_no_default_alpn() {
+        let answers = [https(
+            "example.com.",
+            1,
+            ".",
+            vec![
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["h2".to_owned()])),
+                ),
+                (SvcParamKey::NoDefaultAlpn, SvcParamValue::NoDefaultAlpn),
+            ],
+        )];
+
+        let Records::Service(endpoints) = records(&answers, RecordType::HTTPS, 443, &["http/1.1"])
+        else {
+            panic!("expected ServiceMode records");
+        };
+        assert_eq!(endpoints[0].alpn, ["h2"]);
+    }
+
+    /// Answers the HTTPS queries with the records of their name, and fails the other queries
+    struct HttpsHandler(Vec<Record>);
+
+    impl MockHandler for HttpsHandler {
+        fn handle(&self, _: IpAddr, _: Protocol, request: Message) -> Message {
+            let query = &request.queries[0];
+            if query.query_type != RecordType::HTTPS {
+                return Message::error_msg(request.id, request.op_code, ResponseCode::ServFail);
+            }
+
+            let mut response = Message::response(request.id, OpCode::Query);
+            response.metadata.authoritative = true;
+            response.add_query(query.clone());
+            response.add_answers(
+                self.0
+                    .iter()
+                    .filter(|record| record.name == query.name)
+                    .cloned(),
+            );
+            response
+        }
+    }
+
+    /// Resolver answering HTTPS queries with `records`, and address lookups for the `hosts` only
+    fn resolver(records: Vec<Record>, hosts: &[(&str, Ipv4Addr)]) -> Resolver<MockProvider> {
+        let mut resolver = Resolver::builder_with_config(
+            ResolverConfig::from_name_servers(vec![NameServerConfig::udp(IpAddr::V4(
+                Ipv4Addr::new(192, 0, 2, 53),
+            ))]),
+            MockProvider::new(HttpsHandler(records)),
+        )
+        .build()
+        .unwrap();
+
+        let mut static_hosts = Hosts::default();
+        for (host, ip) in hosts {
+            let name = Name::from_ascii(host).unwrap();
+            static_hosts.insert(
+                name.clone(),
+                RecordType::A,
+                Lookup::from_rdata(Query::new(name, RecordType::A), RData::A(A(*ip))),
+            );
+        }
+        resolver.set_hosts(Arc::new(static_hosts));
+        resolver
+    }
+
+    async fn resolve(
+        resolver: &Resolver<MockProvider>,
+        query: &str,
+    ) -> Result<Vec<ServiceEndpoint>, NetError> {
+        endpoints(
+            resolver,
+            Name::from_ascii(query).unwrap(),
+            RecordType::HTTPS,
+            Name::from_ascii("example.com.").unwrap(),
+            443,
+            &["http/1.1"],
+        )
+        .await
+    }
+
+    fn targets(endpoints: &[ServiceEndpoint]) -> Vec<(Option<u16>, String, Vec<IpAddr>)> {
+        endpoints
+            .iter()
+            .map(|endpoint| {
+                (
+                    endpoint.priority,
+                    endpoint.target.to_string(),
+                    endpoint.addresses.clone(),
+                )
+            })
+            .collect()
+    }
+
+    const HOST_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const SVC_IP: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 2);
+
+    #[tokio::test]
+    async fn test_endpoints_alias() {
+        subscribe();
+        let resolver = resolver(
+            vec![
+                https("example.com.", 0, "svc.example.net.", vec![]),
+                https("svc.example.net.", 1, ".", vec![]),
+            ],
+            &[("example.com.", HOST_IP), ("svc.example.net.", SVC_IP)],
+        );
+
+        let endpoints = resolve(&resolver, "example.com.").await.unwrap();
+        assert_eq!(
+            targets(&endpoints),
+            [
+                (
+                    Some(1),
+                    "svc.example.net.".to_owned(),
+                    vec![IpAddr::V4(SVC_IP)]
+                ),
+                (None, "example.com.".to_owned(), vec![IpAddr::V4(HOST_IP)]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_max_aliases() {
+        subscribe();
+
+        // a0 -> a1 -> ... -> a9, which has a ServiceMode record
+        let name = |index: usize| format!("a{index}.example.com.");
+        let mut records = (0..=MAX_ALIASES)
+            .map(|index| https(&name(index), 0, &name(index + 1), vec![]))
+            .collect::<Vec<_>>();
+        records.push(https(&name(MAX_ALIASES + 1), 1, ".", vec![]));
+        let resolver = resolver(
+            records,
+            &[("example.com.", HOST_IP), (&name(MAX_ALIASES + 1), SVC_IP)],
+        );
+
+        // MAX_ALIASES aliases are followed
+        let endpoints = resolve(&resolver, &name(1)).await.unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].target.to_string(), name(MAX_ALIASES + 1));
+
+        // further ones are not, only the origin host is left
+        let endpoints = resolve(&resolver, &name(0)).await.unwrap();
+        assert_eq!(
+            targets(&endpoints),
+            [(None, "example.com.".to_owned(), vec![IpAddr::V4(HOST_IP)])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_unavailable() {
+        subscribe();
+        let resolver = resolver(
+            vec![https("example.com.", 0, ".", vec![])],
+            &[("example.com.", HOST_IP)],
+        );
+
+        assert!(resolve(&resolver, "example.com.").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_ech_fallback() {
+        subscribe();
+        let ech = || {
+            vec![(
+                SvcParamKey::EchConfigList,
+                SvcParamValue::EchConfigList(EchConfigList(vec![1, 2, 3])),
+            )]
+        };
+        let hosts = [("example.com.", HOST_IP), ("svc.example.net.", SVC_IP)];
+
+        // no fallback to the origin host without ECH when all the endpoints offer it
+        let all_ech = resolver(
+            vec![
+                https("example.com.", 1, "svc.example.net.", ech()),
+                https("example.com.", 2, ".", ech()),
+            ],
+            &hosts,
+        );
+        let endpoints = resolve(&all_ech, "example.com.").await.unwrap();
+        assert_eq!(
+            targets(&endpoints),
+            [
+                (
+                    Some(1),
+                    "svc.example.net.".to_owned(),
+                    vec![IpAddr::V4(SVC_IP)]
+                ),
+                (
+                    Some(2),
+                    "example.com.".to_owned(),
+                    vec![IpAddr::V4(HOST_IP)]
+                ),
+            ]
+        );
+
+        // otherwise the origin host is the last endpoint
+        let some_ech = resolver(
+            vec![
+                https("example.com.", 1, "svc.example.net.", ech()),
+                https("example.com.", 2, ".", vec![]),
+            ],
+            &hosts,
+        );
+        let endpoints = resolve(&some_ech, "example.com.").await.unwrap();
+        assert_eq!(endpoints.len(), 3);
+        assert_eq!(endpoints[2].priority, None);
+        assert!(endpoints[2].ech_config_list.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_hints() {
+        subscribe();
+        let hint = Ipv4Addr::new(192, 0, 2, 3);
+        let resolver = resolver(
+            vec![https(
+                "example.com.",
+                1,
+                "unresolved.example.net.",
+                vec![(
+                    SvcParamKey::Ipv4Hint,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![A(hint)])),
+                )],
+            )],
+            &[("example.com.", HOST_IP)],
+        );
+
+        // the hints are kept when the target can't be resolved
+        let endpoints = resolve(&resolver, "example.com.").await.unwrap();
+        assert_eq!(
+            targets(&endpoints),
+            [
+                (
+                    Some(1),
+                    "unresolved.example.net.".to_owned(),
+                    vec![IpAddr::V4(hint)]
+                ),
+                (None, "example.com.".to_owned(), vec![IpAddr::V4(HOST_IP)]),
+            ]
+        );
+    }
+}
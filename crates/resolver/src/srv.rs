@@ -0,0 +1,323 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Selection of the targets of a service from its SRV records, see
+//! [RFC 2782](https://www.rfc-editor.org/rfc/rfc2782)
+
+use std::net::IpAddr;
+use std::vec;
+
+use futures_util::future::join_all;
+
+use crate::{
+    connection_provider::ConnectionProvider,
+    lookup::Lookup,
+    net::NetError,
+    proto::rr::{Name, RData, rdata::SRV},
+    resolver::Resolver,
+};
+
+/// A target of a service, with the addresses to connect to
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SrvTarget {
+    /// Priority of the target, lower first
+    pub priority: u16,
+    /// Relative weight of the target among those of the same priority
+    pub weight: u16,
+    /// Port of the service on the target
+    pub port: u16,
+    /// Name of the target
+    pub target: Name,
+    /// Addresses of the target, from the additional section or a lookup
+    pub addresses: Vec<IpAddr>,
+}
+
+/// The targets of a service, in the order connections should be attempted
+///
+/// The targets are grouped by increasing priority, and ordered randomly by weight within a
+/// priority. Targets whose addresses could not be resolved are left out.
+#[derive(Clone, Debug, Default)]
+pub struct SrvTargets(vec::IntoIter<SrvTarget>);
+
+impl Iterator for SrvTargets {
+    type Item = SrvTarget;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SrvTargets {}
+
+/// Orders the SRV records of `lookup` and resolves the addresses of their targets
+///
+/// A single record with the root as target means that the service is not available, there is
+/// no target then.
+pub(crate) async fn targets<P: ConnectionProvider>(
+    resolver: &Resolver<P>,
+    lookup: Lookup,
+) -> Result<SrvTargets, NetError> {
+    let records = lookup
+        .answers()
+        .iter()
+        .filter_map(|record| match &record.data {
+            RData::SRV(srv) => Some(srv.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if let [srv] = &records[..] {
+        if srv.target.is_root() {
+            return Ok(SrvTargets::default());
+        }
+    }
+
+    let mut targets = order(records, |sum| rand::random_range(0..=sum))
+        .into_iter()
+        .filter(|srv| !srv.target.is_root())
+        .map(|srv| SrvTarget {
+            addresses: lookup
+                .additionals()
+                .iter()
+                .filter(|record| record.name == srv.target)
+                .filter_map(|record| match &record.data {
+                    RData::A(a) => Some(IpAddr::V4(a.0)),
+                    RData::AAAA(aaaa) => Some(IpAddr::V6(aaaa.0)),
+                    _ => None,
+                })
+                .collect(),
+            priority: srv.priority,
+            weight: srv.weight,
+            port: srv.port,
+            target: srv.target,
+        })
+        .collect::<Vec<_>>();
+
+    // look up the addresses missing from the additional section
+    let resolved = join_all(
+        targets
+            .iter()
+            .filter(|target| target.addresses.is_empty())
+            .map(|target| resolver.lookup_ip(target.target.clone())),
+    )
+    .await;
+
+    let mut error = None;
+    let mut resolved = resolved.into_iter();
+    for target in targets
+        .iter_mut()
+        .filter(|target| target.addresses.is_empty())
+    {
+        match resolved.next() {
+            Some(Ok(lookup)) => target.addresses = lookup.iter().collect(),
+            Some(Err(e)) => error = Some(e),
+            None => break,
+        }
+    }
+
+    targets.retain(|target| !target.addresses.is_empty());
+    match (targets.is_empty(), error) {
+        (true, Some(e)) => Err(e),
+        _ => Ok(SrvTargets(targets.into_iter())),
+    }
+}
+
+/// Orders the records by priority, then by the weighted random selection of RFC 2782
+///
+/// `random` returns a number between 0 and its argument, inclusive.
+fn order(mut records: Vec<SRV>, mut random: impl FnMut(u32) -> u32) -> Vec<SRV> {
+    // records of weight 0 come first, so they are only selected when they are drawn as such
+    records.sort_by_key(|srv| (srv.priority, srv.weight != 0));
+
+    let mut ordered = Vec::with_capacity(records.len());
+    while !records.is_empty() {
+        let priority = records[0].priority;
+        let len = records
+            .iter()
+            .take_while(|srv| srv.priority == priority)
+            .count();
+        let mut group = records.drain(..len).collect::<Vec<_>>();
+
+        while !group.is_empty() {
+            let sum = group.iter().map(|srv| u32::from(srv.weight)).sum();
+            let selected = random(sum);
+
+            let mut running = 0;
+            let index = group
+                .iter()
+                .position(|srv| {
+                    running += u32::from(srv.weight);
+                    running >= selected
+                })
+                .unwrap_or(0);
+            ordered.push(group.remove(index));
+        }
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::config::{GOOGLE, ResolverConfig};
+    use crate::hosts::Hosts;
+    use crate::net::runtime::TokioRuntimeProvider;
+    use crate::proto::op::Query;
+    use crate::proto::rr::{Record, RecordType, rdata::A};
+
+    fn srv(priority: u16, weight: u16, target: &str) -> SRV {
+        SRV::new(priority, weight, 443, Name::from_ascii(target).unwrap())
+    }
+
+    fn targets(records: &[SRV]) -> Vec<String> {
+        records.iter().map(|srv| srv.target.to_string()).collect()
+    }
+
+    #[test]
+    fn test_order_by_priority() {
+        let records = vec![
+            srv(20, 0, "c.example.com."),
+            srv(10, 0, "a.example.com."),
+            srv(30, 0, "d.example.com."),
+            srv(10, 0, "b.example.com."),
+        ];
+
+        assert_eq!(
+            targets(&order(records, |_| 0)),
+            [
+                "a.example.com.",
+                "b.example.com.",
+                "c.example.com.",
+                "d.example.com."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_order_by_weight() {
+        let records = vec![
+            srv(10, 60, "a.example.com."),
+            srv(10, 0, "zero.example.com."),
+            srv(10, 40, "b.example.com."),
+        ];
+
+        // the running sums are 0, 60 and 100, 70 selects b, then 30 selects a
+        let mut draws = vec![0, 30, 70];
+        let ordered = order(records.clone(), |sum| {
+            let selected = draws.pop().unwrap();
+            assert!(selected <= sum);
+            selected
+        });
+        assert_eq!(
+            targets(&ordered),
+            ["b.example.com.", "a.example.com.", "zero.example.com."]
+        );
+
+        // 0 selects the record of weight 0 first
+        let ordered = order(records, |_| 0);
+        assert_eq!(targets(&ordered)[0], "zero.example.com.");
+    }
+
+    #[test]
+    fn test_weight_distribution() {
+        let records = vec![srv(10, 90, "a.example.com."), srv(10, 10, "b.example.com.")];
+
+        let first_a = (0..1000)
+            .filter(|_| {
+                let ordered = order(records.clone(), |sum| rand::random_range(0..=sum));
+                ordered[0].target == records[0].target
+            })
+            .count();
+        assert!((800..980).contains(&first_a), "{first_a}");
+    }
+
+    fn resolver(hosts: Hosts) -> Resolver<TokioRuntimeProvider> {
+        let mut resolver = Resolver::builder_with_config(
+            ResolverConfig::udp_and_tcp(&GOOGLE),
+            TokioRuntimeProvider::default(),
+        )
+        .build()
+        .unwrap();
+        resolver.set_hosts(Arc::new(hosts));
+        resolver
+    }
+
+    #[tokio::test]
+    async fn test_targets() {
+        let service = Name::from_ascii("_imap._tcp.example.com.").unwrap();
+        let a = Name::from_ascii("a.example.com.").unwrap();
+        let b = Name::from_ascii("b.example.com.").unwrap();
+        let unresolved = Name::from_ascii("unresolved.example.com.").unwrap();
+
+        // the address of a is in the additional section, the one of b is looked up
+        let mut lookup = Lookup::new_with_max_ttl(
+            Query::new(service.clone(), RecordType::SRV),
+            [
+                Record::from_rdata(
+                    service.clone(),
+                    86400,
+                    RData::SRV(srv(20, 0, "b.example.com.")),
+                ),
+                Record::from_rdata(
+                    service.clone(),
+                    86400,
+                    RData::SRV(srv(10, 0, "a.example.com.")),
+                ),
+            ],
+        );
+        lookup.extend_additionals([
+            Record::from_rdata(a.clone(), 86400, RData::A(A::new(192, 0, 2, 1))),
+            Record::from_rdata(unresolved, 86400, RData::A(A::new(192, 0, 2, 3))),
+        ]);
+
+        let mut hosts = Hosts::default();
+        hosts.insert(
+            b.clone(),
+            RecordType::A,
+            Lookup::from_rdata(
+                Query::new(b.clone(), RecordType::A),
+                RData::A(A::new(192, 0, 2, 2)),
+            ),
+        );
+
+        let targets = super::targets(&resolver(hosts), lookup)
+            .await
+            .unwrap()
+            .map(|target| (target.target, target.addresses))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            [
+                (a, vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))]),
+                (b, vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unavailable() {
+        let service = Name::from_ascii("_imap._tcp.example.com.").unwrap();
+        let lookup = Lookup::from_rdata(
+            Query::new(service, RecordType::SRV),
+            RData::SRV(SRV::new(0, 0, 0, Name::root())),
+        );
+
+        let targets = super::targets(&resolver(Hosts::default()), lookup)
+            .await
+            .unwrap();
+        assert_eq!(targets.len(), 0);
+    }
+}
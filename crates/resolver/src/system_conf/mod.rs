@@ -18,7 +18,11 @@ mod unix;
 
 #[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
 #[cfg(feature = "system-config")]
-pub use self::unix::{parse_resolv_conf, read_system_conf};
+pub use self::unix::{ResolvConf, UnsupportedSetting, parse_resolv_conf, read_system_conf};
+
+#[cfg(all(unix, not(any(target_os = "android", target_vendor = "apple"))))]
+#[cfg(feature = "system-config")]
+pub(crate) use self::unix::RESOLV_CONF;
 
 #[cfg(windows)]
 #[cfg(feature = "system-config")]
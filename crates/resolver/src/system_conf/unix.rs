@@ -11,36 +11,111 @@
 //!  the host system. It will read from the default location on each operating
 //!  system, e.g. most Unixes have this written to `/etc/resolv.conf`
 
+use std::fmt;
 use std::fs;
 use std::io;
 use std::path::Path;
 use std::str::FromStr;
 use std::time::Duration;
 
-use crate::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use resolv_conf::ParseError;
+use tracing::warn;
+
+use crate::config::{
+    LookupIpStrategy, NameServerConfig, ResolverConfig, ResolverOpts, ServerOrderingStrategy,
+};
 use crate::net::NetError;
 use crate::proto::rr::Name;
 
+/// Location of the resolver configuration
+pub(crate) const RESOLV_CONF: &str = "/etc/resolv.conf";
+
 pub fn read_system_conf() -> Result<(ResolverConfig, ResolverOpts), NetError> {
-    read_resolv_conf("/etc/resolv.conf")
+    read_resolv_conf(RESOLV_CONF)
 }
 
 fn read_resolv_conf<P: AsRef<Path>>(path: P) -> Result<(ResolverConfig, ResolverOpts), NetError> {
     parse_resolv_conf(fs::read(path)?)
 }
 
+/// Parses a resolv.conf, logging the settings which are not supported
 pub fn parse_resolv_conf<T: AsRef<[u8]>>(
     data: T,
 ) -> Result<(ResolverConfig, ResolverOpts), NetError> {
-    let parsed_conf = resolv_conf::Config::parse(&data)
-        .map_err(|e| io::Error::other(format!("Error parsing resolv.conf: {e}")))?;
-    into_resolver_config(parsed_conf)
+    let resolv_conf = ResolvConf::parse(data)?;
+    for setting in &resolv_conf.unsupported {
+        warn!("ignoring resolv.conf setting: {setting}");
+    }
+
+    Ok((resolv_conf.config, resolv_conf.options))
+}
+
+/// The configuration of a resolv.conf, along with the settings which are not supported
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ResolvConf {
+    /// The name servers and search domains
+    pub config: ResolverConfig,
+    /// The options
+    pub options: ResolverOpts,
+    /// The settings which were ignored
+    pub unsupported: Vec<UnsupportedSetting>,
+}
+
+impl ResolvConf {
+    /// Reads `/etc/resolv.conf`
+    pub fn read() -> Result<Self, NetError> {
+        Self::parse(fs::read(RESOLV_CONF)?)
+    }
+
+    /// Parses the contents of a resolv.conf
+    pub fn parse<T: AsRef<[u8]>>(data: T) -> Result<Self, NetError> {
+        let (parsed_conf, errors) = resolv_conf::Config::parse_with_errors(data.as_ref());
+
+        let mut unsupported = Vec::new();
+        for error in errors {
+            match error {
+                ParseError::InvalidDirective(line) | ParseError::InvalidOption(line) => {
+                    // the lines are counted from 0
+                    unsupported.push(UnsupportedSetting::Line(line + 1))
+                }
+                e => {
+                    return Err(io::Error::other(format!("Error parsing resolv.conf: {e}")).into());
+                }
+            }
+        }
+
+        into_resolver_config(parsed_conf, unsupported)
+    }
+}
+
+/// A setting of a resolv.conf which is not supported, and ignored
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnsupportedSetting {
+    /// A known directive, such as `sortlist`
+    Directive(&'static str),
+    /// A known option, such as `inet6`
+    Option(&'static str),
+    /// An unknown directive or option, on the line with this number, counted from 1
+    Line(usize),
+}
+
+impl fmt::Display for UnsupportedSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Directive(directive) => write!(f, "directive {directive}"),
+            Self::Option(option) => write!(f, "option {option}"),
+            Self::Line(line) => write!(f, "unknown directive or option at line {line}"),
+        }
+    }
 }
 
 // TODO: use a custom parsing error type maybe?
 fn into_resolver_config(
     parsed_config: resolv_conf::Config,
-) -> Result<(ResolverConfig, ResolverOpts), NetError> {
+    mut unsupported: Vec<UnsupportedSetting>,
+) -> Result<ResolvConf, NetError> {
     let domain = if let Some(domain) = parsed_config.get_system_domain() {
         // The system domain name maybe appear to be valid to the resolv_conf
         // crate but actually be invalid. For example, if the hostname is "matt.schulte's computer"
@@ -51,11 +126,14 @@ fn into_resolver_config(
         None
     };
 
-    // nameservers
+    // nameservers, only over TCP with use-vc
     let nameservers = parsed_config
         .nameservers
         .iter()
-        .map(|ip| NameServerConfig::udp_and_tcp(ip.into()))
+        .map(|ip| match parsed_config.use_vc {
+            true => NameServerConfig::tcp(ip.into()),
+            false => NameServerConfig::udp_and_tcp(ip.into()),
+        })
         .collect::<Vec<_>>();
     if nameservers.is_empty() {
         Err(io::Error::other("no nameservers found in config"))?;
@@ -75,17 +153,55 @@ fn into_resolver_config(
         );
     }
 
-    let config = ResolverConfig::from_parts(domain, search, nameservers);
-
-    let options = ResolverOpts {
+    // the timeout applies to each name server in turn
+    let server_timeout = Duration::from_secs(u64::from(parsed_config.timeout));
+    let mut options = ResolverOpts {
         ndots: parsed_config.ndots as usize,
-        timeout: Duration::from_secs(u64::from(parsed_config.timeout)),
+        timeout: server_timeout * nameservers.len() as u32,
+        server_timeout: Some(server_timeout),
         attempts: parsed_config.attempts as usize,
         edns0: parsed_config.edns0,
+        trust_ad: Some(parsed_config.trust_ad),
         ..ResolverOpts::default()
     };
 
-    Ok((config, options))
+    if parsed_config.rotate {
+        options.server_ordering_strategy = ServerOrderingStrategy::RoundRobin;
+    }
+    if parsed_config.no_aaaa {
+        options.ip_strategy = LookupIpStrategy::Ipv4Only;
+    }
+
+    for (directive, set) in [
+        ("sortlist", !parsed_config.sortlist.is_empty()),
+        ("lookup", !parsed_config.lookup.is_empty()),
+        ("family", !parsed_config.family.is_empty()),
+    ] {
+        if set {
+            unsupported.push(UnsupportedSetting::Directive(directive));
+        }
+    }
+    for (option, set) in [
+        ("debug", parsed_config.debug),
+        ("no-check-names", parsed_config.no_check_names),
+        ("inet6", parsed_config.inet6),
+        ("ip6-bytestring", parsed_config.ip6_bytestring),
+        ("ip6-dotint", parsed_config.ip6_dotint),
+        ("no-tld-query", parsed_config.no_tld_query),
+        ("no-reload", parsed_config.no_reload),
+        ("single-request", parsed_config.single_request),
+        ("single-request-reopen", parsed_config.single_request_reopen),
+    ] {
+        if set {
+            unsupported.push(UnsupportedSetting::Option(option));
+        }
+    }
+
+    Ok(ResolvConf {
+        config: ResolverConfig::from_parts(domain, search, nameservers),
+        options,
+        unsupported,
+    })
 }
 
 #[cfg(test)]
@@ -174,6 +290,51 @@ mod tests {
         read_resolv_conf(format!("{}/resolv.conf-linux", tests_dir())).expect("linux failed");
     }
 
+    #[test]
+    fn test_options() {
+        let parsed = ResolvConf::parse(
+            "nameserver 127.0.0.1\nnameserver 127.0.0.2\n\
+             options rotate use-vc no-aaaa single-request trust-ad timeout:2",
+        )
+        .expect("failed");
+        assert_eq!(
+            parsed.unsupported,
+            [UnsupportedSetting::Option("single-request")]
+        );
+
+        let name_servers = parsed.config.name_servers();
+        assert_eq!(name_servers[0], NameServerConfig::tcp(name_servers[0].ip));
+        assert_eq!(parsed.options.server_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(parsed.options.timeout, Duration::from_secs(4));
+        assert_eq!(
+            parsed.options.server_ordering_strategy,
+            ServerOrderingStrategy::RoundRobin
+        );
+        assert_eq!(parsed.options.ip_strategy, LookupIpStrategy::Ipv4Only);
+        assert_eq!(
+            parsed.options.num_concurrent_reqs,
+            ResolverOpts::default().num_concurrent_reqs
+        );
+        assert_eq!(parsed.options.trust_ad, Some(true));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let data = fs::read(format!("{}/resolv.conf-linux", tests_dir())).unwrap();
+        let parsed = ResolvConf::parse(data).expect("linux failed");
+        assert_eq!(
+            parsed.unsupported,
+            [
+                UnsupportedSetting::Directive("sortlist"),
+                UnsupportedSetting::Option("inet6"),
+                UnsupportedSetting::Option("no-tld-query"),
+            ]
+        );
+
+        let parsed = ResolvConf::parse("nameserver 127.0.0.1\noptions frobnicate").expect("failed");
+        assert_eq!(parsed.unsupported, [UnsupportedSetting::Line(2)]);
+    }
+
     /// Validate that all options set in `into_resolver_config()` are at default values
     fn is_default_opts(opts: ResolverOpts) {
         assert_eq!(opts.ndots, 1);
@@ -10,6 +10,7 @@
 use std::{
     collections::{HashMap, hash_map::Entry},
     fmt,
+    sync::RwLock,
 };
 
 use hickory_net::xfer::Protocol as NetProtocol;
@@ -21,15 +22,17 @@ use crate::server::{ReportingResponseHandler, ResponseHandler, ResponseInfo};
 use crate::zone_handler::{AuthLookup, LookupControlFlow, ZoneHandler, ZoneType};
 
 pub(super) struct CatalogMetrics {
-    zone_store_metrics: HashMap<(&'static str, ZoneType), ZoneLookupMetrics>,
+    zone_store_metrics: RwLock<HashMap<(&'static str, ZoneType), ZoneLookupMetrics>>,
     request_metrics: DnsClassesRecordTypesMetrics,
     response_metrics: DnsClassesRecordTypesMetrics,
 }
 
 impl CatalogMetrics {
-    pub(super) fn add_handler(&mut self, handler: &dyn ZoneHandler) {
+    pub(super) fn add_handler(&self, handler: &dyn ZoneHandler) {
         match self
             .zone_store_metrics
+            .write()
+            .expect("metrics lock poisoned")
             .entry((handler.metrics_label(), handler.zone_type()))
         {
             Entry::Occupied(_) => {
@@ -49,10 +52,13 @@ impl CatalogMetrics {
         handler: &dyn ZoneHandler,
         lookup: &LookupControlFlow<AuthLookup>,
     ) {
-        // metrics per store are added/removed with the ZoneHandler in the Catalog (requires mut)
-        let Some(zone_store_metrics) = self
+        // metrics per store are added/removed with the ZoneHandler in the Catalog
+        let zone_store_metrics = self
             .zone_store_metrics
-            .get(&(handler.metrics_label(), handler.zone_type()))
+            .read()
+            .expect("metrics lock poisoned");
+        let Some(zone_store_metrics) =
+            zone_store_metrics.get(&(handler.metrics_label(), handler.zone_type()))
         else {
             return;
         };
@@ -91,7 +97,7 @@ impl CatalogMetrics {
 impl Default for CatalogMetrics {
     fn default() -> Self {
         Self {
-            zone_store_metrics: HashMap::new(),
+            zone_store_metrics: RwLock::new(HashMap::new()),
             request_metrics: DnsClassesRecordTypesMetrics::new(Direction::Request),
             response_metrics: DnsClassesRecordTypesMetrics::new(Direction::Response),
         }
@@ -0,0 +1,200 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Selection of the server certificate by the server name indication of TLS clients
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use rustls::{
+    pki_types::CertificateDer,
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use tracing::{debug, warn};
+use webpki::EndEntityCert;
+
+/// Resolves the certificate presented to TLS clients by their server name indication (SNI)
+///
+/// Certificates are selected by the DNS names of their subject alternative names, wildcard names
+/// matching a single label, e.g. `*.example.com` matches `dns.example.com`. The first certificate
+/// is presented to clients without SNI or asking for an unknown name.
+///
+/// The certificates can be replaced at any time, e.g. when renewed, new handshakes then use the
+/// new certificates while established connections are not affected.
+pub struct SniCertResolver {
+    certs: RwLock<Arc<SniCerts>>,
+}
+
+impl SniCertResolver {
+    /// Resolver selecting one of `certs`
+    pub fn new(certs: Vec<Arc<CertifiedKey>>) -> Self {
+        Self {
+            certs: RwLock::new(Arc::new(SniCerts::new(certs))),
+        }
+    }
+
+    /// Atomically replaces the certificates used for new handshakes
+    pub fn replace(&self, certs: Vec<Arc<CertifiedKey>>) {
+        let certs = Arc::new(SniCerts::new(certs));
+        match self.certs.write() {
+            Ok(mut guard) => *guard = certs,
+            Err(poisoned) => *poisoned.into_inner() = certs,
+        }
+    }
+
+    /// DNS names the certificates are selected for, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.current().by_name.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn current(&self) -> Arc<SniCerts> {
+        match self.certs.read() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+impl ResolvesServerCert for SniCertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.current().get(client_hello.server_name())
+    }
+}
+
+impl fmt::Debug for SniCertResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SniCertResolver")
+            .field("names", &self.names())
+            .finish()
+    }
+}
+
+/// Certificates indexed by the names they are valid for
+struct SniCerts {
+    default: Option<Arc<CertifiedKey>>,
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+}
+
+impl SniCerts {
+    fn new(certs: Vec<Arc<CertifiedKey>>) -> Self {
+        let mut by_name = HashMap::new();
+        for cert in &certs {
+            let names = cert.cert.first().map(dns_names).unwrap_or_default();
+            if names.is_empty() {
+                warn!("certificate without DNS names, only usable as the default");
+            }
+
+            for name in names {
+                // the first certificate for a name wins, as for the default certificate
+                by_name
+                    .entry(name.to_ascii_lowercase())
+                    .or_insert_with(|| cert.clone());
+            }
+        }
+
+        Self {
+            default: certs.into_iter().next(),
+            by_name,
+        }
+    }
+
+    /// Certificate for `server_name`, or the default one
+    fn get(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        let Some(server_name) = server_name else {
+            return self.default.clone();
+        };
+
+        let server_name = server_name.to_ascii_lowercase();
+        let wildcard = server_name
+            .split_once('.')
+            .map(|(_, parent)| format!("*.{parent}"));
+        let cert = self.by_name.get(&server_name).or_else(|| {
+            wildcard
+                .as_ref()
+                .and_then(|wildcard| self.by_name.get(wildcard))
+        });
+
+        match cert {
+            Some(cert) => Some(cert.clone()),
+            None => {
+                debug!(%server_name, "no certificate for server name, using the default");
+                self.default.clone()
+            }
+        }
+    }
+}
+
+/// DNS names from the subject alternative names of a certificate
+fn dns_names(cert: &CertificateDer<'_>) -> Vec<String> {
+    match EndEntityCert::try_from(cert) {
+        Ok(cert) => cert.valid_dns_names().map(str::to_owned).collect(),
+        Err(error) => {
+            debug!(%error, "failed to parse certificate");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+
+    use super::*;
+    use crate::net::tls::default_provider;
+
+    fn test_cert() -> Arc<CertifiedKey> {
+        let server_path = env!("CARGO_MANIFEST_DIR");
+        let chain =
+            CertificateDer::pem_file_iter(format!("{server_path}/../../tests/test-data/cert.pem"))
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        let key =
+            PrivateKeyDer::from_pem_file(format!("{server_path}/../../tests/test-data/cert.key"))
+                .unwrap();
+        Arc::new(CertifiedKey::from_der(chain, key, &default_provider()).unwrap())
+    }
+
+    #[test]
+    fn test_sni_certs() {
+        let cert = test_cert();
+        let other = Arc::new(CertifiedKey::new(Vec::new(), cert.key.clone()));
+        let certs = SniCerts::new(vec![other.clone(), cert.clone()]);
+
+        assert_eq!(certs.by_name.keys().collect::<Vec<_>>(), ["ns.example.com"]);
+        assert!(Arc::ptr_eq(
+            &certs.get(Some("NS.example.com")).unwrap(),
+            &cert
+        ));
+        assert!(Arc::ptr_eq(
+            &certs.get(Some("example.com")).unwrap(),
+            &other
+        ));
+        assert!(Arc::ptr_eq(&certs.get(None).unwrap(), &other));
+        assert!(SniCerts::new(Vec::new()).get(None).is_none());
+    }
+
+    #[test]
+    fn test_replace() {
+        let cert = test_cert();
+        let resolver = SniCertResolver::new(Vec::new());
+        assert!(resolver.names().is_empty());
+
+        resolver.replace(vec![cert.clone()]);
+        assert_eq!(resolver.names(), ["ns.example.com"]);
+        assert!(Arc::ptr_eq(
+            &resolver.current().get(Some("ns.example.com")).unwrap(),
+            &cert
+        ));
+    }
+}
@@ -0,0 +1,468 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Identification and authorization of TLS clients by their certificate
+
+#[cfg(feature = "__tls")]
+use std::sync::Arc;
+use std::{fmt, str::FromStr};
+
+use data_encoding::HEXLOWER_PERMISSIVE;
+#[cfg(feature = "__tls")]
+use rustls::{
+    DigitallySignedStruct, DistinguishedName, Error, RootCertStore, SignatureScheme,
+    client::danger::HandshakeSignatureValid,
+    crypto::{CryptoProvider, hash::HashAlgorithm, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, UnixTime},
+    server::{
+        VerifierBuilderError, WebPkiClientVerifier,
+        danger::{ClientCertVerified, ClientCertVerifier},
+    },
+};
+use serde::{Deserialize, Deserializer, de};
+#[cfg(feature = "__tls")]
+use tracing::debug;
+#[cfg(feature = "__tls")]
+use webpki::EndEntityCert;
+
+/// SHA-256 fingerprint of the DER encoding of a client certificate
+///
+/// Written as 64 hexadecimal digits, optionally separated by colons, as printed by
+/// `openssl x509 -noout -fingerprint -sha256`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CertFingerprint([u8; 32]);
+
+impl CertFingerprint {
+    /// Computes the fingerprint of `cert` with the SHA-256 implementation of `provider`
+    #[cfg(feature = "__tls")]
+    pub fn from_cert(cert: &CertificateDer<'_>, provider: &CryptoProvider) -> Option<Self> {
+        let hash = provider
+            .cipher_suites
+            .iter()
+            .filter_map(|suite| suite.tls13())
+            .map(|suite| suite.common.hash_provider)
+            .find(|hash| hash.algorithm() == HashAlgorithm::SHA256)?;
+
+        let digest = hash.hash(cert.as_ref());
+        Some(Self(digest.as_ref().try_into().ok()?))
+    }
+}
+
+impl From<[u8; 32]> for CertFingerprint {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+impl FromStr for CertFingerprint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.replace(':', "");
+        let bytes = HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|e| format!("invalid certificate fingerprint {s}: {e}"))?;
+        let fingerprint = <[u8; 32]>::try_from(bytes)
+            .map_err(|_| format!("certificate fingerprint is not a SHA-256 digest: {s}"))?;
+        Ok(Self(fingerprint))
+    }
+}
+
+impl fmt::Display for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&HEXLOWER_PERMISSIVE.encode(&self.0))
+    }
+}
+
+impl fmt::Debug for CertFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CertFingerprint({self})")
+    }
+}
+
+impl<'de> Deserialize<'de> for CertFingerprint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+/// Identity of a client, established from the certificate it authenticated with over TLS
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientIdentity {
+    fingerprint: CertFingerprint,
+    names: Vec<String>,
+}
+
+impl ClientIdentity {
+    /// A client only known by the fingerprint of its certificate
+    pub fn new(fingerprint: CertFingerprint) -> Self {
+        Self {
+            fingerprint,
+            names: Vec::new(),
+        }
+    }
+
+    /// Sets the subject alternative names of the certificate, once verified
+    pub fn with_names(mut self, names: Vec<String>) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Fingerprint of the certificate of the client
+    pub fn fingerprint(&self) -> &CertFingerprint {
+        &self.fingerprint
+    }
+
+    /// DNS names and URIs from the subject alternative names of the certificate
+    ///
+    /// These are only set if the certificate chain was verified against the CA certificates of
+    /// the [`ClientAuth`] of the server, and are empty otherwise.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+}
+
+impl From<CertFingerprint> for ClientIdentity {
+    fn from(fingerprint: CertFingerprint) -> Self {
+        Self::new(fingerprint)
+    }
+}
+
+/// Pattern matching the [`ClientIdentity`] of clients in access control lists
+///
+/// Written either as a [`CertFingerprint`], or as a name which matches the verified subject
+/// alternative names of the certificate, ignoring ASCII case. A name starting with `*.` matches
+/// any name below the following domain, e.g. `*.devices.example.com` matches
+/// `laptop.devices.example.com`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdentityPattern {
+    /// Certificate with this fingerprint
+    Fingerprint(CertFingerprint),
+    /// Verified certificate with a matching subject alternative name
+    Name(String),
+}
+
+impl IdentityPattern {
+    /// Returns true if `identity` matches this pattern
+    pub fn matches(&self, identity: &ClientIdentity) -> bool {
+        match self {
+            Self::Fingerprint(fingerprint) => identity.fingerprint() == fingerprint,
+            Self::Name(pattern) => identity
+                .names()
+                .iter()
+                .any(|name| name_matches(pattern, name)),
+        }
+    }
+}
+
+impl FromStr for IdentityPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(fingerprint) = CertFingerprint::from_str(s) {
+            return Ok(Self::Fingerprint(fingerprint));
+        }
+
+        match s.strip_prefix("*.").unwrap_or(s) {
+            "" => Err(format!("invalid client identity: {s:?}")),
+            _ => Ok(Self::Name(s.to_owned())),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IdentityPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+fn name_matches(pattern: &str, name: &str) -> bool {
+    let Some(domain) = pattern.strip_prefix("*.") else {
+        return pattern.eq_ignore_ascii_case(name);
+    };
+
+    let name = name.as_bytes();
+    name.len() > domain.len() + 1
+        && name[name.len() - domain.len() - 1] == b'.'
+        && name[name.len() - domain.len()..].eq_ignore_ascii_case(domain.as_bytes())
+}
+
+/// Authentication and authorization of clients by their TLS certificate
+///
+/// Clients are identified by the fingerprint of their certificate and, if it is issued by one of
+/// the configured CAs, by its subject alternative names. The identities allowed and denied access
+/// to the server take precedence over the allowed and denied networks, denied identities over
+/// allowed ones. To only serve known clients, deny all networks and allow their identities.
+///
+/// Certificates are only received from clients if the TLS listeners request them, see
+/// [`client_cert_tls_server_config`](crate::server::client_cert_tls_server_config).
+#[derive(Clone, Debug, Default)]
+pub struct ClientAuth {
+    #[cfg(feature = "__tls")]
+    verifier: Option<Arc<dyn ClientCertVerifier>>,
+    allowed: Vec<IdentityPattern>,
+    denied: Vec<IdentityPattern>,
+}
+
+impl ClientAuth {
+    /// Verifies client certificates against the CA certificates in `roots`
+    #[cfg(feature = "__tls")]
+    pub fn with_ca(mut self, roots: RootCertStore) -> Result<Self, VerifierBuilderError> {
+        let verifier = WebPkiClientVerifier::builder_with_provider(
+            Arc::new(roots),
+            Arc::new(crate::net::tls::default_provider()),
+        )
+        .allow_unauthenticated()
+        .build()?;
+        self.verifier = Some(verifier);
+        Ok(self)
+    }
+
+    /// Allows clients with these identities to access the server
+    pub fn with_allowed(mut self, identities: impl IntoIterator<Item = IdentityPattern>) -> Self {
+        self.allowed.extend(identities);
+        self
+    }
+
+    /// Denies clients with these identities access to the server
+    pub fn with_denied(mut self, identities: impl IntoIterator<Item = IdentityPattern>) -> Self {
+        self.denied.extend(identities);
+        self
+    }
+
+    /// Identifies the client that authenticated with the certificate `chain`
+    ///
+    /// The subject alternative names of the certificate are only included if the chain is
+    /// issued by one of the configured CAs.
+    #[cfg(feature = "__tls")]
+    pub(crate) fn identify(
+        &self,
+        chain: &[CertificateDer<'_>],
+        provider: &CryptoProvider,
+    ) -> Option<ClientIdentity> {
+        let (end_entity, intermediates) = chain.split_first()?;
+        let identity = ClientIdentity::new(CertFingerprint::from_cert(end_entity, provider)?);
+        let Some(verifier) = &self.verifier else {
+            return Some(identity);
+        };
+
+        match verifier.verify_client_cert(end_entity, intermediates, UnixTime::now()) {
+            Ok(_) => Some(identity.with_names(client_names(end_entity))),
+            Err(error) => {
+                debug!(%error, fingerprint = %identity.fingerprint, "client certificate not verified");
+                Some(identity)
+            }
+        }
+    }
+
+    /// Returns whether `identity` is explicitly allowed or denied access to the server
+    pub(crate) fn access(&self, identity: Option<&ClientIdentity>) -> Option<bool> {
+        let identity = identity?;
+        if self.denied.iter().any(|pattern| pattern.matches(identity)) {
+            Some(false)
+        } else if self.allowed.iter().any(|pattern| pattern.matches(identity)) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// Requests an optional client certificate and accepts any, as long as the client proves
+/// possession of its key
+///
+/// The certificate chain is not validated during the handshake, clients are identified by the
+/// [`CertFingerprint`] of their certificate, which is only trusted where it is explicitly listed,
+/// e.g. in [`AxfrPolicy::RequireMutualTls`](crate::zone_handler::AxfrPolicy::RequireMutualTls).
+/// The chain is verified afterwards by the [`ClientAuth`] of the server, if configured with CAs.
+#[cfg(feature = "__tls")]
+#[derive(Debug)]
+pub struct FingerprintClientCertVerifier {
+    provider: CryptoProvider,
+}
+
+#[cfg(feature = "__tls")]
+impl FingerprintClientCertVerifier {
+    /// Verifies client handshake signatures with the algorithms of `provider`
+    pub fn new(provider: CryptoProvider) -> Self {
+        Self { provider }
+    }
+}
+
+#[cfg(feature = "__tls")]
+impl ClientCertVerifier for FingerprintClientCertVerifier {
+    fn client_auth_mandatory(&self) -> bool {
+        false
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// DNS names and URIs from the subject alternative names of a certificate
+#[cfg(feature = "__tls")]
+fn client_names(cert: &CertificateDer<'_>) -> Vec<String> {
+    match EndEntityCert::try_from(cert) {
+        Ok(cert) => cert
+            .valid_dns_names()
+            .chain(cert.valid_uri_names())
+            .map(str::to_owned)
+            .collect(),
+        Err(error) => {
+            debug!(%error, "failed to parse certificate");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fingerprint() {
+        let colons = "AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:\
+                      AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89";
+        let fingerprint = CertFingerprint::from_str(colons).unwrap();
+        assert_eq!(
+            fingerprint.to_string(),
+            "abcdef0123456789abcdef0123456789abcdef0123456789abcdef0123456789"
+        );
+        assert_eq!(
+            CertFingerprint::from_str(&fingerprint.to_string()).unwrap(),
+            fingerprint
+        );
+
+        assert!(CertFingerprint::from_str("abcdef").is_err());
+        assert!(CertFingerprint::from_str("not hex").is_err());
+    }
+
+    #[test]
+    fn test_identity_pattern() {
+        let fingerprint = CertFingerprint::from([0xab; 32]);
+        let identity = ClientIdentity::new(fingerprint).with_names(vec![
+            "Laptop.Devices.example.com".to_owned(),
+            "admin@example.com".to_owned(),
+        ]);
+
+        let pattern = IdentityPattern::from_str(&fingerprint.to_string()).unwrap();
+        assert_eq!(pattern, IdentityPattern::Fingerprint(fingerprint));
+        assert!(pattern.matches(&identity));
+        assert!(!pattern.matches(&ClientIdentity::new(CertFingerprint::from([0xcd; 32]))));
+
+        let matches = |s: &str| IdentityPattern::from_str(s).unwrap().matches(&identity);
+        assert!(matches("laptop.devices.example.com"));
+        assert!(matches("*.devices.example.com"));
+        assert!(matches("*.example.com"));
+        assert!(matches("admin@example.com"));
+        assert!(!matches("devices.example.com"));
+        assert!(!matches("*.laptop.devices.example.com"));
+        assert!(!matches("*.ices.example.com"));
+
+        assert!(IdentityPattern::from_str("*.").is_err());
+        assert!(IdentityPattern::from_str("").is_err());
+    }
+
+    #[test]
+    fn test_client_auth_access() {
+        let device = ClientIdentity::new(CertFingerprint::from([1; 32]))
+            .with_names(vec!["laptop.devices.example.com".to_owned()]);
+        let lost = ClientIdentity::new(CertFingerprint::from([2; 32]))
+            .with_names(vec!["phone.devices.example.com".to_owned()]);
+        let unknown = ClientIdentity::new(CertFingerprint::from([3; 32]));
+
+        let auth = ClientAuth::default()
+            .with_allowed([IdentityPattern::from_str("*.devices.example.com").unwrap()])
+            .with_denied([IdentityPattern::Fingerprint(CertFingerprint::from([2; 32]))]);
+        assert_eq!(auth.access(Some(&device)), Some(true));
+        // denied identities take precedence
+        assert_eq!(auth.access(Some(&lost)), Some(false));
+        assert_eq!(auth.access(Some(&unknown)), None);
+        assert_eq!(auth.access(None), None);
+
+        let auth = ClientAuth::default()
+            .with_denied([IdentityPattern::Fingerprint(CertFingerprint::from([2; 32]))]);
+        assert_eq!(auth.access(Some(&lost)), Some(false));
+        assert_eq!(auth.access(Some(&device)), None);
+    }
+
+    #[cfg(feature = "__tls")]
+    #[test]
+    fn test_subject_alt_names() {
+        use rustls::pki_types::pem::PemObject;
+
+        let server_path = env!("CARGO_MANIFEST_DIR");
+        let cert =
+            CertificateDer::from_pem_file(format!("{server_path}/../../tests/test-data/cert.pem"))
+                .unwrap();
+
+        assert_eq!(client_names(&cert), vec!["ns.example.com".to_owned()]);
+
+        let truncated = CertificateDer::from(&cert[..cert.len() / 2]);
+        assert!(client_names(&truncated).is_empty());
+    }
+
+    #[cfg(feature = "__tls")]
+    #[test]
+    fn test_fingerprint_from_cert() {
+        use crate::net::tls::default_provider;
+
+        let cert = CertificateDer::from(b"not really a certificate".to_vec());
+        let fingerprint = CertFingerprint::from_cert(&cert, &default_provider()).unwrap();
+        // echo -n "not really a certificate" | sha256sum
+        assert_eq!(
+            fingerprint.to_string(),
+            "d6182255e5739d55fc9781759c7a823d4dff3d2b7d17949e7085ee7c0cc22acf"
+        );
+    }
+}
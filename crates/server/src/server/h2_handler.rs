@@ -21,9 +21,9 @@ use tokio_rustls::TlsAcceptor;
 use tracing::{debug, warn};
 
 use super::{
-    ResponseInfo, ServerContext, default_tls_server_config, is_unrecoverable_socket_error,
-    reap_tasks, request_handler::RequestHandler, response_handler::ResponseHandler,
-    sanitize_src_address,
+    ClientIdentity, ResponseInfo, ServerContext, default_tls_server_config,
+    is_unrecoverable_socket_error, reap_tasks, request_handler::RequestHandler,
+    response_handler::ResponseHandler, sanitize_src_address,
 };
 use crate::{
     net::{
@@ -71,6 +71,7 @@ pub(super) async fn handle_h2_with_acceptor(
 ) -> Result<(), NetError> {
     let dns_hostname: Option<Arc<str>> = dns_hostname.map(|n| n.into());
     let http_endpoint: Arc<str> = Arc::from(http_endpoint);
+    let provider = tls_acceptor.config().crypto_provider().clone();
     debug!("registered https: {listener:?}");
 
     let mut inner_join_set = JoinSet::new();
@@ -103,6 +104,7 @@ pub(super) async fn handle_h2_with_acceptor(
         let tls_acceptor = tls_acceptor.clone();
         let dns_hostname = dns_hostname.clone();
         let http_endpoint = http_endpoint.clone();
+        let provider = provider.clone();
         inner_join_set.spawn(async move {
             debug!("starting HTTPS request from: {src_addr}");
 
@@ -121,9 +123,22 @@ pub(super) async fn handle_h2_with_acceptor(
                     return;
                 }
             };
-            debug!("accepted HTTPS request from: {src_addr}");
-
-            h2_handler(tls_stream, src_addr, dns_hostname, http_endpoint, cx).await;
+            let client = tls_stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|chain| cx.identify(chain, &provider));
+            debug!(?client, "accepted HTTPS request from: {src_addr}");
+
+            h2_handler(
+                tls_stream,
+                src_addr,
+                client,
+                dns_hostname,
+                http_endpoint,
+                cx,
+            )
+            .await;
         });
 
         reap_tasks(&mut inner_join_set);
@@ -139,6 +154,7 @@ pub(super) async fn handle_h2_with_acceptor(
 pub(crate) async fn h2_handler(
     io: impl AsyncRead + AsyncWrite + Unpin,
     src_addr: SocketAddr,
+    client: Option<ClientIdentity>,
     dns_hostname: Option<Arc<str>>,
     http_endpoint: Arc<str>,
     cx: Arc<ServerContext<impl RequestHandler>>,
@@ -179,6 +195,7 @@ pub(crate) async fn h2_handler(
         let cx = cx.clone();
         let dns_hostname = dns_hostname.clone();
         let http_endpoint = http_endpoint.clone();
+        let client = client.clone();
         let responder = HttpsResponseHandle(Arc::new(Mutex::new(respond)));
         tokio::spawn(async move {
             let body = match h2::message_from(dns_hostname, http_endpoint, request).await {
@@ -189,8 +206,15 @@ pub(crate) async fn h2_handler(
                 }
             };
 
-            cx.handle_request(body.freeze(), src_addr, Protocol::Https, responder)
-                .await
+            cx.handle_request(
+                body.freeze(),
+                src_addr,
+                Protocol::Https,
+                client,
+                None,
+                responder,
+            )
+            .await
         });
 
         // we'll continue handling requests from here.
@@ -16,7 +16,7 @@ use tokio::{net, task::JoinSet};
 use tracing::{debug, warn};
 
 use super::{
-    ResponseInfo, ServerContext, reap_tasks, request_handler::RequestHandler,
+    ClientIdentity, ResponseInfo, ServerContext, reap_tasks, request_handler::RequestHandler,
     response_handler::ResponseHandler, sanitize_src_address,
 };
 use crate::{
@@ -24,6 +24,7 @@ use crate::{
         NetError,
         h3::h3_server::{H3Connection, H3Server},
         http::{self, Version},
+        tls::default_provider,
         xfer::Protocol,
     },
     proto::rr::Record,
@@ -86,7 +87,10 @@ pub(super) async fn handle_h3_with_server(
             debug!("starting h3 stream request from: {src_addr}");
 
             // TODO: need to consider timeout of total connect...
-            let result = h3_handler(streams, src_addr, dns_hostname, cx).await;
+            let client = streams
+                .peer_certificates()
+                .and_then(|chain| cx.identify(chain, &default_provider()));
+            let result = h3_handler(streams, src_addr, client, dns_hostname, cx).await;
 
             if let Err(error) = result {
                 warn!(%error, %src_addr, "h3 stream processing failed")
@@ -102,6 +106,7 @@ pub(super) async fn handle_h3_with_server(
 pub(crate) async fn h3_handler(
     mut connection: H3Connection,
     src_addr: SocketAddr,
+    client: Option<ClientIdentity>,
     _dns_hostname: Option<Arc<str>>,
     cx: Arc<ServerContext<impl RequestHandler>>,
 ) -> Result<(), NetError> {
@@ -142,10 +147,11 @@ pub(crate) async fn h3_handler(
         );
 
         let cx = cx.clone();
+        let client = client.clone();
         let stream = Arc::new(Mutex::new(stream));
         let responder = H3ResponseHandle(stream.clone());
         tokio::spawn(async move {
-            cx.handle_request(request, src_addr, Protocol::H3, responder)
+            cx.handle_request(request, src_addr, Protocol::H3, client, None, responder)
                 .await
         });
 
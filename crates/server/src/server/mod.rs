@@ -7,10 +7,12 @@
 
 //! `Server` component for hosting a domain name servers operations.
 
+#[cfg(feature = "dnstap")]
+use std::time::SystemTime;
 use std::{
     fmt, io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
-    sync::Arc,
+    sync::{Arc, OnceLock},
     time::Duration,
 };
 
@@ -18,7 +20,9 @@ use bytes::Bytes;
 use futures_util::StreamExt;
 use ipnet::IpNet;
 #[cfg(feature = "__tls")]
-use rustls::{ServerConfig, server::ResolvesServerCert};
+use rustls::{
+    ServerConfig, crypto::CryptoProvider, pki_types::CertificateDer, server::ResolvesServerCert,
+};
 #[cfg(feature = "__tls")]
 use tokio::time::timeout;
 use tokio::{net, task::JoinSet};
@@ -29,6 +33,8 @@ use tracing::{debug, info, warn};
 
 #[cfg(feature = "metrics")]
 use crate::metrics::ResponseHandlerMetrics;
+#[cfg(feature = "dnstap")]
+use crate::net::dnstap::{Dnstap, DnstapMessage, DnstapMessageType};
 #[cfg(feature = "__h3")]
 use crate::net::h3::h3_server::H3Server;
 #[cfg(feature = "__quic")]
@@ -55,12 +61,22 @@ use crate::{
     zone_handler::MessageResponseBuilder,
 };
 
+#[cfg(feature = "__tls")]
+mod cert_resolver;
+#[cfg(feature = "__tls")]
+pub use cert_resolver::SniCertResolver;
+mod client_auth;
 #[cfg(feature = "__https")]
 mod h2_handler;
 #[cfg(feature = "__h3")]
 mod h3_handler;
+mod proxy_protocol;
+use proxy_protocol::TrustedProxies;
 #[cfg(feature = "__quic")]
 mod quic_handler;
+#[cfg(feature = "__tls")]
+pub use client_auth::FingerprintClientCertVerifier;
+pub use client_auth::{CertFingerprint, ClientAuth, ClientIdentity, IdentityPattern};
 mod request_handler;
 pub use request_handler::{Request, RequestHandler, RequestInfo, ResponseInfo};
 mod response_handler;
@@ -73,6 +89,7 @@ pub use timeout_stream::TimeoutStream;
 pub struct Server<T: RequestHandler> {
     context: Arc<ServerContext<T>>,
     join_set: JoinSet<Result<(), NetError>>,
+    trusted_proxies: Option<TrustedProxies>,
 }
 
 impl<T: RequestHandler> Server<T> {
@@ -96,15 +113,55 @@ impl<T: RequestHandler> Server<T> {
                 handler,
                 access,
                 shutdown: CancellationToken::new(),
+                client_auth: OnceLock::new(),
+                #[cfg(feature = "dnstap")]
+                dnstap: OnceLock::new(),
             }),
             join_set: JoinSet::new(),
+            trusted_proxies: None,
+        }
+    }
+
+    /// Accept the HAProxy PROXY protocol from the given networks
+    ///
+    /// Peers in these networks must start each TCP and TLS connection with a v1 or v2 PROXY
+    /// header, and each UDP datagram with a v2 header, the source address of which is then used
+    /// as the client address for access control and by the [`RequestHandler`]. Responses are
+    /// still sent to the proxy. Peers outside these networks are treated as direct clients.
+    ///
+    /// This applies to UDP sockets, TCP and TLS listeners registered after this call.
+    pub fn set_trusted_proxies(&mut self, networks: impl IntoIterator<Item = IpNet>) {
+        let trusted = TrustedProxies::new(networks);
+        self.trusted_proxies = (!trusted.is_empty()).then_some(trusted);
+    }
+
+    /// Identify clients by their TLS certificate, and allow or deny them access by identity
+    ///
+    /// Without this, clients are identified by the fingerprint of their certificate only. This
+    /// applies to all listeners, and can only be set once.
+    pub fn set_client_auth(&mut self, client_auth: ClientAuth) {
+        if self.context.client_auth.set(client_auth).is_err() {
+            warn!("client authentication is already set");
+        }
+    }
+
+    /// Log the queries received from clients and the responses sent to them to dnstap
+    ///
+    /// This applies to all listeners, and can only be set once.
+    #[cfg(feature = "dnstap")]
+    pub fn set_dnstap(&mut self, dnstap: Dnstap) {
+        if self.context.dnstap.set(dnstap).is_err() {
+            warn!("dnstap output is already set");
         }
     }
 
     /// Register a UDP socket. Should be bound before calling this function.
     pub fn register_socket(&mut self, socket: net::UdpSocket) {
-        self.join_set
-            .spawn(handle_udp(socket, self.context.clone()));
+        self.join_set.spawn(handle_udp(
+            socket,
+            self.trusted_proxies.clone(),
+            self.context.clone(),
+        ));
     }
 
     /// Register a TcpListener to the Server. This should already be bound to either an IPv6 or an
@@ -130,6 +187,7 @@ impl<T: RequestHandler> Server<T> {
             listener,
             timeout,
             response_buffer_size,
+            self.trusted_proxies.clone(),
             self.context.clone(),
         ));
     }
@@ -161,6 +219,7 @@ impl<T: RequestHandler> Server<T> {
             listener,
             tls_config,
             handshake_timeout,
+            self.trusted_proxies.clone(),
             self.context.clone(),
         ));
         Ok(())
@@ -288,13 +347,16 @@ impl<T: RequestHandler> Server<T> {
     pub fn register_quic_listener(
         &mut self,
         socket: net::UdpSocket,
-        // TODO: need to set a timeout between requests.
-        _timeout: Duration,
+        timeout: Duration,
         server_cert_resolver: Arc<dyn ResolvesServerCert>,
     ) -> io::Result<()> {
         let cx = self.context.clone();
-        self.join_set
-            .spawn(quic_handler::handle_quic(socket, server_cert_resolver, cx));
+        self.join_set.spawn(quic_handler::handle_quic(
+            socket,
+            timeout,
+            server_cert_resolver,
+            cx,
+        ));
         Ok(())
     }
 
@@ -320,14 +382,14 @@ impl<T: RequestHandler> Server<T> {
     pub fn register_quic_listener_and_tls_config(
         &mut self,
         socket: net::UdpSocket,
-        // TODO: need to set a timeout between requests.
-        _timeout: Duration,
+        timeout: Duration,
         tls_config: Arc<ServerConfig>,
     ) -> Result<(), NetError> {
         let cx = self.context.clone();
 
         self.join_set.spawn(quic_handler::handle_quic_with_server(
             QuicServer::with_socket_and_tls_config(socket, tls_config)?,
+            timeout,
             cx,
         ));
         Ok(())
@@ -438,6 +500,7 @@ impl<T: RequestHandler> Server<T> {
 
 async fn handle_udp(
     socket: net::UdpSocket,
+    trusted_proxies: Option<TrustedProxies>,
     cx: Arc<ServerContext<impl RequestHandler>>,
 ) -> Result<(), NetError> {
     debug!("registering udp: {:?}", socket);
@@ -481,11 +544,34 @@ async fn handle_udp(
             continue;
         }
 
+        let (message, client_addr) = match &trusted_proxies {
+            Some(trusted) if trusted.contains(src_addr.ip()) => {
+                let (message, src_addr) = message.into_parts();
+                match proxy_protocol::strip_v2(&message) {
+                    Ok((payload, client_addr)) => {
+                        (SerialMessage::new(payload.to_vec(), src_addr), client_addr)
+                    }
+                    Err(error) => {
+                        debug!(%src_addr, %error, "invalid PROXY header (UDP)");
+                        continue;
+                    }
+                }
+            }
+            _ => (message, None),
+        };
+
         let cx = cx.clone();
         let stream_handle = stream_handle.with_remote_addr(src_addr);
         inner_join_set.spawn(async move {
-            cx.handle_raw_request(message, Protocol::Udp, stream_handle)
-                .await;
+            cx.handle_raw_request(
+                message,
+                Protocol::Udp,
+                client_addr,
+                None,
+                None,
+                stream_handle,
+            )
+            .await;
         });
 
         reap_tasks(&mut inner_join_set);
@@ -503,6 +589,7 @@ async fn handle_tcp(
     listener: net::TcpListener,
     timeout: Duration,
     response_buffer_size: usize,
+    trusted_proxies: Option<TrustedProxies>,
     cx: Arc<ServerContext<impl RequestHandler>>,
 ) -> Result<(), NetError> {
     debug!("register tcp: {listener:?}");
@@ -536,8 +623,20 @@ async fn handle_tcp(
 
         // and spawn to the io_loop
         let cx = cx.clone();
+        let proxied = trusted_proxies
+            .as_ref()
+            .is_some_and(|trusted| trusted.contains(src_addr.ip()));
         inner_join_set.spawn(async move {
             debug!(%src_addr, "accepted TCP request");
+            let mut tcp_stream = tcp_stream;
+            let client_addr = match read_proxy_header(&mut tcp_stream, proxied, timeout).await {
+                Ok(client_addr) => client_addr,
+                Err(error) => {
+                    debug!(%src_addr, %error, "invalid PROXY header (TCP)");
+                    return;
+                }
+            };
+
             // take the created stream...
             let (buf_stream, stream_handle) = TcpStream::from_stream_with_buffer_size(
                 AsyncIoTokioAsStd(tcp_stream),
@@ -545,6 +644,7 @@ async fn handle_tcp(
                 response_buffer_size,
             );
             let mut timeout_stream = TimeoutStream::new(buf_stream, timeout);
+            let idle_timeout = (!timeout.is_zero()).then_some(timeout);
 
             while let Some(message) = timeout_stream.next().await {
                 let message = match message {
@@ -557,8 +657,15 @@ async fn handle_tcp(
                 };
 
                 // we don't spawn here to limit clients from getting too many resources
-                cx.handle_raw_request(message, Protocol::Tcp, stream_handle.clone())
-                    .await;
+                cx.handle_raw_request(
+                    message,
+                    Protocol::Tcp,
+                    client_addr,
+                    None,
+                    idle_timeout,
+                    stream_handle.clone(),
+                )
+                .await;
             }
         });
 
@@ -577,9 +684,11 @@ async fn handle_tls(
     listener: net::TcpListener,
     tls_config: Arc<ServerConfig>,
     handshake_timeout: Duration,
+    trusted_proxies: Option<TrustedProxies>,
     cx: Arc<ServerContext<impl RequestHandler>>,
 ) -> Result<(), NetError> {
     debug!(?listener, "registered tls");
+    let provider = tls_config.crypto_provider().clone();
     let tls_acceptor = TlsAcceptor::from(tls_config);
 
     let mut inner_join_set = JoinSet::new();
@@ -612,10 +721,25 @@ async fn handle_tls(
 
         let cx = cx.clone();
         let tls_acceptor = tls_acceptor.clone();
+        let provider = provider.clone();
+        let proxied = trusted_proxies
+            .as_ref()
+            .is_some_and(|trusted| trusted.contains(src_addr.ip()));
         // kick out to a different task immediately, let them do the TLS handshake
         inner_join_set.spawn(async move {
             debug!(%src_addr, "starting TLS request");
 
+            // the PROXY header precedes the TLS handshake
+            let mut tcp_stream = tcp_stream;
+            let client_addr =
+                match read_proxy_header(&mut tcp_stream, proxied, handshake_timeout).await {
+                    Ok(client_addr) => client_addr,
+                    Err(error) => {
+                        debug!(%src_addr, %error, "invalid PROXY header (TLS)");
+                        return;
+                    }
+                };
+
             // perform the TLS
             let Ok(tls_stream) = timeout(handshake_timeout, tls_acceptor.accept(tcp_stream)).await
             else {
@@ -624,15 +748,23 @@ async fn handle_tls(
             };
 
             let tls_stream = match tls_stream {
-                Ok(tls_stream) => AsyncIoTokioAsStd(tls_stream),
+                Ok(tls_stream) => tls_stream,
                 Err(error) => {
                     debug!(%src_addr, %error, "tls handshake error");
                     return;
                 }
             };
-            debug!(%src_addr, "accepted TLS request");
+
+            let client = tls_stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|chain| cx.identify(chain, &provider));
+            let tls_stream = AsyncIoTokioAsStd(tls_stream);
+            debug!(%src_addr, ?client, "accepted TLS request");
             let (buf_stream, stream_handle) = tls_from_stream(tls_stream, src_addr);
             let mut timeout_stream = TimeoutStream::new(buf_stream, handshake_timeout);
+            let idle_timeout = (!handshake_timeout.is_zero()).then_some(handshake_timeout);
             while let Some(message) = timeout_stream.next().await {
                 let message = match message {
                     Ok(message) => message,
@@ -647,8 +779,15 @@ async fn handle_tls(
                     }
                 };
 
-                cx.handle_raw_request(message, Protocol::Tls, stream_handle.clone())
-                    .await;
+                cx.handle_raw_request(
+                    message,
+                    Protocol::Tls,
+                    client_addr,
+                    client.clone(),
+                    idle_timeout,
+                    stream_handle.clone(),
+                )
+                .await;
             }
         });
 
@@ -662,6 +801,26 @@ async fn handle_tls(
     }
 }
 
+/// Read the PROXY header of connections from trusted proxies, within `timeout` if not zero
+async fn read_proxy_header(
+    tcp_stream: &mut net::TcpStream,
+    proxied: bool,
+    timeout: Duration,
+) -> io::Result<Option<SocketAddr>> {
+    if !proxied {
+        return Ok(None);
+    }
+
+    let header = proxy_protocol::read_header(tcp_stream);
+    if timeout.is_zero() {
+        return header.await;
+    }
+
+    tokio::time::timeout(timeout, header)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "timeout reading PROXY header"))?
+}
+
 /// Reap finished tasks from a `JoinSet`, without awaiting or blocking.
 fn reap_tasks(join_set: &mut JoinSet<()>) {
     while join_set.try_join_next().is_some() {}
@@ -684,24 +843,85 @@ pub fn default_tls_server_config(
     Ok(config)
 }
 
+/// Construct a `ServerConfig` like [`default_tls_server_config`] that also requests an optional
+/// client certificate
+///
+/// The client certificate is not validated against any CA during the handshake, the client is
+/// identified by its [`CertFingerprint`], or by the names of its certificate if verified by the
+/// [`ClientAuth`] of the server, see [`Request::client_identity`] and
+/// [`FingerprintClientCertVerifier`].
+#[cfg(feature = "__tls")]
+pub fn client_cert_tls_server_config(
+    protocol: &[u8],
+    server_cert_resolver: Arc<dyn ResolvesServerCert>,
+) -> io::Result<ServerConfig> {
+    let provider = default_provider();
+    let verifier = FingerprintClientCertVerifier::new(provider.clone());
+    let mut config = ServerConfig::builder_with_provider(Arc::new(provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| io::Error::other(format!("error creating TLS acceptor: {e}")))?
+        .with_client_cert_verifier(Arc::new(verifier))
+        .with_cert_resolver(server_cert_resolver);
+
+    config.alpn_protocols = vec![protocol.to_vec()];
+
+    Ok(config)
+}
+
 struct ServerContext<T> {
     handler: T,
     access: AccessControl,
     shutdown: CancellationToken,
+    client_auth: OnceLock<ClientAuth>,
+    #[cfg(feature = "dnstap")]
+    dnstap: OnceLock<Dnstap>,
 }
 
 impl<T: RequestHandler> ServerContext<T> {
+    /// Identify the client that authenticated with the certificate `chain`
+    #[cfg(feature = "__tls")]
+    fn identify(
+        &self,
+        chain: &[CertificateDer<'_>],
+        provider: &CryptoProvider,
+    ) -> Option<ClientIdentity> {
+        match self.client_auth.get() {
+            Some(client_auth) => client_auth.identify(chain, provider),
+            None => Some(CertFingerprint::from_cert(chain.first()?, provider)?.into()),
+        }
+    }
+
+    /// Returns true if the client is allowed access, by its identity or else by its address
+    fn allow(&self, ip: IpAddr, client: Option<&ClientIdentity>) -> bool {
+        self.client_auth
+            .get()
+            .and_then(|client_auth| client_auth.access(client))
+            .unwrap_or_else(|| self.access.allow(ip))
+    }
+
+    /// Handle a request received from `message.addr()`, on behalf of `client_addr` if relayed by a
+    /// proxy
     async fn handle_raw_request(
         &self,
         message: SerialMessage,
         protocol: Protocol,
+        client_addr: Option<SocketAddr>,
+        client: Option<ClientIdentity>,
+        idle_timeout: Option<Duration>,
         response_handler: BufDnsStreamHandle,
     ) {
         let (message, src_addr) = message.into_parts();
         let response_handler = ResponseHandle::new(src_addr, response_handler, protocol);
 
-        self.handle_request(Bytes::from(message), src_addr, protocol, response_handler)
-            .await;
+        self.handle_request(
+            Bytes::from(message),
+            client_addr.unwrap_or(src_addr),
+            protocol,
+            client,
+            idle_timeout,
+            response_handler,
+        )
+        .await;
     }
 
     async fn handle_request(
@@ -709,6 +929,8 @@ impl<T: RequestHandler> ServerContext<T> {
         message_bytes: Bytes,
         src_addr: SocketAddr,
         protocol: Protocol,
+        client: Option<ClientIdentity>,
+        idle_timeout: Option<Duration>,
         response_handler: impl ResponseHandler,
     ) {
         let mut decoder = BinDecoder::new(&message_bytes);
@@ -755,7 +977,7 @@ impl<T: RequestHandler> ServerContext<T> {
             }
         };
 
-        if !self.access.allow(src_addr.ip()) {
+        if !self.allow(src_addr.ip(), client.as_ref()) {
             info!(
                 "request:Refused src:{proto}://{addr}#{port}",
                 proto = protocol,
@@ -785,6 +1007,8 @@ impl<T: RequestHandler> ServerContext<T> {
                 raw: message_bytes,
                 src: src_addr,
                 protocol,
+                client,
+                idle_timeout,
             },
             Err(error) => {
                 error_response_handler(
@@ -833,6 +1057,13 @@ impl<T: RequestHandler> ServerContext<T> {
         );
 
         // The reporter will handle making sure to log the result of the request
+        #[cfg(feature = "dnstap")]
+        let tap = self
+            .dnstap
+            .get()
+            .filter(|dnstap| dnstap.sample(query.name()))
+            .map(|dnstap| ResponseTap::query(dnstap, &request));
+
         let reporter = ReportingResponseHandler {
             request_meta: request.metadata,
             query: Some(query.clone()),
@@ -841,6 +1072,8 @@ impl<T: RequestHandler> ServerContext<T> {
             handler: response_handler,
             #[cfg(feature = "metrics")]
             metrics: ResponseHandlerMetrics::default(),
+            #[cfg(feature = "dnstap")]
+            tap,
         };
 
         self.handler
@@ -881,6 +1114,8 @@ async fn error_response_handler(
         handler: response_handler,
         #[cfg(feature = "metrics")]
         metrics: ResponseHandlerMetrics::default(),
+        #[cfg(feature = "dnstap")]
+        tap: None,
     };
 
     let response = match queries.as_ref() {
@@ -906,6 +1141,8 @@ pub(super) struct ReportingResponseHandler<R: ResponseHandler> {
     handler: R,
     #[cfg(feature = "metrics")]
     metrics: ResponseHandlerMetrics,
+    #[cfg(feature = "dnstap")]
+    tap: Option<ResponseTap>,
 }
 
 #[async_trait::async_trait]
@@ -921,6 +1158,12 @@ impl<R: ResponseHandler> ResponseHandler for ReportingResponseHandler<R> {
             impl Iterator<Item = &'a Record> + Send + 'a,
         >,
     ) -> Result<ResponseInfo, NetError> {
+        #[cfg(feature = "dnstap")]
+        let response = match self.tap.take() {
+            Some(tap) => response.with_dnstap(tap),
+            None => response,
+        };
+
         let response_info = self.handler.send_response(response).await?;
 
         let id = self.request_meta.id;
@@ -967,6 +1210,37 @@ impl<R: ResponseHandler> ResponseHandler for ReportingResponseHandler<R> {
     }
 }
 
+/// A client query logged to dnstap, awaiting its response
+#[cfg(feature = "dnstap")]
+#[derive(Clone, Debug)]
+pub(crate) struct ResponseTap {
+    dnstap: Dnstap,
+    message: DnstapMessage,
+}
+
+#[cfg(feature = "dnstap")]
+impl ResponseTap {
+    fn query(dnstap: &Dnstap, request: &Request) -> Self {
+        let mut message = DnstapMessage::new(DnstapMessageType::ClientQuery, request.protocol());
+        message.query_address = Some(request.src());
+        message.query_time = Some(SystemTime::now());
+        message.query_message = Some(request.as_slice().to_vec());
+        dnstap.log(&message);
+
+        message.message_type = DnstapMessageType::ClientResponse;
+        Self {
+            dnstap: dnstap.clone(),
+            message,
+        }
+    }
+
+    pub(crate) fn response(mut self, bytes: &[u8]) {
+        self.message.response_time = Some(SystemTime::now());
+        self.message.response_message = Some(bytes.to_vec());
+        self.dnstap.log(&self.message);
+    }
+}
+
 /// Checks if the IP address is safe for returning messages
 ///
 /// Examples of unsafe addresses are any with a port of `0`
@@ -1017,6 +1291,8 @@ fn is_unrecoverable_socket_error(err: &io::Error) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::net::runtime::Time;
+    use crate::proto::op::{Message, Query};
     use crate::zone_handler::Catalog;
     use futures_util::future;
     #[cfg(feature = "__tls")]
@@ -1026,7 +1302,9 @@ mod tests {
     };
     use std::net::SocketAddr;
     use test_support::subscribe;
+    use tokio::io::AsyncWriteExt;
     use tokio::net::{TcpListener, UdpSocket};
+    use tokio::sync::mpsc;
     use tokio::time::timeout;
 
     #[tokio::test]
@@ -1063,6 +1341,215 @@ mod tests {
         endpoints.rebind_all().await;
     }
 
+    #[tokio::test]
+    async fn test_proxy_protocol() {
+        subscribe();
+
+        struct SrcHandler(mpsc::UnboundedSender<SocketAddr>);
+
+        #[async_trait::async_trait]
+        impl RequestHandler for SrcHandler {
+            async fn handle_request<R: ResponseHandler, T: Time>(
+                &self,
+                request: &Request,
+                _response_handle: R,
+            ) {
+                self.0.send(request.src()).unwrap();
+            }
+        }
+
+        // the proxy itself is denied, only the clients it relays for are allowed
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let localhost = [IpNet::from(IpAddr::from([127, 0, 0, 1]))];
+        let mut server = Server::with_access(SrcHandler(tx), localhost, []);
+        server.set_trusted_proxies(localhost);
+
+        let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let udp_addr = udp.local_addr().unwrap();
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let tcp_addr = tcp.local_addr().unwrap();
+        server.register_socket(udp);
+        server.register_listener(tcp, Duration::from_secs(1), 32);
+
+        let mut query = Message::query();
+        query.add_query(Query::root());
+        let query = query.to_vec().unwrap();
+
+        let mut stream = net::TcpStream::connect(tcp_addr).await.unwrap();
+        let mut request = b"PROXY TCP4 192.0.2.1 127.0.0.1 4242 53\r\n".to_vec();
+        request.extend_from_slice(&(query.len() as u16).to_be_bytes());
+        request.extend_from_slice(&query);
+        stream.write_all(&request).await.unwrap();
+        assert_eq!(
+            rx.recv().await,
+            Some(SocketAddr::from(([192, 0, 2, 1], 4242)))
+        );
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut datagram = b"\r\n\r\n\0\r\nQUIT\n\x21\x12\x00\x0c".to_vec();
+        datagram.extend_from_slice(&[192, 0, 2, 2, 127, 0, 0, 1, 0x10, 0x92, 0, 53]);
+        datagram.extend_from_slice(&query);
+        socket.send_to(&datagram, udp_addr).await.unwrap();
+        assert_eq!(
+            rx.recv().await,
+            Some(SocketAddr::from(([192, 0, 2, 2], 4242)))
+        );
+
+        // without a header, requests from the proxy are dropped
+        socket.send_to(&query, udp_addr).await.unwrap();
+        assert!(
+            timeout(Duration::from_millis(100), rx.recv())
+                .await
+                .is_err()
+        );
+
+        server.shutdown_gracefully().await.unwrap();
+    }
+
+    #[cfg(feature = "dnstap")]
+    #[tokio::test]
+    async fn test_dnstap() {
+        use tokio::io::AsyncReadExt;
+
+        subscribe();
+
+        let (writer, mut reader) = tokio::io::duplex(65_536);
+        let (dnstap, background) = Dnstap::builder().to_writer(writer);
+        let background = tokio::spawn(background);
+
+        let mut server = Server::new(Catalog::new());
+        server.set_dnstap(dnstap);
+        let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let udp_addr = udp.local_addr().unwrap();
+        server.register_socket(udp);
+
+        let mut query = Message::query();
+        query.add_query(Query::root());
+        let query = query.to_vec().unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        socket.send_to(&query, udp_addr).await.unwrap();
+        let mut response = [0; 512];
+        let (len, _) = timeout(Duration::from_secs(5), socket.recv_from(&mut response))
+            .await
+            .expect("timed out waiting for the response")
+            .unwrap();
+        let response = &response[..len];
+
+        server.shutdown_gracefully().await.unwrap();
+        drop(server);
+        timeout(Duration::from_secs(5), background)
+            .await
+            .expect("timed out waiting for the dnstap writer")
+            .unwrap()
+            .unwrap();
+
+        let mut stream = Vec::new();
+        reader.read_to_end(&mut stream).await.unwrap();
+        let messages = data_frames(&stream)
+            .into_iter()
+            .map(|frame| {
+                let Some(Field::Bytes(message)) = field(frame, 14) else {
+                    panic!("missing dnstap message");
+                };
+                let Some(Field::Varint(message_type)) = field(message, 1) else {
+                    panic!("missing dnstap message type");
+                };
+                let bytes = |number| match field(message, number) {
+                    Some(Field::Bytes(bytes)) => Some(bytes),
+                    _ => None,
+                };
+                (message_type, bytes(10), bytes(14))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            [
+                (
+                    u64::from(DnstapMessageType::ClientQuery),
+                    Some(&query[..]),
+                    None
+                ),
+                (
+                    u64::from(DnstapMessageType::ClientResponse),
+                    Some(&query[..]),
+                    Some(response)
+                ),
+            ]
+        );
+    }
+
+    /// The data frames of a Frame Streams stream, skipping the control frames
+    #[cfg(feature = "dnstap")]
+    fn data_frames(mut stream: &[u8]) -> Vec<&[u8]> {
+        let mut frames = Vec::new();
+        let len = |stream: &mut &[u8]| {
+            let (len, rest) = stream.split_at(4);
+            *stream = rest;
+            u32::from_be_bytes(len.try_into().unwrap()) as usize
+        };
+        while !stream.is_empty() {
+            match len(&mut stream) {
+                // control frames have an escape sequence and their own length
+                0 => {
+                    let control = len(&mut stream);
+                    stream = &stream[control..];
+                }
+                data => {
+                    frames.push(&stream[..data]);
+                    stream = &stream[data..];
+                }
+            }
+        }
+        frames
+    }
+
+    #[cfg(feature = "dnstap")]
+    #[derive(Debug)]
+    enum Field<'a> {
+        Varint(u64),
+        Bytes(&'a [u8]),
+    }
+
+    /// The first field `number` of a protobuf message, if any
+    #[cfg(feature = "dnstap")]
+    fn field(mut message: &[u8], number: u64) -> Option<Field<'_>> {
+        fn varint(buf: &mut &[u8]) -> u64 {
+            let mut value = 0;
+            for shift in (0..64).step_by(7) {
+                let (byte, rest) = buf.split_first().unwrap();
+                *buf = rest;
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            value
+        }
+
+        while !message.is_empty() {
+            let key = varint(&mut message);
+            let field = match key & 0x07 {
+                0 => Field::Varint(varint(&mut message)),
+                2 => {
+                    let len = varint(&mut message) as usize;
+                    let (bytes, rest) = message.split_at(len);
+                    message = rest;
+                    Field::Bytes(bytes)
+                }
+                5 => {
+                    message = &message[4..];
+                    continue;
+                }
+                wire_type => panic!("unexpected wire type {wire_type}"),
+            };
+            if key >> 3 == number {
+                return Some(field);
+            }
+        }
+        None
+    }
+
     #[test]
     fn test_sanitize_src_addr() {
         // ipv4 tests
@@ -0,0 +1,258 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HAProxy PROXY protocol, see <https://www.haproxy.org/download/3.0/doc/proxy-protocol.txt>
+//!
+//! Load balancers and TLS terminators prepend a PROXY header to connections, or to each datagram
+//! for UDP, carrying the address of the client they relay for. Both the text (v1) and binary (v2)
+//! formats are accepted on stream listeners, only v2 on UDP.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str;
+
+use ipnet::IpNet;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Signature starting v2 headers
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// Length of the fixed part of v2 headers
+const V2_HEADER_LEN: usize = 16;
+/// Maximum length of v1 headers, including the CRLF
+const V1_MAX_LEN: usize = 107;
+
+/// Networks of the proxies trusted to send PROXY headers
+#[derive(Clone, Debug)]
+pub(super) struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    pub(super) fn new(networks: impl IntoIterator<Item = IpNet>) -> Self {
+        Self(networks.into_iter().collect())
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(super) fn contains(&self, addr: IpAddr) -> bool {
+        self.0.iter().any(|network| network.contains(&addr))
+    }
+}
+
+/// Reads the PROXY header at the start of `stream`
+///
+/// Returns the source address carried by the header, or `None` for headers without an address,
+/// i.e. health checks of the proxy itself (`LOCAL` and `UNKNOWN`). Exactly the header is
+/// consumed from the stream.
+pub(super) async fn read_header<S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> io::Result<Option<SocketAddr>> {
+    // both formats are longer than the v2 signature, the shortest v1 header being
+    // "PROXY UNKNOWN\r\n"
+    let mut header = vec![0; V2_SIGNATURE.len()];
+    stream.read_exact(&mut header).await?;
+
+    if header == V2_SIGNATURE {
+        header.resize(V2_HEADER_LEN, 0);
+        stream.read_exact(&mut header[V2_SIGNATURE.len()..]).await?;
+        let len = usize::from(u16::from_be_bytes([header[14], header[15]]));
+        header.resize(V2_HEADER_LEN + len, 0);
+        stream.read_exact(&mut header[V2_HEADER_LEN..]).await?;
+        return parse_v2(&header).map(|(_, addr)| addr);
+    }
+
+    if !header.starts_with(b"PROXY ") {
+        return Err(invalid("missing PROXY header"));
+    }
+
+    while !header.ends_with(b"\r\n") {
+        if header.len() == V1_MAX_LEN {
+            return Err(invalid("PROXY v1 header too long"));
+        }
+        header.push(stream.read_u8().await?);
+    }
+
+    parse_v1(&header)
+}
+
+/// Strips the v2 PROXY header from a datagram
+///
+/// Returns the payload following the header and the source address carried by the header.
+pub(super) fn strip_v2(datagram: &[u8]) -> io::Result<(&[u8], Option<SocketAddr>)> {
+    let (len, addr) = parse_v2(datagram)?;
+    Ok((&datagram[len..], addr))
+}
+
+/// Parses a complete v1 header, ending with CRLF
+fn parse_v1(header: &[u8]) -> io::Result<Option<SocketAddr>> {
+    let line = header
+        .strip_suffix(b"\r\n")
+        .and_then(|line| str::from_utf8(line).ok())
+        .ok_or_else(|| invalid("invalid PROXY v1 header"))?;
+
+    let mut fields = line.split(' ');
+    if fields.next() != Some("PROXY") {
+        return Err(invalid("invalid PROXY v1 header"));
+    }
+
+    match fields.next() {
+        Some("TCP4" | "TCP6") => {}
+        // the rest of the line is to be ignored for unknown protocols
+        Some("UNKNOWN") => return Ok(None),
+        _ => return Err(invalid("unsupported PROXY v1 protocol")),
+    }
+
+    let (Some(src), Some(_dst), Some(src_port), Some(_dst_port), None) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    ) else {
+        return Err(invalid("invalid PROXY v1 header"));
+    };
+
+    let ip = src
+        .parse::<IpAddr>()
+        .map_err(|_| invalid("invalid PROXY v1 source address"))?;
+    let port = src_port
+        .parse::<u16>()
+        .map_err(|_| invalid("invalid PROXY v1 source port"))?;
+    Ok(Some(SocketAddr::new(ip, port)))
+}
+
+/// Parses a v2 header at the start of `buf`, returns the length of the header and the source
+/// address it carries
+fn parse_v2(buf: &[u8]) -> io::Result<(usize, Option<SocketAddr>)> {
+    if buf.len() < V2_HEADER_LEN || buf[..V2_SIGNATURE.len()] != V2_SIGNATURE {
+        return Err(invalid("missing PROXY v2 header"));
+    }
+
+    let version_command = buf[12];
+    let family = buf[13];
+    let len = V2_HEADER_LEN + usize::from(u16::from_be_bytes([buf[14], buf[15]]));
+    let addresses = buf
+        .get(V2_HEADER_LEN..len)
+        .ok_or_else(|| invalid("truncated PROXY v2 header"))?;
+
+    if version_command >> 4 != 2 {
+        return Err(invalid("unsupported PROXY protocol version"));
+    }
+
+    match version_command & 0x0F {
+        // LOCAL, connections established by the proxy itself
+        0x0 => return Ok((len, None)),
+        // PROXY
+        0x1 => {}
+        _ => return Err(invalid("unsupported PROXY v2 command")),
+    }
+
+    // the high nibble is the address family, the low nibble the transport protocol
+    let addr = match (family >> 4, addresses) {
+        (0x1, [a, b, c, d, _, _, _, _, p1, p2, ..]) => Some(SocketAddr::new(
+            Ipv4Addr::new(*a, *b, *c, *d).into(),
+            u16::from_be_bytes([*p1, *p2]),
+        )),
+        (0x2, _) if addresses.len() >= 36 => {
+            let src = <[u8; 16]>::try_from(&addresses[..16]).expect("slice has 16 bytes");
+            Some(SocketAddr::new(
+                Ipv6Addr::from(src).into(),
+                u16::from_be_bytes([addresses[32], addresses[33]]),
+            ))
+        }
+        (0x1 | 0x2, _) => return Err(invalid("truncated PROXY v2 addresses")),
+        // unspecified or UNIX addresses, which do not identify a client
+        _ => None,
+    };
+
+    Ok((len, addr))
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2_header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(0x20 | command);
+        header.push(family);
+        header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+        header.extend_from_slice(addresses);
+        header
+    }
+
+    #[tokio::test]
+    async fn test_read_v1() {
+        let mut stream: &[u8] = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 53\r\n\x00\x1c";
+        let addr = read_header(&mut stream).await.unwrap();
+        assert_eq!(addr, Some("192.0.2.1:56324".parse().unwrap()));
+        // the DNS message following the header is left in the stream
+        assert_eq!(stream, b"\x00\x1c");
+
+        let mut stream: &[u8] = b"PROXY TCP6 2001:db8::1 2001:db8::2 4242 853\r\n";
+        let addr = read_header(&mut stream).await.unwrap();
+        assert_eq!(addr, Some("[2001:db8::1]:4242".parse().unwrap()));
+
+        let mut stream: &[u8] = b"PROXY UNKNOWN\r\n";
+        assert_eq!(read_header(&mut stream).await.unwrap(), None);
+
+        let mut stream: &[u8] = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324\r\n";
+        assert!(read_header(&mut stream).await.is_err());
+
+        let mut stream: &[u8] = b"\x00\x1c\x12\x34\x01\x00\x00\x01\x00\x00\x00\x00";
+        assert!(read_header(&mut stream).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_v2() {
+        let mut header = v2_header(
+            0x1,
+            0x11,
+            &[192, 0, 2, 1, 198, 51, 100, 1, 0xdc, 0x04, 0, 53],
+        );
+        header.extend_from_slice(b"\x00\x1c");
+        let mut stream = header.as_slice();
+        let addr = read_header(&mut stream).await.unwrap();
+        assert_eq!(addr, Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(stream, b"\x00\x1c");
+
+        let header = v2_header(0x0, 0x00, &[]);
+        let mut stream = header.as_slice();
+        assert_eq!(read_header(&mut stream).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_strip_v2() {
+        let mut addresses = Vec::new();
+        addresses.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        addresses.extend_from_slice(&"2001:db8::2".parse::<Ipv6Addr>().unwrap().octets());
+        addresses.extend_from_slice(&[0x10, 0x92, 0, 53]);
+        // TLVs are ignored
+        addresses.extend_from_slice(&[0x04, 0x00, 0x01, 0x00]);
+        let mut datagram = v2_header(0x1, 0x22, &addresses);
+        datagram.extend_from_slice(b"message");
+
+        let (payload, addr) = strip_v2(&datagram).unwrap();
+        assert_eq!(payload, b"message");
+        assert_eq!(addr, Some("[2001:db8::1]:4242".parse().unwrap()));
+
+        let datagram = v2_header(0x1, 0x21, &addresses[..20]);
+        assert!(strip_v2(&datagram).is_err());
+        assert!(strip_v2(b"message").is_err());
+    }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let trusted = TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]);
+        assert!(trusted.contains("10.1.2.3".parse().unwrap()));
+        assert!(!trusted.contains("192.0.2.1".parse().unwrap()));
+    }
+}
@@ -5,7 +5,7 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use bytes::Bytes;
 use futures_util::lock::Mutex;
@@ -14,30 +14,41 @@ use tokio::{net, task::JoinSet};
 use tracing::{debug, warn};
 
 use super::{
-    ResponseInfo, ServerContext, reap_tasks, request_handler::RequestHandler,
+    ClientIdentity, ResponseInfo, ServerContext, reap_tasks, request_handler::RequestHandler,
     response_handler::ResponseHandler, sanitize_src_address,
 };
 use crate::{
     net::{
         NetError,
         quic::{DoqErrorCode, QuicServer, QuicStream, QuicStreams},
+        tls::default_provider,
         xfer::Protocol,
     },
-    proto::rr::Record,
+    proto::{
+        op::Message,
+        rr::{Record, rdata::opt::EdnsCode},
+    },
     zone_handler::MessageResponse,
 };
 
 pub(super) async fn handle_quic(
     socket: net::UdpSocket,
+    idle_timeout: Duration,
     server_cert_resolver: Arc<dyn ResolvesServerCert>,
     cx: Arc<ServerContext<impl RequestHandler>>,
 ) -> Result<(), NetError> {
     debug!(?socket, "registered quic");
-    handle_quic_with_server(QuicServer::with_socket(socket, server_cert_resolver)?, cx).await
+    handle_quic_with_server(
+        QuicServer::with_socket(socket, server_cert_resolver)?,
+        idle_timeout,
+        cx,
+    )
+    .await
 }
 
 pub(super) async fn handle_quic_with_server(
     mut server: QuicServer,
+    idle_timeout: Duration,
     cx: Arc<ServerContext<impl RequestHandler>>,
 ) -> Result<(), NetError> {
     let mut inner_join_set = JoinSet::new();
@@ -73,7 +84,10 @@ pub(super) async fn handle_quic_with_server(
             debug!("starting quic stream request from: {src_addr}");
 
             // TODO: need to consider timeout of total connect...
-            let result = quic_handler(streams, src_addr, cx).await;
+            let client = streams
+                .peer_certificates()
+                .and_then(|chain| cx.identify(&chain, &default_provider()));
+            let result = quic_handler(streams, src_addr, client, idle_timeout, cx).await;
 
             if let Err(error) = result {
                 warn!(%error, %src_addr, "quic stream processing failed")
@@ -89,10 +103,14 @@ pub(super) async fn handle_quic_with_server(
 pub(crate) async fn quic_handler(
     mut quic_streams: QuicStreams,
     src_addr: SocketAddr,
+    client: Option<ClientIdentity>,
+    idle_timeout: Duration,
     cx: Arc<ServerContext<impl RequestHandler>>,
 ) -> Result<(), NetError> {
     // TODO: we should make this configurable
     let mut max_requests = 100u32;
+    // a zero timeout disables the idle timeout, as for TCP
+    let idle_timeout = (!idle_timeout.is_zero()).then_some(idle_timeout);
 
     // Accept all inbound quic streams sent over the connection.
     loop {
@@ -111,6 +129,10 @@ pub(crate) async fn quic_handler(
                 // A graceful shutdown was initiated.
                 break;
             },
+            _ = idle(idle_timeout) => {
+                debug!(%src_addr, "quic connection idle timeout");
+                break;
+            },
         };
 
         let request = request_stream.receive_bytes().await?;
@@ -120,11 +142,25 @@ pub(crate) async fn quic_handler(
             request.len()
         );
 
+        // the edns-tcp-keepalive option must not be used over DoQ, see RFC 9250 section 5.5.2
+        if has_keepalive(&request) {
+            warn!(%src_addr, "edns-tcp-keepalive option received, closing quic connection");
+            quic_streams.close(DoqErrorCode::ProtocolError);
+            break;
+        }
+
         let stream = Arc::new(Mutex::new(request_stream));
         let responder = QuicResponseHandle(stream.clone());
 
-        cx.handle_request(request.freeze(), src_addr, Protocol::Quic, responder)
-            .await;
+        cx.handle_request(
+            request.freeze(),
+            src_addr,
+            Protocol::Quic,
+            client.clone(),
+            idle_timeout,
+            responder,
+        )
+        .await;
 
         max_requests -= 1;
         if max_requests == 0 {
@@ -139,6 +175,22 @@ pub(crate) async fn quic_handler(
     Ok(())
 }
 
+/// Whether the query in `bytes` has an edns-tcp-keepalive option
+fn has_keepalive(bytes: &[u8]) -> bool {
+    Message::from_vec(bytes)
+        .ok()
+        .and_then(|message| message.edns)
+        .is_some_and(|edns| edns.option(EdnsCode::Keepalive).is_some())
+}
+
+/// Completes after `timeout`, never if there is none
+async fn idle(timeout: Option<Duration>) {
+    match timeout {
+        Some(timeout) => tokio::time::sleep(timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
 #[derive(Clone)]
 struct QuicResponseHandle(Arc<Mutex<QuicStream>>);
 
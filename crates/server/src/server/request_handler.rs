@@ -7,7 +7,7 @@
 
 //! Request Handler for incoming requests
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 use bytes::Bytes;
 
@@ -22,7 +22,7 @@ use crate::{
         },
         serialize::binary::{BinDecodable, BinDecoder},
     },
-    server::ResponseHandler,
+    server::{CertFingerprint, ClientIdentity, ResponseHandler},
 };
 
 /// An incoming request to the DNS catalog
@@ -35,6 +35,10 @@ pub struct Request {
     pub(super) src: SocketAddr,
     /// Protocol of the request
     pub(super) protocol: Protocol,
+    /// Identity of the client, if it authenticated with a certificate over TLS
+    pub(super) client: Option<ClientIdentity>,
+    /// Idle timeout of the connection the request arrived on, for connection-oriented protocols
+    pub(super) idle_timeout: Option<Duration>,
 }
 
 impl Request {
@@ -51,6 +55,8 @@ impl Request {
             raw: Bytes::from(raw),
             src,
             protocol,
+            client: None,
+            idle_timeout: None,
         })
     }
 
@@ -70,6 +76,8 @@ impl Request {
             raw: Bytes::from(encoded),
             src,
             protocol,
+            client: None,
+            idle_timeout: None,
         })
     }
 
@@ -82,6 +90,7 @@ impl Request {
             protocol: self.protocol,
             metadata: &self.message.metadata,
             query: &self.message.queries,
+            client: self.client.as_ref(),
         }
     }
 
@@ -95,6 +104,42 @@ impl Request {
         self.protocol
     }
 
+    /// Fingerprint of the certificate the client authenticated with over TLS, if any
+    pub fn client_cert(&self) -> Option<&CertFingerprint> {
+        self.client.as_ref().map(ClientIdentity::fingerprint)
+    }
+
+    /// Sets the fingerprint of the certificate the client authenticated with
+    pub fn with_client_cert(self, client_cert: CertFingerprint) -> Self {
+        self.with_client_identity(ClientIdentity::new(client_cert))
+    }
+
+    /// Identity of the client, if it authenticated with a certificate over TLS
+    pub fn client_identity(&self) -> Option<&ClientIdentity> {
+        self.client.as_ref()
+    }
+
+    /// Sets the identity of the client
+    pub fn with_client_identity(mut self, client: ClientIdentity) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Idle timeout of the connection the request arrived on, if the protocol is
+    /// connection-oriented
+    ///
+    /// This is the timeout advertised with the edns-tcp-keepalive option, see
+    /// [RFC 7828](https://www.rfc-editor.org/rfc/rfc7828).
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Sets the idle timeout of the connection the request arrived on
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
     /// The raw bytes of the request
     pub fn as_slice(&self) -> &[u8] {
         &self.raw
@@ -122,6 +167,8 @@ pub struct RequestInfo<'a> {
     pub metadata: &'a Metadata,
     /// The query from the request
     pub query: &'a LowerQuery,
+    /// Identity of the client, if it authenticated with a certificate over TLS
+    pub client: Option<&'a ClientIdentity>,
 }
 
 impl<'a> RequestInfo<'a> {
@@ -144,8 +191,15 @@ impl<'a> RequestInfo<'a> {
             protocol,
             metadata,
             query,
+            client: None,
         }
     }
+
+    /// Sets the identity of the client
+    pub fn with_client(mut self, client: &'a ClientIdentity) -> Self {
+        self.client = Some(client);
+        self
+    }
 }
 
 /// Information about the response sent for a request
@@ -0,0 +1,1049 @@
+// Copyright 2015-2026 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Catalog zones, [RFC 9432](https://www.rfc-editor.org/rfc/rfc9432), for provisioning member
+//! zones at runtime
+//!
+//! A catalog zone is a regular DNS zone which lists member zones as `PTR` records below the
+//! `zones` label of the catalog zone:
+//!
+//! ```text
+//! catalog.invalid.                     0 IN SOA invalid. invalid. 1 3600 600 2147483646 0
+//! catalog.invalid.                     0 IN NS  invalid.
+//! version.catalog.invalid.             0 IN TXT "2"
+//! uniq1.zones.catalog.invalid.         0 IN PTR example.com.
+//! group.uniq1.zones.catalog.invalid.   0 IN TXT "customers"
+//! ```
+//!
+//! The [`CatalogZoneConsumer`] reads the catalog zone, either from a file or via a zone transfer,
+//! and inserts or removes zone handlers for the member zones in the [`Catalog`] as members come
+//! and go.
+//!
+//! [`Catalog`]: crate::zone_handler::Catalog
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, btree_map::Entry},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+#[cfg(feature = "__tls")]
+use rustls::{
+    RootCertStore,
+    pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject},
+};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+#[cfg(feature = "__tls")]
+use crate::net::tls::{tls_client_connect, xot_client_config};
+#[cfg(feature = "__dnssec")]
+use crate::store::{in_memory::verify_zonemd, tsig::TsigKeyConfig};
+use crate::{
+    net::{
+        NetError,
+        client::{Client, ClientHandle},
+        runtime::TokioRuntimeProvider,
+        tcp::TcpClientStream,
+        xfer::DnsMultiplexer,
+    },
+    proto::rr::{LowerName, Name, RData, RecordSet, RecordType, RrKey},
+    store::{
+        file::{FileConfig, FileZoneHandler, rooted},
+        in_memory::{InMemoryZoneHandler, zone_from_path},
+    },
+    zone_handler::{AxfrPolicy, CatalogHandle, ZoneHandler, ZoneType},
+};
+
+/// The only catalog zone schema version supported by this implementation
+pub const CATALOG_ZONE_VERSION: &str = "2";
+
+/// The refresh interval used when neither the configuration nor the catalog zone SOA provide one
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// A parsed catalog zone
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogZone {
+    origin: Name,
+    serial: u32,
+    refresh: u32,
+    members: BTreeMap<LowerName, CatalogMember>,
+}
+
+impl CatalogZone {
+    /// Extracts the catalog zone information from the records of a zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the name of the catalog zone
+    /// * `records` - the records of the catalog zone, as loaded from a zone file or transferred
+    ///   from a primary
+    pub fn from_records(
+        origin: &Name,
+        records: &BTreeMap<RrKey, RecordSet>,
+    ) -> Result<Self, CatalogZoneError> {
+        let lower_origin = LowerName::new(origin);
+        let soa = records
+            .get(&RrKey::new(lower_origin.clone(), RecordType::SOA))
+            .and_then(|rrset| match &rrset.records_without_rrsigs().next()?.data {
+                RData::SOA(soa) => Some(soa),
+                _ => None,
+            })
+            .ok_or(CatalogZoneError::MissingSoa)?;
+
+        // RFC 9432, Section 4.2.1: the schema version MUST be present, and catalog zones with an
+        // unsupported version MUST NOT be processed.
+        let version_name = LowerName::new(&origin.prepend_label("version")?);
+        let mut versions = records
+            .get(&RrKey::new(version_name, RecordType::TXT))
+            .into_iter()
+            .flat_map(|rrset| rrset.records_without_rrsigs())
+            .filter_map(|record| match &record.data {
+                RData::TXT(txt) => Some(
+                    txt.txt_data
+                        .iter()
+                        .map(|data| String::from_utf8_lossy(data))
+                        .collect::<String>(),
+                ),
+                _ => None,
+            });
+        match (versions.next(), versions.next()) {
+            (Some(version), None) if version == CATALOG_ZONE_VERSION => {}
+            (Some(version), None) => return Err(CatalogZoneError::UnsupportedVersion(version)),
+            (Some(_), Some(_)) => return Err(CatalogZoneError::MultipleVersions),
+            (None, _) => return Err(CatalogZoneError::MissingVersion),
+        }
+
+        let zones = origin.prepend_label("zones")?;
+        let member_labels = zones.num_labels() + 1;
+        let mut members = BTreeMap::<LowerName, CatalogMember>::new();
+        let mut member_zones = HashMap::<LowerName, LowerName>::new();
+
+        // Member zones, RFC 9432, Section 4.1
+        for rrset in records.values() {
+            let name = rrset.name();
+            if rrset.record_type() != RecordType::PTR
+                || name.num_labels() != member_labels
+                || !zones.zone_of(name)
+            {
+                continue;
+            }
+
+            let mut ptrs = rrset
+                .records_without_rrsigs()
+                .filter_map(|record| match &record.data {
+                    RData::PTR(ptr) => Some(ptr.0.clone()),
+                    _ => None,
+                });
+            let (Some(zone), None) = (ptrs.next(), ptrs.next()) else {
+                warn!(member = %name, "ignoring catalog zone member with multiple PTR records");
+                continue;
+            };
+
+            let id = LowerName::new(name);
+            match member_zones.get(&LowerName::new(&zone)) {
+                Some(existing) => {
+                    warn!(
+                        %zone,
+                        member = %name,
+                        existing = %existing,
+                        "ignoring member zone listed more than once in the catalog zone"
+                    );
+                    continue;
+                }
+                None => member_zones.insert(LowerName::new(&zone), id.clone()),
+            };
+
+            members.insert(
+                id,
+                CatalogMember {
+                    id: name.clone(),
+                    zone,
+                    group: None,
+                    coo: None,
+                },
+            );
+        }
+
+        // Member zone properties, RFC 9432, Section 4.4
+        for rrset in records.values() {
+            let name = rrset.name();
+            if name.num_labels() != member_labels + 1 || !zones.zone_of(name) {
+                continue;
+            }
+
+            let id = LowerName::new(&name.base_name());
+            let Some(member) = members.get_mut(&id) else {
+                continue;
+            };
+
+            let property = name.iter().next().map(|label| label.to_ascii_lowercase());
+            match (property.as_deref(), rrset.record_type()) {
+                (Some(b"coo"), RecordType::PTR) => {
+                    member.coo =
+                        rrset
+                            .records_without_rrsigs()
+                            .find_map(|record| match &record.data {
+                                RData::PTR(ptr) => Some(ptr.0.clone()),
+                                _ => None,
+                            });
+                }
+                (Some(b"group"), RecordType::TXT) => {
+                    member.group = rrset
+                        .records_without_rrsigs()
+                        .find_map(|record| match &record.data {
+                            RData::TXT(txt) => Some(
+                                txt.txt_data
+                                    .iter()
+                                    .map(|data| String::from_utf8_lossy(data))
+                                    .collect::<String>(),
+                            ),
+                            _ => None,
+                        });
+                }
+                _ => debug!(%name, "ignoring unknown catalog zone member property"),
+            }
+        }
+
+        Ok(Self {
+            origin: origin.clone(),
+            serial: soa.serial,
+            refresh: soa.refresh.max(0) as u32,
+            members,
+        })
+    }
+
+    /// The name of the catalog zone
+    pub fn origin(&self) -> &Name {
+        &self.origin
+    }
+
+    /// The serial number of the catalog zone
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    /// The refresh interval from the catalog zone SOA
+    pub fn refresh(&self) -> Duration {
+        Duration::from_secs(u64::from(self.refresh))
+    }
+
+    /// The member zones listed in the catalog zone
+    pub fn members(&self) -> impl Iterator<Item = &CatalogMember> + '_ {
+        self.members.values()
+    }
+}
+
+/// A member zone of a catalog zone
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CatalogMember {
+    /// The member node, i.e. `<unique-id>.zones.<catalog zone>`
+    pub id: Name,
+    /// The name of the member zone
+    pub zone: Name,
+    /// The group property of the member zone, if any
+    pub group: Option<String>,
+    /// The change of ownership property, i.e. the catalog zone the member zone is migrating to
+    pub coo: Option<Name>,
+}
+
+/// Creates the zone handlers for member zones of a catalog zone
+#[async_trait::async_trait]
+pub trait MemberZoneProvider: Send + Sync {
+    /// Load the zone handlers serving the given member zone
+    async fn load(
+        &self,
+        member: &CatalogMember,
+    ) -> Result<Vec<Arc<dyn ZoneHandler>>, CatalogZoneError>;
+}
+
+/// Keeps the zones in a [`Catalog`](crate::zone_handler::Catalog) in sync with a catalog zone
+pub struct CatalogZoneConsumer<M = MemberZoneConfig> {
+    origin: Name,
+    source: CatalogZoneSource,
+    refresh_interval: Option<Duration>,
+    catalog: CatalogHandle,
+    provider: M,
+    /// The member zones added by this consumer, and the member node they were listed under
+    members: HashMap<LowerName, LowerName>,
+    /// The member zones which failed to load, these are retried on every refresh
+    failed: HashSet<LowerName>,
+    current: Option<CatalogZone>,
+}
+
+impl CatalogZoneConsumer {
+    /// Create a consumer for the catalog zone `origin` from the specified configuration
+    ///
+    /// Relative paths in the configuration are resolved against `root_dir`.
+    pub fn from_config(
+        origin: Name,
+        config: &CatalogZoneConfig,
+        root_dir: Option<&Path>,
+        catalog: CatalogHandle,
+    ) -> Self {
+        let source = match &config.source {
+            CatalogZoneSource::File { path } => CatalogZoneSource::File {
+                path: rooted(path, root_dir),
+            },
+            CatalogZoneSource::Transfer(transfer) => {
+                CatalogZoneSource::Transfer(Box::new(transfer.rooted(root_dir)))
+            }
+        };
+
+        let mut provider = config.members.clone();
+        match &mut provider.source {
+            MemberZoneSource::File { directory } => *directory = rooted(directory, root_dir),
+            MemberZoneSource::Transfer(transfer) => **transfer = transfer.rooted(root_dir),
+        }
+
+        Self::new(origin, source, catalog, provider)
+            .with_refresh_interval(config.refresh_interval.map(Duration::from_secs))
+    }
+}
+
+impl<M: MemberZoneProvider> CatalogZoneConsumer<M> {
+    /// Create a new consumer
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the name of the catalog zone
+    /// * `source` - where the catalog zone is read from
+    /// * `catalog` - handle to the catalog the member zones are inserted into
+    /// * `provider` - creates the zone handlers for member zones
+    pub fn new(
+        origin: Name,
+        source: CatalogZoneSource,
+        catalog: CatalogHandle,
+        provider: M,
+    ) -> Self {
+        Self {
+            origin,
+            source,
+            refresh_interval: None,
+            catalog,
+            provider,
+            members: HashMap::new(),
+            failed: HashSet::new(),
+            current: None,
+        }
+    }
+
+    /// Override the refresh interval of the catalog zone SOA
+    pub fn with_refresh_interval(mut self, refresh_interval: Option<Duration>) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// The last successfully loaded catalog zone
+    pub fn catalog_zone(&self) -> Option<&CatalogZone> {
+        self.current.as_ref()
+    }
+
+    /// Reload the catalog zone, adding and removing member zones in the catalog as needed
+    pub async fn refresh(&mut self) -> Result<(), CatalogZoneError> {
+        let records = match &self.source {
+            CatalogZoneSource::File { path } => {
+                zone_from_path(path, self.origin.clone()).map_err(CatalogZoneError::Load)?
+            }
+            CatalogZoneSource::Transfer(transfer) => {
+                transfer.transfer_zone(self.origin.clone()).await?
+            }
+        };
+
+        let catalog_zone = CatalogZone::from_records(&self.origin, &records)?;
+        if self.failed.is_empty()
+            && self
+                .current
+                .as_ref()
+                .is_some_and(|current| current.serial == catalog_zone.serial)
+        {
+            debug!(origin = %self.origin, serial = catalog_zone.serial, "catalog zone unchanged");
+            return Ok(());
+        }
+
+        // Remove member zones which are no longer listed, or which are now listed under a
+        // different member node, which resets the member zone (RFC 9432, Section 5.3)
+        let removed = self
+            .members
+            .iter()
+            .filter(|(zone, id)| {
+                catalog_zone
+                    .members
+                    .get(*id)
+                    .is_none_or(|member| LowerName::new(&member.zone) != **zone)
+            })
+            .map(|(zone, _)| zone.clone())
+            .collect::<Vec<_>>();
+        for zone in removed {
+            info!(origin = %self.origin, %zone, "removing catalog zone member");
+            self.members.remove(&zone);
+            self.catalog.remove(&zone);
+        }
+
+        self.failed.clear();
+        for (id, member) in &catalog_zone.members {
+            let zone = LowerName::new(&member.zone);
+            if self.members.contains_key(&zone) {
+                continue;
+            }
+
+            if self.catalog.contains(&zone) {
+                warn!(
+                    origin = %self.origin,
+                    %zone,
+                    "catalog zone member is already configured, not adding it"
+                );
+                continue;
+            }
+
+            match self.provider.load(member).await {
+                Ok(handlers) => {
+                    info!(origin = %self.origin, %zone, "adding catalog zone member");
+                    self.catalog.upsert(zone.clone(), handlers);
+                    self.members.insert(zone, id.clone());
+                }
+                // Leave the member out of `self.members`, so the next refresh tries again
+                Err(error) => {
+                    warn!(
+                        origin = %self.origin,
+                        %zone,
+                        %error,
+                        "failed to load catalog zone member"
+                    );
+                    self.failed.insert(zone);
+                }
+            }
+        }
+
+        self.current = Some(catalog_zone);
+        Ok(())
+    }
+
+    /// Periodically refresh the catalog zone, this never returns
+    pub async fn run(mut self) {
+        loop {
+            if let Err(error) = self.refresh().await {
+                warn!(origin = %self.origin, %error, "failed to refresh catalog zone");
+            }
+
+            let interval = self
+                .refresh_interval
+                .or_else(|| self.current.as_ref().map(CatalogZone::refresh))
+                .filter(|interval| !interval.is_zero())
+                .unwrap_or(DEFAULT_REFRESH_INTERVAL);
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+/// Configuration for a catalog zone
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogZoneConfig {
+    /// Where the catalog zone is read from
+    pub source: CatalogZoneSource,
+    /// How often to reload the catalog zone, in seconds, defaults to the SOA refresh interval
+    pub refresh_interval: Option<u64>,
+    /// How the member zones are served
+    pub members: MemberZoneConfig,
+}
+
+/// Where a catalog zone is read from
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum CatalogZoneSource {
+    /// A zone file
+    File {
+        /// path to the zone file
+        path: PathBuf,
+    },
+    /// A zone transfer (AXFR) from a primary
+    Transfer(Box<TransferConfig>),
+}
+
+/// Configuration for the member zones of a catalog zone
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MemberZoneConfig {
+    /// The type of the member zones, either `Primary` or `Secondary`
+    pub zone_type: ZoneType,
+    /// The AXFR policy of the member zones
+    #[serde(default)]
+    pub axfr_policy: AxfrPolicy,
+    /// Where the records of the member zones are read from
+    pub source: MemberZoneSource,
+}
+
+/// Where the records of member zones are read from
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum MemberZoneSource {
+    /// Zone files named after the member zone, e.g. `example.com.zone`, in a directory
+    File {
+        /// directory containing the zone files
+        directory: PathBuf,
+    },
+    /// A zone transfer (AXFR) from a primary
+    Transfer(Box<TransferConfig>),
+}
+
+/// Configuration for zone transfers (AXFR) from a primary
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TransferConfig {
+    /// address of the primary server
+    pub primary: SocketAddr,
+    /// TSIG key used to sign the transfer requests and verify the responses of the primary
+    #[cfg(feature = "__dnssec")]
+    #[serde(default)]
+    pub tsig_key: Option<TsigKeyConfig>,
+    /// Transfer the zones over TLS (XoT), instead of plain TCP
+    #[cfg(feature = "__tls")]
+    #[serde(default)]
+    pub tls: Option<TransferTlsConfig>,
+}
+
+impl TransferConfig {
+    /// Resolve the relative paths in the configuration against `root_dir`
+    #[cfg_attr(
+        not(any(feature = "__dnssec", feature = "__tls")),
+        allow(unused_variables)
+    )]
+    fn rooted(&self, root_dir: Option<&Path>) -> Self {
+        #[cfg_attr(not(any(feature = "__dnssec", feature = "__tls")), allow(unused_mut))]
+        let mut config = self.clone();
+
+        #[cfg(feature = "__dnssec")]
+        if let Some(tsig_key) = &mut config.tsig_key {
+            tsig_key.key_file = rooted(&tsig_key.key_file, root_dir);
+        }
+
+        #[cfg(feature = "__tls")]
+        if let Some(tls) = &mut config.tls {
+            tls.ca_file = rooted(&tls.ca_file, root_dir);
+            if let Some(client_auth) = &mut tls.client_auth {
+                client_auth.cert_file = rooted(&client_auth.cert_file, root_dir);
+                client_auth.key_file = rooted(&client_auth.key_file, root_dir);
+            }
+        }
+
+        config
+    }
+
+    /// Transfer all records of the zone `origin` from the primary using AXFR
+    ///
+    /// The transfer runs over TLS if `tls` is configured, and over TCP otherwise. The requests
+    /// are signed with the `tsig_key`, if any, and the signatures of the responses verified.
+    pub async fn transfer_zone(
+        &self,
+        origin: Name,
+    ) -> Result<BTreeMap<RrKey, RecordSet>, CatalogZoneError> {
+        let provider = TokioRuntimeProvider::default();
+
+        #[cfg(feature = "__tls")]
+        if let Some(tls) = &self.tls {
+            let (stream, sender) = tls_client_connect(
+                self.primary,
+                tls.server_name()?,
+                Arc::new(tls.client_config()?),
+                provider,
+            );
+            let multiplexer = self.signed(DnsMultiplexer::new(stream.await?, sender), &origin)?;
+            let (client, bg) = Client::<TokioRuntimeProvider>::from_sender(multiplexer);
+            return transfer_zone(client, tokio::spawn(bg), self.primary, origin).await;
+        }
+
+        let (stream, sender) = TcpClientStream::new(self.primary, None, None, provider);
+        let multiplexer = self.signed(DnsMultiplexer::new(stream.await?, sender), &origin)?;
+        let (client, bg) = Client::<TokioRuntimeProvider>::from_sender(multiplexer);
+        transfer_zone(client, tokio::spawn(bg), self.primary, origin).await
+    }
+
+    /// Sign the requests of the multiplexer with the TSIG key, if configured
+    #[cfg_attr(not(feature = "__dnssec"), allow(unused_variables))]
+    fn signed<S: crate::net::xfer::DnsClientStream>(
+        &self,
+        multiplexer: DnsMultiplexer<S>,
+        origin: &Name,
+    ) -> Result<DnsMultiplexer<S>, CatalogZoneError> {
+        #[cfg(feature = "__dnssec")]
+        if let Some(tsig_key) = &self.tsig_key {
+            let signer = tsig_key
+                .to_signer(origin, None)
+                .map_err(CatalogZoneError::Load)?;
+            return Ok(multiplexer.with_signer(signer));
+        }
+
+        Ok(multiplexer)
+    }
+}
+
+/// TLS configuration for zone transfers over TLS (XoT), see
+/// [RFC 9103](https://www.rfc-editor.org/rfc/rfc9103)
+#[cfg(feature = "__tls")]
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TransferTlsConfig {
+    /// The name in the certificate of the primary
+    pub server_name: String,
+    /// PEM file with the CA certificates the certificate of the primary is verified against
+    pub ca_file: PathBuf,
+    /// The client certificate presented to the primary, if it authenticates secondaries
+    #[serde(default)]
+    pub client_auth: Option<TransferClientAuth>,
+}
+
+#[cfg(feature = "__tls")]
+impl TransferTlsConfig {
+    fn server_name(&self) -> Result<ServerName<'static>, CatalogZoneError> {
+        ServerName::try_from(self.server_name.clone()).map_err(|e| {
+            CatalogZoneError::Load(format!("invalid server name {}: {e}", self.server_name))
+        })
+    }
+
+    fn client_config(&self) -> Result<rustls::ClientConfig, CatalogZoneError> {
+        let mut roots = RootCertStore::empty();
+        let (added, _) = roots.add_parsable_certificates(load_certs(&self.ca_file)?);
+        if added == 0 {
+            return Err(CatalogZoneError::Load(format!(
+                "no CA certificates in {}",
+                self.ca_file.display()
+            )));
+        }
+
+        let client_auth = match &self.client_auth {
+            Some(client_auth) => {
+                let key = PrivateKeyDer::from_pem_file(&client_auth.key_file).map_err(|e| {
+                    CatalogZoneError::Load(format!(
+                        "failed to read private key from {}: {e}",
+                        client_auth.key_file.display()
+                    ))
+                })?;
+                Some((load_certs(&client_auth.cert_file)?, key))
+            }
+            None => None,
+        };
+
+        xot_client_config(roots, client_auth)
+            .map_err(|e| CatalogZoneError::Load(format!("invalid TLS configuration: {e}")))
+    }
+}
+
+/// A client certificate and its private key, both PEM encoded
+#[cfg(feature = "__tls")]
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TransferClientAuth {
+    /// PEM file with the certificate chain
+    pub cert_file: PathBuf,
+    /// PEM file with the private key
+    pub key_file: PathBuf,
+}
+
+#[cfg(feature = "__tls")]
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, CatalogZoneError> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            CatalogZoneError::Load(format!(
+                "failed to read certificates from {}: {e}",
+                path.display()
+            ))
+        })
+}
+
+#[async_trait::async_trait]
+impl MemberZoneProvider for MemberZoneConfig {
+    async fn load(
+        &self,
+        member: &CatalogMember,
+    ) -> Result<Vec<Arc<dyn ZoneHandler>>, CatalogZoneError> {
+        if self.zone_type == ZoneType::External {
+            return Err(CatalogZoneError::Load(
+                "member zones must be primary or secondary zones".to_string(),
+            ));
+        }
+
+        let handler: Arc<dyn ZoneHandler> = match &self.source {
+            MemberZoneSource::File { directory } => {
+                let config = FileConfig {
+                    zone_path: member_zone_path(directory, &member.zone)?,
+                };
+
+                Arc::new(
+                    FileZoneHandler::try_from_config(
+                        member.zone.clone(),
+                        self.zone_type,
+                        self.axfr_policy.clone(),
+                        None,
+                        &config,
+                        #[cfg(feature = "__dnssec")]
+                        None,
+                    )
+                    .map_err(CatalogZoneError::Load)?,
+                )
+            }
+            MemberZoneSource::Transfer(transfer) => {
+                let records = transfer.transfer_zone(member.zone.clone()).await?;
+                Arc::new(
+                    InMemoryZoneHandler::<TokioRuntimeProvider>::new(
+                        member.zone.clone(),
+                        records,
+                        self.zone_type,
+                        self.axfr_policy.clone(),
+                        #[cfg(feature = "__dnssec")]
+                        None,
+                    )
+                    .map_err(CatalogZoneError::Load)?,
+                )
+            }
+        };
+
+        Ok(vec![handler])
+    }
+}
+
+/// The path of the zone file of a member zone, e.g. `example.com.zone`, in `directory`
+///
+/// Member names come from the catalog zone, so only labels made of letters, digits, `-` and `_`
+/// are accepted, anything else could name a file outside of `directory`.
+fn member_zone_path(directory: &Path, zone: &Name) -> Result<PathBuf, CatalogZoneError> {
+    let zone = zone.to_lowercase();
+    if zone.is_root() {
+        return Err(CatalogZoneError::InvalidMemberName(zone));
+    }
+
+    let mut file_name = String::new();
+    for label in zone.iter() {
+        if label.is_empty()
+            || !label
+                .iter()
+                .all(|b| b.is_ascii_alphanumeric() || *b == b'-' || *b == b'_')
+        {
+            return Err(CatalogZoneError::InvalidMemberName(zone));
+        }
+
+        // only ASCII was accepted above
+        file_name.push_str(&String::from_utf8_lossy(label));
+        file_name.push('.');
+    }
+    file_name.push_str("zone");
+
+    Ok(directory.join(file_name))
+}
+
+/// Receive all records of the zone `origin` with AXFR over the connected `client`
+async fn transfer_zone(
+    mut client: Client<TokioRuntimeProvider>,
+    bg: tokio::task::JoinHandle<()>,
+    primary: SocketAddr,
+    origin: Name,
+) -> Result<BTreeMap<RrKey, RecordSet>, CatalogZoneError> {
+    let mut records = BTreeMap::<RrKey, RecordSet>::new();
+    let mut responses = client.zone_transfer(origin.clone(), None);
+    while let Some(response) = responses.next().await {
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                bg.abort();
+                return Err(e.into());
+            }
+        };
+
+        for record in response.into_message().answers {
+            if !origin.zone_of(&record.name) {
+                bg.abort();
+                return Err(CatalogZoneError::Net(NetError::from(format!(
+                    "zone transfer for {origin} contains out of zone record: {}",
+                    record.name
+                ))));
+            }
+
+            match records.entry(RrKey::new(
+                LowerName::new(&record.name),
+                record.record_type(),
+            )) {
+                Entry::Vacant(entry) => {
+                    entry.insert(RecordSet::from(record));
+                }
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().insert(record, 0);
+                }
+            }
+        }
+    }
+
+    bg.abort();
+
+    #[cfg(feature = "__dnssec")]
+    verify_zonemd(&origin, &records)?;
+
+    debug!(%origin, %primary, records = records.len(), "zone transfer complete");
+    Ok(records)
+}
+
+/// An error related to catalog zones
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CatalogZoneError {
+    /// The catalog zone has no SOA record
+    #[error("catalog zone is missing its SOA record")]
+    MissingSoa,
+    /// The catalog zone has no version property
+    #[error("catalog zone is missing the version property")]
+    MissingVersion,
+    /// The catalog zone has more than one version property
+    #[error("catalog zone has more than one version property")]
+    MultipleVersions,
+    /// The catalog zone schema version is not supported
+    #[error("unsupported catalog zone version: {0}")]
+    UnsupportedVersion(String),
+    /// A zone could not be loaded
+    #[error("failed to load zone: {0}")]
+    Load(String),
+    /// The name of a member zone can not be mapped to a zone file
+    #[error("member zone name is not usable as a file name: {0}")]
+    InvalidMemberName(Name),
+    /// A network error occurred while transferring a zone
+    #[error("net error: {0}")]
+    Net(#[from] NetError),
+}
+
+impl From<crate::proto::ProtoError> for CatalogZoneError {
+    fn from(e: crate::proto::ProtoError) -> Self {
+        Self::Net(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use test_support::subscribe;
+
+    use super::*;
+    use crate::proto::rr::{
+        Record,
+        rdata::{PTR, SOA, TXT},
+    };
+    use crate::zone_handler::Catalog;
+
+    fn catalog_records(version: &str, members: &[(&str, &str)]) -> BTreeMap<RrKey, RecordSet> {
+        let origin = Name::from_str("catalog.invalid.").unwrap();
+        let mut records = vec![
+            Record::from_rdata(
+                origin.clone(),
+                0,
+                RData::SOA(SOA::new(
+                    Name::from_str("invalid.").unwrap(),
+                    Name::from_str("invalid.").unwrap(),
+                    7,
+                    60,
+                    600,
+                    2147483646,
+                    0,
+                )),
+            ),
+            Record::from_rdata(
+                Name::from_str("version.catalog.invalid.").unwrap(),
+                0,
+                RData::TXT(TXT::new(vec![version.to_string()])),
+            ),
+        ];
+
+        for (id, zone) in members {
+            records.push(Record::from_rdata(
+                Name::from_str(&format!("{id}.zones.catalog.invalid.")).unwrap(),
+                0,
+                RData::PTR(PTR(Name::from_str(zone).unwrap())),
+            ));
+        }
+
+        let mut map = BTreeMap::<RrKey, RecordSet>::new();
+        for record in records {
+            match map.entry(RrKey::new(
+                LowerName::new(&record.name),
+                record.record_type(),
+            )) {
+                Entry::Vacant(entry) => {
+                    entry.insert(RecordSet::from(record));
+                }
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().insert(record, 0);
+                }
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_parse_members() {
+        let origin = Name::from_str("catalog.invalid.").unwrap();
+        let mut records =
+            catalog_records("2", &[("uniq1", "example.com."), ("uniq2", "example.net.")]);
+        let group = Record::from_rdata(
+            Name::from_str("group.uniq2.zones.catalog.invalid.").unwrap(),
+            0,
+            RData::TXT(TXT::new(vec!["customers".to_string()])),
+        );
+        records.insert(
+            RrKey::new(LowerName::new(&group.name), RecordType::TXT),
+            RecordSet::from(group),
+        );
+
+        let catalog_zone = CatalogZone::from_records(&origin, &records).unwrap();
+        assert_eq!(catalog_zone.serial(), 7);
+        assert_eq!(catalog_zone.refresh(), Duration::from_secs(60));
+
+        let members = catalog_zone.members().collect::<Vec<_>>();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].zone, Name::from_str("example.com.").unwrap());
+        assert_eq!(members[0].group, None);
+        assert_eq!(members[1].zone, Name::from_str("example.net.").unwrap());
+        assert_eq!(members[1].group.as_deref(), Some("customers"));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let origin = Name::from_str("catalog.invalid.").unwrap();
+        let records = catalog_records("1", &[("uniq1", "example.com.")]);
+
+        assert!(matches!(
+            CatalogZone::from_records(&origin, &records),
+            Err(CatalogZoneError::UnsupportedVersion(version)) if version == "1"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_consumer_adds_members() {
+        subscribe();
+
+        let catalog = Catalog::new();
+        let config = CatalogZoneConfig {
+            source: CatalogZoneSource::File {
+                path: PathBuf::from("catalog.invalid.zone"),
+            },
+            refresh_interval: None,
+            members: MemberZoneConfig {
+                zone_type: ZoneType::Primary,
+                axfr_policy: AxfrPolicy::Deny,
+                source: MemberZoneSource::File {
+                    directory: PathBuf::from("."),
+                },
+            },
+        };
+
+        let mut consumer = CatalogZoneConsumer::from_config(
+            Name::from_str("catalog.invalid.").unwrap(),
+            &config,
+            Some(Path::new("../../tests/test-data/test_configs")),
+            catalog.handle(),
+        );
+        consumer
+            .refresh()
+            .await
+            .expect("failed to refresh catalog zone");
+
+        assert_eq!(consumer.catalog_zone().unwrap().serial(), 1);
+        assert!(catalog.contains(&LowerName::from_str("example.com.").unwrap()));
+        assert!(!catalog.contains(&LowerName::from_str("catalog.invalid.").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_consumer_retries_failed_members() {
+        subscribe();
+
+        /// Fails to load the first time, and serves the member zones from files afterwards
+        struct FailOnce {
+            config: MemberZoneConfig,
+            failed: std::sync::atomic::AtomicBool,
+        }
+
+        #[async_trait::async_trait]
+        impl MemberZoneProvider for FailOnce {
+            async fn load(
+                &self,
+                member: &CatalogMember,
+            ) -> Result<Vec<Arc<dyn ZoneHandler>>, CatalogZoneError> {
+                if !self.failed.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                    return Err(CatalogZoneError::Load("unavailable".to_string()));
+                }
+                self.config.load(member).await
+            }
+        }
+
+        let root = Path::new("../../tests/test-data/test_configs");
+        let catalog = Catalog::new();
+        let mut consumer = CatalogZoneConsumer::new(
+            Name::from_str("catalog.invalid.").unwrap(),
+            CatalogZoneSource::File {
+                path: root.join("catalog.invalid.zone"),
+            },
+            catalog.handle(),
+            FailOnce {
+                config: MemberZoneConfig {
+                    zone_type: ZoneType::Primary,
+                    axfr_policy: AxfrPolicy::Deny,
+                    source: MemberZoneSource::File {
+                        directory: root.to_path_buf(),
+                    },
+                },
+                failed: std::sync::atomic::AtomicBool::new(false),
+            },
+        );
+
+        let example = LowerName::from_str("example.com.").unwrap();
+        consumer.refresh().await.unwrap();
+        assert!(!catalog.contains(&example));
+
+        // the serial of the catalog zone is unchanged, the failed member is still retried
+        consumer.refresh().await.unwrap();
+        assert!(catalog.contains(&example));
+    }
+
+    #[test]
+    fn test_member_zone_path() {
+        let directory = Path::new("/zones");
+        assert_eq!(
+            member_zone_path(directory, &Name::from_str("Example.com.").unwrap()).unwrap(),
+            Path::new("/zones/example.com.zone")
+        );
+
+        for labels in [
+            vec!["..", "com"],
+            vec!["a/b", "com"],
+            vec!["..\\..", "com"],
+            vec!["a b", "com"],
+        ] {
+            let name = Name::from_labels(labels.iter().map(|l| l.as_bytes())).unwrap();
+            assert!(matches!(
+                member_zone_path(directory, &name),
+                Err(CatalogZoneError::InvalidMemberName(_))
+            ));
+        }
+        assert!(member_zone_path(directory, &Name::root()).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_member_zone() {
+        let origin = Name::from_str("catalog.invalid.").unwrap();
+        let records = catalog_records("2", &[("uniq1", "example.com."), ("uniq2", "Example.com.")]);
+
+        let catalog_zone = CatalogZone::from_records(&origin, &records).unwrap();
+        let members = catalog_zone.members().collect::<Vec<_>>();
+        assert_eq!(members.len(), 1);
+        assert_eq!(
+            members[0].id,
+            Name::from_str("uniq1.zones.catalog.invalid.").unwrap()
+        );
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Advertisement of the encrypted transports of the server to its clients, see
+//! [RFC 9462 Discovery of Designated Resolvers](https://www.rfc-editor.org/rfc/rfc9462)
+
+use std::net::IpAddr;
+
+use crate::{
+    net::runtime::TokioRuntimeProvider,
+    proto::rr::{
+        Name, RData, Record,
+        rdata::{
+            A, AAAA, SOA, SVCB,
+            svcb::{Alpn, IpHint, SvcParamKey, SvcParamValue},
+        },
+    },
+    store::in_memory::InMemoryZoneHandler,
+    zone_handler::{AxfrPolicy, ZoneType},
+};
+
+/// Time to live of the records
+const TTL: u32 = 300;
+
+/// The encrypted transports of this server, advertised as its designated resolvers
+///
+/// Clients knowing the server by its IP address query the `_dns.resolver.arpa.` SVCB records to
+/// discover the DNS-over-TLS, -HTTPS and -QUIC endpoints they can upgrade to. The certificate of
+/// the server must be valid for the IP addresses clients use for unencrypted queries.
+#[derive(Clone, Debug)]
+pub struct DesignatedResolver {
+    target: Name,
+    addresses: Vec<IpAddr>,
+    endpoints: Vec<Endpoint>,
+}
+
+impl DesignatedResolver {
+    /// Designated resolver reachable by the name `target`
+    ///
+    /// The root name denotes the addresses clients send unencrypted queries to.
+    pub fn new(target: Name) -> Self {
+        Self {
+            target,
+            addresses: Vec::new(),
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Addresses of the target, advertised as hints, unspecified addresses are ignored
+    pub fn with_addresses(mut self, addresses: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.addresses = addresses
+            .into_iter()
+            .filter(|address| !address.is_unspecified())
+            .collect();
+        self
+    }
+
+    /// Advertise DNS-over-TLS on `port`
+    pub fn with_tls(mut self, port: u16) -> Self {
+        self.endpoints.push(Endpoint::new("dot", port, None));
+        self
+    }
+
+    /// Advertise DNS-over-HTTPS over HTTP/2 on `port`, answering queries on `path`
+    pub fn with_https(mut self, port: u16, path: &str) -> Self {
+        self.endpoints.push(Endpoint::new("h2", port, Some(path)));
+        self
+    }
+
+    /// Advertise DNS-over-QUIC on `port`
+    pub fn with_quic(mut self, port: u16) -> Self {
+        self.endpoints.push(Endpoint::new("doq", port, None));
+        self
+    }
+
+    /// Advertise DNS-over-HTTPS over HTTP/3 on `port`, answering queries on `path`
+    pub fn with_h3(mut self, port: u16, path: &str) -> Self {
+        self.endpoints.push(Endpoint::new("h3", port, Some(path)));
+        self
+    }
+
+    /// Whether no transport is advertised
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    /// The `_dns.resolver.arpa.` SVCB records, one per transport in the order they were added
+    pub fn records(&self) -> Vec<Record> {
+        let name = Name::from_ascii("_dns.resolver.arpa.").unwrap();
+        self.endpoints
+            .iter()
+            .zip(1..)
+            .map(|(endpoint, priority)| {
+                let mut params = vec![
+                    (
+                        SvcParamKey::Alpn,
+                        SvcParamValue::Alpn(Alpn(vec![endpoint.alpn.to_owned()])),
+                    ),
+                    (SvcParamKey::Port, SvcParamValue::Port(endpoint.port)),
+                ];
+
+                // hints are meaningless for the root target, which are the addresses in use
+                if !self.target.is_root() {
+                    let v4 = self
+                        .addresses
+                        .iter()
+                        .filter_map(|address| match address {
+                            IpAddr::V4(ip) => Some(A(*ip)),
+                            IpAddr::V6(_) => None,
+                        })
+                        .collect::<Vec<_>>();
+                    if !v4.is_empty() {
+                        params.push((SvcParamKey::Ipv4Hint, SvcParamValue::Ipv4Hint(IpHint(v4))));
+                    }
+
+                    let v6 = self
+                        .addresses
+                        .iter()
+                        .filter_map(|address| match address {
+                            IpAddr::V4(_) => None,
+                            IpAddr::V6(ip) => Some(AAAA(*ip)),
+                        })
+                        .collect::<Vec<_>>();
+                    if !v6.is_empty() {
+                        params.push((SvcParamKey::Ipv6Hint, SvcParamValue::Ipv6Hint(IpHint(v6))));
+                    }
+                }
+
+                if let Some(template) = &endpoint.doh_path {
+                    params.push((
+                        SvcParamKey::DohPath,
+                        SvcParamValue::DohPath(template.clone()),
+                    ));
+                }
+
+                let svcb = SVCB::new(priority, self.target.clone(), params);
+                Record::from_rdata(name.clone(), TTL, RData::SVCB(svcb))
+            })
+            .collect()
+    }
+
+    /// The `resolver.arpa.` zone answering the `_dns.resolver.arpa.` SVCB queries
+    pub fn zone_handler(&self) -> InMemoryZoneHandler<TokioRuntimeProvider> {
+        let origin = Name::from_ascii("resolver.arpa.").unwrap();
+        let mut handler = InMemoryZoneHandler::empty(
+            origin.clone(),
+            ZoneType::Primary,
+            AxfrPolicy::Deny,
+            #[cfg(feature = "__dnssec")]
+            None,
+        );
+
+        let soa = SOA::new(
+            origin.clone(),
+            Name::from_ascii("nobody.invalid.").unwrap(),
+            1,
+            3600,
+            600,
+            86400,
+            TTL,
+        );
+        handler.upsert_mut(Record::from_rdata(origin, TTL, RData::SOA(soa)), 1);
+        for record in self.records() {
+            handler.upsert_mut(record, 1);
+        }
+
+        handler
+    }
+}
+
+/// An encrypted transport of the server
+#[derive(Clone, Debug)]
+struct Endpoint {
+    alpn: &'static str,
+    port: u16,
+    /// URI template of the queries, for DNS-over-HTTPS
+    doh_path: Option<String>,
+}
+
+impl Endpoint {
+    fn new(alpn: &'static str, port: u16, path: Option<&str>) -> Self {
+        Self {
+            alpn,
+            port,
+            doh_path: path.map(|path| format!("{path}{{?dns}}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+    use crate::proto::rr::{LowerName, RecordType};
+    use crate::zone_handler::{LookupControlFlow, LookupOptions, ZoneHandler};
+
+    #[test]
+    fn test_records() {
+        let resolver = DesignatedResolver::new(Name::from_ascii("dns.example.com.").unwrap())
+            .with_addresses([
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53)),
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ])
+            .with_tls(853)
+            .with_https(443, "/dns-query");
+
+        let records = resolver
+            .records()
+            .into_iter()
+            .map(|record| record.data.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            [
+                "1 dns.example.com. alpn=dot, port=853 ipv4hint=192.0.2.53, ipv6hint=::1,",
+                "2 dns.example.com. alpn=h2, port=443 ipv4hint=192.0.2.53, ipv6hint=::1, dohpath=/dns-query{?dns}",
+            ]
+        );
+
+        let root = DesignatedResolver::new(Name::root())
+            .with_addresses([IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53))])
+            .with_quic(853);
+        assert_eq!(root.records()[0].data.to_string(), "1 . alpn=doq, port=853");
+    }
+
+    #[tokio::test]
+    async fn test_zone_handler() {
+        let handler = DesignatedResolver::new(Name::root())
+            .with_tls(853)
+            .zone_handler();
+
+        let name = LowerName::from(Name::from_ascii("_dns.resolver.arpa.").unwrap());
+        let LookupControlFlow::Continue(Ok(lookup)) = handler
+            .lookup(&name, RecordType::SVCB, None, LookupOptions::default())
+            .await
+        else {
+            panic!("expected an answer");
+        };
+        assert_eq!(lookup.iter().count(), 1);
+    }
+}
@@ -16,6 +16,8 @@ use std::sync::Arc;
 use serde::Deserialize;
 use tracing::{debug, info};
 
+#[cfg(feature = "dnstap")]
+use crate::net::dnstap::{Dnstap, UpstreamRole, UpstreamTap};
 #[cfg(feature = "__dnssec")]
 use crate::{dnssec::NxProofKind, proto::dnssec::TrustAnchors, zone_handler::Nsec3QueryInfo};
 use crate::{
@@ -44,6 +46,8 @@ pub struct ForwardZoneHandlerBuilder<P: ConnectionProvider> {
 
     #[cfg(feature = "__dnssec")]
     trust_anchor: Option<Arc<TrustAnchors>>,
+    #[cfg(feature = "dnstap")]
+    dnstap: Option<Dnstap>,
 }
 
 impl<P: ConnectionProvider> ForwardZoneHandlerBuilder<P> {
@@ -63,6 +67,13 @@ impl<P: ConnectionProvider> ForwardZoneHandlerBuilder<P> {
         self
     }
 
+    /// Log the exchanges with the upstream resolvers to dnstap.
+    #[cfg(feature = "dnstap")]
+    pub fn with_dnstap(mut self, dnstap: Dnstap) -> Self {
+        self.dnstap = Some(dnstap);
+        self
+    }
+
     /// Returns a mutable reference to the [`ResolverOpts`].
     pub fn options_mut(&mut self) -> &mut ResolverOpts {
         self.config
@@ -92,6 +103,8 @@ impl<P: ConnectionProvider> ForwardZoneHandlerBuilder<P> {
             runtime,
             #[cfg(feature = "__dnssec")]
             trust_anchor,
+            #[cfg(feature = "dnstap")]
+            dnstap,
         } = self;
         info!(%origin, "loading forwarder config");
 
@@ -139,6 +152,12 @@ impl<P: ConnectionProvider> ForwardZoneHandlerBuilder<P> {
             (None, None) => {}
         }
 
+        #[cfg(feature = "dnstap")]
+        if let Some(dnstap) = dnstap {
+            resolver_builder =
+                resolver_builder.with_dnstap(UpstreamTap::new(dnstap, UpstreamRole::Forwarder));
+        }
+
         *resolver_builder.options_mut() = options;
         let resolver = resolver_builder.build().map_err(|err| err.to_string())?;
 
@@ -187,6 +206,8 @@ impl<P: ConnectionProvider> ForwardZoneHandler<P> {
             runtime,
             #[cfg(feature = "__dnssec")]
             trust_anchor: None,
+            #[cfg(feature = "dnstap")]
+            dnstap: None,
         }
     }
 }
@@ -24,6 +24,10 @@ use crate::{
             DnsSecResult, DnssecSigner, Nsec3HashAlgorithm,
             rdata::{DNSSECRData, NSEC, NSEC3, NSEC3PARAM, RRSIG},
         },
+        rr::rdata::{
+            ZONEMD,
+            zonemd::{HashAlgorithm, Scheme},
+        },
     },
     zone_handler::{LookupError, Nsec3QueryInfo},
 };
@@ -578,8 +582,14 @@ impl InnerInMemory {
         origin: &LowerName,
         dns_class: DNSClass,
         nx_proof_kind: Option<&NxProofKind>,
+        zonemd: Option<HashAlgorithm>,
         signature_inception: OffsetDateTime,
     ) -> DnsSecResult<()> {
+        // a placeholder ZONEMD is needed so that the type is covered by the NSEC(3) of the apex
+        if let Some(hash_algorithm) = zonemd {
+            self.zonemd_placeholder(origin, dns_class, hash_algorithm);
+        }
+
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sure IXFR works properly
         match nx_proof_kind {
@@ -598,7 +608,86 @@ impl InnerInMemory {
         self.increment_soa_serial(origin, dns_class);
 
         // TODO: should we auto sign here? or maybe up a level...
-        self.sign_zone(origin, dns_class, signature_inception)
+        self.sign_zone(origin, dns_class, signature_inception)?;
+
+        // the digest covers the signatures, so it must be computed last
+        match zonemd {
+            Some(hash_algorithm) => {
+                self.zonemd_zone(origin, dns_class, hash_algorithm, signature_inception)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Inserts a placeholder ZONEMD at the apex, unless one is already present
+    #[cfg(feature = "__dnssec")]
+    fn zonemd_placeholder(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        hash_algorithm: HashAlgorithm,
+    ) {
+        let rr_key = RrKey::new(origin.clone(), RecordType::ZONEMD);
+        if self.records.contains_key(&rr_key) {
+            return;
+        }
+
+        let Some(ttl) = self.soa_ttl(origin) else {
+            return;
+        };
+
+        let serial = self.serial(origin);
+        let zonemd = ZONEMD::new(serial, Scheme::Simple, hash_algorithm, Vec::new());
+        let record = Record::from_rdata(origin.into(), ttl, RData::ZONEMD(zonemd));
+        self.upsert(record, serial, dns_class);
+    }
+
+    /// Replaces the ZONEMD at the apex with the digest of the zone, and signs it
+    #[cfg(feature = "__dnssec")]
+    fn zonemd_zone(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        hash_algorithm: HashAlgorithm,
+        inception: OffsetDateTime,
+    ) -> DnsSecResult<()> {
+        let Some(ttl) = self.soa_ttl(origin) else {
+            error!("could not lookup SOA for zone handler: {origin}");
+            return Ok(());
+        };
+
+        debug!("generating zonemd record: {}", origin);
+        let rr_key = RrKey::new(origin.clone(), RecordType::ZONEMD);
+        self.records.remove(&rr_key);
+
+        let serial = self.serial(origin);
+        let zonemd = ZONEMD::from_zone(
+            &Name::from(origin),
+            serial,
+            hash_algorithm,
+            self.records.values().map(|rr_set| &**rr_set),
+        )?;
+        let record = Record::from_rdata(origin.into(), ttl, RData::ZONEMD(zonemd));
+        self.upsert(record, serial, dns_class);
+
+        if let Some(rr_set) = self.records.get_mut(&rr_key) {
+            Self::sign_rrset(
+                Arc::make_mut(rr_set),
+                &self.secure_keys,
+                dns_class,
+                inception,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// The TTL of the SOA of the zone
+    #[cfg(feature = "__dnssec")]
+    fn soa_ttl(&self, origin: &LowerName) -> Option<u32> {
+        self.records
+            .get(&RrKey::new(origin.clone(), RecordType::SOA))
+            .map(|rr_set| rr_set.ttl())
     }
 
     #[cfg(feature = "__dnssec")]
@@ -16,13 +16,20 @@ use std::{
     sync::Arc,
 };
 
+#[cfg(feature = "__dnssec")]
+use std::collections::HashSet;
+
 #[cfg(feature = "__dnssec")]
 use crate::{
     dnssec::NxProofKind,
     net::runtime::Time,
-    proto::dnssec::{
-        DnsSecResult, DnssecSigner,
-        rdata::{DNSKEY, DNSSECRData},
+    proto::{
+        ProtoError,
+        dnssec::{
+            DnsSecResult, DnssecSigner,
+            rdata::{DNSKEY, DNSSECRData},
+        },
+        rr::rdata::zonemd::{HashAlgorithm, Scheme},
     },
     zone_handler::{DnssecZoneHandler, Nsec3QueryInfo},
 };
@@ -62,6 +69,8 @@ pub struct InMemoryZoneHandler<P = TokioRuntimeProvider> {
     inner: RwLock<InnerInMemory>,
     #[cfg(feature = "__dnssec")]
     nx_proof_kind: Option<NxProofKind>,
+    #[cfg(feature = "__dnssec")]
+    zonemd: Option<HashAlgorithm>,
     _phantom: PhantomData<P>,
 }
 
@@ -145,6 +154,8 @@ impl<P: RuntimeProvider + Send + Sync> InMemoryZoneHandler<P> {
 
             #[cfg(feature = "__dnssec")]
             nx_proof_kind,
+            #[cfg(feature = "__dnssec")]
+            zonemd: None,
 
             _phantom: PhantomData,
         }
@@ -155,6 +166,14 @@ impl<P: RuntimeProvider + Send + Sync> InMemoryZoneHandler<P> {
         self.class
     }
 
+    /// Publish a ZONEMD digest of the given hash algorithm whenever the zone is secured
+    ///
+    /// By default no ZONEMD is generated.
+    #[cfg(feature = "__dnssec")]
+    pub fn set_zonemd(&mut self, hash_algorithm: Option<HashAlgorithm>) {
+        self.zonemd = hash_algorithm;
+    }
+
     /// Set the AXFR policy for testing purposes
     #[cfg(any(test, feature = "testing"))]
     pub fn set_axfr_policy(&mut self, policy: AxfrPolicy) {
@@ -279,6 +298,7 @@ impl<P: RuntimeProvider + Send + Sync> InMemoryZoneHandler<P> {
             origin,
             self.class,
             self.nx_proof_kind.as_ref(),
+            self.zonemd,
             Self::current_time()?,
         )
     }
@@ -309,7 +329,7 @@ impl<P: RuntimeProvider + Send + Sync> ZoneHandler for InMemoryZoneHandler<P> {
 
     /// Return the policy for determining if AXFR requests are allowed
     fn axfr_policy(&self) -> AxfrPolicy {
-        self.axfr_policy
+        self.axfr_policy.clone()
     }
 
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
@@ -528,7 +548,7 @@ impl<P: RuntimeProvider + Send + Sync> ZoneHandler for InMemoryZoneHandler<P> {
         let request_info = request.request_info();
         if request_info.query.query_type() == RecordType::AXFR {
             // TODO: support more advanced AXFR options
-            if !matches!(self.axfr_policy, AxfrPolicy::AllowAll) {
+            if !self.axfr_policy.allows(request) {
                 return Some((Err(LookupError::from(ResponseCode::Refused)), None));
             }
         }
@@ -683,6 +703,7 @@ impl<P: RuntimeProvider + Send + Sync> DnssecZoneHandler for InMemoryZoneHandler
             self.origin(),
             self.class,
             self.nx_proof_kind.as_ref(),
+            self.zonemd,
             Self::current_time()?,
         )
     }
@@ -734,7 +755,221 @@ pub(crate) fn zone_from_path(
         .parse()
         .map_err(|e| format!("failed to parse {}: {e:?}", zone_path.display()))?;
 
+    #[cfg(feature = "__dnssec")]
+    verify_zonemd(&origin, &records)
+        .map_err(|e| format!("failed to verify {}: {e}", zone_path.display()))?;
+
     info!("zone file loaded: {origin} with {} records", records.len());
     debug!("zone: {records:#?}");
     Ok(records)
 }
+
+/// Verifies the ZONEMD records at the apex of the zone, as described in
+/// [RFC 8976, section 4](https://tools.ietf.org/html/rfc8976#section-4)
+///
+/// Zones without a ZONEMD, or with only ZONEMD records of unsupported schemes and hash algorithms,
+/// are accepted. Zones with several ZONEMD records of the same scheme and hash algorithm are
+/// rejected. Otherwise, at least one supported ZONEMD must match the digest of the zone.
+///
+/// # Arguments
+///
+/// * `origin` - the apex of the zone
+/// * `records` - all records of the zone, including RRSIGs
+#[cfg(feature = "__dnssec")]
+pub fn verify_zonemd(
+    origin: &Name,
+    records: &BTreeMap<RrKey, RecordSet>,
+) -> Result<(), ProtoError> {
+    let apex = LowerName::new(origin);
+    let Some(zonemd_set) = records.get(&RrKey::new(apex.clone(), RecordType::ZONEMD)) else {
+        return Ok(());
+    };
+
+    let serial = records
+        .get(&RrKey::new(apex, RecordType::SOA))
+        .and_then(|rrset| match &rrset.records_without_rrsigs().next()?.data {
+            RData::SOA(soa) => Some(soa.serial),
+            _ => None,
+        })
+        .ok_or_else(|| ProtoError::from(format!("SOA record must be present: {origin}")))?;
+
+    // a zone with several digests of the same scheme and hash algorithm can't be verified
+    let mut seen = HashSet::new();
+    for record in zonemd_set.records_without_rrsigs() {
+        let RData::ZONEMD(zonemd) = &record.data else {
+            continue;
+        };
+
+        if !seen.insert((zonemd.scheme, zonemd.hash_algorithm)) {
+            return Err(ProtoError::from(format!(
+                "multiple ZONEMD with scheme {} and hash algorithm {} for {origin}",
+                u8::from(zonemd.scheme),
+                u8::from(zonemd.hash_algorithm)
+            )));
+        }
+    }
+
+    let mut result = Ok(());
+    for record in zonemd_set.records_without_rrsigs() {
+        let RData::ZONEMD(zonemd) = &record.data else {
+            continue;
+        };
+
+        if zonemd.scheme != Scheme::Simple
+            || matches!(zonemd.hash_algorithm, HashAlgorithm::Unassigned(_))
+        {
+            warn!("skipping unsupported ZONEMD for {origin}: {zonemd}");
+            continue;
+        }
+
+        match zonemd.verify(origin, serial, records.values()) {
+            Ok(()) => return Ok(()),
+            Err(e) => result = Err(e),
+        }
+    }
+
+    result
+}
+
+#[cfg(all(test, feature = "__dnssec"))]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use super::*;
+    use crate::proto::{
+        dnssec::{SigningKey, crypto::Ed25519SigningKey},
+        rr::rdata::{A, SOA, ZONEMD},
+    };
+
+    #[test]
+    fn test_zonemd_primary_zone() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let mut handler = InMemoryZoneHandler::<TokioRuntimeProvider>::empty(
+            origin.clone(),
+            ZoneType::Primary,
+            AxfrPolicy::Deny,
+            Some(NxProofKind::Nsec),
+        );
+        handler.set_zonemd(Some(HashAlgorithm::SHA384));
+
+        let inner = handler.inner.get_mut();
+        let soa = SOA::new(
+            Name::from_str("ns.example.com.").unwrap(),
+            Name::from_str("hostmaster.example.com.").unwrap(),
+            1,
+            3600,
+            3600,
+            3600,
+            3600,
+        );
+        inner.upsert(
+            Record::from_rdata(origin.clone(), 3600, RData::SOA(soa)),
+            1,
+            DNSClass::IN,
+        );
+        inner.upsert(
+            Record::from_rdata(
+                Name::from_str("www.example.com.").unwrap(),
+                3600,
+                RData::A(A::new(127, 0, 0, 1)),
+            ),
+            1,
+            DNSClass::IN,
+        );
+
+        let key =
+            Ed25519SigningKey::from_pkcs8(&Ed25519SigningKey::generate_pkcs8().unwrap()).unwrap();
+        handler
+            .add_zone_signing_key_mut(DnssecSigner::new(
+                DNSKEY::from_key(&key.to_public_key().unwrap()),
+                Box::new(key),
+                origin.clone(),
+                Duration::from_secs(3600),
+            ))
+            .unwrap();
+        handler.secure_zone_mut().unwrap();
+
+        let inner = handler.inner.get_mut();
+        let mut records = inner
+            .records
+            .iter()
+            .map(|(key, rrset)| (key.clone(), RecordSet::clone(rrset)))
+            .collect::<BTreeMap<_, _>>();
+
+        let apex = LowerName::new(&origin);
+        let zonemd = &records[&RrKey::new(apex.clone(), RecordType::ZONEMD)];
+        assert_eq!(zonemd.records_without_rrsigs().count(), 1);
+        assert!(!zonemd.rrsigs().is_empty());
+
+        let Some(RData::DNSSEC(DNSSECRData::NSEC(nsec))) = records
+            [&RrKey::new(apex, RecordType::NSEC)]
+            .records_without_rrsigs()
+            .next()
+            .map(|r| &r.data)
+        else {
+            panic!("apex NSEC not found");
+        };
+        assert!(nsec.type_bit_maps().any(|t| t == RecordType::ZONEMD));
+
+        verify_zonemd(&origin, &records).expect("ZONEMD should verify");
+
+        // any change to the zone must invalidate the digest
+        let www = RrKey::new(
+            LowerName::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        );
+        records.get_mut(&www).unwrap().insert(
+            Record::from_rdata(
+                Name::from_str("www.example.com.").unwrap(),
+                3600,
+                RData::A(A::new(127, 0, 0, 2)),
+            ),
+            0,
+        );
+        assert!(verify_zonemd(&origin, &records).is_err());
+    }
+
+    #[test]
+    fn test_zonemd_duplicate_scheme_and_hash_algorithm() {
+        let origin = Name::from_str("example.com.").unwrap();
+        let soa = SOA::new(
+            Name::from_str("ns.example.com.").unwrap(),
+            Name::from_str("hostmaster.example.com.").unwrap(),
+            1,
+            3600,
+            3600,
+            3600,
+            3600,
+        );
+        let soa = Record::from_rdata(origin.clone(), 3600, RData::SOA(soa));
+        let apex = LowerName::new(&origin);
+        let mut records = BTreeMap::new();
+        records.insert(
+            RrKey::new(apex.clone(), RecordType::SOA),
+            RecordSet::from(soa),
+        );
+
+        let zonemd = ZONEMD::new(1, Scheme::Simple, HashAlgorithm::SHA384, Vec::new());
+        let digest =
+            ZONEMD::from_zone(&origin, 1, HashAlgorithm::SHA384, records.values()).unwrap();
+        let mut zonemd_set = RecordSet::from(Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::ZONEMD(digest),
+        ));
+        records.insert(
+            RrKey::new(apex.clone(), RecordType::ZONEMD),
+            zonemd_set.clone(),
+        );
+        verify_zonemd(&origin, &records).expect("ZONEMD should verify");
+
+        // a second digest of the same scheme and hash algorithm, even if the first one matches
+        zonemd_set.insert(
+            Record::from_rdata(origin.clone(), 3600, RData::ZONEMD(zonemd)),
+            0,
+        );
+        records.insert(RrKey::new(apex, RecordType::ZONEMD), zonemd_set);
+        let error = verify_zonemd(&origin, &records).unwrap_err();
+        assert!(error.to_string().contains("multiple ZONEMD"));
+    }
+}
@@ -8,9 +8,13 @@
 //! All persistent store implementations
 
 pub mod blocklist;
+pub mod catalog_zone;
+pub mod designated_resolver;
 pub mod file;
 pub mod forwarder;
 pub mod in_memory;
 pub mod recursor;
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
+#[cfg(feature = "__dnssec")]
+pub mod tsig;
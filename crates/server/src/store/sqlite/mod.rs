@@ -7,11 +7,7 @@
 
 //! SQLite serving with Dynamic DNS and journaling support
 
-#[cfg(feature = "__dnssec")]
-use std::fs;
 use std::marker::PhantomData;
-#[cfg(feature = "__dnssec")]
-use std::str::FromStr;
 use std::{
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
@@ -25,18 +21,33 @@ use tracing::{debug, error, info, warn};
 #[cfg(feature = "metrics")]
 use crate::metrics::PersistentStoreMetrics;
 #[cfg(feature = "__dnssec")]
+pub use crate::store::tsig::TsigKeyConfig;
+#[cfg(feature = "__dnssec")]
 use crate::{
     dnssec::NxProofKind,
     proto::{
-        dnssec::{DnsSecResult, DnssecSigner},
-        op::UpdateRequest,
+        dnssec::{
+            DnsSecResult, DnssecSigner, TBS, Verifier,
+            rdata::{
+                DNSSECRData, SIG,
+                key::{KeyTrust, KeyUsage, Protocol as KeyProtocol},
+            },
+        },
+        op::{MessageSignature, UpdateRequest},
         rr::{
-            TSigner,
-            rdata::tsig::{TSIG, TsigAlgorithm, TsigError},
+            SerialNumber, TSigner, accept_tkey,
+            rdata::{
+                TKEY,
+                tkey::TkeyMode,
+                tsig::{TSIG, TsigError},
+            },
+            tkey_error, tkey_record,
         },
     },
     zone_handler::{DnssecZoneHandler, Nsec3QueryInfo},
 };
+#[cfg(feature = "__dnssec")]
+use crate::{net::runtime::Time, store::tsig::default_fudge, zone_handler::LookupRecords};
 use crate::{
     net::runtime::{RuntimeProvider, TokioRuntimeProvider},
     proto::{
@@ -59,6 +70,16 @@ use crate::{
 
 pub mod persistence;
 pub use persistence::{Journal, PersistenceError};
+#[cfg(feature = "__dnssec")]
+pub mod update_policy;
+#[cfg(feature = "__dnssec")]
+pub use update_policy::{NameMatch, UpdateAction, UpdateRule};
+#[cfg(feature = "__dnssec")]
+pub mod tkey;
+#[cfg(feature = "__dnssec")]
+use tkey::KeyTable;
+#[cfg(feature = "__dnssec")]
+pub use tkey::TkeyConfig;
 
 /// SqliteZoneHandler is responsible for storing the resource records for a particular zone.
 ///
@@ -75,6 +96,14 @@ pub struct SqliteZoneHandler<P = TokioRuntimeProvider> {
     metrics: PersistentStoreMetrics,
     #[cfg(feature = "__dnssec")]
     tsig_signers: Vec<TSigner>,
+    #[cfg(feature = "__dnssec")]
+    sig0_signers: Vec<Name>,
+    #[cfg(feature = "__dnssec")]
+    update_policy: Vec<UpdateRule>,
+    #[cfg(feature = "__dnssec")]
+    tkey: Option<TkeyConfig>,
+    #[cfg(feature = "__dnssec")]
+    tkey_keys: KeyTable,
     _phantom: PhantomData<P>,
 }
 
@@ -108,6 +137,14 @@ impl<P: RuntimeProvider + Send + Sync> SqliteZoneHandler<P> {
             metrics: PersistentStoreMetrics::new("sqlite"),
             #[cfg(feature = "__dnssec")]
             tsig_signers: Vec::new(),
+            #[cfg(feature = "__dnssec")]
+            sig0_signers: Vec::new(),
+            #[cfg(feature = "__dnssec")]
+            update_policy: Vec::new(),
+            #[cfg(feature = "__dnssec")]
+            tkey: None,
+            #[cfg(feature = "__dnssec")]
+            tkey_keys: KeyTable::default(),
             _phantom: PhantomData,
         }
     }
@@ -208,6 +245,13 @@ impl<P: RuntimeProvider + Send + Sync> SqliteZoneHandler<P> {
                 .push(config.to_signer(&zone_name, root_dir)?);
         }
 
+        #[cfg(feature = "__dnssec")]
+        handler.set_sig0_signers(config.sig0_signers.clone());
+        #[cfg(feature = "__dnssec")]
+        handler.set_update_policy(config.update_policy.clone());
+        #[cfg(feature = "__dnssec")]
+        handler.set_tkey(config.tkey.clone());
+
         Ok(handler)
     }
 
@@ -300,6 +344,29 @@ impl<P: RuntimeProvider + Send + Sync> SqliteZoneHandler<P> {
         self.tsig_signers = signers;
     }
 
+    /// Set the names of the KEY records allowed to authenticate updates with SIG(0)
+    ///
+    /// SIG(0) signatures are refused unless the signer is listed, even if its KEY is published in
+    /// the zone.
+    #[cfg(feature = "__dnssec")]
+    pub fn set_sig0_signers(&mut self, signers: Vec<Name>) {
+        self.sig0_signers = signers;
+    }
+
+    /// Restricts which records the TSIG keys may update, see [`UpdateRule`]
+    ///
+    /// With no rules, any valid TSIG key or allowed SIG(0) signer may update the whole zone.
+    #[cfg(feature = "__dnssec")]
+    pub fn set_update_policy(&mut self, rules: Vec<UpdateRule>) {
+        self.update_policy = rules;
+    }
+
+    /// Enables negotiation of short-lived TSIG keys with TKEY, see [`TkeyConfig`]
+    #[cfg(feature = "__dnssec")]
+    pub fn set_tkey(&mut self, config: Option<TkeyConfig>) {
+        self.tkey = config;
+    }
+
     /// Set the AXFR policy for testing purposes
     #[cfg(feature = "testing")]
     pub fn set_axfr_policy(&mut self, policy: AxfrPolicy) {
@@ -567,33 +634,69 @@ impl<P: RuntimeProvider + Send + Sync> SqliteZoneHandler<P> {
         }
 
         match request.signature() {
-            Some(tsig) => {
+            Some(MessageSignature::Tsig(tsig)) => {
                 let (resp, signer) = self.authorized_tsig(tsig, request, now).await;
+                let identity = self.tsig_identity(&tsig.name, now);
+                let resp = resp.and_then(|()| self.authorized_policy(&identity, request));
                 (resp, Some(signer))
             }
-            None => (Err(ResponseCode::Refused), None),
+            Some(MessageSignature::Sig0(sig)) => {
+                let resp = self.authorized_sig0(sig, request, now).await;
+                let key_name = &sig.data.input().signer_name;
+                let resp = resp.and_then(|()| self.authorized_policy(key_name, request));
+                (resp, None)
+            }
+            Some(_) | None => (Err(ResponseCode::Refused), None),
+        }
+    }
+
+    /// Checks the records of an update signed with `key_name` against the update policy
+    #[cfg(feature = "__dnssec")]
+    fn authorized_policy(&self, key_name: &Name, request: &Request) -> Result<(), ResponseCode> {
+        if self.update_policy.is_empty() {
+            return Ok(());
         }
+
+        update_policy::authorize_records(
+            &self.update_policy,
+            key_name,
+            request.prerequisites().iter().chain(request.updates()),
+        )
     }
 
     /// Checks that an AXFR `Request` has a valid signature, or returns an error
     async fn authorize_axfr(
         &self,
-        _request: &Request,
+        request: &Request,
         _now: u64,
     ) -> (Result<(), ResponseCode>, Option<TSigResponseContext>) {
-        match self.axfr_policy {
+        match &self.axfr_policy {
             // Deny without checking any signatures.
             AxfrPolicy::Deny => (Err(ResponseCode::Refused), None),
             // Allow without checking any signatures.
             AxfrPolicy::AllowAll => (Ok(()), None),
+            // Allow only over TLS, or from a client certificate, without checking any signatures.
+            policy @ (AxfrPolicy::RequireTls | AxfrPolicy::RequireMutualTls(_)) => {
+                match policy.allows(request) {
+                    true => (Ok(()), None),
+                    false => {
+                        warn!(protocol = %request.protocol(), "AXFR transport or client refused");
+                        (Err(ResponseCode::Refused), None)
+                    }
+                }
+            }
             // Allow only if a valid signature is present.
             #[cfg(feature = "__dnssec")]
-            AxfrPolicy::AllowSigned => match _request.signature() {
-                Some(tsig) => {
-                    let (resp, signer) = self.authorized_tsig(tsig, _request, _now).await;
+            AxfrPolicy::AllowSigned => match request.signature() {
+                Some(MessageSignature::Tsig(tsig)) => {
+                    let (resp, signer) = self.authorized_tsig(tsig, request, _now).await;
                     (resp, Some(signer))
                 }
-                None => {
+                Some(MessageSignature::Sig0(_)) => {
+                    warn!("AXFR request was signed with SIG(0), only TSIG is accepted");
+                    (Err(ResponseCode::Refused), None)
+                }
+                Some(_) | None => {
                     warn!("AXFR request was not signed");
                     (Err(ResponseCode::Refused), None)
                 }
@@ -961,11 +1064,17 @@ impl<P: RuntimeProvider + Send + Sync> SqliteZoneHandler<P> {
         // RFC 8945 Section 5.5: "To prevent cross-algorithm attacks, there SHOULD only be
         // one algorithm associated with any given key name." We rely on this and only check
         // the key name when filtering TSIG keys.
-        let Some(tsigner) = self
+        let tsigner = self
             .tsig_signers
             .iter()
             .find(|tsigner| tsigner.signer_name() == &tsig.name)
-        else {
+            .cloned()
+            .or_else(|| {
+                self.tkey_keys
+                    .get(&tsig.name, now)
+                    .map(|(signer, _)| signer)
+            });
+        let Some(tsigner) = tsigner else {
             warn!("no TSIG key name matched: id {req_id}");
             return (
                 Err(ResponseCode::NotAuth),
@@ -993,9 +1102,221 @@ impl<P: RuntimeProvider + Send + Sync> SqliteZoneHandler<P> {
 
         (
             response,
-            TSigResponseContext::new(req_id, now, tsigner.clone(), tsig.data.mac.clone(), error),
+            TSigResponseContext::new(req_id, now, tsigner, tsig.data.mac.clone(), error),
         )
     }
+
+    /// Verifies a SIG(0) signature with the KEY records published in the zone at the signer name
+    ///
+    /// Only the signers configured with [`Self::set_sig0_signers`] are accepted.
+    #[cfg(feature = "__dnssec")]
+    async fn authorized_sig0(
+        &self,
+        sig: &Record<SIG>,
+        request: &Request,
+        now: u64,
+    ) -> Result<(), ResponseCode> {
+        let req_id = request.id();
+        let input = sig.data.input();
+
+        debug!("authorizing with SIG(0) of: {}", input.signer_name);
+        let signer_name = LowerName::from(&input.signer_name);
+        if !self.origin().zone_of(&signer_name) {
+            warn!(
+                "SIG(0) signer {} is not in zone {}: id {req_id}",
+                input.signer_name,
+                self.origin()
+            );
+            return Err(ResponseCode::NotAuth);
+        }
+
+        if !self.sig0_signers.contains(&input.signer_name) {
+            warn!(
+                "SIG(0) signer {} is not allowed to update {}: id {req_id}",
+                input.signer_name,
+                self.origin()
+            );
+            return Err(ResponseCode::Refused);
+        }
+
+        let Ok((tbs, _)) = TBS::from_sig0_message(request.as_slice()) else {
+            warn!("malformed SIG(0) signed message: id {req_id}");
+            return Err(ResponseCode::FormErr);
+        };
+
+        let keys = self
+            .lookup(
+                &signer_name,
+                RecordType::KEY,
+                None,
+                LookupOptions::default(),
+            )
+            .await
+            .unwrap_or_default();
+
+        let verified = keys
+            .iter()
+            .filter_map(|record| match &record.data {
+                RData::DNSSEC(DNSSECRData::KEY(key)) => Some(key),
+                _ => None,
+            })
+            .filter(|key| {
+                // the key must be usable for authentication of DNS messages
+                !matches!(key.key_trust(), KeyTrust::NotAuth | KeyTrust::DoNotTrust)
+                    && key.key_usage() != KeyUsage::Reserved
+                    && key.protocol() == KeyProtocol::DNSSEC
+                    && key.algorithm() == input.algorithm
+            })
+            .any(|key| key.verify(tbs.as_ref(), sig.data.sig()).is_ok());
+
+        if !verified {
+            warn!("no KEY verified the SIG(0) signature: id {req_id}");
+            return Err(ResponseCode::NotAuth);
+        }
+
+        let now = SerialNumber::new(now as u32);
+        if !(input.sig_inception <= now && now <= input.sig_expiration) {
+            warn!("expired SIG(0) signature: id {req_id}");
+            return Err(ResponseCode::NotAuth);
+        }
+
+        Ok(())
+    }
+
+    /// The key name the update policy is evaluated with for the TSIG key `key_name`, i.e. the
+    /// name of the key that bootstrapped it if it was negotiated with TKEY
+    #[cfg(feature = "__dnssec")]
+    fn tsig_identity(&self, key_name: &Name, now: u64) -> Name {
+        match self.tkey_keys.get(key_name, now) {
+            Some((_, identity)) => identity,
+            None => key_name.clone(),
+        }
+    }
+
+    /// Answers a TKEY query, see [RFC 2930](https://tools.ietf.org/html/rfc2930#section-4)
+    ///
+    /// The query must be signed with SIG(0) or a TSIG key of the zone. Negotiation failures are
+    /// reported in the error field of the TKEY answer, the response is signed if the query was
+    /// signed with TSIG.
+    #[cfg(feature = "__dnssec")]
+    async fn negotiate_tkey(
+        &self,
+        request: &Request,
+        now: u64,
+    ) -> (Result<AuthLookup, LookupError>, Option<TSigResponseContext>) {
+        let Some(config) = &self.tkey else {
+            warn!("TKEY negotiation is not enabled for zone {}", self.origin());
+            return (Err(LookupError::ResponseCode(ResponseCode::Refused)), None);
+        };
+
+        let key_name = Name::from(request.request_info().query.name());
+        let Some(tkey) = request
+            .additionals
+            .iter()
+            .filter(|record| record.name == key_name)
+            .find_map(|record| match &record.data {
+                RData::TKEY(tkey) => Some(tkey),
+                _ => None,
+            })
+        else {
+            warn!("TKEY query without a TKEY record for {key_name}");
+            return (Err(LookupError::ResponseCode(ResponseCode::FormErr)), None);
+        };
+
+        let (identity, signer) = match request.signature() {
+            Some(MessageSignature::Tsig(tsig)) => {
+                let (resp, signer) = self.authorized_tsig(tsig, request, now).await;
+                if let Err(code) = resp {
+                    return (Err(LookupError::ResponseCode(code)), Some(signer));
+                }
+                (self.tsig_identity(&tsig.name, now), Some(signer))
+            }
+            Some(MessageSignature::Sig0(sig)) => {
+                if let Err(code) = self.authorized_sig0(sig, request, now).await {
+                    return (Err(LookupError::ResponseCode(code)), None);
+                }
+                (sig.data.input().signer_name.clone(), None)
+            }
+            Some(_) | None => {
+                warn!("TKEY query was not signed");
+                return (Err(LookupError::ResponseCode(ResponseCode::Refused)), None);
+            }
+        };
+
+        let answer = match tkey.mode {
+            TkeyMode::KeyDeletion => self.delete_tkey(&key_name, tkey, request),
+            _ => self.accept_tkey(&key_name, tkey, identity, now, config),
+        }
+        .unwrap_or_else(|error| {
+            warn!("refused TKEY negotiation of {key_name}: {error:?}");
+            tkey_error(tkey, error)
+        });
+
+        let answers = LookupRecords::Section(vec![tkey_record(key_name, answer)]);
+        (Ok(AuthLookup::answers(answers, None)), signer)
+    }
+
+    /// Negotiates the key `key_name` on behalf of `identity` and adds it to the key table
+    #[cfg(feature = "__dnssec")]
+    fn accept_tkey(
+        &self,
+        key_name: &Name,
+        tkey: &TKEY,
+        identity: Name,
+        now: u64,
+        config: &TkeyConfig,
+    ) -> Result<TKEY, TsigError> {
+        if self
+            .tsig_signers
+            .iter()
+            .any(|signer| signer.signer_name() == key_name)
+        {
+            return Err(TsigError::BadName);
+        }
+
+        let expiration = u64::from(tkey.expiration).min(now + u64::from(config.max_lifetime));
+        if expiration <= now {
+            return Err(TsigError::BadTime);
+        }
+
+        let (answer, signer) = accept_tkey(
+            key_name,
+            tkey,
+            now as u32,
+            expiration as u32,
+            default_fudge(),
+        )?;
+        if !self
+            .tkey_keys
+            .insert(signer, identity.clone(), expiration, now, config.max_keys)
+        {
+            return Err(TsigError::BadName);
+        }
+
+        info!("negotiated TKEY {key_name} for {identity}, expires at {expiration}");
+        Ok(answer)
+    }
+
+    /// Deletes the negotiated key `key_name`, the request must be signed with that key
+    #[cfg(feature = "__dnssec")]
+    fn delete_tkey(
+        &self,
+        key_name: &Name,
+        tkey: &TKEY,
+        request: &Request,
+    ) -> Result<TKEY, TsigError> {
+        match request.signature() {
+            Some(MessageSignature::Tsig(tsig)) if &tsig.name == key_name => {}
+            _ => return Err(TsigError::BadKey),
+        }
+
+        if !self.tkey_keys.remove(key_name) {
+            return Err(TsigError::BadName);
+        }
+
+        info!("deleted TKEY {key_name}");
+        Ok(tkey.clone())
+    }
 }
 
 impl<P> Deref for SqliteZoneHandler<P> {
@@ -1021,7 +1342,7 @@ impl<P: RuntimeProvider + Send + Sync> ZoneHandler for SqliteZoneHandler<P> {
 
     /// Return a policy that can be used to determine how AXFR requests should be handled.
     fn axfr_policy(&self) -> AxfrPolicy {
-        self.axfr_policy
+        self.axfr_policy.clone()
     }
 
     /// Takes the UpdateMessage, extracts the Records, and applies the changes to the record set.
@@ -1116,6 +1437,13 @@ impl<P: RuntimeProvider + Send + Sync> ZoneHandler for SqliteZoneHandler<P> {
             );
         }
 
+        #[cfg(feature = "__dnssec")]
+        if request_info.query.query_type() == RecordType::TKEY {
+            let now = <P::Timer as Time>::current_time();
+            let (result, signer) = self.negotiate_tkey(request, now).await;
+            return (LookupControlFlow::Break(result), signer);
+        }
+
         let (search, _) = self.in_memory.search(request, lookup_options).await;
 
         (search, None)
@@ -1213,51 +1541,21 @@ pub struct SqliteConfig {
     #[cfg(feature = "__dnssec")]
     #[serde(default)]
     pub tsig_keys: Vec<TsigKeyConfig>,
-}
-
-/// Configuration for a TSIG authentication signer key
-#[derive(Deserialize, PartialEq, Eq, Debug)]
-#[serde(deny_unknown_fields)]
-#[cfg(feature = "__dnssec")]
-pub struct TsigKeyConfig {
-    /// The key name
-    pub name: String,
-    /// A path to the unencoded symmetric HMAC key data
-    pub key_file: PathBuf,
-    /// The key algorithm
-    pub algorithm: TsigAlgorithm,
-    /// Allowed +/- difference (in seconds) between the time a TSIG request was signed
-    /// and when it is verified.
-    ///
-    /// A fudge value that is too large may leave the server open to replay attacks.
-    /// A fudge value that is too small may cause failures from latency and clock
-    /// desynchronization.
+    /// Names of the KEY records in the zone allowed to authenticate updates with SIG(0) if
+    /// `allow_update` is true
+    #[cfg(feature = "__dnssec")]
+    #[serde(default)]
+    pub sig0_signers: Vec<Name>,
+    /// Rules restricting the records each TSIG key may update, evaluated in order
     ///
-    /// RFC 8945 recommends a fudge value of 300 seconds (the default if not specified).
-    #[serde(default = "default_fudge")]
-    pub fudge: u16,
-}
-
-#[cfg(feature = "__dnssec")]
-impl TsigKeyConfig {
-    fn to_signer(&self, zone_name: &Name, root_dir: Option<&Path>) -> Result<TSigner, String> {
-        let key_file = rooted(&self.key_file, root_dir);
-        let key_data = fs::read(&key_file)
-            .map_err(|e| format!("error reading TSIG key file: {}: {e}", key_file.display()))?;
-        let signer_name = Name::from_str(&self.name).unwrap_or_else(|_| zone_name.clone());
-
-        TSigner::new(key_data, self.algorithm.clone(), signer_name, self.fudge)
-            .map_err(|e| format!("invalid TSIG key configuration: {e}"))
-    }
-}
-
-/// Default TSIG fudge value (seconds).
-///
-/// Per RFC 8945 §10:
-///   "The RECOMMENDED value in most situations is 300 seconds."
-#[cfg(feature = "__dnssec")]
-pub(crate) fn default_fudge() -> u16 {
-    300
+    /// If empty, any of the `tsig_keys` and `sig0_signers` may update the whole zone.
+    #[cfg(feature = "__dnssec")]
+    #[serde(default)]
+    pub update_policy: Vec<UpdateRule>,
+    /// Allows negotiating short-lived TSIG keys with TKEY if set
+    #[cfg(feature = "__dnssec")]
+    #[serde(default)]
+    pub tkey: Option<TkeyConfig>,
 }
 
 #[cfg(test)]
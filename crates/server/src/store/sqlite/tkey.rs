@@ -0,0 +1,150 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TSIG keys negotiated with TKEY, see [RFC 2930](https://www.rfc-editor.org/rfc/rfc2930)
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+
+use crate::proto::rr::{LowerName, Name, TSigner};
+
+/// Configuration of TKEY key negotiation for a zone
+///
+/// Clients authenticated with SIG(0) or one of the configured TSIG keys may negotiate short-lived
+/// TSIG keys with an X25519 key agreement, see [`TkeyMode::X25519HkdfSha256`]. A negotiated key
+/// carries the identity of the key that bootstrapped it, which is the key name the update policy
+/// is evaluated with.
+///
+/// [`TkeyMode::X25519HkdfSha256`]: crate::proto::rr::rdata::tkey::TkeyMode::X25519HkdfSha256
+///
+/// ```toml
+/// [zones.stores.tkey]
+/// max_lifetime = 3600
+/// ```
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TkeyConfig {
+    /// Maximum validity period of negotiated keys, in seconds
+    #[serde(default = "default_max_lifetime")]
+    pub max_lifetime: u32,
+    /// Maximum number of negotiated keys kept at once, further negotiations are refused
+    #[serde(default = "default_max_keys")]
+    pub max_keys: usize,
+}
+
+impl Default for TkeyConfig {
+    fn default() -> Self {
+        Self {
+            max_lifetime: default_max_lifetime(),
+            max_keys: default_max_keys(),
+        }
+    }
+}
+
+fn default_max_lifetime() -> u32 {
+    3600
+}
+
+fn default_max_keys() -> usize {
+    1024
+}
+
+/// Time-limited table of negotiated TSIG keys
+#[derive(Default)]
+pub(crate) struct KeyTable {
+    keys: Mutex<HashMap<LowerName, NegotiatedKey>>,
+}
+
+impl KeyTable {
+    /// Returns the signer and the bootstrap identity of the key `name`, if it has not expired
+    pub(crate) fn get(&self, name: &Name, now: u64) -> Option<(TSigner, Name)> {
+        let mut keys = self.keys.lock().expect("key table lock poisoned");
+        keys.retain(|_, key| key.expiration > now);
+        keys.get(&LowerName::from(name))
+            .map(|key| (key.signer.clone(), key.identity.clone()))
+    }
+
+    /// Adds a key valid until `expiration`, returns false if the table is full or the name is
+    /// already taken by an unexpired key
+    pub(crate) fn insert(
+        &self,
+        signer: TSigner,
+        identity: Name,
+        expiration: u64,
+        now: u64,
+        max_keys: usize,
+    ) -> bool {
+        let mut keys = self.keys.lock().expect("key table lock poisoned");
+        keys.retain(|_, key| key.expiration > now);
+
+        let name = LowerName::from(signer.signer_name());
+        if keys.len() >= max_keys || keys.contains_key(&name) {
+            return false;
+        }
+
+        keys.insert(
+            name,
+            NegotiatedKey {
+                signer,
+                identity,
+                expiration,
+            },
+        );
+        true
+    }
+
+    /// Removes the key `name`, returns false if there was no such key
+    pub(crate) fn remove(&self, name: &Name) -> bool {
+        let mut keys = self.keys.lock().expect("key table lock poisoned");
+        keys.remove(&LowerName::from(name)).is_some()
+    }
+}
+
+struct NegotiatedKey {
+    signer: TSigner,
+    identity: Name,
+    expiration: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::proto::rr::rdata::tsig::TsigAlgorithm;
+
+    fn signer(name: &str) -> TSigner {
+        TSigner::new(
+            vec![0; 32],
+            TsigAlgorithm::HmacSha256,
+            Name::from_str(name).unwrap(),
+            300,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_key_table_expiration() {
+        let table = KeyTable::default();
+        let identity = Name::from_str("host.example.com.").unwrap();
+        let name = Name::from_str("tmp.example.com.").unwrap();
+
+        assert!(table.insert(signer("tmp.example.com."), identity.clone(), 100, 0, 10));
+        assert!(!table.insert(signer("TMP.example.com."), identity.clone(), 100, 0, 10));
+        assert!(!table.insert(signer("other.example.com."), identity.clone(), 100, 0, 1));
+
+        let (_, found) = table.get(&name, 99).expect("key not found");
+        assert_eq!(found, identity);
+        assert!(table.get(&name, 100).is_none());
+
+        assert!(table.insert(signer("tmp.example.com."), identity, 200, 100, 1));
+        assert!(table.remove(&name));
+        assert!(!table.remove(&name));
+    }
+}
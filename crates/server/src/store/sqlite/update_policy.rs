@@ -0,0 +1,297 @@
+// Copyright 2015-2025 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Fine-grained authorization of dynamic updates, similar to BIND's `update-policy`
+
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::proto::{
+    op::ResponseCode,
+    rr::{Name, Record, RecordType},
+};
+
+/// A rule granting or denying dynamic updates to the holder of a TSIG key
+///
+/// Rules are evaluated in order against each record of the prerequisite and update sections of
+/// an UPDATE, the first rule matching the key, the owner name and the type of the record decides
+/// whether it is allowed. An update is only applied if all of its records are allowed.
+///
+/// ```toml
+/// [[zones.stores.update_policy]]
+/// action = "grant"
+/// key = "dhcp.example.com."
+/// match = { subdomain = "hosts.example.com." }
+/// types = ["A", "AAAA", "TXT"]
+/// ```
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateRule {
+    /// Whether matching records are allowed or refused
+    pub action: UpdateAction,
+    /// Name of the TSIG key this rule applies to, a wildcard name matches all keys below it
+    pub key: Name,
+    /// The owner names of records this rule applies to
+    #[serde(rename = "match")]
+    pub name: NameMatch,
+    /// The record types this rule applies to
+    ///
+    /// If empty, all types except RRSIG, NS, SOA, NSEC and NSEC3 match. Records of type ANY,
+    /// i.e. deletions of all RecordSets of a name, only match rules with no types listed.
+    #[serde(default)]
+    pub types: Vec<RecordType>,
+}
+
+impl UpdateRule {
+    /// Returns true if this rule applies to the `record` updated with the key `key_name`
+    pub fn matches(&self, key_name: &Name, record: &Record) -> bool {
+        self.matches_key(key_name)
+            && self.name.matches(key_name, &record.name)
+            && self.matches_type(record.record_type())
+    }
+
+    fn matches_key(&self, key_name: &Name) -> bool {
+        match self.key.is_wildcard() {
+            true => is_below(&self.key.base_name(), key_name),
+            false => is_same(&self.key, key_name),
+        }
+    }
+
+    fn matches_type(&self, record_type: RecordType) -> bool {
+        if !self.types.is_empty() {
+            return self.types.contains(&record_type);
+        }
+
+        !matches!(
+            record_type,
+            RecordType::RRSIG
+                | RecordType::NS
+                | RecordType::SOA
+                | RecordType::NSEC
+                | RecordType::NSEC3
+        )
+    }
+}
+
+/// The action of an [`UpdateRule`]
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateAction {
+    /// Allow matching records to be updated
+    Grant,
+    /// Refuse updates containing matching records
+    Deny,
+}
+
+/// The owner names matched by an [`UpdateRule`]
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum NameMatch {
+    /// The owner name is the same as the name of the key, e.g. `match = "self"`
+    #[serde(rename = "self")]
+    KeyName,
+    /// The owner name is the name or any name below it
+    Subdomain(Name),
+    /// The owner name matches the wildcard name, i.e. it is any name below its base name
+    Wildcard(Name),
+    /// The owner name is exactly the name
+    Name(Name),
+}
+
+impl NameMatch {
+    /// Returns true if `name` is matched for the key `key_name`
+    pub fn matches(&self, key_name: &Name, name: &Name) -> bool {
+        match self {
+            Self::KeyName => is_same(key_name, name),
+            Self::Subdomain(zone) => zone.zone_of(name),
+            Self::Wildcard(wildcard) => is_below(&wildcard.base_name(), name),
+            Self::Name(exact) => is_same(exact, name),
+        }
+    }
+}
+
+/// Checks all `records` of an update signed with the key `key_name` against the `rules`
+///
+/// Returns `Refused` if any record is not granted by the first matching rule.
+pub(crate) fn authorize_records<'a>(
+    rules: &[UpdateRule],
+    key_name: &Name,
+    records: impl IntoIterator<Item = &'a Record>,
+) -> Result<(), ResponseCode> {
+    for record in records {
+        let action = rules
+            .iter()
+            .find(|rule| rule.matches(key_name, record))
+            .map(|rule| rule.action);
+
+        match action {
+            Some(UpdateAction::Grant) => {
+                debug!(
+                    "update policy grants {key_name} {} {}",
+                    record.name,
+                    record.record_type()
+                );
+            }
+            Some(UpdateAction::Deny) | None => {
+                warn!(
+                    "update policy refuses {key_name} {} {}",
+                    record.name,
+                    record.record_type()
+                );
+                return Err(ResponseCode::Refused);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Case-insensitive equality, regardless of whether the names are fully qualified
+fn is_same(a: &Name, b: &Name) -> bool {
+    a.num_labels() == b.num_labels() && a.zone_of(b)
+}
+
+/// True if `name` is strictly below `zone`
+fn is_below(zone: &Name, name: &Name) -> bool {
+    name.num_labels() > zone.num_labels() && zone.zone_of(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::proto::rr::{RData, rdata::A};
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn a_record(owner: &str) -> Record {
+        Record::from_rdata(name(owner), 3600, RData::A(A::new(192, 0, 2, 1)))
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_deserialize() {
+        #[derive(Deserialize)]
+        struct Config {
+            update_policy: Vec<UpdateRule>,
+        }
+
+        let config: Config = toml::from_str(
+            r#"
+[[update_policy]]
+action = "grant"
+key = "host.example.com."
+match = "self"
+
+[[update_policy]]
+action = "deny"
+key = "*.dhcp."
+match = { subdomain = "example.com." }
+types = ["NS", "SOA"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.update_policy,
+            vec![
+                UpdateRule {
+                    action: UpdateAction::Grant,
+                    key: name("host.example.com."),
+                    name: NameMatch::KeyName,
+                    types: vec![],
+                },
+                UpdateRule {
+                    action: UpdateAction::Deny,
+                    key: name("*.dhcp."),
+                    name: NameMatch::Subdomain(name("example.com.")),
+                    types: vec![RecordType::NS, RecordType::SOA],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_name_match() {
+        let key = name("host.example.com.");
+
+        assert!(NameMatch::KeyName.matches(&key, &name("HOST.example.com.")));
+        assert!(!NameMatch::KeyName.matches(&key, &name("www.host.example.com.")));
+
+        let subdomain = NameMatch::Subdomain(name("hosts.example.com."));
+        assert!(subdomain.matches(&key, &name("hosts.example.com.")));
+        assert!(subdomain.matches(&key, &name("a.b.hosts.example.com.")));
+        assert!(!subdomain.matches(&key, &name("example.com.")));
+
+        let wildcard = NameMatch::Wildcard(name("*.hosts.example.com."));
+        assert!(!wildcard.matches(&key, &name("hosts.example.com.")));
+        assert!(wildcard.matches(&key, &name("a.hosts.example.com.")));
+        assert!(wildcard.matches(&key, &name("a.b.hosts.example.com.")));
+
+        let exact = NameMatch::Name(name("www.example.com."));
+        assert!(exact.matches(&key, &name("www.example.com")));
+        assert!(!exact.matches(&key, &name("a.www.example.com.")));
+    }
+
+    #[test]
+    fn test_authorize_records() {
+        let rules = vec![
+            UpdateRule {
+                action: UpdateAction::Deny,
+                key: name("*.dhcp.example.com."),
+                name: NameMatch::Name(name("router.example.com.")),
+                types: vec![],
+            },
+            UpdateRule {
+                action: UpdateAction::Grant,
+                key: name("*.dhcp.example.com."),
+                name: NameMatch::Subdomain(name("example.com.")),
+                types: vec![RecordType::A],
+            },
+            UpdateRule {
+                action: UpdateAction::Grant,
+                key: name("acme.example.com."),
+                name: NameMatch::Wildcard(name("*.example.com.")),
+                types: vec![RecordType::TXT],
+            },
+        ];
+
+        let dhcp = name("server1.dhcp.example.com.");
+        let acme = name("acme.example.com.");
+
+        assert!(authorize_records(&rules, &dhcp, [&a_record("host.example.com.")]).is_ok());
+        assert_eq!(
+            authorize_records(&rules, &dhcp, [&a_record("router.example.com.")]),
+            Err(ResponseCode::Refused)
+        );
+        assert_eq!(
+            authorize_records(
+                &rules,
+                &dhcp,
+                [
+                    &a_record("host.example.com."),
+                    &a_record("other.example.net.")
+                ]
+            ),
+            Err(ResponseCode::Refused)
+        );
+        assert_eq!(
+            authorize_records(&rules, &acme, [&a_record("host.example.com.")]),
+            Err(ResponseCode::Refused)
+        );
+
+        let mut delete_all = a_record("host.example.com.");
+        delete_all.data = RData::Update0(RecordType::ANY);
+        assert_eq!(
+            authorize_records(&rules, &dhcp, [&delete_all]),
+            Err(ResponseCode::Refused)
+        );
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2015-2026 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TSIG key configuration shared by the stores
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    proto::rr::{Name, TSigner, rdata::tsig::TsigAlgorithm},
+    store::file::rooted,
+};
+
+/// Configuration for a TSIG authentication signer key
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TsigKeyConfig {
+    /// The key name
+    pub name: String,
+    /// A path to the unencoded symmetric HMAC key data
+    pub key_file: PathBuf,
+    /// The key algorithm
+    pub algorithm: TsigAlgorithm,
+    /// Allowed +/- difference (in seconds) between the time a TSIG request was signed
+    /// and when it is verified.
+    ///
+    /// A fudge value that is too large may leave the server open to replay attacks.
+    /// A fudge value that is too small may cause failures from latency and clock
+    /// desynchronization.
+    ///
+    /// RFC 8945 recommends a fudge value of 300 seconds (the default if not specified).
+    #[serde(default = "default_fudge")]
+    pub fudge: u16,
+}
+
+impl TsigKeyConfig {
+    pub(crate) fn to_signer(
+        &self,
+        zone_name: &Name,
+        root_dir: Option<&Path>,
+    ) -> Result<TSigner, String> {
+        let key_file = rooted(&self.key_file, root_dir);
+        let key_data = fs::read(&key_file)
+            .map_err(|e| format!("error reading TSIG key file: {}: {e}", key_file.display()))?;
+        let signer_name = Name::from_str(&self.name).unwrap_or_else(|_| zone_name.clone());
+
+        TSigner::new(key_data, self.algorithm.clone(), signer_name, self.fudge)
+            .map_err(|e| format!("invalid TSIG key configuration: {e}"))
+    }
+}
+
+/// Default TSIG fudge value (seconds).
+///
+/// Per RFC 8945 §10:
+///   "The RECOMMENDED value in most situations is 300 seconds."
+pub(crate) fn default_fudge() -> u16 {
+    300
+}
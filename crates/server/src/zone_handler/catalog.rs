@@ -8,12 +8,18 @@
 // TODO, I've implemented this as a separate entity from the cache, but I wonder if the cache
 //  should be the only "front-end" for lookups, where if that misses, then we go to the catalog
 //  then, if requested, do a recursive lookup... i.e. the catalog would only point to files.
-use std::{collections::HashMap, iter, sync::Arc};
+use std::{
+    collections::HashMap,
+    iter,
+    sync::{Arc, RwLock},
+};
 
 use tracing::{debug, error, info, trace, warn};
 
 #[cfg(feature = "metrics")]
 use crate::metrics::CatalogMetrics;
+#[cfg(feature = "__quic")]
+use crate::net::xfer::Protocol;
 #[cfg(feature = "__dnssec")]
 use crate::{
     dnssec::NxProofKind,
@@ -27,10 +33,13 @@ use crate::{
 use crate::{
     net::runtime::Time,
     proto::{
-        op::{Edns, LowerQuery, Message, MessageType, Metadata, OpCode, ResponseCode},
+        op::{
+            Edns, LowerQuery, Message, MessageType, Metadata, OpCode, RESPONSE_PADDING_BLOCK_LEN,
+            ResponseCode,
+        },
         rr::{
             LowerName, RecordSet, RecordType,
-            rdata::opt::{EdnsCode, EdnsOption, NSIDPayload},
+            rdata::opt::{EdnsCode, EdnsOption, NSIDPayload, TcpKeepalive},
         },
     },
     server::{Request, RequestHandler, RequestInfo, ResponseHandler, ResponseInfo},
@@ -49,9 +58,77 @@ use crate::{
 #[derive(Default)]
 pub struct Catalog {
     nsid_payload: Option<NSIDPayload>,
-    handlers: HashMap<LowerName, Vec<Arc<dyn ZoneHandler>>>,
+    zones: CatalogHandle,
+}
+
+/// A cloneable handle to the zones of a [`Catalog`].
+///
+/// Zones can be inserted or removed through a handle while the `Catalog` is serving requests,
+/// e.g. as member zones of a catalog zone are added and removed.
+#[derive(Clone, Default)]
+pub struct CatalogHandle {
+    handlers: Arc<RwLock<ZoneHandlers>>,
     #[cfg(feature = "metrics")]
-    metrics: CatalogMetrics,
+    metrics: Arc<CatalogMetrics>,
+}
+
+type ZoneHandlers = HashMap<LowerName, Vec<Arc<dyn ZoneHandler>>>;
+
+impl CatalogHandle {
+    /// Insert or update the provided zone handlers
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    /// * `handlers` - a vec of zone handler objects
+    pub fn upsert(&self, name: LowerName, handlers: Vec<Arc<dyn ZoneHandler>>) {
+        #[cfg(feature = "metrics")]
+        for handler in handlers.iter() {
+            self.metrics.add_handler(handler.as_ref())
+        }
+
+        self.handlers
+            .write()
+            .expect("catalog lock poisoned")
+            .insert(name, handlers);
+    }
+
+    /// Remove a zone from the catalog
+    pub fn remove(&self, name: &LowerName) -> Option<Vec<Arc<dyn ZoneHandler>>> {
+        // NOTE: metrics are not removed to avoid dropping counters that are potentially still
+        // being used by other zone handlers having the same labels
+        self.handlers
+            .write()
+            .expect("catalog lock poisoned")
+            .remove(name)
+    }
+
+    /// Checks whether the `Catalog` contains DNS records for `name`
+    ///
+    /// See [`Catalog::contains`].
+    pub fn contains(&self, name: &LowerName) -> bool {
+        self.handlers
+            .read()
+            .expect("catalog lock poisoned")
+            .contains_key(name)
+    }
+
+    /// Recursively searches the catalog for a matching zone handler
+    pub fn find(&self, name: &LowerName) -> Option<Vec<Arc<dyn ZoneHandler>>> {
+        debug!("searching zone handlers for: {name}");
+        let handlers = self.handlers.read().expect("catalog lock poisoned");
+        let mut name = name.clone();
+        loop {
+            if let Some(found) = handlers.get(&name) {
+                return Some(found.clone());
+            }
+
+            if name.is_root() {
+                return None;
+            }
+            name = name.base_name();
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -122,6 +199,43 @@ impl RequestHandler for Catalog {
                 (None, _) => {}
             };
 
+            // RFC 7828 "The edns-tcp-keepalive EDNS0 Option" handling.
+            let keepalive = req_edns.option(EdnsCode::Keepalive);
+            // "DoQ servers MUST NOT send edns-tcp-keepalive EDNS(0) Option in responses", queries
+            // with the option are rejected by the QUIC handler, see RFC 9250 section 5.5.2
+            #[cfg(feature = "__quic")]
+            let keepalive = keepalive.filter(|_| request.protocol() != Protocol::Quic);
+            if let Some(EdnsOption::Keepalive(keepalive)) = keepalive {
+                // "A DNS server that receives a query using UDP transport that includes the
+                //  edns-tcp-keepalive option MUST ignore the option." Queries over connections
+                //  carry the idle timeout of the connection.
+                if keepalive.timeout().is_some() {
+                    // "A DNS server that receives a query sent using TCP transport that includes
+                    //  an edns-tcp-keepalive option with a TIMEOUT value MUST send a response
+                    //  with the RCODE FORMERR."
+                    if request.idle_timeout().is_some() {
+                        send_error_response(
+                            request,
+                            ResponseCode::FormErr,
+                            Some(&resp_edns),
+                            response_handle,
+                        )
+                        .await;
+                        return;
+                    }
+                } else if let Some(idle_timeout) = request.idle_timeout() {
+                    resp_edns
+                        .options_mut()
+                        .insert(EdnsOption::Keepalive(TcpKeepalive::new(Some(idle_timeout))));
+                }
+            }
+
+            // RFC 7830 "The EDNS(0) Padding Option", responses are only padded over encrypted
+            // transports, and when the client padded its query.
+            if request.protocol().is_encrypted() && req_edns.option(EdnsCode::Padding).is_some() {
+                resp_edns.set_padding_block(Some(RESPONSE_PADDING_BLOCK_LEN));
+            }
+
             Some(&resp_edns)
         } else {
             None
@@ -174,10 +288,8 @@ impl Catalog {
     /// Constructs a new Catalog
     pub fn new() -> Self {
         Self {
-            handlers: HashMap::new(),
             nsid_payload: None,
-            #[cfg(feature = "metrics")]
-            metrics: CatalogMetrics::default(),
+            zones: CatalogHandle::default(),
         }
     }
 
@@ -188,19 +300,18 @@ impl Catalog {
     /// * `name` - zone name, e.g. example.com.
     /// * `handlers` - a vec of zone handler objects
     pub fn upsert(&mut self, name: LowerName, handlers: Vec<Arc<dyn ZoneHandler>>) {
-        #[cfg(feature = "metrics")]
-        for handler in handlers.iter() {
-            self.metrics.add_handler(handler.as_ref())
-        }
-
-        self.handlers.insert(name, handlers);
+        self.zones.upsert(name, handlers);
     }
 
     /// Remove a zone from the catalog
     pub fn remove(&mut self, name: &LowerName) -> Option<Vec<Arc<dyn ZoneHandler>>> {
-        // NOTE: metrics are not removed to avoid dropping counters that are potentially still
-        // being used by other zone handlers having the same labels
-        self.handlers.remove(name)
+        self.zones.remove(name)
+    }
+
+    /// Returns a handle that can be used to insert or remove zones after the `Catalog` has been
+    /// handed to a [`Server`](crate::Server)
+    pub fn handle(&self) -> CatalogHandle {
+        self.zones.clone()
     }
 
     /// Set a specified name server identifier (NSID) in responses
@@ -301,7 +412,7 @@ impl Catalog {
         // verify the zone type and number of zones in request, then find the zone to update
         if let Some(handlers) = self.find(request_info.query.name()) {
             #[allow(clippy::never_loop)]
-            for handler in handlers {
+            for handler in handlers.iter() {
                 #[cfg_attr(not(feature = "__dnssec"), expect(unused))]
                 let (response_code, signer) = match handler.zone_type() {
                     ZoneType::Secondary => {
@@ -389,7 +500,7 @@ impl Catalog {
     /// If you do not know the exact domain name to use or you actually
     /// want to use the zone handler it contains, use `find` instead.
     pub fn contains(&self, name: &LowerName) -> bool {
-        self.handlers.contains_key(name)
+        self.zones.contains(name)
     }
 
     /// Given the requested query, lookup and return any matching results.
@@ -423,7 +534,7 @@ impl Catalog {
         if request_info.query.query_type() == RecordType::AXFR {
             zone_transfer(
                 request_info,
-                handlers,
+                &handlers,
                 request,
                 response_edns,
                 now,
@@ -433,28 +544,20 @@ impl Catalog {
         } else {
             lookup(
                 request_info,
-                handlers,
+                &handlers,
                 request,
                 response_edns,
                 response_handle.clone(),
                 #[cfg(feature = "metrics")]
-                &self.metrics,
+                &self.zones.metrics,
             )
             .await
         }
     }
 
     /// Recursively searches the catalog for a matching zone handler
-    pub fn find(&self, name: &LowerName) -> Option<&Vec<Arc<dyn ZoneHandler + 'static>>> {
-        debug!("searching zone handlers for: {name}");
-        self.handlers.get(name).or_else(|| {
-            if !name.is_root() {
-                let name = name.base_name();
-                self.find(&name)
-            } else {
-                None
-            }
-        })
+    pub fn find(&self, name: &LowerName) -> Option<Vec<Arc<dyn ZoneHandler + 'static>>> {
+        self.zones.find(name)
     }
 }
 
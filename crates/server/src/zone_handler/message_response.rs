@@ -7,13 +7,15 @@
 
 use tracing::{debug, error};
 
+#[cfg(feature = "dnstap")]
+use crate::server::ResponseTap;
 use crate::{
     net::xfer::Protocol,
     proto::{
         ProtoError,
         op::{
-            Edns, Header, HeaderCounts, MessageRequest, MessageType, Metadata, OpCode, Queries,
-            QueriesEmitAndCount, ResponseCode, emit_message_parts,
+            Edns, Header, HeaderCounts, MessageRequest, MessageSignature, MessageType, Metadata,
+            OpCode, Queries, QueriesEmitAndCount, ResponseCode, emit_message_parts,
         },
         rr::{Record, rdata::TSIG},
         serialize::binary::{BinEncodable, BinEncoder},
@@ -39,8 +41,10 @@ where
     authorities: Authorities,
     soa: Soa,
     additionals: Additionals,
-    signature: Option<Box<Record<TSIG>>>,
+    signature: Option<MessageSignature>,
     edns: Option<&'q Edns>,
+    #[cfg(feature = "dnstap")]
+    tap: Option<ResponseTap>,
 }
 
 impl<'q, 'a, A, N, S, D> MessageResponse<'q, 'a, A, N, S, D>
@@ -73,10 +77,32 @@ where
 
     /// Set the message signature
     pub fn set_signature(&mut self, signature: Box<Record<TSIG>>) {
-        self.signature = Some(signature);
+        self.signature = Some(MessageSignature::Tsig(signature));
     }
 
-    pub(crate) fn encode(self, protocol: Protocol) -> Result<(ResponseInfo, Vec<u8>), ProtoError> {
+    pub(crate) fn encode(
+        #[cfg_attr(not(feature = "dnstap"), allow(unused_mut))] mut self,
+        protocol: Protocol,
+    ) -> Result<(ResponseInfo, Vec<u8>), ProtoError> {
+        #[cfg(feature = "dnstap")]
+        let tap = self.tap.take();
+        let result = self.encode_message(protocol);
+        #[cfg(feature = "dnstap")]
+        if let (Some(tap), Ok((_, bytes))) = (tap, &result) {
+            tap.response(bytes);
+        }
+
+        result
+    }
+
+    /// Logs the encoded response to dnstap
+    #[cfg(feature = "dnstap")]
+    pub(crate) fn with_dnstap(mut self, tap: ResponseTap) -> Self {
+        self.tap = Some(tap);
+        self
+    }
+
+    fn encode_message(self, protocol: Protocol) -> Result<(ResponseInfo, Vec<u8>), ProtoError> {
         let id = self.metadata.id;
         debug!(
             id,
@@ -135,7 +161,7 @@ where
             &mut authorities,
             &mut self.additionals,
             self.edns,
-            self.signature.as_deref(),
+            self.signature.as_ref(),
             encoder,
         )?;
 
@@ -146,7 +172,7 @@ where
 /// A builder for MessageResponses
 pub struct MessageResponseBuilder<'q> {
     queries: Option<&'q Queries>,
-    signature: Option<Box<Record<TSIG>>>,
+    signature: Option<MessageSignature>,
     edns: Option<&'q Edns>,
 }
 
@@ -243,6 +269,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: additionals.into_iter(),
             signature: self.signature,
             edns: self.edns,
+            #[cfg(feature = "dnstap")]
+            tap: None,
         }
     }
 
@@ -267,6 +295,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: Box::new(None.into_iter()),
             signature: self.signature,
             edns: self.edns,
+            #[cfg(feature = "dnstap")]
+            tap: None,
         }
     }
 
@@ -295,6 +325,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: Box::new(None.into_iter()),
             signature: self.signature,
             edns: self.edns,
+            #[cfg(feature = "dnstap")]
+            tap: None,
         }
     }
 }
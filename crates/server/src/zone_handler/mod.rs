@@ -15,6 +15,8 @@ use thiserror::Error;
 
 #[cfg(feature = "__dnssec")]
 use crate::dnssec::NxProofKind;
+#[cfg(feature = "__tls")]
+use crate::net::xfer::Protocol;
 use crate::net::{DnsError, NetError, NoRecords};
 use crate::proto::ProtoError;
 #[cfg(feature = "__dnssec")]
@@ -29,7 +31,7 @@ use crate::proto::rr::{
 };
 #[cfg(feature = "recursor")]
 use crate::resolver::recursor::RecursorError;
-use crate::server::{Request, RequestInfo};
+use crate::server::{IdentityPattern, Request, RequestInfo};
 
 mod auth_lookup;
 mod catalog;
@@ -39,7 +41,7 @@ pub use self::auth_lookup::{
     AuthLookup, AuthLookupIter, AxfrRecords, AxfrRecordsIter, LookupRecords, LookupRecordsIter,
     ZoneTransfer,
 };
-pub use self::catalog::Catalog;
+pub use self::catalog::{Catalog, CatalogHandle};
 pub use self::message_response::{MessageResponse, MessageResponseBuilder};
 
 /// ZoneHandler implementations can be used with a `Catalog`
@@ -556,7 +558,7 @@ impl LookupOptions {
 /// AxfrPolicy describes how to handle AXFR requests
 ///
 /// By default, all AXFR requests are denied.
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
 pub enum AxfrPolicy {
     /// Deny all AXFR requests.
     #[default]
@@ -566,6 +568,51 @@ pub enum AxfrPolicy {
     /// Allow all AXFR requests that have a valid TSIG signature.
     #[cfg(feature = "__dnssec")]
     AllowSigned,
+    /// Allow all AXFR requests received over DNS-over-TLS, i.e. zone transfers over TLS (XoT),
+    /// see [RFC 9103](https://www.rfc-editor.org/rfc/rfc9103).
+    ///
+    /// Requests received over DNS-over-HTTPS, DNS-over-QUIC or DNS-over-HTTP/3 are refused.
+    RequireTls,
+    /// Allow AXFR requests received over DNS-over-TLS from clients with one of these
+    /// identities, see [`IdentityPattern`].
+    ///
+    /// The TLS listener must request client certificates, see
+    /// [`client_cert_tls_server_config`](crate::server::client_cert_tls_server_config).
+    RequireMutualTls(Vec<IdentityPattern>),
+}
+
+impl AxfrPolicy {
+    /// Returns true if the transport and the client of `request` satisfy this policy
+    ///
+    /// Signatures are not checked, `AllowSigned` returns false as the signature must be verified
+    /// with the keys of the zone by the zone handler.
+    pub fn allows(&self, request: &Request) -> bool {
+        match self {
+            Self::Deny => false,
+            Self::AllowAll => true,
+            #[cfg(feature = "__dnssec")]
+            Self::AllowSigned => false,
+            Self::RequireTls => received_over_tls(request),
+            Self::RequireMutualTls(identities) => {
+                received_over_tls(request)
+                    && request.client_identity().is_some_and(|client| {
+                        identities.iter().any(|pattern| pattern.matches(client))
+                    })
+            }
+        }
+    }
+}
+
+/// Returns true if `request` was received over DNS-over-TLS, the transport of XoT
+fn received_over_tls(request: &Request) -> bool {
+    cfg_if! {
+        if #[cfg(feature = "__tls")] {
+            matches!(request.protocol(), Protocol::Tls)
+        } else {
+            let _ = request;
+            false
+        }
+    }
 }
 
 /// The type of zone stored in a Catalog
@@ -103,6 +103,10 @@ impl TestResponseHandler {
         })
     }
 
+    pub async fn into_bytes(self) -> Vec<u8> {
+        self.into_inner().await
+    }
+
     pub async fn into_message(self) -> Message {
         let bytes = self.into_inner().await;
         let mut decoder = BinDecoder::new(&bytes);
@@ -10,13 +10,15 @@ use hickory_proto::{
         LowerName, Name, RData, Record, RecordType,
         rdata::{
             A, AAAA, CNAME, NS, SOA,
-            opt::{EdnsCode, EdnsOption, NSIDPayload},
+            opt::{EdnsCode, EdnsOption, NSIDPayload, TcpKeepalive},
         },
     },
     serialize::binary::BinEncodable,
 };
 #[cfg(feature = "__dnssec")]
 use hickory_server::dnssec::NxProofKind;
+#[cfg(feature = "__tls")]
+use hickory_server::server::{CertFingerprint, ClientIdentity, IdentityPattern};
 #[cfg(feature = "sqlite")]
 use hickory_server::store::sqlite::SqliteZoneHandler;
 use hickory_server::{
@@ -679,6 +681,111 @@ async fn test_axfr_deny_unsigned() {
     assert!(result.additionals.is_empty());
 }
 
+#[tokio::test]
+#[cfg(feature = "__tls")]
+async fn test_axfr_require_mutual_tls() {
+    subscribe();
+
+    let allowed = CertFingerprint::from([1; 32]);
+    let mut test = create_test();
+    test.set_axfr_policy(AxfrPolicy::RequireMutualTls(vec![
+        IdentityPattern::Fingerprint(allowed),
+        IdentityPattern::Name("*.secondaries.example.com".to_owned()),
+    ]));
+
+    let origin = test.origin().clone();
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.clone(), vec![Arc::new(test)]);
+
+    let mut question = Message::query();
+    question.add_query(Query::new(origin.into(), RecordType::AXFR));
+    let question_bytes = question.to_bytes().unwrap();
+
+    let other = ClientIdentity::new(CertFingerprint::from([2; 32]));
+    let secondary = other
+        .clone()
+        .with_names(vec!["ns2.secondaries.example.com".to_owned()]);
+    for (protocol, client, response_code) in [
+        (Protocol::Tcp, Some(allowed.into()), ResponseCode::Refused),
+        (Protocol::Tls, None, ResponseCode::Refused),
+        (Protocol::Tls, Some(other), ResponseCode::Refused),
+        (Protocol::Tls, Some(allowed.into()), ResponseCode::NoError),
+        (Protocol::Tls, Some(secondary), ResponseCode::NoError),
+    ] {
+        let mut request = Request::from_bytes(
+            question_bytes.clone(),
+            ([127, 0, 0, 1], 5553).into(),
+            protocol,
+        )
+        .unwrap();
+        if let Some(client) = client {
+            request = request.with_client_identity(client);
+        }
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .lookup(
+                &request,
+                None,
+                TokioTime::current_time(),
+                response_handler.clone(),
+            )
+            .await;
+        let result = response_handler.into_message().await;
+
+        assert_eq!(result.metadata.response_code, response_code);
+        assert_eq!(
+            result.answers.is_empty(),
+            response_code == ResponseCode::Refused
+        );
+    }
+}
+
+#[tokio::test]
+#[cfg(all(feature = "__tls", feature = "__https"))]
+async fn test_axfr_require_tls() {
+    subscribe();
+
+    let mut test = create_test();
+    test.set_axfr_policy(AxfrPolicy::RequireTls);
+
+    let origin = test.origin().clone();
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.clone(), vec![Arc::new(test)]);
+
+    let mut question = Message::query();
+    question.add_query(Query::new(origin.into(), RecordType::AXFR));
+    let question_bytes = question.to_bytes().unwrap();
+
+    for (protocol, response_code) in [
+        (Protocol::Tcp, ResponseCode::Refused),
+        (Protocol::Https, ResponseCode::Refused),
+        (Protocol::Tls, ResponseCode::NoError),
+    ] {
+        let request = Request::from_bytes(
+            question_bytes.clone(),
+            ([127, 0, 0, 1], 5553).into(),
+            protocol,
+        )
+        .unwrap();
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .lookup(
+                &request,
+                None,
+                TokioTime::current_time(),
+                response_handler.clone(),
+            )
+            .await;
+        let result = response_handler.into_message().await;
+
+        assert_eq!(result.metadata.response_code, response_code);
+    }
+}
+
 // Test that requesting NSID produces no NSID response when a payload isn't configured.
 #[tokio::test]
 async fn test_nsid_disabled_requested() {
@@ -792,6 +899,118 @@ fn test_nsid_request(origin: LowerName, request_nsid: bool) -> Request {
     Request::from_bytes(question_bytes, ([127, 0, 0, 1], 5553).into(), Protocol::Udp).unwrap()
 }
 
+/// Build an `A` query for `origin` with a single EDNS option, received over `protocol`.
+fn test_option_request(origin: LowerName, option: EdnsOption, protocol: Protocol) -> Request {
+    let mut edns = Edns::new();
+    edns.options_mut().insert(option);
+
+    let mut question = Message::query();
+    question.add_query(Query::new(origin.into(), RecordType::A));
+    question.set_edns(edns);
+
+    let question_bytes = question.to_bytes().unwrap();
+    Request::from_bytes(question_bytes, ([127, 0, 0, 1], 5553).into(), protocol).unwrap()
+}
+
+// The idle timeout of the connection is advertised to clients sending an empty keepalive option
+// over TCP, but not over UDP.
+#[tokio::test]
+async fn test_keepalive() {
+    subscribe();
+
+    let mem_handler = create_test();
+    let origin = mem_handler.origin().clone();
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.clone(), vec![Arc::new(mem_handler)]);
+
+    let keepalive = EdnsOption::Keepalive(TcpKeepalive::new(None));
+    let idle_timeout = std::time::Duration::from_secs(10);
+
+    let udp_request = test_option_request(origin.clone(), keepalive.clone(), Protocol::Udp);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request::<_, TokioTime>(&udp_request, response_handler.clone())
+        .await;
+    let response = response_handler.into_message().await;
+    let edns = response.edns.as_ref().expect("missing response EDNS");
+    assert!(edns.option(EdnsCode::Keepalive).is_none());
+
+    let tcp_request = test_option_request(origin.clone(), keepalive, Protocol::Tcp)
+        .with_idle_timeout(idle_timeout);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request::<_, TokioTime>(&tcp_request, response_handler.clone())
+        .await;
+    let response = response_handler.into_message().await;
+    assert_eq!(response.metadata.response_code, ResponseCode::NoError);
+    let edns = response.edns.as_ref().expect("missing response EDNS");
+    assert_eq!(
+        edns.option(EdnsCode::Keepalive),
+        Some(&EdnsOption::Keepalive(TcpKeepalive::new(Some(
+            idle_timeout
+        ))))
+    );
+
+    // clients must not send a timeout
+    let keepalive = EdnsOption::Keepalive(TcpKeepalive::new(Some(idle_timeout)));
+    let tcp_request = test_option_request(origin.clone(), keepalive, Protocol::Tcp)
+        .with_idle_timeout(idle_timeout);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request::<_, TokioTime>(&tcp_request, response_handler.clone())
+        .await;
+    let response = response_handler.into_message().await;
+    assert_eq!(response.metadata.response_code, ResponseCode::FormErr);
+
+    // the option is not used over DoQ
+    #[cfg(feature = "__quic")]
+    {
+        let keepalive = EdnsOption::Keepalive(TcpKeepalive::new(None));
+        let quic_request =
+            test_option_request(origin, keepalive, Protocol::Quic).with_idle_timeout(idle_timeout);
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .handle_request::<_, TokioTime>(&quic_request, response_handler.clone())
+            .await;
+        let response = response_handler.into_message().await;
+        assert_eq!(response.metadata.response_code, ResponseCode::NoError);
+        let edns = response.edns.as_ref().expect("missing response EDNS");
+        assert!(edns.option(EdnsCode::Keepalive).is_none());
+    }
+}
+
+// Responses to padded queries are padded over encrypted transports only.
+#[tokio::test]
+#[cfg(feature = "__tls")]
+async fn test_padding() {
+    use hickory_proto::op::RESPONSE_PADDING_BLOCK_LEN;
+
+    subscribe();
+
+    let mem_handler = create_test();
+    let origin = mem_handler.origin().clone();
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.clone(), vec![Arc::new(mem_handler)]);
+
+    for (protocol, padded) in [(Protocol::Tcp, false), (Protocol::Tls, true)] {
+        let request = test_option_request(origin.clone(), EdnsOption::Padding(8), protocol);
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .handle_request::<_, TokioTime>(&request, response_handler.clone())
+            .await;
+        let bytes = response_handler.into_bytes().await;
+
+        let response = Message::from_vec(&bytes).unwrap();
+        assert_eq!(response.metadata.response_code, ResponseCode::NoError);
+        assert!(!response.answers.is_empty());
+        let edns = response.edns.as_ref().expect("missing response EDNS");
+        assert_eq!(edns.option(EdnsCode::Padding).is_some(), padded);
+        if padded {
+            assert_eq!(bytes.len() % usize::from(RESPONSE_PADDING_BLOCK_LEN), 0);
+        }
+    }
+}
+
 // The DO bit should not be reflected in the response if the client did
 // not set it in the request
 #[tokio::test]
@@ -1090,6 +1090,257 @@ async fn test_update_tsig_invalid_stale_sig() {
     assert_eq!(error, Some(TsigError::BadTime))
 }
 
+#[cfg(feature = "__dnssec")]
+#[tokio::test]
+async fn test_update_tsig_policy() {
+    use hickory_server::store::sqlite::{NameMatch, UpdateAction, UpdateRule};
+
+    subscribe();
+
+    let key_name = Name::from_str("dhcp.example.com.").unwrap();
+    let signer = test_tsig_signer(key_name.clone());
+
+    let mut handler = create_example();
+    handler.set_allow_update(true);
+    handler.set_tsig_signers(vec![signer.clone()]);
+    handler.set_update_policy(vec![UpdateRule {
+        action: UpdateAction::Grant,
+        key: key_name,
+        name: NameMatch::Subdomain(Name::from_str("hosts.example.com.").unwrap()),
+        types: vec![RecordType::A],
+    }]);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_secs())
+        .unwrap();
+    let signed_update = |name: &str| {
+        let mut message = test_update_message(Name::from_str(name).unwrap());
+        let (sig, _) = signer.sign_message(&message, now).unwrap();
+        message.set_signature(sig);
+        Request::from_bytes(
+            message.to_bytes().unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 53)),
+            Protocol::Udp,
+        )
+        .unwrap()
+    };
+
+    // names covered by the policy can be updated
+    let request = signed_update("laptop.hosts.example.com.");
+    let (resp, resp_signer) = handler.update(&request, TokioTime::current_time()).await;
+    assert!(resp.unwrap());
+    assert!(resp_signer.is_some());
+
+    // all other names are refused, even though the TSIG signature is valid
+    let request = signed_update("www.example.com.");
+    let (resp, resp_signer) = handler.update(&request, TokioTime::current_time()).await;
+    assert_eq!(resp, Err(ResponseCode::Refused));
+    assert!(resp_signer.is_some());
+}
+
+#[cfg(feature = "__dnssec")]
+#[tokio::test]
+#[allow(deprecated)] // UpdateScope, only for constructing the KEY
+async fn test_update_sig0() {
+    use std::time::Duration;
+
+    use hickory_proto::dnssec::{
+        Algorithm, DnssecSigner, PublicKey, SigningKey,
+        crypto::Ed25519SigningKey,
+        rdata::{
+            DNSKEY, DNSSECRData, KEY,
+            key::{KeyTrust, KeyUsage, Protocol as KeyProtocol, UpdateScope},
+        },
+    };
+
+    subscribe();
+
+    let sig0_signer = |key_name: &str| {
+        let key =
+            Ed25519SigningKey::from_pkcs8(&Ed25519SigningKey::generate_pkcs8().unwrap()).unwrap();
+        DnssecSigner::new(
+            DNSKEY::from_key(&key.to_public_key().unwrap()),
+            Box::new(key),
+            Name::from_str(key_name).unwrap(),
+            Duration::from_secs(300),
+        )
+    };
+
+    let signer = sig0_signer("host.example.com.");
+    let unpublished = sig0_signer("other.example.com.");
+    let no_auth = sig0_signer("noauth.example.com.");
+
+    // the KEY of the unpublished signer is missing from the zone
+    let mut handler = create_example();
+    handler.set_allow_update(true);
+    for (signer, key_trust) in [
+        (&signer, KeyTrust::default()),
+        (&no_auth, KeyTrust::NotAuth),
+    ] {
+        let key = KEY::new(
+            key_trust,
+            KeyUsage::Host,
+            UpdateScope::default(),
+            KeyProtocol::default(),
+            Algorithm::ED25519,
+            signer
+                .key()
+                .to_public_key()
+                .unwrap()
+                .public_bytes()
+                .to_vec(),
+        );
+        handler.upsert_mut(
+            Record::from_rdata(
+                signer.signer_name().clone(),
+                3600,
+                RData::DNSSEC(DNSSECRData::KEY(key)),
+            ),
+            0,
+        );
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_secs())
+        .unwrap();
+    let signed_update = |signer: &DnssecSigner| {
+        let mut message = test_update_message(Name::from_str("laptop.example.com.").unwrap());
+        message.finalize(signer, now).unwrap();
+        Request::from_bytes(
+            message.to_bytes().unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 53)),
+            Protocol::Udp,
+        )
+        .unwrap()
+    };
+
+    // a published KEY alone does not authorize the signer
+    let request = signed_update(&signer);
+    let (resp, _) = handler.update(&request, TokioTime::current_time()).await;
+    assert_eq!(resp, Err(ResponseCode::Refused));
+
+    handler.set_sig0_signers(vec![
+        signer.signer_name().clone(),
+        unpublished.signer_name().clone(),
+        no_auth.signer_name().clone(),
+    ]);
+
+    let request = signed_update(&signer);
+    let (resp, resp_signer) = handler.update(&request, TokioTime::current_time()).await;
+    assert!(resp.unwrap());
+    assert!(resp_signer.is_none());
+
+    let request = signed_update(&unpublished);
+    let (resp, _) = handler.update(&request, TokioTime::current_time()).await;
+    assert_eq!(resp, Err(ResponseCode::NotAuth));
+
+    // the flags of the KEY prohibit its use for authentication
+    let request = signed_update(&no_auth);
+    let (resp, _) = handler.update(&request, TokioTime::current_time()).await;
+    assert_eq!(resp, Err(ResponseCode::NotAuth));
+}
+
+#[cfg(feature = "__dnssec")]
+#[tokio::test]
+async fn test_tkey_negotiation() {
+    use hickory_proto::rr::TKeyExchange;
+    use hickory_server::store::sqlite::{NameMatch, TkeyConfig, UpdateAction, UpdateRule};
+
+    subscribe();
+
+    let bootstrap = test_tsig_signer(Name::from_str("dhcp.example.com.").unwrap());
+    let key_name = Name::from_str("tmp.dhcp.example.com.").unwrap();
+
+    let mut handler = create_example();
+    handler.set_allow_update(true);
+    handler.set_tsig_signers(vec![bootstrap.clone()]);
+    handler.set_tkey(Some(TkeyConfig::default()));
+    // the negotiated key is evaluated with the identity of the bootstrap key
+    handler.set_update_policy(vec![UpdateRule {
+        action: UpdateAction::Grant,
+        key: Name::from_str("dhcp.example.com.").unwrap(),
+        name: NameMatch::Subdomain(Name::from_str("example.com.").unwrap()),
+        types: vec![RecordType::A],
+    }]);
+
+    let now = TokioTime::current_time();
+    let to_request = |message: Message| {
+        Request::from_bytes(
+            message.to_bytes().unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 53)),
+            Protocol::Udp,
+        )
+        .unwrap()
+    };
+
+    // unsigned negotiations are refused
+    let exchange = TKeyExchange::new(key_name.clone(), TsigAlgorithm::HmacSha256).unwrap();
+    let request = to_request(exchange.request(now as u32, now as u32 + 600));
+    let (result, _) = handler.search(&request, LookupOptions::default()).await;
+    assert!(matches!(
+        result.map_result(),
+        Some(Err(LookupError::ResponseCode(ResponseCode::Refused)))
+    ));
+
+    let mut message = exchange.request(now as u32, now as u32 + 600);
+    message.finalize(&bootstrap, now).unwrap();
+    let (result, resp_signer) = handler
+        .search(&to_request(message), LookupOptions::default())
+        .await;
+    assert!(resp_signer.is_some());
+    let lookup = result.map_result().unwrap().unwrap();
+    let mut response = Message::query();
+    response.add_answers(lookup.iter().cloned());
+
+    let (negotiated, expiration) = exchange.finish(&response, 300).unwrap();
+    assert_eq!(expiration, now as u32 + 600);
+
+    let signed_update = |signer: &TSigner, name: &str| {
+        let mut message = test_update_message(Name::from_str(name).unwrap());
+        message.finalize(signer, now).unwrap();
+        to_request(message)
+    };
+
+    let (resp, resp_signer) = handler
+        .update(&signed_update(&negotiated, "laptop.example.com."), now)
+        .await;
+    assert!(resp.unwrap());
+    assert!(resp_signer.is_some());
+
+    let (resp, _) = handler
+        .update(&signed_update(&negotiated, "laptop.example.net."), now)
+        .await;
+    assert_eq!(resp, Err(ResponseCode::Refused));
+
+    // the key is gone once expired
+    let (resp, _) = handler
+        .update(
+            &signed_update(&negotiated, "laptop.example.com."),
+            now + 601,
+        )
+        .await;
+    assert_eq!(resp, Err(ResponseCode::NotAuth));
+
+    // negotiated keys can't shadow configured keys
+    let exchange = TKeyExchange::new(
+        Name::from_str("dhcp.example.com.").unwrap(),
+        TsigAlgorithm::HmacSha256,
+    )
+    .unwrap();
+    let mut message = exchange.request(now as u32, now as u32 + 600);
+    message.finalize(&bootstrap, now).unwrap();
+    let (result, _) = handler
+        .search(&to_request(message), LookupOptions::default())
+        .await;
+    let lookup = result.map_result().unwrap().unwrap();
+    let RData::TKEY(tkey) = &lookup.iter().next().unwrap().data else {
+        panic!("expected TKEY answer");
+    };
+    assert_eq!(tkey.error, Some(TsigError::BadName));
+}
+
 #[cfg(feature = "__dnssec")]
 fn test_tsig_signer(key_name: Name) -> TSigner {
     // openssl rand -hex 32
@@ -1524,3 +1775,57 @@ async fn test_axfr_allow_tsig_signed() {
     // between updates + AXFR.
     assert!(resp_signer.is_some());
 }
+
+#[cfg(feature = "__dnssec")]
+#[tokio::test]
+async fn test_axfr_refuse_sig0_signed() {
+    use std::time::Duration;
+
+    use hickory_proto::dnssec::{
+        DnssecSigner, SigningKey, crypto::Ed25519SigningKey, rdata::DNSKEY,
+    };
+
+    subscribe();
+
+    let key = Ed25519SigningKey::from_pkcs8(&Ed25519SigningKey::generate_pkcs8().unwrap()).unwrap();
+    let signer = DnssecSigner::new(
+        DNSKEY::from_key(&key.to_public_key().unwrap()),
+        Box::new(key),
+        Name::from_str("host.example.com.").unwrap(),
+        Duration::from_secs(300),
+    );
+
+    let mut handler = create_example();
+    handler.set_axfr_policy(AxfrPolicy::AllowSigned);
+    handler.set_sig0_signers(vec![signer.signer_name().clone()]);
+
+    let mut message = Message::query();
+    message.add_query(Query::new(
+        Name::from_str("example.com.").unwrap(),
+        RecordType::AXFR,
+    ));
+    message
+        .finalize(&signer, TokioTime::current_time())
+        .unwrap();
+
+    let request = Request::from_bytes(
+        message.to_bytes().unwrap(),
+        SocketAddr::from(([127, 0, 0, 1], 53)),
+        Protocol::Tcp,
+    )
+    .unwrap();
+
+    // SIG(0) is only accepted for updates, AllowSigned requires TSIG
+    let (resp, _) = handler
+        .zone_transfer(
+            &request,
+            LookupOptions::default(),
+            TokioTime::current_time(),
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        resp,
+        Err(LookupError::ResponseCode(ResponseCode::Refused))
+    ));
+}
@@ -0,0 +1,248 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The signzone program
+
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::Parser;
+use data_encoding::HEXLOWER_PERMISSIVE;
+use tracing::info;
+
+use hickory_net::runtime::TokioRuntimeProvider;
+use hickory_proto::{
+    dnssec::{
+        Algorithm, DnssecSigner, Nsec3HashAlgorithm, crypto::signing_key_from_file, rdata::DNSKEY,
+    },
+    rr::{Name, rdata::zonemd::HashAlgorithm},
+    serialize::txt::{self, ZoneWriter},
+};
+use hickory_server::{
+    dnssec::NxProofKind,
+    store::in_memory::InMemoryZoneHandler,
+    zone_handler::{AxfrPolicy, ZoneType},
+};
+
+/// A CLI interface for signing zone files offline.
+///
+/// This utility reads a zone file, adds the DNSKEYs of the given keys to the apex, generates the
+///   NSEC or NSEC3 chain, signs all RecordSets and writes the signed zone in zone file format.
+///   The SOA serial is incremented and a ZONEMD digest of the signed zone is added.
+#[derive(Debug, Parser)]
+#[clap(name = "signzone")]
+struct Opts {
+    /// Zone file to sign
+    zone_file: PathBuf,
+
+    /// Origin of the zone, defaults to the `$ORIGIN` of the zone file
+    #[clap(short = 'o', long)]
+    origin: Option<Name>,
+
+    /// Private key to sign the zone with, as ALGORITHM:FILE, e.g. ED25519:zsk.pk8
+    ///
+    /// The key file is either PEM or DER encoded. May be specified multiple times.
+    #[clap(short = 'k', long = "key", required = true, value_parser = parse_key_arg)]
+    keys: Vec<(Algorithm, PathBuf)>,
+
+    /// Validity period of the signatures in seconds
+    #[clap(long, default_value_t = 60 * 60 * 24 * 7 * 52)]
+    validity: u64,
+
+    /// Use NSEC3 instead of NSEC for authenticated denial of existence
+    #[clap(long)]
+    nsec3: bool,
+
+    /// Number of additional NSEC3 hash iterations
+    #[clap(long, default_value_t = 0, requires = "nsec3")]
+    iterations: u16,
+
+    /// Hex encoded NSEC3 salt, empty by default
+    #[clap(long, default_value = "", requires = "nsec3", value_parser = parse_salt)]
+    salt: Arc<[u8]>,
+
+    /// Set the NSEC3 Opt-Out flag, insecure delegations are not covered by the NSEC3 chain
+    #[clap(long, requires = "nsec3")]
+    opt_out: bool,
+
+    /// File to write the signed zone to, defaults to stdout
+    #[clap(short = 'f', long)]
+    output: Option<PathBuf>,
+
+    /// Configure log verbosity.
+    #[clap(flatten)]
+    log_config: hickory_util::LogConfig,
+}
+
+/// Run the signzone program
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let opts = Opts::parse();
+
+    // enable logging early
+    hickory_util::logger(env!("CARGO_BIN_NAME"), opts.log_config.level());
+
+    let zone = sign_zone(&opts)?;
+    match &opts.output {
+        Some(path) => fs::write(path, zone)?,
+        None => io::stdout().write_all(zone.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// Signs the zone file of `opts`, returning the signed zone in zone file format
+fn sign_zone(opts: &Opts) -> Result<String, Box<dyn Error>> {
+    info!("reading zone file: {}", opts.zone_file.display());
+    let buf = fs::read_to_string(&opts.zone_file)?;
+    let (origin, records) =
+        txt::Parser::new(buf, Some(opts.zone_file.clone()), opts.origin.clone()).parse()?;
+
+    let nx_proof_kind = match opts.nsec3 {
+        true => NxProofKind::Nsec3 {
+            algorithm: Nsec3HashAlgorithm::SHA1,
+            salt: opts.salt.clone(),
+            iterations: opts.iterations,
+            opt_out: opts.opt_out,
+        },
+        false => NxProofKind::Nsec,
+    };
+
+    let mut handler = InMemoryZoneHandler::<TokioRuntimeProvider>::new(
+        origin.clone(),
+        records,
+        ZoneType::Primary,
+        AxfrPolicy::Deny,
+        Some(nx_proof_kind),
+    )?;
+    handler.set_zonemd(Some(HashAlgorithm::SHA384));
+
+    for (algorithm, path) in &opts.keys {
+        info!("reading key: {}", path.display());
+        let key = signing_key_from_file(path, *algorithm)?;
+        let public_key = key.to_public_key()?;
+        let signer = DnssecSigner::new(
+            DNSKEY::from_key(&public_key),
+            key,
+            origin.clone(),
+            Duration::from_secs(opts.validity),
+        );
+        signer
+            .test_key()
+            .map_err(|e| format!("key {} failed test: {e}", path.display()))?;
+
+        info!("adding key to zone: {}", path.display());
+        handler.add_zone_signing_key_mut(signer)?;
+    }
+
+    info!("signing zone: {origin}");
+    handler.secure_zone_mut()?;
+
+    let mut zone = String::new();
+    ZoneWriter::new(origin).write(
+        &mut zone,
+        handler.records_get_mut().values().map(|rrset| &**rrset),
+    )?;
+
+    Ok(zone)
+}
+
+fn parse_key_arg(arg: &str) -> Result<(Algorithm, PathBuf), String> {
+    let Some((algorithm, path)) = arg.split_once(':') else {
+        return Err(format!("expected ALGORITHM:FILE, got: {arg}"));
+    };
+
+    let algorithm = [
+        Algorithm::RSASHA256,
+        Algorithm::RSASHA512,
+        Algorithm::ECDSAP256SHA256,
+        Algorithm::ECDSAP384SHA384,
+        Algorithm::ED25519,
+    ]
+    .into_iter()
+    .find(|supported| supported.as_str().eq_ignore_ascii_case(algorithm))
+    .ok_or_else(|| format!("unsupported algorithm: {algorithm}"))?;
+
+    Ok((algorithm, PathBuf::from(path)))
+}
+
+fn parse_salt(salt: &str) -> Result<Arc<[u8]>, String> {
+    HEXLOWER_PERMISSIVE
+        .decode(salt.as_bytes())
+        .map(Arc::from)
+        .map_err(|e| format!("invalid salt: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use hickory_proto::{
+        dnssec::{Verifier, rdata::DNSSECRData},
+        rr::{RData, RecordType, RrKey},
+    };
+    use hickory_server::store::in_memory::verify_zonemd;
+
+    use super::*;
+
+    #[test]
+    fn test_sign_zone() {
+        let test_data = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tests/test-data/test_configs/dnssec"
+        );
+        let opts = Opts::parse_from([
+            "signzone".to_owned(),
+            format!("{test_data}/example.com.zone"),
+            "--origin=example.com.".to_owned(),
+            format!("--key=ED25519:{test_data}/ed25519.pk8"),
+        ]);
+        let zone = sign_zone(&opts).unwrap();
+
+        let (origin, records) = txt::Parser::new(zone, None, None).parse().unwrap();
+        assert_eq!(origin, Name::from_ascii("example.com.").unwrap());
+        let apex = |record_type| &records[&RrKey::new(origin.clone().into(), record_type)];
+
+        assert_eq!(apex(RecordType::ZONEMD).records_count(), 1);
+        verify_zonemd(&origin, &records).unwrap();
+
+        let Some(RData::DNSSEC(DNSSECRData::DNSKEY(dnskey))) = apex(RecordType::DNSKEY)
+            .records_without_rrsigs()
+            .next()
+            .map(|record| &record.data)
+        else {
+            panic!("DNSKEY missing at the apex");
+        };
+
+        for record_type in [RecordType::SOA, RecordType::DNSKEY, RecordType::NSEC] {
+            assert!(
+                !apex(record_type).rrsigs().is_empty(),
+                "{record_type} unsigned"
+            );
+        }
+
+        for rrset in records.values() {
+            for rrsig in rrset.rrsigs() {
+                let RData::DNSSEC(DNSSECRData::RRSIG(sig)) = &rrsig.data else {
+                    panic!("not an RRSIG: {rrsig}");
+                };
+
+                dnskey
+                    .verify_rrsig(
+                        rrset.name(),
+                        rrset.dns_class(),
+                        sig,
+                        rrset.records_without_rrsigs(),
+                    )
+                    .unwrap_or_else(|e| panic!("invalid RRSIG of {rrsig}: {e}"));
+            }
+        }
+    }
+}